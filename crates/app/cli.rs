@@ -1,19 +1,78 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use backend::{doctor::Status, NameRequest, SPECIFICATION_VERSION};
+use clap::{ArgAction, ArgGroup, Parser, Subcommand};
 use config::Config;
 
 /// The notification system which derives a notification to user
 /// using wayland client.
 #[derive(Parser)]
 #[command(version, about, name = env!("APP_NAME"))]
-pub enum Args {
+pub struct Args {
+    #[arg(
+        short,
+        long,
+        global = true,
+        action = ArgAction::Count,
+        help = "Increase verbosity of logs",
+        long_help = "Increase verbosity of logs: -v for debug and -vv for trace level. \
+        The NOTI_LOG environment variable takes precedence when it is set"
+    )]
+    verbose: u8,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Address of D-Bus to connect",
+        long_help = "Address of D-Bus to connect instead of the session bus, e.g. \
+        'unix:path=/tmp/noti-bus' to run the daemon and client on a private bus"
+    )]
+    bus_address: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
     /// Start the backend. Use it in systemd, openrc or any other service.
     Run(Box<RunCommand>),
 
     /// Send the notification
     Send(Box<SendCommand>),
 
+    /// Close the notification by id, all notifications or the notifications of application
+    Close(CloseCommand),
+
     /// Print server information
     ServerInfo,
+
+    /// Clear the transient runtime state of the server without restarting
+    ResetState,
+
+    /// Copy the body of the most recent notification to the clipboard
+    CopyLast(CopyLastCommand),
+
+    /// Pause the expiration of shown notifications until resumed
+    Pause,
+
+    /// Resume the expiration of shown notifications
+    Resume,
+
+    /// List the outputs of the Wayland compositor
+    Outputs(OutputsCommand),
+
+    /// Print version information
+    Version(VersionCommand),
+
+    /// Check the layout file for errors without rendering it
+    CheckLayout(CheckLayoutCommand),
+
+    /// Show the notifications from a file without D-Bus
+    Replay(ReplayCommand),
+
+    /// Check the environment which the daemon depends on
+    Doctor(DoctorCommand),
 }
 
 #[derive(Parser)]
@@ -22,8 +81,94 @@ pub struct RunCommand {
         short,
         long,
         help = "Path to config file",
-        long_help = "Path to config file which will be used primarily"
+        long_help = "Path to config file which will be used primarily. Use '-' to read the config \
+        from stdin without reloading it"
+    )]
+    config: Option<String>,
+
+    #[arg(
+        long,
+        help = "Replace the running daemon",
+        long_help = "Replace the running notification daemon if it allows the replacement"
+    )]
+    replace: bool,
+
+    #[arg(
+        long,
+        default_value_t = 3,
+        help = "Retries to acquire the bus name",
+        long_help = "Number of retries with increasing delay to acquire the notifications bus name \
+        when it's owned by another daemon"
+    )]
+    name_retries: u8,
+}
+
+#[derive(Parser)]
+pub struct OutputsCommand {
+    #[arg(long, help = "Print the outputs in JSON format")]
+    json: bool,
+}
+
+#[derive(Parser)]
+pub struct VersionCommand {
+    #[arg(
+        long,
+        help = "Print full version information",
+        long_help = "Print the supported version of notification specification, the Wayland \
+        protocols which the compositor supports and the optional features with the programs or \
+        protocols which provide them, e.g. to attach them to bug reports"
+    )]
+    full: bool,
+
+    #[arg(long, help = "Print the version information in JSON format")]
+    json: bool,
+}
+
+#[derive(Parser)]
+#[command(group(ArgGroup::new("target").required(true).args(["id", "all", "app"])))]
+pub struct CloseCommand {
+    #[arg(help = "The id of notification to close")]
+    id: Option<u32>,
+
+    #[arg(long, help = "Close all shown and queued notifications")]
+    all: bool,
+
+    #[arg(long, help = "Close all notifications of the application")]
+    app: Option<String>,
+}
+
+#[derive(Parser)]
+pub struct CopyLastCommand {
+    #[arg(long, help = "Copy the summary instead of the body")]
+    summary: bool,
+}
+
+#[derive(Parser)]
+pub struct CheckLayoutCommand {
+    #[arg(
+        help = "Path to layout file",
+        long_help = "Path to layout file. Syntax errors, unknown widgets, aliases and invalid \
+        fields are printed with their positions"
     )]
+    path: PathBuf,
+}
+
+#[derive(Parser)]
+pub struct ReplayCommand {
+    #[arg(
+        help = "Path to replay file",
+        long_help = "Path to TOML or JSON file with the list of notifications under the \
+        'notification' key. Each entry can set the 'delay_ms' before it's shown"
+    )]
+    path: PathBuf,
+
+    #[arg(short, long, help = "Path to config file")]
+    config: Option<String>,
+}
+
+#[derive(Parser)]
+pub struct DoctorCommand {
+    #[arg(short, long, help = "Path to config file")]
     config: Option<String>,
 }
 
@@ -180,26 +325,181 @@ pub struct SendCommand {
 }
 
 impl Args {
-    pub async fn process(self) -> anyhow::Result<()> {
-        if let Args::Run(ref args) = self {
-            run(args).await?
+    /// Returns the default level of logs which is chosen by the number of `-v` flags.
+    pub fn log_level(&self) -> &'static str {
+        match self.verbose {
+            0 => "info",
+            1 => "debug",
+            _ => "trace",
         }
+    }
 
-        let noti = client::NotiClient::init().await?;
+    pub async fn process(self) -> anyhow::Result<()> {
+        match self.command {
+            Command::Run(ref args) => return run(args, self.bus_address.as_deref()).await,
+            Command::Outputs(ref args) => return outputs(args),
+            Command::Version(ref args) => return version(args),
+            Command::CheckLayout(ref args) => return check_layout(args),
+            Command::Replay(ref args) => return replay(args).await,
+            Command::Doctor(ref args) => return doctor(args, self.bus_address.as_deref()).await,
+            _ => (),
+        }
 
-        match self {
-            Args::Run { .. } => unreachable!(),
-            Args::Send(args) => send(noti, *args).await?,
-            Args::ServerInfo => server_info(noti).await?,
+        let noti = client::NotiClient::init(self.bus_address.as_deref()).await?;
+
+        match self.command {
+            Command::Run { .. }
+            | Command::Outputs { .. }
+            | Command::Version { .. }
+            | Command::CheckLayout { .. }
+            | Command::Replay { .. }
+            | Command::Doctor { .. } => unreachable!(),
+            Command::Send(args) => send(noti, *args).await?,
+            Command::Close(args) => close(noti, args).await?,
+            Command::ServerInfo => server_info(noti).await?,
+            Command::ResetState => reset_state(noti).await?,
+            Command::CopyLast(args) => copy_last(noti, args).await?,
+            Command::Pause => pause(noti).await?,
+            Command::Resume => resume(noti).await?,
         }
 
         Ok(())
     }
 }
 
-async fn run(args: &RunCommand) -> anyhow::Result<()> {
+async fn run(args: &RunCommand, bus_address: Option<&str>) -> anyhow::Result<()> {
+    let config = match args.config.as_deref() {
+        Some("-") => Config::from_stdin()?,
+        config => Config::init(config),
+    };
+    let name_request = NameRequest {
+        replace: args.replace,
+        retries: args.name_retries,
+    };
+    backend::run(config, name_request, bus_address).await
+}
+
+async fn replay(args: &ReplayCommand) -> anyhow::Result<()> {
+    let config = Config::init(args.config.as_deref());
+    backend::replay(config, &args.path).await
+}
+
+async fn doctor(args: &DoctorCommand, bus_address: Option<&str>) -> anyhow::Result<()> {
     let config = Config::init(args.config.as_deref());
-    backend::run(config).await
+    let checks = backend::doctor::checks(&config, bus_address).await;
+
+    for check in &checks {
+        println!("[{}] {}: {}", check.status, check.name, check.message);
+        if let Some(hint) = check.hint {
+            println!("       hint: {hint}");
+        }
+    }
+
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == Status::Fail)
+        .count();
+    if failed > 0 {
+        anyhow::bail!("{failed} check(s) failed");
+    }
+
+    Ok(())
+}
+
+fn outputs(args: &OutputsCommand) -> anyhow::Result<()> {
+    let outputs = backend::outputs::outputs()?;
+
+    if args.json {
+        println!("{}", serde_json::to_string(&outputs)?);
+        return Ok(());
+    }
+
+    for output in outputs {
+        println!("{}", output.name.as_deref().unwrap_or("unknown"));
+        if let Some(description) = output.description.as_ref() {
+            println!("  description: {description}");
+        }
+        println!(
+            "  resolution: {}x{} @ {:.2} Hz",
+            output.width,
+            output.height,
+            output.refresh as f32 / 1000.0
+        );
+        println!("  scale: {}", output.scale);
+        println!("  transform: {}", output.transform);
+    }
+
+    Ok(())
+}
+
+fn version(args: &VersionCommand) -> anyhow::Result<()> {
+    let name = env!("APP_NAME");
+    let version = env!("CARGO_PKG_VERSION");
+
+    if !args.full {
+        match args.json {
+            true => println!(
+                "{}",
+                serde_json::json!({ "name": name, "version": version })
+            ),
+            false => println!("{name} {version}"),
+        }
+        return Ok(());
+    }
+
+    // INFO: the version information is useful even when the compositor isn't reachable, so the
+    // error is printed instead of failing
+    let protocols = backend::protocols::protocols();
+    let features = backend::features::features(protocols.as_deref().ok());
+
+    if args.json {
+        let version = serde_json::json!({
+            "name": name,
+            "version": version,
+            "specification_version": SPECIFICATION_VERSION,
+            "protocols": protocols.as_ref().ok(),
+            "features": features,
+        });
+        println!("{version}");
+        return Ok(());
+    }
+
+    println!("{name} {version}");
+    println!("notification specification: {SPECIFICATION_VERSION}");
+    match protocols {
+        Ok(protocols) => {
+            println!("wayland protocols:");
+            for protocol in protocols {
+                match protocol.version {
+                    Some(version) => println!("  {} v{version}", protocol.name),
+                    None => println!("  {} (unsupported)", protocol.name),
+                }
+            }
+        }
+        Err(err) => println!("wayland protocols: unavailable ({err})"),
+    }
+    println!("features:");
+    for feature in features {
+        match feature.provider {
+            Some(provider) => println!("  {} by {provider}", feature.name),
+            None => println!("  {} (unavailable)", feature.name),
+        }
+    }
+
+    Ok(())
+}
+
+fn check_layout(args: &CheckLayoutCommand) -> anyhow::Result<()> {
+    let issues = filetype::check_layout(&args.path)?;
+    if issues.is_empty() {
+        println!("The layout is valid");
+        return Ok(());
+    }
+
+    for issue in &issues {
+        println!("{issue}\n");
+    }
+    anyhow::bail!("The layout has {} issue(s)", issues.len())
 }
 
 async fn send(noti: client::NotiClient<'_>, args: SendCommand) -> anyhow::Result<()> {
@@ -241,3 +541,23 @@ async fn send(noti: client::NotiClient<'_>, args: SendCommand) -> anyhow::Result
 async fn server_info(noti: client::NotiClient<'_>) -> anyhow::Result<()> {
     noti.get_server_info().await
 }
+
+async fn reset_state(noti: client::NotiClient<'_>) -> anyhow::Result<()> {
+    noti.reset_state().await
+}
+
+async fn close(noti: client::NotiClient<'_>, args: CloseCommand) -> anyhow::Result<()> {
+    noti.close(args.id, args.app.as_deref()).await
+}
+
+async fn copy_last(noti: client::NotiClient<'_>, args: CopyLastCommand) -> anyhow::Result<()> {
+    noti.copy_last(args.summary).await
+}
+
+async fn pause(noti: client::NotiClient<'_>) -> anyhow::Result<()> {
+    noti.pause().await
+}
+
+async fn resume(noti: client::NotiClient<'_>) -> anyhow::Result<()> {
+    noti.resume().await
+}
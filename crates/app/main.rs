@@ -6,13 +6,16 @@ use cli::Args;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    setup_logger();
-
     let args = Args::parse();
+    setup_logger(args.log_level());
+
     args.process().await
 }
 
-fn setup_logger() {
+/// Initializes the logger with the filter from `NOTI_LOG` environment variable or with the given
+/// level when it isn't set.
+fn setup_logger(default_level: &str) {
     const ENV_NAME: &str = "NOTI_LOG";
-    env_logger::Builder::from_env(env_logger::Env::default().filter_or(ENV_NAME, "info")).init();
+    env_logger::Builder::from_env(env_logger::Env::default().filter_or(ENV_NAME, default_level))
+        .init();
 }
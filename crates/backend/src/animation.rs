@@ -0,0 +1,157 @@
+//! The animations which are played once when the banner appears. They're applied to the drawn
+//! framebuffer of banner, so the layout isn't recompiled on every frame.
+
+use std::{borrow::Cow, f32::consts::PI, time::Duration};
+
+use config::display::Animation;
+
+const DURATION: Duration = Duration::from_millis(600);
+
+/// The maximum part of white which is mixed into the banner by the flash.
+const FLASH_INTENSITY: f32 = 0.6;
+const FLASHES: f32 = 2.0;
+
+/// The maximum horizontal shift of banner in pixels by the shake.
+const SHAKE_AMPLITUDE: f32 = 8.0;
+const SHAKES: f32 = 3.0;
+
+/// The state of animation at some moment.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct AnimationFrame {
+    /// The part of white which is mixed into the banner.
+    flash: f32,
+    /// The horizontal shift of banner in pixels.
+    shift: i32,
+}
+
+/// Returns the frame of animation which is played for `elapsed` time or `None` when it's over.
+/// Both animations fade out to the still banner by the end.
+pub(crate) fn frame(animation: Animation, elapsed: Duration) -> Option<AnimationFrame> {
+    if elapsed >= DURATION {
+        return None;
+    }
+
+    let progress = elapsed.as_secs_f32() / DURATION.as_secs_f32();
+    let envelope = 1.0 - progress;
+
+    match animation {
+        Animation::None => None,
+        Animation::Flash => Some(AnimationFrame {
+            flash: FLASH_INTENSITY * (progress * PI * FLASHES).sin().abs() * envelope,
+            shift: 0,
+        }),
+        Animation::Shake => Some(AnimationFrame {
+            flash: 0.0,
+            shift: (SHAKE_AMPLITUDE * (progress * 2.0 * PI * SHAKES).sin() * envelope).round()
+                as i32,
+        }),
+    }
+}
+
+/// Applies the frame to the premultiplied BGRA data of banner with the given width. The data is
+/// kept as is when there is no frame.
+pub(crate) fn apply(
+    data: Cow<'_, [u8]>,
+    width: usize,
+    frame: Option<AnimationFrame>,
+) -> Cow<'_, [u8]> {
+    let Some(frame) = frame else {
+        return data;
+    };
+
+    let mut data = data.into_owned();
+    if frame.flash > 0.0 {
+        // INFO: the white of the same alpha is the alpha itself in premultiplied colors
+        data.chunks_exact_mut(4).for_each(|pixel| {
+            let alpha = pixel[3] as f32;
+            pixel[..3].iter_mut().for_each(|channel| {
+                *channel = (*channel as f32 + (alpha - *channel as f32) * frame.flash).round() as u8
+            });
+        });
+    }
+
+    let shift = frame.shift.unsigned_abs() as usize * 4;
+    if shift > 0 && width > 0 {
+        data.chunks_exact_mut(width * 4).for_each(|row| {
+            let shift = shift.min(row.len());
+            if frame.shift > 0 {
+                row.rotate_right(shift);
+                row[..shift].fill(0);
+            } else {
+                row.rotate_left(shift);
+                let len = row.len();
+                row[len - shift..].fill(0);
+            }
+        });
+    }
+
+    Cow::Owned(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, time::Duration};
+
+    use config::display::Animation;
+
+    use super::{apply, frame, AnimationFrame, DURATION};
+
+    #[test]
+    fn animations_are_over_after_duration() {
+        for animation in [Animation::Flash, Animation::Shake] {
+            assert!(frame(animation, Duration::from_millis(100)).is_some());
+            assert!(frame(animation, DURATION).is_none());
+        }
+        assert!(frame(Animation::None, Duration::ZERO).is_none());
+
+        assert_eq!(
+            frame(Animation::Flash, Duration::ZERO),
+            Some(AnimationFrame::default())
+        );
+        assert!(frame(Animation::Flash, DURATION / 4).unwrap().flash > 0.0);
+        assert_ne!(frame(Animation::Shake, DURATION / 12).unwrap().shift, 0);
+    }
+
+    #[test]
+    fn frames_are_applied_to_framebuffer() {
+        let data = [10, 20, 30, 255, 0, 0, 0, 0, 40, 50, 60, 128];
+
+        let flashed = apply(
+            Cow::Borrowed(&data),
+            3,
+            Some(AnimationFrame {
+                flash: 0.5,
+                shift: 0,
+            }),
+        );
+        assert_eq!(
+            flashed.as_ref(),
+            &[133, 138, 143, 255, 0, 0, 0, 0, 84, 89, 94, 128]
+        );
+
+        let shifted = apply(
+            Cow::Borrowed(&data),
+            3,
+            Some(AnimationFrame {
+                flash: 0.0,
+                shift: 1,
+            }),
+        );
+        assert_eq!(shifted.as_ref(), &[0, 0, 0, 0, 10, 20, 30, 255, 0, 0, 0, 0]);
+
+        let shifted = apply(
+            Cow::Borrowed(&data),
+            3,
+            Some(AnimationFrame {
+                flash: 0.0,
+                shift: -1,
+            }),
+        );
+        assert_eq!(shifted.as_ref(), &[0, 0, 0, 0, 40, 50, 60, 128, 0, 0, 0, 0]);
+
+        assert!(matches!(
+            apply(Cow::Borrowed(&data), 3, None),
+            Cow::Borrowed(_)
+        ));
+    }
+}
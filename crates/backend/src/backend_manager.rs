@@ -1,59 +1,159 @@
+use crate::clipboard::Clipboard;
 use crate::dispatcher::Dispatcher;
 use crate::idle_manager::IdleManager;
+use crate::power_manager::PowerManager;
+use crate::remote_image::RemoteImages;
 
 use config::Config;
-use dbus::{actions::Signal, notification::Notification};
-use log::debug;
+use dbus::{
+    actions::Signal,
+    notification::{Notification, NotificationAction},
+};
+use log::{debug, warn};
+use shared::log_target::BACKEND;
 
 use super::window_manager::WindowManager;
 
 pub(crate) struct BackendManager {
     window_manager: WindowManager,
     idle_manager: IdleManager,
+    power_manager: PowerManager,
+    clipboard: Clipboard,
+    remote_images: RemoteImages,
 }
 
 impl BackendManager {
     pub(crate) fn init(config: &Config) -> anyhow::Result<Self> {
+        let window_manager = WindowManager::init(config)?;
+        let power_manager = PowerManager::init(window_manager.connection())?;
+        let clipboard = Clipboard::init(window_manager.connection())?;
+
         Ok(Self {
-            window_manager: WindowManager::init(config)?,
+            window_manager,
             idle_manager: IdleManager::init(config)?,
+            power_manager,
+            clipboard,
+            remote_images: RemoteImages::init(),
         })
     }
 
-    pub(crate) fn create_notification(&mut self, notification: Box<Notification>) {
+    pub(crate) fn create_notification(
+        &mut self,
+        mut notification: Box<Notification>,
+        config: &Config,
+    ) {
         let id = notification.id;
+        self.remote_images
+            .prepare(&mut notification, config.general().allow_remote_images);
+        if let Some(code) = config.otp().extract_code(&notification.body.body) {
+            debug!(
+                target: BACKEND,
+                "Backend Manager: Found the one-time code in notification with id {id}"
+            );
+            notification
+                .actions
+                .push(NotificationAction::copy_code(code));
+        }
         self.window_manager.create_notification(notification);
-        debug!("Backend Manager: Received notification with id {id} to append queue");
+        debug!(
+            target: BACKEND,
+            "Backend Manager: Received notification with id {id} to append queue"
+        );
     }
 
     pub(crate) fn close_notification(&mut self, notification_id: u32) {
         self.window_manager.close_notification(notification_id);
-        debug!("Backend Manager: Received notification id {notification_id} to close");
+        debug!(
+            target: BACKEND,
+            "Backend Manager: Received notification id {notification_id} to close"
+        );
+    }
+
+    /// Closes all notifications by the client request. Returns the number of closed
+    /// notifications.
+    pub(crate) fn close_all_notifications(&mut self) -> anyhow::Result<u32> {
+        let closed = self.window_manager.close_all_notifications()?;
+        debug!(target: BACKEND, "Backend Manager: Closed all {closed} notifications");
+        Ok(closed)
+    }
+
+    /// Closes the notifications of application. Returns the number of closed notifications.
+    pub(crate) fn close_notifications_by_app(
+        &mut self,
+        app_name: &str,
+        config: &Config,
+    ) -> anyhow::Result<u32> {
+        let closed = self
+            .window_manager
+            .close_notifications_by_app(app_name, config)?;
+        debug!(target: BACKEND, "Backend Manager: Closed {closed} notifications");
+        Ok(closed)
+    }
+
+    pub(crate) fn set_inhibited(&mut self, inhibited: bool) {
+        self.window_manager.set_inhibited(inhibited);
+        debug!(target: BACKEND, "Backend Manager: Set inhibited state to {inhibited}");
+    }
+
+    pub(crate) fn set_paused(&mut self, paused: bool) {
+        self.window_manager.set_paused(paused);
+        debug!(target: BACKEND, "Backend Manager: Set paused state to {paused}");
+    }
+
+    pub(crate) fn copy(&mut self, text: String) -> anyhow::Result<()> {
+        self.clipboard.copy(text)?;
+        debug!(target: BACKEND, "Backend Manager: Copied the text to clipboard");
+        Ok(())
     }
 
     pub(crate) fn poll(&mut self, config: &Config) -> anyhow::Result<()> {
         let Self {
             idle_manager,
+            power_manager,
             window_manager,
+            clipboard,
+            remote_images,
         } = self;
 
-        if !idle_manager.is_idled() {
-            if idle_manager.was_idled() {
+        for (id, image_path) in remote_images.poll() {
+            window_manager.update_image_path(
+                id,
+                image_path.to_string_lossy().into_owned(),
+                config,
+            )?;
+        }
+
+        let pause_on_screen_off = config.general().pause_on_screen_off;
+        let is_screen_off = pause_on_screen_off && power_manager.is_screen_off();
+
+        if !idle_manager.is_idled() && !is_screen_off {
+            let was_screen_off = pause_on_screen_off && power_manager.was_screen_off();
+            power_manager.reset_screen_off_state();
+
+            if idle_manager.was_idled() || was_screen_off {
                 idle_manager.reset_idle_state();
 
                 window_manager.reset_timeouts()?;
             }
 
             window_manager.show_window(config)?;
+            window_manager.remove_failed(config)?;
 
             window_manager.handle_close_notifications(config)?;
             window_manager.remove_expired(config)?;
 
             window_manager.handle_actions(config)?;
+            copy_codes(clipboard, window_manager.take_copied_codes());
+            window_manager.handle_scroll(config)?;
+            window_manager.refresh_ages(config)?;
+            window_manager.animate(config)?;
+            window_manager.enforce_memory_budget(config);
         }
 
         window_manager.dispatch()?;
         idle_manager.dispatch()?;
+        power_manager.dispatch()?;
+        clipboard.dispatch()?;
 
         if window_manager.update_cache() {
             window_manager.update_by_config(config)?;
@@ -62,14 +162,42 @@ impl BackendManager {
         Ok(())
     }
 
+    /// Replaces the window manager by a new one after the panic of render path keeping its
+    /// notifications.
+    pub(crate) fn restart_window_manager(&mut self, config: &Config) -> anyhow::Result<()> {
+        self.window_manager.restart(config)?;
+        debug!(target: BACKEND, "Backend Manager: Restarted the window manager");
+        Ok(())
+    }
+
+    pub(crate) fn reset_runtime_state(&mut self, config: &Config) {
+        self.window_manager.reset_runtime_state(config);
+        debug!(target: BACKEND, "Backend Manager: Reset runtime state");
+    }
+
+    pub(crate) fn close_all(&mut self) -> anyhow::Result<()> {
+        self.window_manager.close_all()?;
+        debug!(target: BACKEND, "Backend Manager: Closed all notifications");
+        Ok(())
+    }
+
     pub(crate) fn pop_signal(&mut self) -> Option<Signal> {
-        self.window_manager.pop_signal()
+        let signal = self.window_manager.pop_signal();
+        if let Some(Signal::NotificationClosed {
+            notification_id, ..
+        }) = signal
+        {
+            self.remote_images.cancel(notification_id);
+        }
+
+        signal
     }
 
     pub(crate) fn update_config(&mut self, config: &Config) -> anyhow::Result<()> {
         let Self {
             window_manager,
             idle_manager,
+            ..
         } = self;
 
         window_manager.update_by_config(config)?;
@@ -78,3 +206,35 @@ impl BackendManager {
         Ok(())
     }
 }
+
+/// Copies the one-time codes of banners which are clicked by the middle button. The compositor
+/// may not support the clipboard, so the failure is only logged to keep the daemon running.
+fn copy_codes(clipboard: &mut Clipboard, codes: Vec<String>) {
+    for code in codes {
+        if let Err(err) = clipboard.copy(code) {
+            warn!(
+                target: BACKEND,
+                "Backend Manager: Failed to copy the one-time code. Error: {err}"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+
+    use wayland_client::Connection;
+
+    use super::*;
+
+    #[test]
+    fn copying_without_data_control_manager_is_not_an_error() {
+        let (socket, _compositor) = UnixStream::pair().unwrap();
+        let connection = Connection::from_socket(socket).unwrap();
+        let mut clipboard = Clipboard::unbound(&connection);
+
+        assert!(clipboard.copy("123456".to_string()).is_err());
+        copy_codes(&mut clipboard, vec!["123456".to_string()]);
+    }
+}
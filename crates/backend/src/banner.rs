@@ -1,145 +1,565 @@
-use std::{path::PathBuf, time};
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
 
 use config::{
-    display::{Border, DisplayConfig},
+    display::{Animation, BuiltInLayout},
+    general::TimeoutClock,
     Config,
 };
 use dbus::notification::Notification;
-use log::{debug, trace};
+use log::{debug, trace, warn};
 
 use render::{
-    color::{Bgra, Color},
-    drawer::Drawer,
+    banner::{self, CachedEmblem, Emblem, ShowMore},
+    error::RenderError,
     font::FontCollection,
+    image::ScaledImage,
     types::RectSize,
-    widget::{
-        self, Alignment, Draw, FlexContainerBuilder, Position, WImage, WText, WTextKind, Widget,
-        WidgetConfiguration,
-    },
+    widget::{current_age_label, Widget, WidgetConfiguration},
+};
+use shared::{cached_data::CachedData, log_target::WINDOW};
+
+use crate::{
+    animation::{self, AnimationFrame},
+    cache::CachedLayout,
+    clock::Timestamp,
 };
-use shared::cached_data::CachedData;
 
-use crate::cache::CachedLayout;
+/// The sequence number of the next created banner.
+static NEXT_SEQUENCE: AtomicU64 = AtomicU64::new(0);
 
 pub struct BannerRect {
     data: Notification,
-    created_at: time::Instant,
+    /// The monotonic number of banner by creation. It orders the banners which the sorting
+    /// doesn't tell apart, so their order doesn't change between redraws.
+    sequence: u64,
+    created_at: Timestamp,
+    /// The time when the banner is revealed. It's hidden until then.
+    revealed_at: Instant,
+    /// The time from the start of timeout until the banner is revealed. It isn't counted to the
+    /// timeout, so the staggered banners are shown for their whole timeouts.
+    reveal_delay: Duration,
+    /// The animation which is played since the banner is revealed. It's chosen by the urgency
+    /// when the banner is drawn.
+    animation: Animation,
+    animation_finished: bool,
 
+    drawn: DrawnBanner,
+    highlighted: bool,
+    /// Whether the banner is expanded to the whole body by click on the "Show more" label.
+    expanded: bool,
+    /// The time of the last full draw. The animations of least recently drawn banners are
+    /// dropped first when the memory budget is exceeded.
+    drawn_at: Instant,
+    /// Whether the memory budget is exceeded, so the new images are downscaled more.
+    memory_pressure: bool,
+    /// Whether the banner is being drawn. It stays set when the draw panics, so the banner
+    /// isn't shown again after the restart of render path.
+    drawing: bool,
+}
+
+/// The results of draw which the banner keeps to show them and to redraw it without compiling
+/// the layout again.
+#[derive(Default)]
+struct DrawnBanner {
     framebuffer: Vec<u8>,
+    /// The images which are placed over the framebuffer and scaled by the compositor.
+    scaled_images: Vec<ScaledImage>,
+    /// The compiled layout which contains animated images or progress bars. It keeps to redraw the next frames
+    /// without recompilation. The emblem is drawn over the layout.
+    animated_layout: Option<(Widget, Option<Emblem>, RectSize)>,
+    /// The emblem icon which is kept between draws to not look it up again.
+    emblem: CachedEmblem,
+    /// The relative time since the notification arrived which is drawn when the timestamp is
+    /// shown.
+    age_label: Option<String>,
+    /// The width which the content takes at `max_width`. It's measured only when the banners fit
+    /// their content and kept until the content changes, so the hover redraws don't compile the
+    /// layout again.
+    content_width: Option<usize>,
+    /// The height of banner which fits its content, i.e. the expanded banner takes the whole body
+    /// and the compact one is reduced to the summary line. Other banners have the height from
+    /// config.
+    fitted_height: Option<usize>,
+    show_more: Option<ShowMore>,
+    /// The moment when the animated layout shows the next frame, so the banner is redrawn only
+    /// when its frame changes.
+    next_frame_at: Option<Instant>,
+}
+
+impl DrawnBanner {
+    /// Drops the frame of failed draw, so the banner is left empty. The measured sizes are kept
+    /// for the next draw.
+    fn clear_frame(&mut self) {
+        self.framebuffer.clear();
+        self.scaled_images.clear();
+        self.animated_layout = None;
+        self.next_frame_at = None;
+        self.show_more = None;
+    }
+}
+
+/// The surroundings which the banners are drawn in.
+pub(crate) struct DrawContext<'a> {
+    pub(crate) font_collection: &'a FontCollection,
+    pub(crate) config: &'a Config,
+    pub(crate) cached_layouts: &'a CachedData<PathBuf, CachedLayout>,
+    /// The width of banner and the height which the expanded banner can take at most.
+    pub(crate) max_size: RectSize,
+    /// Whether the window can place the images which are scaled by the compositor, so the
+    /// vector images are rasterized by the `output_scale`.
+    pub(crate) viewport_scaling: bool,
+    pub(crate) output_scale: u32,
 }
 
 impl BannerRect {
-    pub(crate) fn init(notification: Notification) -> Self {
-        debug!("Banner (id={}): Created", notification.id);
+    pub(crate) fn init(notification: Notification, clock: TimeoutClock) -> Self {
+        debug!(target: WINDOW, "Banner (id={}): Created", notification.id);
 
         Self {
             data: notification,
-            created_at: time::Instant::now(),
+            sequence: NEXT_SEQUENCE.fetch_add(1, Ordering::Relaxed),
+            created_at: Timestamp::now(clock),
+            revealed_at: Instant::now(),
+            reveal_delay: Duration::ZERO,
+            animation: Animation::None,
+            animation_finished: false,
 
-            framebuffer: vec![],
+            drawn: DrawnBanner::default(),
+            highlighted: false,
+            expanded: false,
+            drawn_at: Instant::now(),
+            memory_pressure: false,
+            drawing: false,
         }
     }
 
+    /// Returns the position of banner which likely made the render path panic: the one which was
+    /// being drawn, otherwise the newest one.
+    pub(crate) fn find_panicked(banners: &[BannerRect]) -> Option<usize> {
+        banners
+            .iter()
+            .position(|banner| banner.drawing)
+            .or_else(|| {
+                banners
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(_, banner)| banner.sequence)
+                    .map(|(position, _)| position)
+            })
+    }
+
     pub(crate) fn notification(&self) -> &Notification {
         &self.data
     }
 
+    pub(crate) fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
     pub(crate) fn destroy_and_get_notification(self) -> Notification {
-        debug!("Banner (id={}): Destroyed", self.data.id);
+        debug!(target: WINDOW, "Banner (id={}): Destroyed", self.data.id);
         self.data
     }
 
-    pub(crate) fn created_at(&self) -> &time::Instant {
-        &self.created_at
+    /// Returns how long the banner is shown since it's revealed or its timeout is reset.
+    pub(crate) fn shown_for(&self) -> Duration {
+        self.created_at.elapsed().saturating_sub(self.reveal_delay)
     }
 
-    pub(crate) fn reset_timeout(&mut self) {
-        self.created_at = time::Instant::now();
+    pub(crate) fn reset_timeout(&mut self, clock: TimeoutClock) {
+        self.created_at = Timestamp::now(clock);
+        self.reveal_delay = self.revealed_at.saturating_duration_since(Instant::now());
 
-        trace!("Banner (id={}): Timeout reset", self.data.id);
+        trace!(target: WINDOW, "Banner (id={}): Timeout reset", self.data.id);
     }
 
-    pub(crate) fn update_data(&mut self, notification: Notification) {
+    pub(crate) fn delay_timeout(&mut self, duration: Duration) {
+        self.created_at.delay(duration);
+        trace!(target: WINDOW, "Banner (id={}): Timeout delayed", self.data.id);
+    }
+
+    /// Replaces the notification and resets the timeout. The interactive state of the previous
+    /// notification is reset too, so the new content starts collapsed. The highlight is kept
+    /// because it follows the pointer which is still over the banner.
+    pub(crate) fn update_data(&mut self, notification: Notification, clock: TimeoutClock) {
         self.data = notification;
-        self.created_at = time::Instant::now();
+        self.created_at = Timestamp::now(clock);
+        self.reveal_delay = self.revealed_at.saturating_duration_since(Instant::now());
+        self.expanded = false;
+        self.drawn.fitted_height = None;
+        self.drawn.show_more = None;
+        self.drawn.content_width = None;
         debug!(
+            target: WINDOW,
             "Banner (id={}): Updated notification data and timeout",
             self.data.id
         );
     }
 
+    /// Sets the path to the image without resetting the timeout, e.g. when the remote image is
+    /// fetched.
+    pub(crate) fn set_image_path(&mut self, image_path: String) {
+        self.data.hints.image_path = Some(image_path);
+        self.drawn.content_width = None;
+        debug!(target: WINDOW, "Banner (id={}): Updated image path", self.data.id);
+    }
+
+    pub(crate) fn is_highlighted(&self) -> bool {
+        self.highlighted
+    }
+
+    /// Sets whether the banner is highlighted. It takes effect on the next draw.
+    pub(crate) fn set_highlighted(&mut self, highlighted: bool) {
+        self.highlighted = highlighted;
+        trace!(
+            target: WINDOW,
+            "Banner (id={}): Set highlighted to {highlighted}",
+            self.data.id
+        );
+    }
+
+    /// Returns the relative time since the notification arrived when the banner shows it.
+    fn current_age_label(&self, config: &Config) -> Option<String> {
+        config
+            .display_by_notification(&self.data)
+            .show_timestamp
+            .then(|| current_age_label(self.data.created_at))
+    }
+
+    /// Returns true when the drawn relative time since the notification arrived is outdated.
+    pub(crate) fn is_age_outdated(&self, config: &Config) -> bool {
+        self.drawn.age_label != self.current_age_label(config)
+    }
+
+    pub(crate) fn reveal_at(&mut self, revealed_at: Instant) {
+        self.revealed_at = revealed_at;
+        self.reveal_delay = revealed_at.saturating_duration_since(Instant::now());
+    }
+
+    pub(crate) fn is_revealed(&self) -> bool {
+        Instant::now() >= self.revealed_at
+    }
+
+    /// Returns the current frame of animation or `None` when the banner is still.
+    pub(crate) fn animation_frame(&self) -> Option<AnimationFrame> {
+        if self.animation_finished || !self.is_revealed() {
+            return None;
+        }
+
+        animation::frame(self.animation, self.revealed_at.elapsed())
+    }
+
+    /// Returns true until the still banner is drawn after the animation.
+    pub(crate) fn is_playing_animation(&self) -> bool {
+        self.animation != Animation::None && !self.animation_finished
+    }
+
+    /// Marks the animation as finished when it's over. It should be called after the draw, so the
+    /// last drawn frame is the still banner.
+    pub(crate) fn update_animation(&mut self) {
+        if self.is_revealed() && self.animation_frame().is_none() {
+            self.animation_finished = true;
+        }
+    }
+
+    pub(crate) fn content_width(&self) -> Option<usize> {
+        self.drawn.content_width
+    }
+
+    /// Drops the measured content width, e.g. when the config or fonts are changed, so it's
+    /// measured again by the next draw.
+    pub(crate) fn reset_content_width(&mut self) {
+        self.drawn.content_width = None;
+    }
+
+    pub(crate) fn height(&self, config: &Config) -> usize {
+        self.drawn
+            .fitted_height
+            .unwrap_or(config.general().height as usize)
+    }
+
+    /// Checks whether the point of banner is over the "Show more" label.
+    pub(crate) fn is_over_show_more(&self, x: usize, y: usize) -> bool {
+        self.drawn
+            .show_more
+            .as_ref()
+            .is_some_and(|show_more| show_more.contains(x, y))
+    }
+
+    pub(crate) fn is_expanded(&self) -> bool {
+        self.expanded
+    }
+
+    /// Expands the banner to the whole body. It takes effect on the next draw.
+    pub(crate) fn expand(&mut self) {
+        self.expanded = true;
+        self.drawn.content_width = None;
+        debug!(target: WINDOW, "Banner (id={}): Expanded", self.data.id);
+    }
+
     #[inline]
     pub(crate) fn framebuffer(&self) -> &[u8] {
-        &self.framebuffer
+        &self.drawn.framebuffer
     }
 
-    pub(crate) fn draw(
-        &mut self,
-        font_collection: &FontCollection,
-        config: &Config,
-        cached_layouts: &CachedData<PathBuf, CachedLayout>,
-    ) {
-        debug!("Banner (id={}): Beginning of draw", self.data.id);
+    pub(crate) fn scaled_images(&self) -> &[ScaledImage] {
+        &self.drawn.scaled_images
+    }
 
-        let rect_size = RectSize::new(
-            config.general().width as usize,
-            config.general().height as usize,
-        );
+    /// Draws the banner in the width of context. The default layout is used when the custom
+    /// layout fails and `layout_fallback` is enabled, and the emblem is skipped when its icon
+    /// cannot be decoded. Other errors are returned and the banner is left empty.
+    ///
+    /// The expanded banner is as high as its content within the maximum height of context.
+    pub(crate) fn draw(&mut self, context: &DrawContext) -> Result<(), RenderError> {
+        debug!(target: WINDOW, "Banner (id={}): Beginning of draw", self.data.id);
 
-        let display = config.display_by_app(&self.data.app_name);
-        let mut drawer = Drawer::new(Color::Fill(Bgra::new()), rect_size.clone());
+        self.drawing = true;
+        let result = self.try_draw(context);
+        self.drawing = false;
+        if result.is_err() {
+            self.drawn.clear_frame();
+        }
 
-        let mut layout = match &display.layout {
-            config::display::Layout::Default => Self::default_layout(display),
+        result
+    }
+
+    fn try_draw(&mut self, context: &DrawContext) -> Result<(), RenderError> {
+        let DrawContext {
+            font_collection,
+            config,
+            cached_layouts,
+            viewport_scaling,
+            output_scale,
+            ..
+        } = *context;
+        let RectSize {
+            width,
+            height: max_height,
+        } = context.max_size;
+        let display = config.display_by_notification(&self.data);
+        let expanded = self.expanded && display.expandable_body;
+        let age_label = self.current_age_label(config);
+        self.animation = display.animation.by_urgency(&self.data.hints.urgency);
+        let custom_layout = match &display.layout {
+            config::display::Layout::BuiltIn(_) => None,
             config::display::Layout::FromPath { path_buf } => cached_layouts
                 .get(path_buf)
                 .and_then(CachedLayout::layout)
-                .cloned()
-                .unwrap_or_else(|| Self::default_layout(display)),
+                .cloned(),
+        };
+
+        let configuration = WidgetConfiguration {
+            display_config: display,
+            theme: config.theme_by_notification(&self.data),
+            notification: &self.data,
+            font_collection,
+            override_properties: display.layout.is_built_in(),
+            highlight: config
+                .general()
+                .hover_highlight
+                .as_ref()
+                .filter(|_| self.highlighted),
+            hovered: self.highlighted,
+            viewport_scaling,
+            output_scale,
+            expanded,
+            memory_pressure: self.memory_pressure,
+            age_label: age_label.as_deref(),
+        };
+        let compile_layout = |rect_size: RectSize| match banner::compile_layout(
+            custom_layout.clone(),
+            rect_size.clone(),
+            &configuration,
+        ) {
+            Err(err @ RenderError::Layout { .. }) if display.layout_fallback => {
+                let first_fallback = match &display.layout {
+                    config::display::Layout::FromPath { path_buf } => cached_layouts
+                        .get(path_buf)
+                        .is_none_or(CachedLayout::report_fallback),
+                    config::display::Layout::BuiltIn(_) => true,
+                };
+                if first_fallback {
+                    warn!(
+                        target: WINDOW,
+                        "Banner (id={}): {err}, using the default layout until the layout \
+                        changes",
+                        self.data.id
+                    );
+                } else {
+                    debug!(
+                        target: WINDOW,
+                        "Banner (id={}): {err}, using the default layout",
+                        self.data.id
+                    );
+                }
+                banner::compile_built_in_layout(BuiltInLayout::default(), rect_size, &configuration)
+            }
+            result => result,
+        };
+
+        let height = config.general().height as usize;
+        let height = match expanded {
+            // INFO: the layout is compiled in the whole available height to measure the body
+            true => compile_layout(RectSize::new(width, max_height.max(height)))?
+                .content_height()
+                .clamp(height, max_height.max(height)),
+            // INFO: the compact banner shrinks to its single line but never exceeds the height
+            // from config
+            false if display.compact => compile_layout(RectSize::new(width, height))?
+                .content_height()
+                .clamp(1, height.max(1)),
+            false => height,
         };
+        let rect_size = RectSize::new(width, height);
+
+        // INFO: the age label is the only content which changes by itself
+        let content_changed =
+            self.drawn.content_width.is_none() || age_label != self.drawn.age_label;
+        self.drawn.content_width = match config.general().max_width {
+            Some(max_width) if content_changed => {
+                Some(compile_layout(RectSize::new(max_width as usize, height))?.content_width())
+            }
+            Some(_) => self.drawn.content_width,
+            None => None,
+        };
+
+        let layout = compile_layout(rect_size.clone())?;
+        let emblem =
+            match banner::compile_emblem(&rect_size, &configuration, &mut self.drawn.emblem) {
+                Ok(emblem) => emblem,
+                Err(err @ RenderError::ImageDecode { .. }) => {
+                    warn!(
+                        target: WINDOW,
+                        "Banner (id={}): {err}, drawing without emblem",
+                        self.data.id
+                    );
+                    None
+                }
+                Err(err) => return Err(err),
+            };
+
+        self.drawn.show_more = (display.expandable_body && !expanded && layout.is_body_truncated())
+            .then(|| banner::compile_show_more(&rect_size, &configuration))
+            .flatten();
+
+        let frame = banner::render_layout(
+            &layout,
+            emblem.as_ref(),
+            self.drawn.show_more.as_ref(),
+            rect_size.clone(),
+        )?;
+        self.drawn.framebuffer = frame.data;
+        self.drawn.fitted_height = (expanded || display.compact).then_some(height);
+        self.drawn.scaled_images = frame.scaled_images;
+        self.drawn.age_label = age_label;
+        self.drawn.animated_layout = layout.is_animated().then_some((layout, emblem, rect_size));
+        self.schedule_next_frame();
+        self.drawn_at = Instant::now();
+
+        debug!(target: WINDOW, "Banner (id={}): Complete draw", self.data.id);
+        Ok(())
+    }
 
-        layout.compile(
-            rect_size,
-            &WidgetConfiguration {
-                display_config: display,
-                theme: config.theme_by_app(&self.data.app_name),
-                notification: &self.data,
-                font_collection,
-                override_properties: display.layout.is_default(),
-            },
+    /// Checks whether the banner fully covers its rectangle without any transparent pixels, so the
+    /// compositor can skip blending the content behind it.
+    pub(crate) fn is_opaque(&self, config: &Config) -> bool {
+        let display = config.display_by_notification(&self.data);
+        let colors = config
+            .theme_by_notification(&self.data)
+            .colors(&self.data.hints.urgency, self.highlighted);
+        let border = colors.border.resolve(&display.border);
+
+        border.radius == 0
+            && colors.background.is_opaque()
+            && (border.size == 0 || colors.border.color.is_opaque())
+    }
+
+    /// Sets whether the memory budget is exceeded. It takes effect on the next draw.
+    pub(crate) fn set_memory_pressure(&mut self, memory_pressure: bool) {
+        self.memory_pressure = memory_pressure;
+    }
+
+    pub(crate) fn drawn_at(&self) -> Instant {
+        self.drawn_at
+    }
+
+    /// Returns the approximate number of bytes which the drawn banner takes with its images.
+    pub(crate) fn approximate_bytes(&self) -> usize {
+        self.drawn.framebuffer.len()
+            + self
+                .drawn
+                .scaled_images
+                .iter()
+                .map(|scaled_image| scaled_image.data.len())
+                .sum::<usize>()
+            + self.animation_bytes()
+    }
+
+    /// Returns the approximate number of bytes which the decoded images of animated layout take.
+    pub(crate) fn animation_bytes(&self) -> usize {
+        self.drawn
+            .animated_layout
+            .as_ref()
+            .map_or(0, |(layout, _, _)| layout.approximate_bytes())
+    }
+
+    /// Drops the animated layout to free its decoded images. The banner keeps the current frame
+    /// until the next draw.
+    pub(crate) fn evict_animation(&mut self) {
+        self.drawn.animated_layout = None;
+        self.drawn.next_frame_at = None;
+        debug!(
+            target: WINDOW,
+            "Banner (id={}): Evicted the animation due the memory budget",
+            self.data.id
         );
+    }
 
-        layout.draw(&mut drawer);
-        self.framebuffer = drawer.into();
-
-        debug!("Banner (id={}): Complete draw", self.data.id);
-    }
-
-    fn default_layout(display_config: &DisplayConfig) -> Widget {
-        FlexContainerBuilder::default()
-            .spacing(display_config.padding.clone())
-            .border(display_config.border.clone())
-            .direction(widget::Direction::Horizontal)
-            .alignment(Alignment::new(Position::Start, Position::Center))
-            .children(vec![
-                WImage::new().into(),
-                FlexContainerBuilder::default()
-                    .spacing(Default::default())
-                    .border(Border::default())
-                    .direction(widget::Direction::Vertical)
-                    .alignment(Alignment::new(Position::Center, Position::Center))
-                    .transparent_background(true)
-                    .children(vec![
-                        WText::new(WTextKind::Title).into(),
-                        WText::new(WTextKind::Body).into(),
-                    ])
-                    .build()
-                    .unwrap()
-                    .into(),
-            ])
-            .build()
-            .unwrap()
-            .into()
+    /// Redraws the banner with the current frames of animated images.
+    pub(crate) fn draw_animation_frame(&mut self) {
+        self.drawing = true;
+        // INFO: the animated layout is kept only after the successful draw, so it has the area to
+        // draw into
+        if let Some(Ok(frame)) =
+            self.drawn
+                .animated_layout
+                .as_ref()
+                .map(|(layout, emblem, rect_size)| {
+                    banner::render_layout(
+                        layout,
+                        emblem.as_ref(),
+                        self.drawn.show_more.as_ref(),
+                        rect_size.clone(),
+                    )
+                })
+        {
+            self.drawn.framebuffer = frame.data;
+            self.drawn.scaled_images = frame.scaled_images;
+            trace!(target: WINDOW, "Banner (id={}): Drew next animation frame", self.data.id);
+        }
+        self.schedule_next_frame();
+        self.drawing = false;
+    }
+
+    /// Checks whether the animated layout shows another frame since the last draw.
+    pub(crate) fn is_animation_frame_due(&self) -> bool {
+        self.drawn
+            .next_frame_at
+            .is_some_and(|next_frame_at| next_frame_at <= Instant::now())
+    }
+
+    fn schedule_next_frame(&mut self) {
+        self.drawn.next_frame_at = self
+            .drawn
+            .animated_layout
+            .as_ref()
+            .and_then(|(layout, _, _)| layout.next_frame_in())
+            .map(|next_frame_in| Instant::now() + next_frame_in);
     }
 }
 
@@ -148,3 +568,75 @@ impl<'a> From<&'a BannerRect> for &'a Notification {
         &value.data
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use config::general::TimeoutClock;
+    use dbus::notification::Notification;
+
+    use super::BannerRect;
+
+    fn notification(body: &str) -> Notification {
+        Notification::test_default().with_body(body)
+    }
+
+    #[test]
+    fn replace_resets_expanded_banner() {
+        let mut rect = BannerRect::init(notification("Old body"), TimeoutClock::default());
+        rect.set_highlighted(true);
+        rect.expand();
+        rect.drawn.fitted_height = Some(400);
+        rect.drawn.content_width = Some(250);
+
+        rect.update_data(notification("New body"), TimeoutClock::default());
+
+        assert!(!rect.expanded);
+        assert!(rect.drawn.fitted_height.is_none());
+        assert!(rect.drawn.show_more.is_none());
+        assert!(rect.content_width().is_none());
+        assert_eq!(rect.notification().body.body, "New body");
+        // The pointer is still over the banner
+        assert!(rect.is_highlighted());
+    }
+
+    #[test]
+    fn content_width_is_kept_until_content_changes() {
+        let mut rect = BannerRect::init(notification("Body"), TimeoutClock::default());
+        rect.drawn.content_width = Some(250);
+
+        rect.set_highlighted(true);
+        assert_eq!(rect.content_width(), Some(250));
+
+        rect.set_image_path("/tmp/image.png".to_string());
+        assert!(rect.content_width().is_none());
+
+        rect.drawn.content_width = Some(250);
+        rect.expand();
+        assert!(rect.content_width().is_none());
+    }
+
+    #[test]
+    fn timeout_starts_at_reveal() {
+        let mut rect = BannerRect::init(notification("Body"), TimeoutClock::default());
+        rect.reveal_at(Instant::now() + Duration::from_secs(60));
+
+        assert!(!rect.is_revealed());
+        assert_eq!(rect.shown_for(), Duration::ZERO);
+    }
+
+    #[test]
+    fn panicked_banner_is_found() {
+        let mut banners: Vec<BannerRect> = ["First", "Second", "Third"]
+            .into_iter()
+            .map(|body| BannerRect::init(notification(body), TimeoutClock::default()))
+            .collect();
+        assert_eq!(BannerRect::find_panicked(&banners), Some(2));
+
+        banners[0].drawing = true;
+        assert_eq!(BannerRect::find_panicked(&banners), Some(0));
+
+        assert_eq!(BannerRect::find_panicked(&[]), None);
+    }
+}
@@ -1,15 +1,22 @@
-use std::path::{Path, PathBuf};
+use std::{
+    cell::Cell,
+    path::{Path, PathBuf},
+};
 
 use log::warn;
 use render::widget::Widget;
 use shared::{
-    cached_data::{CacheUpdate, CachedValueError},
+    cached_data::{ApproximateBytes, CacheUpdate, CachedValueError},
     file_watcher::{FileState, FilesWatcher},
+    log_target::LAYOUT,
 };
 
 pub(super) struct CachedLayout {
     watcher: FilesWatcher,
     layout: Option<Widget>,
+    /// Whether the fallback to the default layout is already reported. It's reset when the
+    /// layout file changes.
+    fallback_reported: Cell<bool>,
 }
 
 impl CachedLayout {
@@ -17,11 +24,17 @@ impl CachedLayout {
         self.layout.as_ref()
     }
 
+    /// Marks the fallback of layout as reported. Returns true only for the first fallback since
+    /// the layout is loaded, so the same error isn't logged for every banner.
+    pub(super) fn report_fallback(&self) -> bool {
+        !self.fallback_reported.replace(true)
+    }
+
     fn load_layout(path: &Path) -> Option<Widget> {
         match filetype::parse_layout(path) {
             Ok(widget) => Some(widget),
             Err(err) => {
-                warn!("The layout by path {path:?} is not valid. Error: {err}");
+                warn!(target: LAYOUT, "The layout by path {path:?} is not valid. Error: {err}");
                 None
             }
         }
@@ -34,7 +47,14 @@ impl CacheUpdate for CachedLayout {
     }
 
     fn update(&mut self) {
-        self.layout = self.watcher.get_watching_path().and_then(Self::load_layout)
+        self.layout = self.watcher.get_watching_path().and_then(Self::load_layout);
+        self.fallback_reported.set(false);
+    }
+}
+
+impl ApproximateBytes for CachedLayout {
+    fn approximate_bytes(&self) -> usize {
+        self.layout.as_ref().map_or(0, Widget::approximate_bytes)
     }
 }
 
@@ -53,6 +73,10 @@ impl<'a> TryFrom<&'a PathBuf> for CachedLayout {
             .get_watching_path()
             .and_then(CachedLayout::load_layout);
 
-        Ok(CachedLayout { watcher, layout })
+        Ok(CachedLayout {
+            watcher,
+            layout,
+            fallback_reported: Cell::new(false),
+        })
     }
 }
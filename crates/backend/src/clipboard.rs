@@ -0,0 +1,58 @@
+use crate::{data_control::DataControl, dispatcher::Dispatcher};
+use log::debug;
+use shared::log_target::CLIPBOARD;
+use wayland_client::{Connection, EventQueue};
+
+pub struct Clipboard {
+    event_queue: EventQueue<DataControl>,
+    data_control: DataControl,
+}
+
+impl Dispatcher for Clipboard {
+    type State = DataControl;
+
+    fn get_event_queue_and_state(
+        &mut self,
+    ) -> Option<(&mut EventQueue<Self::State>, &mut Self::State)> {
+        Some((&mut self.event_queue, &mut self.data_control))
+    }
+}
+
+impl Clipboard {
+    pub(crate) fn init(connection: &Connection) -> anyhow::Result<Self> {
+        let mut event_queue = connection.new_event_queue();
+        connection.display().get_registry(&event_queue.handle(), ());
+
+        let mut data_control = DataControl::init();
+        event_queue.roundtrip(&mut data_control)?;
+
+        let clipboard = Self {
+            event_queue,
+            data_control,
+        };
+        debug!(target: CLIPBOARD, "Clipboard: Initialized");
+
+        Ok(clipboard)
+    }
+
+    pub(crate) fn copy(&mut self, text: String) -> anyhow::Result<()> {
+        self.data_control
+            .set_selection(text, &self.event_queue.handle())?;
+        self.event_queue.flush()?;
+        debug!(target: CLIPBOARD, "Clipboard: Copied the text");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl Clipboard {
+    /// Returns the clipboard which hasn't received the globals of compositor, as if it doesn't
+    /// support the `wlr-data-control` protocol.
+    pub(crate) fn unbound(connection: &Connection) -> Self {
+        Self {
+            event_queue: connection.new_event_queue(),
+            data_control: DataControl::init(),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use config::general::TimeoutClock;
+use log::warn;
+use shared::log_target::WINDOW;
+
+/// The source of time points which are used to measure the banner timeouts.
+pub(crate) trait TimeSource: Sized {
+    fn now() -> Self;
+
+    fn elapsed(&self) -> Duration;
+}
+
+impl TimeSource for Instant {
+    fn now() -> Self {
+        Instant::now()
+    }
+
+    fn elapsed(&self) -> Duration {
+        Instant::elapsed(self)
+    }
+}
+
+/// The time point of `CLOCK_BOOTTIME` clock which keeps advancing while the system is suspended.
+#[derive(Clone, Copy)]
+pub(crate) struct BoottimeInstant(Duration);
+
+impl BoottimeInstant {
+    /// Returns the current time or `None` when the clock is unavailable, e.g. the call is
+    /// blocked by the seccomp filter of sandbox.
+    fn current_time() -> Option<Duration> {
+        let mut timespec = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+
+        // SAFETY: the timespec is valid pointer
+        let result = unsafe { libc::clock_gettime(libc::CLOCK_BOOTTIME, &mut timespec) };
+        (result == 0).then(|| Duration::new(timespec.tv_sec as u64, timespec.tv_nsec as u32))
+    }
+
+    /// Checks the clock once and reports when it's unavailable.
+    fn is_available() -> bool {
+        static AVAILABLE: OnceLock<bool> = OnceLock::new();
+
+        *AVAILABLE.get_or_init(|| {
+            let available = Self::current_time().is_some();
+            if !available {
+                warn!(
+                    target: WINDOW,
+                    "Clock: The CLOCK_BOOTTIME clock is unavailable, the timeouts of banners are \
+                    measured by the monotonic clock"
+                );
+            }
+            available
+        })
+    }
+}
+
+impl TimeSource for BoottimeInstant {
+    fn now() -> Self {
+        Self(Self::current_time().unwrap_or_default())
+    }
+
+    fn elapsed(&self) -> Duration {
+        Self::current_time().map_or(Duration::ZERO, |time| time.saturating_sub(self.0))
+    }
+}
+
+/// The time point which remembers the clock it was taken by, so the elapsed time is always
+/// measured by the same clock even when the configured clock is changed.
+#[derive(Clone, Copy)]
+pub(crate) enum Timestamp {
+    Monotonic(Instant),
+    Boottime(BoottimeInstant),
+}
+
+impl Timestamp {
+    pub(crate) fn now(clock: TimeoutClock) -> Self {
+        match clock {
+            TimeoutClock::Monotonic => Timestamp::Monotonic(TimeSource::now()),
+            TimeoutClock::Boottime if BoottimeInstant::is_available() => {
+                Timestamp::Boottime(TimeSource::now())
+            }
+            TimeoutClock::Boottime => Timestamp::Monotonic(TimeSource::now()),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        match self {
+            Timestamp::Monotonic(instant) => TimeSource::elapsed(instant),
+            Timestamp::Boottime(instant) => instant.elapsed(),
+        }
+    }
+
+    /// Moves the time point forward by the duration, e.g. to not count the time while the
+    /// timeouts are paused. It's never moved past the current time.
+    pub(crate) fn delay(&mut self, duration: Duration) {
+        let duration = duration.min(self.elapsed());
+        match self {
+            Timestamp::Monotonic(instant) => *instant += duration,
+            Timestamp::Boottime(instant) => instant.0 += duration,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::Timestamp;
+
+    #[test]
+    fn delay_is_capped_by_elapsed_time() {
+        let now = Instant::now();
+        let mut timestamp = Timestamp::Monotonic(now - Duration::from_secs(10));
+
+        timestamp.delay(Duration::from_secs(4));
+        let Timestamp::Monotonic(instant) = timestamp else {
+            unreachable!()
+        };
+        assert_eq!(now.duration_since(instant), Duration::from_secs(6));
+
+        timestamp.delay(Duration::from_secs(60));
+        assert!(timestamp.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,191 @@
+use std::{fs::File, io::Write};
+
+use anyhow::bail;
+
+use log::{debug, warn};
+use shared::log_target::CLIPBOARD;
+use wayland_client::{
+    delegate_noop, event_created_child,
+    protocol::{wl_registry, wl_seat::WlSeat},
+    Dispatch, QueueHandle,
+};
+use wayland_protocols_wlr::data_control::v1::client::{
+    zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+    zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+    zwlr_data_control_offer_v1::ZwlrDataControlOfferV1,
+    zwlr_data_control_source_v1::{self, ZwlrDataControlSourceV1},
+};
+
+/// Owns the clipboard selection by the `wlr-data-control` protocol, so the text is copied
+/// without focusing any window.
+pub struct DataControl {
+    manager: Option<ZwlrDataControlManagerV1>,
+    seat: Option<WlSeat>,
+    device: Option<ZwlrDataControlDeviceV1>,
+
+    source: Option<ZwlrDataControlSourceV1>,
+    text: String,
+}
+
+impl DataControl {
+    const MIME_TYPES: [&'static str; 5] = [
+        "text/plain;charset=utf-8",
+        "text/plain",
+        "UTF8_STRING",
+        "STRING",
+        "TEXT",
+    ];
+
+    pub(crate) fn init() -> Self {
+        debug!(target: CLIPBOARD, "Data Control: Initialized");
+        Self {
+            manager: None,
+            seat: None,
+            device: None,
+
+            source: None,
+            text: String::new(),
+        }
+    }
+
+    /// Offers the text as the new clipboard selection. The previous selection of daemon is
+    /// replaced.
+    pub(crate) fn set_selection(
+        &mut self,
+        text: String,
+        qhandle: &QueueHandle<Self>,
+    ) -> anyhow::Result<()> {
+        let (Some(manager), Some(device)) = (self.manager.as_ref(), self.device.as_ref()) else {
+            bail!(
+                "The compositor doesn't support zwlr_data_control_manager_v1, can't copy the text"
+            );
+        };
+
+        let source = manager.create_data_source(qhandle, ());
+        Self::MIME_TYPES
+            .into_iter()
+            .for_each(|mime_type| source.offer(mime_type.to_string()));
+        device.set_selection(Some(&source));
+
+        if let Some(previous_source) = self.source.replace(source) {
+            previous_source.destroy();
+        }
+        self.text = text;
+        debug!(target: CLIPBOARD, "Data Control: Set the selection");
+
+        Ok(())
+    }
+
+    fn init_device(&mut self, qhandle: &QueueHandle<Self>) {
+        if self.device.is_some() {
+            return;
+        }
+
+        if let (Some(manager), Some(seat)) = (self.manager.as_ref(), self.seat.as_ref()) {
+            self.device = Some(manager.get_data_device(seat, qhandle, ()));
+            debug!(target: CLIPBOARD, "Data Control: Created the data device");
+        }
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for DataControl {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: <wl_registry::WlRegistry as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &wayland_client::Connection,
+        qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        if let wl_registry::Event::Global {
+            name,
+            interface,
+            version,
+        } = event
+        {
+            match interface.as_ref() {
+                "wl_seat" if state.seat.is_none() => {
+                    state.seat = Some(registry.bind::<WlSeat, _, _>(name, version, qhandle, ()));
+                }
+                "zwlr_data_control_manager_v1" => {
+                    state.manager = Some(registry.bind::<ZwlrDataControlManagerV1, _, _>(
+                        name,
+                        version.min(2),
+                        qhandle,
+                        (),
+                    ));
+                    debug!(target: CLIPBOARD, "Data Control: Bound the data control manager");
+                }
+                _ => return,
+            }
+
+            state.init_device(qhandle);
+        }
+    }
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for DataControl {
+    fn event(
+        state: &mut Self,
+        _device: &ZwlrDataControlDeviceV1,
+        event: <ZwlrDataControlDeviceV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &wayland_client::Connection,
+        _qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        match event {
+            // INFO: the daemon doesn't read the selections of other clients, so the offers are
+            // released right away
+            zwlr_data_control_device_v1::Event::Selection { id: Some(offer) }
+            | zwlr_data_control_device_v1::Event::PrimarySelection { id: Some(offer) } => {
+                offer.destroy();
+            }
+            zwlr_data_control_device_v1::Event::Finished => {
+                if let Some(device) = state.device.take() {
+                    device.destroy();
+                }
+                debug!(target: CLIPBOARD, "Data Control: The data device is no longer valid");
+            }
+            _ => (),
+        }
+    }
+
+    event_created_child!(DataControl, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrDataControlSourceV1, ()> for DataControl {
+    fn event(
+        state: &mut Self,
+        source: &ZwlrDataControlSourceV1,
+        event: <ZwlrDataControlSourceV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &wayland_client::Connection,
+        _qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_data_control_source_v1::Event::Send { mime_type, fd } => {
+                if let Err(err) = File::from(fd).write_all(state.text.as_bytes()) {
+                    warn!(
+                        target: CLIPBOARD,
+                        "Data Control: Failed to send the text as {mime_type}. Error: {err}"
+                    );
+                }
+            }
+            zwlr_data_control_source_v1::Event::Cancelled => {
+                source.destroy();
+                if state.source.as_ref() == Some(source) {
+                    state.source = None;
+                    state.text.clear();
+                }
+                debug!(target: CLIPBOARD, "Data Control: The selection is replaced");
+            }
+            _ => (),
+        }
+    }
+}
+
+delegate_noop!(DataControl: ignore WlSeat);
+delegate_noop!(DataControl: ignore ZwlrDataControlManagerV1);
+delegate_noop!(DataControl: ignore ZwlrDataControlOfferV1);
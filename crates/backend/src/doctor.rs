@@ -0,0 +1,314 @@
+//! Checks the environment which the daemon depends on and reports the problems with the hints
+//! how to fix them.
+
+use std::{fmt::Display, path::PathBuf, process::Command};
+
+use config::Config;
+use dbus::{client::Client, server::SERVER_NAME};
+
+use crate::{protocols::ProtocolInfo, remote_image};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Status::Pass => "pass",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+        })
+    }
+}
+
+/// The result of single check.
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub message: String,
+    /// The hint how to fix the problem. It's set only when the check isn't passed.
+    pub hint: Option<&'static str>,
+}
+
+impl Check {
+    fn pass(name: &'static str, message: String) -> Self {
+        Self {
+            name,
+            status: Status::Pass,
+            message,
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, message: String, hint: &'static str) -> Self {
+        Self {
+            name,
+            status: Status::Warn,
+            message,
+            hint: Some(hint),
+        }
+    }
+
+    fn fail(name: &'static str, message: String, hint: &'static str) -> Self {
+        Self {
+            name,
+            status: Status::Fail,
+            message,
+            hint: Some(hint),
+        }
+    }
+}
+
+/// Runs all checks in order. The checks don't stop on failures, so the whole report is shown.
+pub async fn checks(config: &Config, bus_address: Option<&str>) -> Vec<Check> {
+    let general = config.general();
+    let server_name = match Client::init(bus_address).await {
+        Ok(client) => client.server_name().await,
+        Err(err) => Err(err),
+    };
+
+    vec![
+        layer_shell_check(crate::protocols::protocols()),
+        fc_list_check(fc_list(&["--version"])),
+        font_check(
+            "Font",
+            &general.font.name,
+            Status::Fail,
+            fc_list(&font_args(&general.font.name)),
+        ),
+        font_check(
+            "Emoji font",
+            &general.emoji_font,
+            Status::Warn,
+            fc_list(&font_args(&general.emoji_font)),
+        ),
+        cache_dir_check(remote_image::cache_dir()),
+        curl_check(general.allow_remote_images.then(curl_version)),
+        bus_name_check(server_name),
+    ]
+}
+
+fn layer_shell_check(protocols: anyhow::Result<Vec<ProtocolInfo>>) -> Check {
+    const NAME: &str = "Compositor";
+
+    match protocols {
+        Ok(protocols) => match protocols
+            .iter()
+            .find(|protocol| protocol.name == "zwlr_layer_shell_v1")
+            .and_then(|protocol| protocol.version)
+        {
+            Some(version) => {
+                Check::pass(NAME, format!("zwlr_layer_shell_v1 v{version} is supported"))
+            }
+            None => Check::fail(
+                NAME,
+                "The compositor doesn't support zwlr_layer_shell_v1".to_string(),
+                "Use the compositor which supports the layer shell, e.g. Sway, Hyprland or river",
+            ),
+        },
+        Err(err) => Check::fail(
+            NAME,
+            format!("Failed to connect to the Wayland compositor: {err}"),
+            "Run it inside the Wayland session or check the WAYLAND_DISPLAY environment variable",
+        ),
+    }
+}
+
+fn fc_list(args: &[&str]) -> std::io::Result<String> {
+    Command::new("fc-list")
+        .args(args)
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn font_args(font_name: &str) -> [&str; 3] {
+    [font_name, "--format", "%{file}\n"]
+}
+
+fn fc_list_check(version: std::io::Result<String>) -> Check {
+    const NAME: &str = "Fontconfig";
+
+    match version {
+        Ok(version) => Check::pass(NAME, version.trim().to_string()),
+        Err(err) => Check::fail(
+            NAME,
+            format!("Failed to run fc-list: {err}"),
+            "Install fontconfig, the fonts are looked up by fc-list",
+        ),
+    }
+}
+
+fn font_check(
+    name: &'static str,
+    font_name: &str,
+    missing_status: Status,
+    files: std::io::Result<String>,
+) -> Check {
+    let hint = "Install the font or set the name of installed one in the general section of config";
+
+    match files.map(|files| files.lines().next().map(str::to_string)) {
+        Ok(Some(file)) => Check::pass(name, format!("'{font_name}' is found at {file}")),
+        Ok(None) | Err(_) => Check {
+            name,
+            status: missing_status,
+            message: format!("'{font_name}' is not found"),
+            hint: Some(hint),
+        },
+    }
+}
+
+fn cache_dir_check(cache_dir: Option<PathBuf>) -> Check {
+    const NAME: &str = "Image cache";
+
+    let Some(cache_dir) = cache_dir else {
+        return Check::warn(
+            NAME,
+            "Neither XDG_CACHE_HOME nor HOME is set".to_string(),
+            "Set XDG_CACHE_HOME to cache the remote images",
+        );
+    };
+
+    match std::fs::create_dir_all(&cache_dir).and_then(|_| tempfile::tempfile_in(&cache_dir)) {
+        Ok(_) => Check::pass(NAME, format!("{} is writable", cache_dir.display())),
+        Err(err) => Check::warn(
+            NAME,
+            format!("{} is not writable: {err}", cache_dir.display()),
+            "Fix the permissions of directory, otherwise the remote images are fetched every time",
+        ),
+    }
+}
+
+fn curl_version() -> std::io::Result<String> {
+    Command::new("curl")
+        .arg("--version")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Checks the `curl` which fetches the remote images. The version is `None` when the remote
+/// images are disallowed.
+fn curl_check(version: Option<std::io::Result<String>>) -> Check {
+    const NAME: &str = "Remote images";
+
+    match version {
+        None => Check::pass(NAME, "Remote images are disallowed".to_string()),
+        Some(Ok(version)) => {
+            Check::pass(NAME, version.lines().next().unwrap_or_default().to_string())
+        }
+        Some(Err(err)) => Check::warn(
+            NAME,
+            format!("Failed to run curl: {err}"),
+            "Install curl to fetch the remote images or disable allow_remote_images",
+        ),
+    }
+}
+
+fn bus_name_check(server_name: anyhow::Result<Option<String>>) -> Check {
+    const NAME: &str = "Bus name";
+
+    match server_name {
+        Ok(None) => Check::pass(NAME, "org.freedesktop.Notifications is free".to_string()),
+        Ok(Some(server_name)) if server_name == SERVER_NAME => Check::pass(
+            NAME,
+            format!("org.freedesktop.Notifications is owned by running {SERVER_NAME}"),
+        ),
+        Ok(Some(server_name)) => Check::warn(
+            NAME,
+            format!("org.freedesktop.Notifications is owned by {server_name}"),
+            "Stop the other notification daemon or start with `run --replace`",
+        ),
+        Err(err) => Check::fail(
+            NAME,
+            format!("Failed to connect to D-Bus: {err}"),
+            "Check that the session bus is running and DBUS_SESSION_BUS_ADDRESS is set",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dbus::server::SERVER_NAME;
+
+    use crate::protocols::ProtocolInfo;
+
+    use super::{
+        bus_name_check, cache_dir_check, curl_check, font_check, layer_shell_check, Status,
+    };
+
+    #[test]
+    fn layer_shell_is_required() {
+        let protocols = |version| {
+            Ok(vec![ProtocolInfo {
+                name: "zwlr_layer_shell_v1",
+                version,
+            }])
+        };
+
+        assert_eq!(layer_shell_check(protocols(Some(4))).status, Status::Pass);
+        assert_eq!(layer_shell_check(protocols(None)).status, Status::Fail);
+        assert_eq!(
+            layer_shell_check(Err(anyhow::anyhow!("No compositor"))).status,
+            Status::Fail
+        );
+    }
+
+    #[test]
+    fn missing_fonts_have_given_status() {
+        let found = font_check(
+            "Font",
+            "Noto Sans",
+            Status::Fail,
+            Ok("/a.ttf\n".to_string()),
+        );
+        assert_eq!(found.status, Status::Pass);
+        assert!(found.hint.is_none());
+
+        let missing = font_check("Emoji font", "Emoji", Status::Warn, Ok(String::new()));
+        assert_eq!(missing.status, Status::Warn);
+        assert!(missing.hint.is_some());
+    }
+
+    #[test]
+    fn cache_dir_is_writable() {
+        let dir = tempfile::tempdir().unwrap();
+        let check = cache_dir_check(Some(dir.path().join("noti").join("images")));
+        assert_eq!(check.status, Status::Pass);
+
+        assert_eq!(cache_dir_check(None).status, Status::Warn);
+    }
+
+    #[test]
+    fn curl_is_needed_by_remote_images() {
+        assert_eq!(curl_check(None).status, Status::Pass);
+
+        let installed = curl_check(Some(Ok("curl 8.5.0 (x86_64-pc-linux-gnu)\n".to_string())));
+        assert_eq!(installed.status, Status::Pass);
+        assert_eq!(installed.message, "curl 8.5.0 (x86_64-pc-linux-gnu)");
+
+        let missing = curl_check(Some(Err(std::io::ErrorKind::NotFound.into())));
+        assert_eq!(missing.status, Status::Warn);
+        assert!(missing.hint.is_some());
+    }
+
+    #[test]
+    fn bus_name_owners() {
+        assert_eq!(bus_name_check(Ok(None)).status, Status::Pass);
+        assert_eq!(
+            bus_name_check(Ok(Some(SERVER_NAME.to_string()))).status,
+            Status::Pass
+        );
+        assert_eq!(
+            bus_name_check(Ok(Some("mako".to_string()))).status,
+            Status::Warn
+        );
+        assert_eq!(
+            bus_name_check(Err(anyhow::anyhow!("No bus"))).status,
+            Status::Fail
+        );
+    }
+}
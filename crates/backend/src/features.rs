@@ -0,0 +1,95 @@
+use serde::Serialize;
+
+use crate::protocols::ProtocolInfo;
+
+/// The optional feature which works only when its provider, the external program or the Wayland
+/// protocol, is available.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureInfo {
+    pub name: &'static str,
+    /// The program or protocol which provides the feature or `None` if none of them is available.
+    pub provider: Option<&'static str>,
+}
+
+/// Checks which optional features are available. The protocols are `None` when the compositor
+/// isn't reachable, so the features which need them are unavailable.
+pub fn features(protocols: Option<&[ProtocolInfo]>) -> Vec<FeatureInfo> {
+    let protocol = |name| {
+        protocols
+            .into_iter()
+            .flatten()
+            .find(|protocol| protocol.name == name && protocol.version.is_some())
+            .map(|protocol| protocol.name)
+    };
+
+    vec![
+        FeatureInfo {
+            name: "sound files",
+            provider: find_program(&["pw-play", "paplay"]),
+        },
+        FeatureInfo {
+            name: "sound names",
+            provider: find_program(&["canberra-gtk-play"]),
+        },
+        FeatureInfo {
+            name: "remote images",
+            provider: find_program(&["curl"]),
+        },
+        FeatureInfo {
+            name: "clipboard",
+            provider: protocol("zwlr_data_control_manager_v1"),
+        },
+        FeatureInfo {
+            name: "screen power",
+            provider: protocol("zwlr_output_power_manager_v1"),
+        },
+    ]
+}
+
+/// Returns the first program which is found in the `PATH` directories.
+fn find_program(programs: &[&'static str]) -> Option<&'static str> {
+    let paths = std::env::var_os("PATH")?;
+    programs
+        .iter()
+        .copied()
+        .find(|program| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocols::ProtocolInfo;
+
+    use super::{features, find_program};
+
+    #[test]
+    fn features_by_protocols() {
+        let provider = |protocols: Option<&[ProtocolInfo]>, name| {
+            features(protocols)
+                .into_iter()
+                .find(|feature| feature.name == name)
+                .and_then(|feature| feature.provider)
+        };
+        let protocols = [
+            ProtocolInfo {
+                name: "zwlr_data_control_manager_v1",
+                version: Some(2),
+            },
+            ProtocolInfo {
+                name: "zwlr_output_power_manager_v1",
+                version: None,
+            },
+        ];
+
+        assert_eq!(
+            provider(Some(&protocols), "clipboard"),
+            Some("zwlr_data_control_manager_v1")
+        );
+        assert_eq!(provider(Some(&protocols), "screen power"), None);
+        assert_eq!(provider(None, "clipboard"), None);
+    }
+
+    #[test]
+    fn missing_program_is_not_found() {
+        assert_eq!(find_program(&["noti-missing-program"]), None);
+    }
+}
@@ -4,6 +4,7 @@ use crate::{
 };
 use config::Config;
 use log::debug;
+use shared::log_target::IDLE;
 use wayland_client::{Connection, EventQueue, QueueHandle};
 
 pub struct IdleManager {
@@ -38,7 +39,7 @@ impl IdleManager {
             qhandle,
             idle_notifier,
         };
-        debug!("Idle Manager: Initialized");
+        debug!(target: IDLE, "Idle Manager: Initialized");
 
         Ok(idle_manager)
     }
@@ -1,5 +1,6 @@
 use config::Config;
 use log::debug;
+use shared::log_target::IDLE;
 use wayland_client::{
     delegate_noop,
     protocol::{
@@ -40,7 +41,7 @@ impl IdleNotifier {
             threshold,
             was_idled: false,
         };
-        debug!("Idle Notifier: Initialized");
+        debug!(target: IDLE, "Idle Notifier: Initialized");
         Ok(idle_notifier)
     }
 
@@ -52,7 +53,7 @@ impl IdleNotifier {
                 self.idle_state = None;
                 self.was_idled = false;
                 notification.destroy();
-                debug!("Idle Notifier: Destroyed")
+                debug!(target: IDLE, "Idle Notifier: Destroyed")
             }
 
             if self.wl_seat.is_some() && self.threshold != 0 {
@@ -62,7 +63,7 @@ impl IdleNotifier {
                     qh,
                     (),
                 ));
-                debug!("Idle Notifier: Recreated by new idle_threshold value");
+                debug!(target: IDLE, "Idle Notifier: Recreated by new idle_threshold value");
             }
         }
     }
@@ -87,12 +88,12 @@ impl Dispatch<wl_registry::WlRegistry, ()> for IdleNotifier {
                 "wl_seat" => {
                     state.wl_seat =
                         Some(registry.bind::<wl_seat::WlSeat, _, _>(name, version, qhandle, ()));
-                    debug!("Idle Notifier: Bound the wl_seat");
+                    debug!(target: IDLE, "Idle Notifier: Bound the wl_seat");
                 }
                 "ext_idle_notifier_v1" => {
                     let idle_notifier =
                         registry.bind::<ExtIdleNotifierV1, _, _>(name, version, qhandle, ());
-                    debug!("Idle Notifier: Bound the ext_idle_notifier_v1");
+                    debug!(target: IDLE, "Idle Notifier: Bound the ext_idle_notifier_v1");
 
                     if state.wl_seat.is_some() && state.threshold != 0 {
                         state
@@ -126,11 +127,11 @@ impl Dispatch<ExtIdleNotificationV1, ()> for IdleNotifier {
             ext_idle_notification_v1::Event::Idled => {
                 state.idle_state = Some(IdleState::Idled);
                 state.was_idled = true;
-                debug!("Idle Notifier: Idled");
+                debug!(target: IDLE, "Idle Notifier: Idled");
             }
             ext_idle_notification_v1::Event::Resumed => {
                 state.idle_state = Some(IdleState::Resumed);
-                debug!("Idle Notifier: Resumed");
+                debug!(target: IDLE, "Idle Notifier: Resumed");
             }
             _ => (),
         }
@@ -1,58 +1,183 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    time::Instant,
+};
+
 use config::Config;
-use log::{debug, info, warn};
+use log::{debug, error, info, warn};
 use scheduler::Scheduler;
-use shared::file_watcher::FileState;
-use tokio::sync::mpsc::unbounded_channel;
+use shared::{file_watcher::FileState, log_target::BACKEND};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+};
 
+mod animation;
 mod backend_manager;
 mod banner;
 mod cache;
+mod clipboard;
+mod clock;
+mod data_control;
 mod dispatcher;
+pub mod doctor;
+pub mod features;
 mod idle_manager;
 mod idle_notifier;
+mod memory_budget;
+mod output_power;
+pub mod outputs;
+mod power_manager;
+pub mod protocols;
+mod remote_image;
+pub mod replay;
 mod scheduler;
+mod sound;
+mod watchdog;
 mod window;
 mod window_manager;
 
-use dbus::actions::{Action, ClosingReason, Signal};
+use dbus::actions::{Action, Signal};
 use dbus::server::Server;
+pub use dbus::server::{NameRequest, SPECIFICATION_VERSION};
 
 use backend_manager::BackendManager;
+use watchdog::{panic_message, Watchdog};
+
+pub async fn run(
+    config: Config,
+    name_request: NameRequest,
+    bus_address: Option<&str>,
+) -> anyhow::Result<()> {
+    let (sender, receiver) = unbounded_channel();
+
+    listen_termination(sender.clone())?;
+    let server = Server::init(sender, &name_request, bus_address).await?;
+    apply_server_config(Some(&server), &config);
+    info!(target: BACKEND, "Backend: Server initialized");
+
+    serve(config, receiver, Some(server)).await
+}
+
+/// Runs the backend without D-Bus and shows the notifications of replay file with their delays.
+/// The signals are only logged because there are no clients to receive them.
+pub async fn replay(config: Config, path: &Path) -> anyhow::Result<()> {
+    let entries = replay::load(path)?;
+    let (sender, receiver) = unbounded_channel();
 
-pub async fn run(mut config: Config) -> anyhow::Result<()> {
-    let (sender, mut receiver) = unbounded_channel();
+    listen_termination(sender.clone())?;
+    info!(
+        target: BACKEND,
+        "Backend: Replaying {} notifications from {path:?} without D-Bus",
+        entries.len()
+    );
+    replay::spawn(entries, sender, config.general());
 
-    let server = Server::init(sender).await?;
-    info!("Backend: Server initialized");
+    serve(config, receiver, None).await
+}
+
+/// Handles the actions and draws the notifications until the `Shutdown` action.
+async fn serve(
+    mut config: Config,
+    mut receiver: UnboundedReceiver<Action>,
+    server: Option<Server>,
+) -> anyhow::Result<()> {
     let mut backend_manager = BackendManager::init(&config)?;
-    info!("Backend: Manager initialized");
+    info!(target: BACKEND, "Backend: Manager initialized");
 
     let mut scheduler = Scheduler::new();
-    info!("Backend: Scheduler initialized");
+    info!(target: BACKEND, "Backend: Scheduler initialized");
 
+    let mut watchdog = Watchdog::new();
     let mut partially_default_config = false;
 
     loop {
         while let Ok(action) = receiver.try_recv() {
             match action {
                 Action::Show(notification) => {
-                    backend_manager.create_notification(notification);
+                    backend_manager.create_notification(notification, &config);
                 }
                 Action::Close(Some(id)) => {
                     backend_manager.close_notification(id);
                 }
                 Action::Schedule(notification) => {
                     debug!(
+                        target: BACKEND,
                         "Backend: Scheduled notification with id {} for time {}",
                         &notification.id, &notification.time
                     );
                     scheduler.add(notification);
                 }
                 Action::Close(None) => {
-                    warn!("Backend: Received 'Close' action without an id. Ignored");
+                    warn!(
+                        target: BACKEND,
+                        "Backend: Received 'Close' action without an id. Ignored"
+                    );
+                }
+                Action::CloseAll(closed) => {
+                    let count = backend_manager
+                        .close_all_notifications()
+                        .unwrap_or_else(|err| {
+                            warn!(
+                                target: BACKEND,
+                                "Backend: Failed to close all notifications. Error: {err}"
+                            );
+                            0
+                        });
+                    // INFO: the client may be gone before the reply, there is nobody to notify
+                    let _ = closed.send(count);
+                }
+                Action::CloseByApp { app_name, closed } => {
+                    let count = backend_manager
+                        .close_notifications_by_app(&app_name, &config)
+                        .unwrap_or_else(|err| {
+                            warn!(
+                                target: BACKEND,
+                                "Backend: Failed to close the notifications of '{app_name}'. \
+                                Error: {err}"
+                            );
+                            0
+                        });
+                    // INFO: the client may be gone before the reply, there is nobody to notify
+                    let _ = closed.send(count);
                 }
-                Action::CloseAll => {
-                    warn!("Backend: Received unsupported 'CloseAll' action. Ignored");
+                Action::Copy { text, copied } => {
+                    let result = backend_manager.copy(text).map_err(|err| {
+                        warn!(target: BACKEND, "Backend: Failed to copy the text. Error: {err}");
+                        err.to_string()
+                    });
+                    // INFO: the client may be gone before the reply, there is nobody to notify
+                    let _ = copied.send(result);
+                }
+                Action::ResetState => {
+                    backend_manager.reset_runtime_state(&config);
+                    info!(target: BACKEND, "Backend: Reset runtime state");
+                }
+                Action::Inhibit(inhibited) => {
+                    backend_manager.set_inhibited(inhibited);
+                    info!(
+                        target: BACKEND,
+                        "Backend: Notifications are {}",
+                        if inhibited { "inhibited" } else { "uninhibited" }
+                    );
+                }
+                Action::Pause(paused) => {
+                    backend_manager.set_paused(paused);
+                    info!(
+                        target: BACKEND,
+                        "Backend: Timeouts of notifications are {}",
+                        if paused { "paused" } else { "resumed" }
+                    );
+                }
+                Action::Shutdown => {
+                    info!(target: BACKEND, "Backend: Shutting down");
+                    backend_manager.close_all()?;
+                    emit_signals(server.as_ref(), &mut backend_manager).await?;
+                    if let Some(server) = server {
+                        server.shutdown().await?;
+                    }
+                    return Ok(());
                 }
             }
         }
@@ -61,58 +186,146 @@ pub async fn run(mut config: Config) -> anyhow::Result<()> {
             .pop_due_notifications()
             .into_iter()
             .for_each(|scheduled| {
-                backend_manager.create_notification(scheduled.data);
+                backend_manager.create_notification(scheduled.data, &config);
                 debug!(
+                    target: BACKEND,
                     "Backend: Notification with id {} due for delivery",
                     &scheduled.id
                 );
             });
 
-        backend_manager.poll(&config)?;
+        supervise(
+            &mut backend_manager,
+            &mut watchdog,
+            &config,
+            |backend_manager| backend_manager.poll(&config),
+        )?;
 
         match config.check_updates() {
             FileState::Updated => {
                 partially_default_config = false;
                 config.update();
-                backend_manager.update_config(&config)?;
-                info!("Renderer: Detected changes of config files and updated")
+                supervise(
+                    &mut backend_manager,
+                    &mut watchdog,
+                    &config,
+                    |backend_manager| backend_manager.update_config(&config),
+                )?;
+                apply_server_config(server.as_ref(), &config);
+                info!(target: BACKEND, "Renderer: Detected changes of config files and updated")
             }
             FileState::NotFound if !partially_default_config => {
                 partially_default_config = true;
                 config.update();
-                backend_manager.update_config(&config)?;
-                info!("The main or imported configuration file is not found, reverting this part to default values.");
+                supervise(
+                    &mut backend_manager,
+                    &mut watchdog,
+                    &config,
+                    |backend_manager| backend_manager.update_config(&config),
+                )?;
+                apply_server_config(server.as_ref(), &config);
+                info!(
+                    target: BACKEND,
+                    "The main or imported configuration file is not found, reverting this part to default values."
+                );
             }
             FileState::NotFound | FileState::NothingChanged => (),
         };
 
-        while let Some(signal) = backend_manager.pop_signal() {
-            //INFO: ignore this one because it always emits at server
-            if let Signal::NotificationClosed {
-                reason: ClosingReason::CallCloseNotification,
-                ..
-            } = &signal
-            {
-                continue;
-            }
-            debug_signal(&signal);
-            server.emit_signal(signal).await?;
-        }
+        emit_signals(server.as_ref(), &mut backend_manager).await?;
 
         tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
         std::hint::spin_loop();
     }
 }
 
+/// Runs the render work and restarts the window manager when it panics, so the daemon doesn't
+/// silently stop showing notifications while the D-Bus server is alive. The shown and queued
+/// notifications are preserved.
+fn supervise(
+    backend_manager: &mut BackendManager,
+    watchdog: &mut Watchdog,
+    config: &Config,
+    work: impl FnOnce(&mut BackendManager) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let payload = match panic::catch_unwind(AssertUnwindSafe(|| work(backend_manager))) {
+        Ok(result) => return result,
+        Err(payload) => payload,
+    };
+
+    let message = panic_message(&*payload);
+    error!(target: BACKEND, "Backend: The render path panicked: {message}");
+    if !watchdog.allow_restart(Instant::now()) {
+        anyhow::bail!("The render path keeps panicking, the last panic: {message}");
+    }
+
+    backend_manager.restart_window_manager(config)?;
+    info!(target: BACKEND, "Backend: Restarted the render path after panic");
+    Ok(())
+}
+
+/// Sends the `Shutdown` action when the daemon receives SIGTERM or SIGINT, so it closes the
+/// notifications before exit.
+fn listen_termination(sender: UnboundedSender<Action>) -> anyhow::Result<()> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+
+    tokio::spawn(async move {
+        let signal_name = tokio::select! {
+            _ = terminate.recv() => "SIGTERM",
+            _ = interrupt.recv() => "SIGINT",
+        };
+        info!(target: BACKEND, "Backend: Received {signal_name}");
+        let _ = sender.send(Action::Shutdown);
+    });
+
+    Ok(())
+}
+
+async fn emit_signals(
+    server: Option<&Server>,
+    backend_manager: &mut BackendManager,
+) -> anyhow::Result<()> {
+    while let Some(signal) = backend_manager.pop_signal() {
+        debug_signal(&signal);
+        if let Some(server) = server {
+            server.emit_signal(signal).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_server_config(server: Option<&Server>, config: &Config) {
+    let Some(server) = server else {
+        return;
+    };
+
+    server.set_markup_tags(config.general().markup_tags.tags().clone());
+    server.set_default_urgency(config.general().default_urgency);
+    server.set_sanitize_text(config.general().sanitize_text);
+    server.set_strict_replaces(config.general().strict_replaces);
+}
+
 fn debug_signal(signal: &Signal) {
     match signal {
         Signal::ActionInvoked {
             notification_id,
             action_key,
-        } => debug!("Action '{action_key}' was invoked for notification id {notification_id}"),
+            activation_token,
+        } => debug!(
+            target: BACKEND,
+            "Action '{action_key}' was invoked for notification id {notification_id} \
+            with activation token {activation_token:?}"
+        ),
         Signal::NotificationClosed {
             notification_id,
             reason,
-        } => debug!("Notification with id {notification_id} closed by {reason} reason"),
+        } => {
+            debug!(
+                target: BACKEND,
+                "Notification with id {notification_id} closed by {reason} reason"
+            )
+        }
     }
 }
@@ -0,0 +1,60 @@
+//! The soft memory budget of cached layouts and drawn banners. When the budget is exceeded, the
+//! animations of least recently drawn banners are dropped because their decoded frames take the
+//! most memory.
+//!
+//! Only the animations are evicted. The framebuffers and scaled images are needed to show the
+//! banners, and the decoded images aren't cached apart from the banners. The fetched remote
+//! images are stored on disk, so they don't count.
+
+use std::time::Instant;
+
+/// The entry which can be evicted to free its memory.
+pub(crate) struct Evictable<K> {
+    pub(crate) key: K,
+    pub(crate) used_at: Instant,
+    pub(crate) bytes: usize,
+}
+
+/// Returns the keys of least recently used entries which are evicted to fit their memory usage
+/// into the budget.
+pub(crate) fn lru_evictions<K>(mut entries: Vec<Evictable<K>>, budget: usize) -> Vec<K> {
+    entries.sort_by_key(|entry| entry.used_at);
+
+    let mut usage = entries.iter().map(|entry| entry.bytes).sum::<usize>();
+    entries
+        .into_iter()
+        .take_while(|entry| {
+            let is_over_budget = usage > budget;
+            usage = usage.saturating_sub(entry.bytes);
+            is_over_budget
+        })
+        .map(|entry| entry.key)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{lru_evictions, Evictable};
+
+    #[test]
+    fn least_recently_used_entries_are_evicted() {
+        let now = Instant::now();
+        let entries = || {
+            [(1, 30, 100), (2, 10, 200), (3, 20, 300)]
+                .into_iter()
+                .map(|(key, seconds_ago, bytes)| Evictable {
+                    key,
+                    used_at: now - Duration::from_secs(seconds_ago),
+                    bytes,
+                })
+                .collect()
+        };
+
+        assert!(lru_evictions(entries(), 600).is_empty());
+        assert_eq!(lru_evictions(entries(), 550), [1]);
+        assert_eq!(lru_evictions(entries(), 450), [1, 3]);
+        assert_eq!(lru_evictions(entries(), 0), [1, 3, 2]);
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use log::debug;
+use shared::log_target::POWER;
+use wayland_client::{
+    delegate_noop,
+    protocol::{wl_output::WlOutput, wl_registry},
+    Dispatch, Proxy, QueueHandle, WEnum,
+};
+use wayland_protocols_wlr::output_power_management::v1::client::{
+    zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1,
+    zwlr_output_power_v1::{self, Mode, ZwlrOutputPowerV1},
+};
+
+/// Tracks the power mode of outputs by the `wlr-output-power-management` protocol to know when
+/// the screen is turned off by DPMS.
+pub struct OutputPower {
+    manager: Option<ZwlrOutputPowerManagerV1>,
+    outputs: HashMap<u32, OutputState>,
+
+    pub was_off: bool,
+}
+
+struct OutputState {
+    output: WlOutput,
+    power: Option<ZwlrOutputPowerV1>,
+    mode: Option<Mode>,
+}
+
+impl OutputPower {
+    pub(crate) fn init() -> Self {
+        debug!(target: POWER, "Output Power: Initialized");
+        Self {
+            manager: None,
+            outputs: HashMap::new(),
+            was_off: false,
+        }
+    }
+
+    /// Returns true when the power mode of every output is known and all outputs are turned off.
+    pub(crate) fn is_off(&self) -> bool {
+        are_off(self.outputs.values().map(|state| state.mode))
+    }
+
+    fn watch_output(&mut self, name: u32, qhandle: &QueueHandle<Self>) {
+        let (Some(manager), Some(state)) = (self.manager.as_ref(), self.outputs.get_mut(&name))
+        else {
+            return;
+        };
+
+        if state.power.is_none() {
+            state.power = Some(manager.get_output_power(&state.output, qhandle, name));
+            debug!(target: POWER, "Output Power: Watching the power mode of output {name}");
+        }
+    }
+
+    fn forget_output(&mut self, name: u32) {
+        let Some(state) = self.outputs.remove(&name) else {
+            return;
+        };
+
+        if let Some(power) = state.power {
+            power.destroy();
+        }
+        if state.output.version() >= 3 {
+            state.output.release();
+        }
+        debug!(target: POWER, "Output Power: Forgot the output {name}");
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for OutputPower {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: <wl_registry::WlRegistry as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &wayland_client::Connection,
+        qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } => match interface.as_ref() {
+                "wl_output" => {
+                    let output = registry.bind::<WlOutput, _, _>(name, version.min(3), qhandle, ());
+                    state.outputs.insert(
+                        name,
+                        OutputState {
+                            output,
+                            power: None,
+                            mode: None,
+                        },
+                    );
+                    state.watch_output(name, qhandle);
+                }
+                "zwlr_output_power_manager_v1" => {
+                    state.manager = Some(registry.bind::<ZwlrOutputPowerManagerV1, _, _>(
+                        name,
+                        version,
+                        qhandle,
+                        (),
+                    ));
+                    debug!(target: POWER, "Output Power: Bound the zwlr_output_power_manager_v1");
+
+                    let names: Vec<u32> = state.outputs.keys().copied().collect();
+                    names
+                        .into_iter()
+                        .for_each(|name| state.watch_output(name, qhandle));
+                }
+                _ => (),
+            },
+            wl_registry::Event::GlobalRemove { name } => state.forget_output(name),
+            _ => (),
+        }
+    }
+}
+
+impl Dispatch<ZwlrOutputPowerV1, u32> for OutputPower {
+    fn event(
+        state: &mut Self,
+        _power: &ZwlrOutputPowerV1,
+        event: <ZwlrOutputPowerV1 as wayland_client::Proxy>::Event,
+        name: &u32,
+        _conn: &wayland_client::Connection,
+        _qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_output_power_v1::Event::Mode {
+                mode: WEnum::Value(mode),
+            } => {
+                if let Some(output) = state.outputs.get_mut(name) {
+                    output.mode = Some(mode);
+                }
+                if state.is_off() {
+                    state.was_off = true;
+                }
+                debug!(target: POWER, "Output Power: The output {name} is turned {mode:?}");
+            }
+            zwlr_output_power_v1::Event::Failed => {
+                // INFO: the power mode of output can't be watched anymore, so the output is
+                // forgotten to not keep the screen as turned off forever.
+                state.forget_output(*name);
+            }
+            _ => (),
+        }
+    }
+}
+
+delegate_noop!(OutputPower: ignore WlOutput);
+delegate_noop!(OutputPower: ignore ZwlrOutputPowerManagerV1);
+
+fn are_off(modes: impl IntoIterator<Item = Option<Mode>>) -> bool {
+    let mut modes = modes.into_iter().peekable();
+    modes.peek().is_some() && modes.all(|mode| mode == Some(Mode::Off))
+}
+
+#[cfg(test)]
+mod tests {
+    use wayland_protocols_wlr::output_power_management::v1::client::zwlr_output_power_v1::Mode;
+
+    use super::are_off;
+
+    #[test]
+    fn screen_is_off_when_every_output_is_off() {
+        assert!(are_off([Some(Mode::Off)]));
+        assert!(are_off([Some(Mode::Off), Some(Mode::Off)]));
+
+        assert!(!are_off([]));
+        assert!(!are_off([Some(Mode::Off), Some(Mode::On)]));
+        // INFO: the output whose mode isn't received yet may be turned on
+        assert!(!are_off([Some(Mode::Off), None]));
+    }
+}
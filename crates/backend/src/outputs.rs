@@ -0,0 +1,165 @@
+use log::debug;
+use serde::{Serialize, Serializer};
+use shared::log_target::WINDOW;
+use wayland_client::{
+    protocol::{wl_output, wl_registry},
+    Connection, Dispatch, Proxy, QueueHandle, WEnum,
+};
+
+/// The output as it's seen by the Wayland compositor.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct OutputInfo {
+    /// The name of output, e.g. `DP-1`. It's known only when the compositor supports the 4th
+    /// version of `wl_output`.
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub width: i32,
+    pub height: i32,
+    /// The refresh rate in mHz.
+    pub refresh: i32,
+    pub scale: i32,
+    pub transform: Transform,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Transform {
+    #[default]
+    Normal,
+    Rotated90,
+    Rotated180,
+    Rotated270,
+    Flipped,
+    Flipped90,
+    Flipped180,
+    Flipped270,
+}
+
+impl Serialize for Transform {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl std::fmt::Display for Transform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Transform::Normal => "normal",
+            Transform::Rotated90 => "90",
+            Transform::Rotated180 => "180",
+            Transform::Rotated270 => "270",
+            Transform::Flipped => "flipped",
+            Transform::Flipped90 => "flipped-90",
+            Transform::Flipped180 => "flipped-180",
+            Transform::Flipped270 => "flipped-270",
+        })
+    }
+}
+
+impl From<wl_output::Transform> for Transform {
+    fn from(transform: wl_output::Transform) -> Self {
+        match transform {
+            wl_output::Transform::_90 => Transform::Rotated90,
+            wl_output::Transform::_180 => Transform::Rotated180,
+            wl_output::Transform::_270 => Transform::Rotated270,
+            wl_output::Transform::Flipped => Transform::Flipped,
+            wl_output::Transform::Flipped90 => Transform::Flipped90,
+            wl_output::Transform::Flipped180 => Transform::Flipped180,
+            wl_output::Transform::Flipped270 => Transform::Flipped270,
+            _ => Transform::Normal,
+        }
+    }
+}
+
+/// Connects to the Wayland compositor and returns the outputs which it advertises.
+pub fn outputs() -> anyhow::Result<Vec<OutputInfo>> {
+    let connection = Connection::connect_to_env()?;
+    let mut event_queue = connection.new_event_queue();
+    connection.display().get_registry(&event_queue.handle(), ());
+
+    let mut state = Outputs::default();
+    // INFO: the first roundtrip binds the outputs and the second one receives their properties
+    event_queue.roundtrip(&mut state)?;
+    event_queue.roundtrip(&mut state)?;
+
+    state
+        .proxies
+        .iter()
+        .filter(|output| output.version() >= 3)
+        .for_each(wl_output::WlOutput::release);
+
+    debug!(target: WINDOW, "Outputs: Received {} outputs", state.infos.len());
+    Ok(state.infos)
+}
+
+#[derive(Default)]
+struct Outputs {
+    proxies: Vec<wl_output::WlOutput>,
+    infos: Vec<OutputInfo>,
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for Outputs {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _data: &(),
+        _conn: &Connection,
+        qhandle: &QueueHandle<Self>,
+    ) {
+        if let wl_registry::Event::Global {
+            name,
+            interface,
+            version,
+        } = event
+        {
+            if interface == "wl_output" {
+                let index = state.infos.len();
+                state
+                    .proxies
+                    .push(registry.bind::<wl_output::WlOutput, _, _>(
+                        name,
+                        version.min(4),
+                        qhandle,
+                        index,
+                    ));
+                state.infos.push(OutputInfo {
+                    scale: 1,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, usize> for Outputs {
+    fn event(
+        state: &mut Self,
+        _output: &wl_output::WlOutput,
+        event: wl_output::Event,
+        index: &usize,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let info = &mut state.infos[*index];
+        match event {
+            wl_output::Event::Geometry {
+                transform: WEnum::Value(transform),
+                ..
+            } => info.transform = transform.into(),
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                refresh,
+            } if flags.contains(wl_output::Mode::Current) => {
+                info.width = width;
+                info.height = height;
+                info.refresh = refresh;
+            }
+            wl_output::Event::Scale { factor } => info.scale = factor,
+            wl_output::Event::Name { name } => info.name = Some(name),
+            wl_output::Event::Description { description } => info.description = Some(description),
+            _ => (),
+        }
+    }
+}
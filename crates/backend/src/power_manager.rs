@@ -0,0 +1,46 @@
+use crate::{dispatcher::Dispatcher, output_power::OutputPower};
+use log::debug;
+use shared::log_target::POWER;
+use wayland_client::{Connection, EventQueue};
+
+pub struct PowerManager {
+    event_queue: EventQueue<OutputPower>,
+    output_power: OutputPower,
+}
+
+impl Dispatcher for PowerManager {
+    type State = OutputPower;
+
+    fn get_event_queue_and_state(
+        &mut self,
+    ) -> Option<(&mut EventQueue<Self::State>, &mut Self::State)> {
+        Some((&mut self.event_queue, &mut self.output_power))
+    }
+}
+
+impl PowerManager {
+    pub(crate) fn init(connection: &Connection) -> anyhow::Result<Self> {
+        let event_queue = connection.new_event_queue();
+        connection.display().get_registry(&event_queue.handle(), ());
+
+        let power_manager = Self {
+            event_queue,
+            output_power: OutputPower::init(),
+        };
+        debug!(target: POWER, "Power Manager: Initialized");
+
+        Ok(power_manager)
+    }
+
+    pub(crate) fn is_screen_off(&self) -> bool {
+        self.output_power.is_off()
+    }
+
+    pub(crate) fn was_screen_off(&self) -> bool {
+        self.output_power.was_off
+    }
+
+    pub(crate) fn reset_screen_off_state(&mut self) {
+        self.output_power.was_off = false;
+    }
+}
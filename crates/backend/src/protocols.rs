@@ -0,0 +1,80 @@
+use log::debug;
+use serde::Serialize;
+use shared::log_target::WINDOW;
+use wayland_client::{protocol::wl_registry, Connection, Dispatch, QueueHandle};
+
+/// The Wayland protocols which the daemon binds when the compositor advertises them.
+const USED_PROTOCOLS: [&str; 13] = [
+    "wl_compositor",
+    "wl_shm",
+    "wl_output",
+    "wl_seat",
+    "zwlr_layer_shell_v1",
+    "xdg_activation_v1",
+    "wl_subcompositor",
+    "wp_viewporter",
+    "wp_single_pixel_buffer_manager_v1",
+    "wp_cursor_shape_manager_v1",
+    "ext_idle_notifier_v1",
+    "zwlr_output_power_manager_v1",
+    "zwlr_data_control_manager_v1",
+];
+
+/// The protocol which is used by the daemon with the version advertised by the compositor.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProtocolInfo {
+    pub name: &'static str,
+    /// The highest version which the compositor supports or `None` if it doesn't advertise the
+    /// protocol.
+    pub version: Option<u32>,
+}
+
+/// Connects to the Wayland compositor and checks which of the used protocols it advertises.
+pub fn protocols() -> anyhow::Result<Vec<ProtocolInfo>> {
+    let connection = Connection::connect_to_env()?;
+    let mut event_queue = connection.new_event_queue();
+    connection.display().get_registry(&event_queue.handle(), ());
+
+    let mut state = Protocols {
+        infos: USED_PROTOCOLS
+            .into_iter()
+            .map(|name| ProtocolInfo {
+                name,
+                version: None,
+            })
+            .collect(),
+    };
+    event_queue.roundtrip(&mut state)?;
+
+    debug!(
+        target: WINDOW,
+        "Protocols: The compositor supports {} of {} used protocols",
+        state.infos.iter().filter(|info| info.version.is_some()).count(),
+        state.infos.len()
+    );
+    Ok(state.infos)
+}
+
+struct Protocols {
+    infos: Vec<ProtocolInfo>,
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for Protocols {
+    fn event(
+        state: &mut Self,
+        _registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if let wl_registry::Event::Global {
+            interface, version, ..
+        } = event
+        {
+            if let Some(info) = state.infos.iter_mut().find(|info| info.name == interface) {
+                info.version = Some(info.version.map_or(version, |known| known.max(version)));
+            }
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+};
+
+use dbus::notification::Notification;
+use log::{debug, warn};
+use shared::log_target::IMAGE;
+
+/// Fetches the images of notifications whose `image-path` hint is a remote URL.
+///
+/// The download is delegated to `curl` which runs in background so the event loop isn't blocked.
+/// Fetched images are stored in the cache directory and reused for the same URLs. Until the image
+/// is fetched, the notification is shown without it. The notifications with the same URL wait for
+/// one download which is cancelled when all of them are closed.
+pub(crate) struct RemoteImages {
+    cache_dir: Option<PathBuf>,
+    /// The downloads by paths of their cached images.
+    downloads: HashMap<PathBuf, Download>,
+    /// Whether the missing `curl` was reported, so it isn't reported for each notification.
+    missing_curl_reported: bool,
+    /// Spawns the download of URL into the file. It's replaced in tests to not fetch the images.
+    fetch: fn(&str, &Path) -> std::io::Result<Child>,
+}
+
+struct Download {
+    child: Child,
+    url: String,
+    /// The ids of notifications which wait for the image.
+    ids: Vec<u32>,
+}
+
+impl RemoteImages {
+    /// The maximum time in seconds of fetching one image.
+    const MAX_TIME: u32 = 10;
+    /// The maximum size of image in bytes.
+    const MAX_FILESIZE: u32 = 10 * 1024 * 1024;
+
+    pub(crate) fn init() -> Self {
+        Self {
+            cache_dir: cache_dir(),
+            downloads: HashMap::new(),
+            missing_curl_reported: false,
+            fetch: Self::fetch_by_curl,
+        }
+    }
+
+    /// Replaces the remote URL in the `image-path` hint by the path to the cached image or
+    /// removes it and starts fetching when the image isn't cached yet.
+    pub(crate) fn prepare(&mut self, notification: &mut Notification, allowed: bool) {
+        let id = notification.id;
+        let Some(url) = notification
+            .hints
+            .image_path
+            .take_if(|image_path| is_remote(image_path))
+        else {
+            // INFO: the replaced notification may wait for the image of previous one
+            self.cancel(id);
+            return;
+        };
+
+        if !allowed {
+            debug!(
+                target: IMAGE,
+                "Remote Images (id={id}): Remote images are disallowed, ignored {url}"
+            );
+            self.cancel(id);
+            return;
+        }
+
+        let Some(cache_dir) = self.cache_dir.clone() else {
+            warn!(
+                target: IMAGE,
+                "Remote Images (id={id}): Cannot determine the cache directory, ignored {url}"
+            );
+            return;
+        };
+
+        let path = cache_dir.join(cache_key(&url));
+        self.cancel_except(id, Some(&path));
+
+        if path.is_file() {
+            debug!(target: IMAGE, "Remote Images (id={id}): Using cached image of {url}");
+            notification.hints.image_path = Some(path.to_string_lossy().into_owned());
+            return;
+        }
+
+        if let Some(download) = self.downloads.get_mut(&path) {
+            if !download.ids.contains(&id) {
+                download.ids.push(id);
+            }
+            debug!(target: IMAGE, "Remote Images (id={id}): Waiting for the fetch of {url}");
+            return;
+        }
+
+        if let Err(error) = std::fs::create_dir_all(&cache_dir) {
+            warn!(
+                target: IMAGE,
+                "Remote Images (id={id}): Failed to create the cache directory. Error: {error}"
+            );
+            return;
+        }
+
+        match (self.fetch)(&url, &path) {
+            Ok(child) => {
+                debug!(target: IMAGE, "Remote Images (id={id}): Started fetching {url}");
+                self.downloads.insert(
+                    path,
+                    Download {
+                        child,
+                        url,
+                        ids: vec![id],
+                    },
+                );
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                // INFO: curl may be installed later, so it's spawned again for next images
+                if !self.missing_curl_reported {
+                    warn!(
+                        target: IMAGE,
+                        "Remote Images: curl is not found, install it to fetch the remote images"
+                    );
+                    self.missing_curl_reported = true;
+                }
+            }
+            Err(error) => {
+                warn!(
+                    target: IMAGE,
+                    "Remote Images (id={id}): Failed to spawn curl. Error: {error}"
+                )
+            }
+        }
+    }
+
+    /// Stops waiting for the image of closed notification. The download is cancelled when no
+    /// other notification waits for it.
+    pub(crate) fn cancel(&mut self, id: u32) {
+        self.cancel_except(id, None);
+    }
+
+    fn cancel_except(&mut self, id: u32, kept_path: Option<&Path>) {
+        self.downloads.retain(|path, download| {
+            if Some(path.as_path()) == kept_path {
+                return true;
+            }
+
+            download.ids.retain(|&waiting_id| waiting_id != id);
+            if !download.ids.is_empty() {
+                return true;
+            }
+
+            download.cancel(path);
+            debug!(
+                target: IMAGE,
+                "Remote Images (id={id}): Cancelled fetching {}", download.url
+            );
+            false
+        });
+    }
+
+    /// Returns the ids of notifications with paths to their fetched images.
+    pub(crate) fn poll(&mut self) -> Vec<(u32, PathBuf)> {
+        let mut fetched = vec![];
+
+        self.downloads.retain(|path, download| {
+            match download.child.try_wait() {
+                Ok(None) => return true,
+                Ok(Some(status)) if status.success() => {
+                    match std::fs::rename(partial_path(path), path) {
+                        Ok(()) => {
+                            debug!(target: IMAGE, "Remote Images: Fetched {}", download.url);
+                            fetched.extend(download.ids.iter().map(|&id| (id, path.clone())));
+                        }
+                        Err(error) => warn!(
+                            target: IMAGE,
+                            "Remote Images: Failed to store {}. Error: {error}", download.url
+                        ),
+                    }
+                }
+                Ok(Some(status)) => {
+                    warn!(
+                        target: IMAGE,
+                        "Remote Images: Failed to fetch {}, curl exited with {status}",
+                        download.url
+                    );
+                    remove_partial(path);
+                }
+                Err(error) => {
+                    warn!(
+                        target: IMAGE,
+                        "Remote Images: Failed to wait for curl. Error: {error}"
+                    );
+                    remove_partial(path);
+                }
+            }
+
+            false
+        });
+
+        fetched
+    }
+
+    fn fetch_by_curl(url: &str, path: &Path) -> std::io::Result<Child> {
+        Command::new("curl")
+            .args([
+                "--silent",
+                "--fail",
+                "--location",
+                "--proto",
+                "=http,https",
+                "--max-time",
+                &Self::MAX_TIME.to_string(),
+                "--max-filesize",
+                &Self::MAX_FILESIZE.to_string(),
+                "--output",
+            ])
+            .arg(partial_path(path))
+            .arg(url)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+    }
+}
+
+impl Download {
+    fn cancel(&mut self, path: &Path) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        remove_partial(path);
+    }
+}
+
+impl Drop for RemoteImages {
+    fn drop(&mut self) {
+        self.downloads
+            .iter_mut()
+            .for_each(|(path, download)| download.cancel(path));
+    }
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    path.with_extension("part")
+}
+
+fn remove_partial(path: &Path) {
+    // INFO: curl doesn't always create the output file so the error is ignored
+    let _ = std::fs::remove_file(partial_path(path));
+}
+
+fn is_remote(image_path: &str) -> bool {
+    image_path.starts_with("http://") || image_path.starts_with("https://")
+}
+
+/// Returns the file name of cached image by the 64-bit FNV-1a hash of URL. Unlike the hasher of
+/// standard library, it doesn't change between Rust releases, so the cached images are found
+/// after the update.
+fn cache_key(url: &str) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let hash = url.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    });
+    format!("{hash:016x}")
+}
+
+pub(crate) fn cache_dir() -> Option<PathBuf> {
+    std::env::var("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map(|path| path.join("noti").join("images"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        path::Path,
+        process::{Child, Command, Stdio},
+    };
+
+    use dbus::notification::Notification;
+
+    use super::{cache_key, is_remote, RemoteImages};
+
+    const URL: &str = "https://example.com/image.png";
+
+    #[test]
+    fn remote_paths() {
+        assert!(is_remote("https://example.com/image.png"));
+        assert!(is_remote("http://example.com/image.png"));
+        assert!(!is_remote("/usr/share/icons/image.png"));
+        assert!(!is_remote("file:///usr/share/icons/image.png"));
+    }
+
+    #[test]
+    fn stable_cache_key() {
+        assert_eq!(
+            cache_key("https://example.com/image.png"),
+            cache_key("https://example.com/image.png")
+        );
+        assert_ne!(
+            cache_key("https://example.com/image.png"),
+            cache_key("https://example.com/other.png")
+        );
+        // INFO: the known values of 64-bit FNV-1a
+        assert_eq!(cache_key(""), "cbf29ce484222325");
+        assert_eq!(cache_key("a"), "af63dc4c8601ec8c");
+    }
+
+    /// Spawns the test binary which only lists the tests, so nothing is fetched and the test
+    /// doesn't depend on the programs of host.
+    fn fake_fetch(_url: &str, _path: &Path) -> std::io::Result<Child> {
+        Command::new(std::env::current_exe()?)
+            .arg("--list")
+            .stdout(Stdio::null())
+            .spawn()
+    }
+
+    fn notification_with_image(id: u32) -> Notification {
+        let mut notification = Notification {
+            id,
+            ..Notification::test_default()
+        };
+        notification.hints.image_path = Some(URL.to_string());
+        notification
+    }
+
+    #[test]
+    fn notifications_wait_for_one_download() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let path = cache_dir.path().join(cache_key(URL));
+        // INFO: the remaining downloads are killed and reaped on drop when the assertion fails
+        let mut remote_images = RemoteImages {
+            cache_dir: Some(cache_dir.path().to_path_buf()),
+            downloads: HashMap::new(),
+            missing_curl_reported: false,
+            fetch: fake_fetch,
+        };
+
+        remote_images.prepare(&mut notification_with_image(1), true);
+        remote_images.prepare(&mut notification_with_image(2), true);
+        assert_eq!(remote_images.downloads.len(), 1);
+        assert_eq!(remote_images.downloads[&path].ids, [1, 2]);
+
+        remote_images.cancel(1);
+        assert_eq!(remote_images.downloads[&path].ids, [2]);
+
+        remote_images.cancel(2);
+        assert!(remote_images.downloads.is_empty());
+    }
+}
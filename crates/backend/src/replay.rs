@@ -0,0 +1,232 @@
+//! Replays the notifications from a file without D-Bus, e.g. to reproduce layout bugs
+//! deterministically during development.
+//!
+//! The file is either TOML with the `[[notification]]` entries or JSON with the same structure,
+//! which is chosen by the file extension.
+
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use config::general::GeneralConfig;
+use dbus::{
+    actions::Action,
+    notification::{Notification, NotifyArgs, NotifyOptions, ScheduledNotification},
+};
+use log::{debug, info};
+use serde::Deserialize;
+use shared::log_target::BACKEND;
+use tokio::sync::mpsc::UnboundedSender;
+use zbus::zvariant::Value;
+
+#[derive(Debug, Deserialize)]
+struct ReplayFile {
+    #[serde(default)]
+    notification: Vec<ReplayEntry>,
+}
+
+/// The notification of replay file. Its fields follow the arguments of the `Notify` method.
+#[derive(Debug, Deserialize)]
+pub struct ReplayEntry {
+    /// The delay in milliseconds before the notification is shown since the previous one.
+    #[serde(default)]
+    delay_ms: u64,
+    /// The id of notification to replace. The ids are assigned in order when it's not set.
+    id: Option<u32>,
+    #[serde(default = "default_app_name")]
+    app_name: String,
+    #[serde(default)]
+    app_icon: String,
+    #[serde(default)]
+    summary: String,
+    #[serde(default)]
+    body: String,
+    /// The actions as the flat list of keys and labels like in the `Notify` method.
+    #[serde(default)]
+    actions: Vec<String>,
+    #[serde(default)]
+    hints: HashMap<String, HintValue>,
+    #[serde(default = "default_timeout")]
+    timeout: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum HintValue {
+    Bool(bool),
+    Int(i64),
+    String(String),
+}
+
+impl HintValue {
+    fn as_value(&self) -> Value<'_> {
+        match self {
+            HintValue::Bool(value) => Value::Bool(*value),
+            HintValue::Int(value) => {
+                Value::I32((*value).clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+            }
+            HintValue::String(value) => Value::from(value.as_str()),
+        }
+    }
+}
+
+fn default_app_name() -> String {
+    "Noti".to_string()
+}
+
+fn default_timeout() -> i32 {
+    -1
+}
+
+/// Reads the notifications of replay file.
+pub fn load(path: &Path) -> anyhow::Result<Vec<ReplayEntry>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| anyhow::anyhow!("Failed to read the replay file {path:?}. Error: {err}"))?;
+
+    parse(
+        &content,
+        path.extension()
+            .is_some_and(|extension| extension == "json"),
+    )
+    .map_err(|err| anyhow::anyhow!("Failed to parse the replay file {path:?}. Error: {err}"))
+}
+
+fn parse(content: &str, is_json: bool) -> anyhow::Result<Vec<ReplayEntry>> {
+    let replay_file: ReplayFile = match is_json {
+        true => serde_json::from_str(content)?,
+        false => toml::from_str(content)?,
+    };
+
+    Ok(replay_file.notification)
+}
+
+/// Sends the notifications to the backend through the action channel with their delays.
+pub(crate) fn spawn(
+    entries: Vec<ReplayEntry>,
+    sender: UnboundedSender<Action>,
+    general: &GeneralConfig,
+) {
+    let options = NotifyOptions {
+        markup_tags: general.markup_tags.tags().clone(),
+        default_urgency: general.default_urgency,
+        sanitize_text: general.sanitize_text,
+    };
+
+    tokio::spawn(async move {
+        let mut next_id = 1;
+        let total = entries.len();
+
+        for entry in entries {
+            tokio::time::sleep(Duration::from_millis(entry.delay_ms)).await;
+
+            let id = entry.id.unwrap_or(next_id);
+            next_id = next_id.max(id.saturating_add(1));
+
+            let notification = entry.into_notification(id, &options);
+            debug!(target: BACKEND, "Replay: Sending notification with id {id}");
+
+            let action = match notification.hints.schedule.clone() {
+                Some(time) => Action::Schedule(ScheduledNotification {
+                    id,
+                    time,
+                    data: notification.into(),
+                }),
+                None => Action::Show(notification.into()),
+            };
+            if sender.send(action).is_err() {
+                return;
+            }
+        }
+
+        info!(target: BACKEND, "Replay: Sent all {total} notifications");
+    });
+}
+
+impl ReplayEntry {
+    fn into_notification(self, id: u32, options: &NotifyOptions) -> Notification {
+        let args = NotifyArgs {
+            actions: self.actions.iter().map(String::as_str).collect(),
+            hints: self
+                .hints
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_value()))
+                .collect(),
+            app_name: self.app_name,
+            app_icon: self.app_icon,
+            summary: self.summary,
+            body: self.body,
+            expire_timeout: self.timeout,
+        };
+
+        Notification::from_notify(id, args, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dbus::{
+        notification::{NotifyOptions, Progress, Timeout, Urgency},
+        text::TagKind,
+    };
+
+    use super::parse;
+
+    #[test]
+    fn toml_and_json_files_are_parsed_alike() {
+        let toml = r#"
+            [[notification]]
+            summary = "First"
+            body = "<b>Bold</b> body"
+
+            [[notification]]
+            delay_ms = 500
+            id = 7
+            summary = "Second"
+            timeout = 0
+            actions = ["default", "Open"]
+            hints = { urgency = "critical", value = 40, transient = true }
+        "#;
+        let json = r#"{"notification": [
+            {"summary": "First", "body": "<b>Bold</b> body"},
+            {
+                "delay_ms": 500, "id": 7, "summary": "Second", "timeout": 0,
+                "actions": ["default", "Open"],
+                "hints": {"urgency": "critical", "value": 40, "transient": true}
+            }
+        ]}"#;
+
+        let options = NotifyOptions {
+            markup_tags: TagKind::all(),
+            default_urgency: Urgency::Normal,
+            sanitize_text: true,
+        };
+
+        for entries in [parse(toml, false).unwrap(), parse(json, true).unwrap()] {
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries[0].delay_ms, 0);
+            assert_eq!(entries[0].app_name, "Noti");
+            assert_eq!(entries[1].delay_ms, 500);
+            assert_eq!(entries[1].id, Some(7));
+
+            let mut entries = entries.into_iter();
+            let first = entries.next().unwrap();
+            let first = first.into_notification(1, &options);
+            assert_eq!(first.body.body, "Bold body");
+            assert_eq!(first.hints.urgency, Urgency::Normal);
+            assert!(matches!(first.expire_timeout, Timeout::Configurable));
+
+            let second = entries.next().unwrap();
+            let second = second.into_notification(7, &options);
+            assert_eq!(second.id, 7);
+            assert_eq!(second.hints.urgency, Urgency::Critical);
+            assert_eq!(second.hints.progress(), Some(Progress::Determinate(40)));
+            assert_eq!(second.hints.transient, Some(true));
+            assert!(matches!(second.expire_timeout, Timeout::Never));
+            assert_eq!(second.actions.len(), 1);
+        }
+    }
+
+    #[test]
+    fn malformed_file_is_an_error() {
+        assert!(parse("[[notification]]\nsummary = 1", false).is_err());
+        assert!(parse("{\"notification\": 1}", true).is_err());
+    }
+}
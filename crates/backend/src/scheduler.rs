@@ -1,6 +1,7 @@
 use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeZone, Utc};
 use dbus::notification::ScheduledNotification;
 use log::{debug, warn};
+use shared::log_target::SCHEDULER;
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
 
@@ -19,6 +20,7 @@ impl Scheduler {
         match Self::parse_time(&notification.time) {
             Ok(parsed_time) => {
                 debug!(
+                    target: SCHEDULER,
                     "Successfully parsed time '{}' for scheduling: {}",
                     &notification.time, &parsed_time
                 );
@@ -32,6 +34,7 @@ impl Scheduler {
             }
             Err(e) => {
                 warn!(
+                    target: SCHEDULER,
                     "Failed to parse time '{}' for notification with id '{}': {}",
                     &notification.time, &notification.data.id, e
                 );
@@ -0,0 +1,172 @@
+use std::{
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use config::{sound::Sound, Config};
+use dbus::notification::{Notification, Urgency};
+use log::{debug, warn};
+use shared::log_target::SOUND;
+
+/// Plays the notification sounds and skips the ones which come too soon after the previous sound
+/// by the `debounce_ms` option.
+#[derive(Default)]
+pub(crate) struct SoundPlayer {
+    last_played_at: Option<Instant>,
+}
+
+impl SoundPlayer {
+    pub(crate) fn play(&mut self, notification: &Notification, config: &Config) {
+        let now = Instant::now();
+        let debounce = Duration::from_millis(config.sound().debounce_ms as u64);
+
+        if notification.hints.urgency != Urgency::Critical
+            && is_debounced(self.last_played_at, now, debounce)
+        {
+            debug!(
+                target: SOUND,
+                "Sound (id={}): Skipped because the previous sound is played recently",
+                notification.id
+            );
+            return;
+        }
+
+        if play(notification, config) {
+            self.last_played_at = Some(now);
+        }
+    }
+}
+
+/// Checks whether the sound at `now` is too soon after the last played one.
+fn is_debounced(last_played_at: Option<Instant>, now: Instant, debounce: Duration) -> bool {
+    last_played_at.is_some_and(|last_played_at| now.duration_since(last_played_at) < debounce)
+}
+
+/// Plays the sound which is requested by notification hints with the configured volume. When the
+/// notification has no sound hints, the sound of app config or notification urgency is played.
+/// Returns true when the playback is started.
+///
+/// The playback is delegated to external players so the sound is played only when one of them
+/// is installed in the system.
+fn play(notification: &Notification, config: &Config) -> bool {
+    if notification.hints.suppress_sound.unwrap_or(false) {
+        debug!(
+            target: SOUND,
+            "Sound (id={}): Suppressed by the notification hint",
+            notification.id
+        );
+        return false;
+    }
+
+    let volume = config.sound().volume.value();
+    if volume == 0.0 {
+        return false;
+    }
+
+    let commands = if let Some(sound_file) = notification.hints.sound_file.as_deref() {
+        let sound_file = sound_file.strip_prefix("file://").unwrap_or(sound_file);
+        file_commands(sound_file, volume)
+    } else if let Some(sound_name) = notification.hints.sound_name.as_deref() {
+        name_commands(sound_name, volume)
+    } else {
+        match config.sound_by_notification(notification) {
+            Some(Sound::File(sound_file)) => file_commands(&sound_file.to_string_lossy(), volume),
+            Some(Sound::Name(sound_name)) => name_commands(sound_name, volume),
+            None => return false,
+        }
+    };
+
+    let id = notification.id;
+    for mut command in commands {
+        match command.spawn() {
+            Ok(mut child) => {
+                debug!(target: SOUND, "Sound (id={id}): Started playback with volume {volume}");
+                // INFO: waiting for the child in separate thread to avoid zombie processes
+                std::thread::spawn(move || {
+                    if let Err(error) = child.wait() {
+                        warn!(
+                            target: SOUND,
+                            "Sound (id={id}): Failed to wait for player. Error: {error}"
+                        );
+                    }
+                });
+                return true;
+            }
+            Err(error) => debug!(
+                target: SOUND,
+                "Sound (id={id}): Failed to spawn {:?}. Error: {error}",
+                command.get_program()
+            ),
+        }
+    }
+
+    warn!(
+        target: SOUND,
+        "Sound (id={id}): No sound player is available to play the notification sound"
+    );
+    false
+}
+
+fn file_commands(sound_file: &str, volume: f32) -> Vec<Command> {
+    vec![
+        command(
+            "pw-play",
+            [format!("--volume={volume}"), sound_file.to_string()],
+        ),
+        command(
+            "paplay",
+            [
+                // INFO: the PulseAudio volume is linear where 65536 is 100%
+                format!("--volume={}", (volume * 65536.0).round() as u32),
+                sound_file.to_string(),
+            ],
+        ),
+    ]
+}
+
+fn name_commands(sound_name: &str, volume: f32) -> Vec<Command> {
+    vec![command(
+        "canberra-gtk-play",
+        [
+            format!("--id={sound_name}"),
+            // INFO: the libcanberra volume is in decibels
+            format!("--volume={:.2}", 20.0 * volume.log10()),
+        ],
+    )]
+}
+
+fn command<const N: usize>(program: &str, args: [String; N]) -> Command {
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::is_debounced;
+
+    #[test]
+    fn sounds_are_debounced() {
+        let now = Instant::now();
+        let debounce = Duration::from_millis(500);
+
+        assert!(!is_debounced(None, now, debounce));
+        assert!(is_debounced(
+            Some(now),
+            now + Duration::from_millis(200),
+            debounce
+        ));
+        assert!(!is_debounced(
+            Some(now),
+            now + Duration::from_millis(500),
+            debounce
+        ));
+        assert!(!is_debounced(Some(now), now, Duration::ZERO));
+    }
+}
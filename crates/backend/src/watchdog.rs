@@ -0,0 +1,82 @@
+use std::{
+    any::Any,
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Limits the restarts of render path after panics, so a notification which makes it panic every
+/// time doesn't keep the daemon restarting forever.
+pub(crate) struct Watchdog {
+    restarts: VecDeque<Instant>,
+}
+
+impl Watchdog {
+    const MAX_RESTARTS: usize = 5;
+    const PERIOD: Duration = Duration::from_secs(60);
+
+    pub(crate) fn new() -> Self {
+        Self {
+            restarts: VecDeque::new(),
+        }
+    }
+
+    /// Registers the restart at the given moment. Returns false when the render path was
+    /// restarted too many times during the last minute.
+    pub(crate) fn allow_restart(&mut self, now: Instant) -> bool {
+        while self
+            .restarts
+            .front()
+            .is_some_and(|restart| now.duration_since(*restart) > Self::PERIOD)
+        {
+            self.restarts.pop_front();
+        }
+
+        if self.restarts.len() >= Self::MAX_RESTARTS {
+            return false;
+        }
+
+        self.restarts.push_back(now);
+        true
+    }
+}
+
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown reason")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        any::Any,
+        time::{Duration, Instant},
+    };
+
+    use super::{panic_message, Watchdog};
+
+    #[test]
+    fn restarts_are_limited_within_period() {
+        let mut watchdog = Watchdog::new();
+        let start = Instant::now();
+
+        for _ in 0..Watchdog::MAX_RESTARTS {
+            assert!(watchdog.allow_restart(start));
+        }
+        assert!(!watchdog.allow_restart(start + Duration::from_secs(1)));
+        assert!(watchdog.allow_restart(start + Watchdog::PERIOD + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn panic_message_is_extracted_from_payload() {
+        let literal: Box<dyn Any + Send> = Box::new("literal");
+        let formatted: Box<dyn Any + Send> = Box::new(format!("formatted {}", 1));
+        let unknown: Box<dyn Any + Send> = Box::new(1);
+
+        assert_eq!(panic_message(&*literal), "literal");
+        assert_eq!(panic_message(&*formatted), "formatted 1");
+        assert_eq!(panic_message(&*unknown), "unknown reason");
+    }
+}
@@ -1,7 +1,8 @@
 use indexmap::{indexmap, IndexMap};
-use log::{debug, error, trace};
-use shared::cached_data::CachedData;
+use log::{debug, error, trace, warn};
+use shared::{cached_data::CachedData, log_target::WINDOW};
 use std::{
+    borrow::Cow,
     cell::RefCell,
     cmp::Ordering,
     collections::VecDeque,
@@ -12,39 +13,75 @@ use std::{
     },
     path::PathBuf,
     rc::Rc,
+    time::{Duration, Instant},
 };
 use wayland_client::{
     delegate_noop,
     protocol::{
-        wl_buffer, wl_callback, wl_compositor,
+        wl_buffer, wl_callback, wl_compositor, wl_output,
         wl_pointer::{self, ButtonState},
-        wl_registry, wl_seat, wl_shm, wl_shm_pool, wl_surface,
+        wl_region, wl_registry, wl_seat, wl_shm, wl_shm_pool, wl_subcompositor, wl_subsurface,
+        wl_surface,
     },
-    Dispatch, QueueHandle, WEnum,
+    Dispatch, Proxy, QueueHandle, WEnum,
 };
-use wayland_protocols::wp::cursor_shape::v1::client::{
-    wp_cursor_shape_device_v1, wp_cursor_shape_manager_v1,
+use wayland_protocols::{
+    wp::{
+        cursor_shape::v1::client::{wp_cursor_shape_device_v1, wp_cursor_shape_manager_v1},
+        single_pixel_buffer::v1::client::wp_single_pixel_buffer_manager_v1,
+        viewporter::client::{wp_viewport, wp_viewporter},
+    },
+    xdg::activation::v1::client::{xdg_activation_token_v1, xdg_activation_v1},
 };
 use wayland_protocols_wlr::layer_shell::v1::client::{
     zwlr_layer_shell_v1,
     zwlr_layer_surface_v1::{self, Anchor},
 };
 
-use config::{self, Config};
+use config::{
+    self,
+    general::{Gravity, InputPassthrough},
+    sorting::Sorting,
+    Config,
+};
 use dbus::{
     actions::Signal,
-    notification::{self, Notification},
+    notification::{self, Notification, NotificationAction, Urgency},
+};
+
+use crate::{
+    animation,
+    banner::{BannerRect, DrawContext},
+    cache::CachedLayout,
 };
+use render::{error::RenderError, font::FontCollection, image::ScaledImage, types::RectSize};
 
-use crate::{banner::BannerRect, cache::CachedLayout};
-use render::{font::FontCollection, types::RectSize};
+mod buffer_slots;
+mod subsurfaces;
+mod surface_outputs;
+
+use buffer_slots::BufferSlots;
+use subsurfaces::Subsurfaces;
+use surface_outputs::{OutputState, SurfaceOutputs};
 
 pub(super) struct Window {
     banners: IndexMap<u32, BannerRect>,
+    /// The ids of banners which cannot be drawn by their layouts and should be dropped.
+    failed_banners: Vec<u32>,
+    /// The reveal time of the last new banner which the next ones are staggered after.
+    last_revealed_at: Option<Instant>,
     font_collection: Rc<RefCell<FontCollection>>,
 
     rect_size: RectSize,
+    /// The width of every banner in the window. It's fitted to the widest banner content when
+    /// `max_width` is set.
+    banner_width: usize,
     margin: Margin,
+    offset: (u8, u8),
+    gap: u8,
+    anchor: config::general::Anchor,
+    outputs: SurfaceOutputs,
+    timeout_clock: config::general::TimeoutClock,
 
     compositor: Option<wl_compositor::WlCompositor>,
     layer_shell: Option<zwlr_layer_shell_v1::ZwlrLayerShellV1>,
@@ -54,11 +91,22 @@ pub(super) struct Window {
     shm: Option<wl_shm::WlShm>,
     shm_pool: Option<wl_shm_pool::WlShmPool>,
     buffer: Option<Buffer>,
-    wl_buffer: Option<wl_buffer::WlBuffer>,
+    buffer_slots: BufferSlots,
+    subsurfaces: Subsurfaces,
 
     configuration_state: ConfigurationState,
+    frame_ready: bool,
+    /// The draw is postponed because the compositor still reads both buffer slots.
+    pending_draw: bool,
     pointer_state: PointerState,
     cursor_manager: Option<wp_cursor_shape_manager_v1::WpCursorShapeManagerV1>,
+
+    seat: Option<wl_seat::WlSeat>,
+    activation: Option<xdg_activation_v1::XdgActivationV1>,
+    invoked_actions: Vec<Signal>,
+    copied_codes: Vec<String>,
+    /// The memory budget is exceeded, so the new banners don't animate their images.
+    memory_pressure: bool,
 }
 
 pub(super) enum ConfigurationState {
@@ -68,18 +116,37 @@ pub(super) enum ConfigurationState {
 }
 
 impl Window {
-    pub(super) fn init(font_collection: Rc<RefCell<FontCollection>>, config: &Config) -> Self {
-        debug!("Window: Initialized");
+    pub(super) fn init(
+        font_collection: Rc<RefCell<FontCollection>>,
+        config: &Config,
+        anchor: config::general::Anchor,
+    ) -> Self {
+        debug!(target: WINDOW, "Window: Initialized with anchor {anchor:?}");
 
         Self {
             banners: indexmap! {},
+            failed_banners: vec![],
+            last_revealed_at: None,
             font_collection,
 
             rect_size: RectSize::new(
                 config.general().width.into(),
                 config.general().height.into(),
             ),
+            banner_width: config.general().width.into(),
             margin: Margin::new(),
+            offset: *config
+                .general()
+                .offset
+                .by_anchor(&config.general().anchor, &anchor),
+            gap: config
+                .general()
+                .gap
+                .by_anchor(&config.general().anchor, &anchor)
+                .0,
+            anchor,
+            outputs: SurfaceOutputs::default(),
+            timeout_clock: config.general().timeout_clock,
 
             compositor: None,
             layer_shell: None,
@@ -89,15 +156,26 @@ impl Window {
             shm: None,
             shm_pool: None,
             buffer: None,
-            wl_buffer: None,
+            buffer_slots: Default::default(),
+            subsurfaces: Default::default(),
 
             configuration_state: ConfigurationState::NotConfiured,
+            frame_ready: true,
+            pending_draw: false,
             pointer_state: Default::default(),
             cursor_manager: None,
+
+            seat: None,
+            activation: None,
+            invoked_actions: vec![],
+            copied_codes: vec![],
+            memory_pressure: false,
         }
     }
 
-    pub(super) fn deinit(&self) {
+    pub(super) fn deinit(&mut self) {
+        self.subsurfaces.destroy();
+
         if let Some(layer_surface) = self.layer_surface.as_ref() {
             layer_surface.destroy();
         }
@@ -106,20 +184,25 @@ impl Window {
             surface.destroy();
         }
 
-        if let Some(buffer) = self.wl_buffer.as_ref() {
-            buffer.destroy();
-        }
+        self.buffer_slots.destroy();
 
         if let Some(shm_pool) = self.shm_pool.as_ref() {
             shm_pool.destroy()
         }
 
-        debug!("Window: Deinitialized");
+        debug!(target: WINDOW, "Window: Deinitialized");
+    }
+
+    pub(super) fn pick_buffer_format(&mut self) {
+        self.buffer_slots.pick_format();
     }
 
     pub(super) fn configure(&mut self, qhandle: &QueueHandle<Window>, config: &Config) {
         let Some(layer_shell) = self.layer_shell.as_ref() else {
-            error!("Tried to configure window when it doesn't have zwlr_layer_shell_v1");
+            error!(
+                target: WINDOW,
+                "Tried to configure window when it doesn't have zwlr_layer_shell_v1"
+            );
             return;
         };
 
@@ -128,19 +211,23 @@ impl Window {
                             "The wl_compositor protocol must be before than the zwlr-layer-shell-v1 protocol.\
                             If it is not correct, please contact to developers with this information"
                         ).create_surface(qhandle, ());
-        debug!("Window: Created surface");
+        debug!(target: WINDOW, "Window: Created surface");
 
         self.layer_surface = Some(layer_shell.get_layer_surface(
             &surface,
             None,
             zwlr_layer_shell_v1::Layer::Overlay,
-            "noti".to_string(),
+            config.general().layer_namespace.clone(),
             qhandle,
             (),
         ));
-        debug!("Window: Created layer surface");
+        debug!(
+            target: WINDOW,
+            "Window: Created layer surface with namespace {}",
+            config.general().layer_namespace
+        );
 
-        self.relocate(config.general().offset, &config.general().anchor);
+        self.relocate(self.offset, &self.anchor.clone());
 
         {
             let layer_surface = unsafe { self.layer_surface.as_ref().unwrap_unchecked() };
@@ -152,22 +239,40 @@ impl Window {
 
         self.surface = Some(surface);
 
-        debug!("Window: Configured");
+        debug!(target: WINDOW, "Window: Configured");
     }
 
     pub(super) fn reconfigure(&mut self, config: &Config) {
-        self.relocate(config.general().offset, &config.general().anchor);
+        let general = config.general();
+        self.gap = general.gap.by_anchor(&general.anchor, &self.anchor).0;
+        self.relocate(
+            *general.offset.by_anchor(&general.anchor, &self.anchor),
+            &self.anchor.clone(),
+        );
+        self.timeout_clock = config.general().timeout_clock;
         self.banners
-            .sort_by_values(config.general().sorting.get_cmp::<BannerRect>());
-        debug!("Window: Re-sorted the notification banners");
+            .sort_by_values(cmp_banners(&config.general().sorting));
+        self.banners
+            .values_mut()
+            .for_each(BannerRect::reset_content_width);
+        debug!(target: WINDOW, "Window: Re-sorted the notification banners");
 
-        debug!("Window: Reconfigured by updated config");
+        debug!(target: WINDOW, "Window: Reconfigured by updated config");
     }
 
-    fn relocate(&mut self, (x, y): (u8, u8), anchor_cfg: &config::general::Anchor) {
+    fn relocate(&mut self, offset: (u8, u8), anchor_cfg: &config::general::Anchor) {
+        self.offset = offset;
+        self.anchor = anchor_cfg.clone();
+
         if let Some(layer_surface) = self.layer_surface.as_ref() {
-            debug!("Window: Relocate to anchor {anchor_cfg:?} with offsets x - {x} and y - {y}");
-            self.margin = Margin::from_anchor(x as i32, y as i32, anchor_cfg);
+            let ((x, y), anchor_cfg) =
+                transform_anchor(offset, anchor_cfg, self.outputs.current().transform);
+
+            debug!(
+                target: WINDOW,
+                "Window: Relocate to anchor {anchor_cfg:?} with offsets x - {x} and y - {y}"
+            );
+            self.margin = Margin::from_anchor(x as i32, y as i32, &anchor_cfg);
 
             let anchor = match anchor_cfg {
                 config::general::Anchor::Top => Anchor::Top,
@@ -185,6 +290,105 @@ impl Window {
         }
     }
 
+    /// Applies the update of outputs and relocates the window when the transform of its output
+    /// is changed.
+    fn update_outputs(&mut self, update: impl FnOnce(&mut SurfaceOutputs)) {
+        let transform = self.outputs.current().transform;
+        update(&mut self.outputs);
+
+        let new_transform = self.outputs.current().transform;
+        if new_transform != transform {
+            debug!(target: WINDOW, "Window: Output transform changed to {new_transform:?}");
+            self.relocate(self.offset, &self.anchor.clone());
+        }
+    }
+
+    /// Returns the height of output in surface coordinates or `None` when it isn't known yet.
+    fn output_height(&self) -> Option<usize> {
+        let OutputState {
+            transform,
+            mode,
+            scale,
+        } = self.outputs.current();
+        let (width, height) = mode?;
+        Some(logical_output_height(width, height, scale, transform))
+    }
+
+    /// Returns the width of banners and the height which the expanded banner can take at most.
+    /// It's the height of output with margins or unlimited while the height is unknown.
+    fn max_banner_size(&self) -> RectSize {
+        let margin = (self.margin.top + self.margin.bottom).max(0) as usize;
+        RectSize::new(
+            self.banner_width,
+            self.output_height()
+                .map_or(u16::MAX as usize, |height| height.saturating_sub(margin)),
+        )
+    }
+
+    /// Returns the size which the expanded banner can take at most. It's the height of output
+    /// without other banners, so the expanded banner doesn't push them off the screen.
+    fn expanded_banner_size(&self, id: u32, config: &Config) -> RectSize {
+        let mut banner_size = self.max_banner_size();
+        let others_height: usize = self
+            .banners
+            .iter()
+            .filter(|(&other_id, _)| other_id != id)
+            .map(|(_, banner)| banner.height(config) + self.gap as usize)
+            .sum();
+        banner_size.height = banner_size.height.saturating_sub(others_height);
+        banner_size
+    }
+
+    /// Returns the heights of banners in order from the top of window to the bottom.
+    fn banner_heights(&self, config: &Config) -> Vec<usize> {
+        self.banners_from_top(config)
+            .into_iter()
+            .map(|banner| banner.height(config))
+            .collect()
+    }
+
+    /// Returns the number of banners which fit into the height of output with margins, so the
+    /// stack never exceeds the screen.
+    pub(super) fn banners_capacity(&self, config: &Config) -> usize {
+        let shown_heights: Vec<usize> = self
+            .banners
+            .values()
+            .map(|banner| banner.height(config))
+            .collect();
+        banners_capacity(
+            self.output_height(),
+            (self.margin.top + self.margin.bottom).max(0) as usize,
+            &shown_heights,
+            config.general().height.into(),
+            self.gap.into(),
+        )
+    }
+
+    /// Removes the last banners which don't fit into the output anymore, e.g. after the output
+    /// is rotated, and returns their notifications to hold them in the queue.
+    pub(super) fn take_overflowing_banners(&mut self, config: &Config) -> Vec<Notification> {
+        let capacity = self.banners_capacity(config);
+        if self.banners.len() <= capacity {
+            return vec![];
+        }
+
+        debug!(
+            target: WINDOW,
+            "Window: Took {} banners which exceed the output height",
+            self.banners.len() - capacity
+        );
+        self.banners
+            .split_off(capacity)
+            .into_values()
+            .map(BannerRect::destroy_and_get_notification)
+            .collect()
+    }
+
+    /// Returns the anchor which is used by the compositor after applying the output transform.
+    fn logical_anchor(&self) -> config::general::Anchor {
+        transform_anchor(self.offset, &self.anchor, self.outputs.current().transform).1
+    }
+
     pub(super) fn total_banners(&self) -> usize {
         self.banners.len()
     }
@@ -203,51 +407,95 @@ impl Window {
         config: &Config,
         cached_layouts: &CachedData<PathBuf, CachedLayout>,
     ) {
-        self.banners
-            .extend(notifications.into_iter().map(|notification| {
-                let mut banner_rect = BannerRect::init(notification);
-                banner_rect.draw(&self.font_collection.borrow(), config, cached_layouts);
-                (banner_rect.notification().id, banner_rect)
-            }));
+        let stagger = Duration::from_millis(config.general().stagger_ms.into());
+        for notification in notifications {
+            let mut banner_rect = BannerRect::init(notification, self.timeout_clock);
+            let revealed_at = next_reveal(self.last_revealed_at, Instant::now(), stagger);
+            self.last_revealed_at = Some(revealed_at);
+            banner_rect.reveal_at(revealed_at);
+            banner_rect.set_memory_pressure(self.memory_pressure);
+
+            // INFO: the banner is drawn in place, so it's found when the draw panics
+            let id = banner_rect.notification().id;
+            self.banners.insert(id, banner_rect);
+            self.redraw_banner(id, config, cached_layouts);
+        }
 
         self.banners
-            .sort_by_values(config.general().sorting.get_cmp::<BannerRect>());
-        debug!("Window: Sorted the notification banners");
+            .sort_by_values(cmp_banners(&config.general().sorting));
+        debug!(target: WINDOW, "Window: Sorted the notification banners");
 
-        debug!("Window: Completed update the notification banners")
+        debug!(target: WINDOW, "Window: Completed update the notification banners")
     }
 
+    /// Replaces the banners by the queued notifications with the same id. The banners which are
+    /// shown less than `min_display_ms` are kept, so their replacements are taken from the queue
+    /// and returned to be queued again.
     pub(super) fn replace_by_indices(
         &mut self,
         notifications: &mut VecDeque<Notification>,
         config: &Config,
         cached_layouts: &CachedData<PathBuf, CachedLayout>,
-    ) {
+    ) -> Vec<Notification> {
         let matching_indices: Vec<usize> = notifications
             .iter()
             .enumerate()
             .filter_map(|(i, notification)| self.banners.get(&notification.id).map(|_| i))
             .collect();
 
+        let min_display = config.general().min_display_ms as u128;
+        let mut held_notifications = vec![];
         for notification_index in matching_indices.into_iter().rev() {
             let notification = notifications.remove(notification_index).unwrap();
+            let id = notification.id;
 
-            let rect = &mut self.banners[&notification.id];
-            rect.update_data(notification);
-            rect.draw(&self.font_collection.borrow(), config, cached_layouts);
+            if !is_shown_long_enough(&self.banners[&id], min_display) {
+                debug!(target: WINDOW, "Window: Held the replacement of notification by id {id}");
+                held_notifications.push(notification);
+                continue;
+            }
 
-            debug!(
-                "Window: Replaced notification by id {}",
-                rect.notification().id
-            );
+            self.banners[&id].update_data(notification, self.timeout_clock);
+            self.redraw_banner(id, config, cached_layouts);
+
+            debug!(target: WINDOW, "Window: Replaced notification by id {id}");
         }
+
+        held_notifications.reverse();
+        held_notifications
+    }
+
+    /// Checks whether the banner with the id is shown at least `min_display_ms`, so it can be
+    /// replaced.
+    pub(super) fn is_replaceable(&self, notification_id: u32, config: &Config) -> bool {
+        self.banners
+            .get(&notification_id)
+            .is_some_and(|rect| is_shown_long_enough(rect, config.general().min_display_ms as u128))
+    }
+
+    /// Sets the image path of the banner by notification id and redraws it. Returns false when
+    /// there is no such banner.
+    pub(super) fn update_image_path(
+        &mut self,
+        notification_id: u32,
+        image_path: String,
+        config: &Config,
+        cached_layouts: &CachedData<PathBuf, CachedLayout>,
+    ) -> bool {
+        let Some(rect) = self.banners.get_mut(&notification_id) else {
+            return false;
+        };
+
+        rect.set_image_path(image_path);
+        self.redraw_banner(notification_id, config, cached_layouts);
+        true
     }
 
     pub(super) fn remove_banners_by_id(
         &mut self,
         notification_indices: &[u32],
     ) -> Vec<Notification> {
-        debug!("Window: Remove banners by id");
+        debug!(target: WINDOW, "Window: Remove banners by id");
 
         notification_indices
             .iter()
@@ -259,110 +507,383 @@ impl Window {
             .collect()
     }
 
+    /// Removes the banners of application.
+    pub(super) fn remove_banners_by_app(&mut self, app_name: &str) -> Vec<Notification> {
+        let notification_ids: Vec<u32> = self
+            .banners
+            .values()
+            .map(BannerRect::notification)
+            .filter(|notification| notification.app_name == app_name)
+            .map(|notification| notification.id)
+            .collect();
+
+        self.remove_banners_by_id(&notification_ids)
+    }
+
+    /// Removes all banners and returns their notifications in the order of banners.
+    pub(super) fn take_notifications(&mut self) -> Vec<Notification> {
+        self.take_banners()
+            .into_iter()
+            .map(BannerRect::destroy_and_get_notification)
+            .collect()
+    }
+
+    pub(super) fn take_banners(&mut self) -> Vec<BannerRect> {
+        debug!(target: WINDOW, "Window: Take all banners");
+
+        self.banners.drain(..).map(|(_, banner)| banner).collect()
+    }
+
+    /// Returns the timeout of banner in milliseconds or `None` if the banner never expires.
+    fn banner_timeout(rect: &BannerRect, config: &Config) -> Option<u128> {
+        let notification = rect.notification();
+        let timeout = match &notification.expire_timeout {
+            notification::Timeout::Millis(millis) => Some(*millis as u128),
+            notification::Timeout::Never => None,
+            notification::Timeout::Configurable => {
+                let timeout = config
+                    .display_by_notification(notification)
+                    .timeout
+                    .by_urgency(&notification.hints.urgency);
+                (timeout != 0).then_some(timeout as u128)
+            }
+        };
+
+        let general = config.general();
+        timeout.or_else(|| {
+            never_timeout_fallback(
+                notification,
+                general.never_timeout_fallback_ms,
+                general.never_timeout_fallback_critical,
+            )
+        })
+    }
+
+    pub(super) fn all_banners_never_expire(&self, config: &Config) -> bool {
+        !self.banners.is_empty()
+            && self
+                .banners
+                .values()
+                .all(|rect| Self::banner_timeout(rect, config).is_none())
+    }
+
+    /// Removes the oldest banner when it's shown at least `min_display_ms`.
+    pub(super) fn remove_oldest_banner(&mut self, config: &Config) -> Option<Notification> {
+        let rect = self
+            .banners
+            .values()
+            .min_by_key(|rect| (rect.notification().created_at, rect.notification().id))?;
+
+        if !is_shown_long_enough(rect, config.general().min_display_ms as u128) {
+            return None;
+        }
+
+        let id = rect.notification().id;
+
+        debug!(target: WINDOW, "Window: Remove the oldest banner with id {id}");
+        self.remove_banners_by_id(&[id]).pop()
+    }
+
+    /// Removes the banners which cannot be drawn by their layouts.
+    pub(super) fn remove_failed_banners(&mut self) -> Vec<Notification> {
+        if self.failed_banners.is_empty() {
+            return vec![];
+        }
+
+        let failed_banners = std::mem::take(&mut self.failed_banners);
+        debug!(target: WINDOW, "Window: Remove failed banners by indices: {failed_banners:?}");
+        self.remove_banners_by_id(&failed_banners)
+    }
+
     pub(super) fn remove_expired_banners(&mut self, config: &Config) -> Vec<Notification> {
         let indices_to_remove: Vec<u32> = self
             .banners
             .values()
-            .filter_map(|rect| match &rect.notification().expire_timeout {
-                notification::Timeout::Millis(millis) => {
-                    if rect.created_at().elapsed().as_millis() > *millis as u128 {
-                        Some(rect.notification().id)
-                    } else {
-                        None
-                    }
-                }
-                notification::Timeout::Never => None,
-                notification::Timeout::Configurable => {
-                    let notification = rect.notification();
-                    let timeout = config
-                        .display_by_app(&notification.app_name)
-                        .timeout
-                        .by_urgency(&notification.hints.urgency);
-                    if timeout != 0 && rect.created_at().elapsed().as_millis() > timeout as u128 {
-                        Some(rect.notification().id)
-                    } else {
-                        None
-                    }
-                }
+            .filter_map(|rect| {
+                Self::banner_timeout(rect, config)
+                    .filter(|timeout| {
+                        is_banner_expired(rect, *timeout, config.general().min_display_ms as u128)
+                    })
+                    .map(|_| rect.notification().id)
             })
             .collect();
 
         if indices_to_remove.is_empty() {
             vec![]
         } else {
-            debug!("Window: Remove expired banners by indices: {indices_to_remove:?}");
+            debug!(
+                target: WINDOW,
+                "Window: Remove expired banners by indices: {indices_to_remove:?}"
+            );
             self.remove_banners_by_id(&indices_to_remove)
         }
     }
 
     pub(super) fn handle_hover(&mut self, config: &Config) {
         if let Some(index) = self.get_hovered_banner(config) {
-            self.banners[&index].reset_timeout();
+            self.banners[&index].reset_timeout(self.timeout_clock);
 
             // INFO: because of every tracking pointer position, it emits very frequently and it's
             // annoying. So moved to 'TRACE' level for specific situations.
-            trace!("Window: Updated timeout of hovered notification banner with id {index}");
+            trace!(
+                target: WINDOW,
+                "Window: Updated timeout of hovered notification banner with id {index}"
+            );
         }
     }
 
+    /// Redraws the banners which show the relative time since the notification arrived when the
+    /// time is changed. Returns true when any banner is redrawn.
+    pub(super) fn refresh_ages(
+        &mut self,
+        config: &Config,
+        cached_layouts: &CachedData<PathBuf, CachedLayout>,
+    ) -> bool {
+        let outdated: Vec<u32> = self
+            .banners
+            .iter()
+            .filter(|(_, rect)| rect.is_age_outdated(config))
+            .map(|(&id, _)| id)
+            .collect();
+        for &id in &outdated {
+            self.redraw_banner(id, config, cached_layouts);
+        }
+
+        let refreshed = !outdated.is_empty();
+        if refreshed {
+            self.fit_width(config, cached_layouts);
+            trace!(target: WINDOW, "Window: Refreshed the relative time of banners");
+        }
+        refreshed
+    }
+
+    /// Highlights the hovered banner and restores the previously highlighted one when the option
+    /// `hover_highlight` is enabled or the theme of banner has the hover colors. Returns true
+    /// when any banner is redrawn.
+    pub(super) fn update_hover_highlight(
+        &mut self,
+        config: &Config,
+        cached_layouts: &CachedData<PathBuf, CachedLayout>,
+    ) -> bool {
+        let hovered = self.get_hovered_banner(config).filter(|id| {
+            config.general().hover_highlight.is_some()
+                || config
+                    .theme_by_notification(self.banners[id].notification())
+                    .hover
+                    .is_some()
+        });
+        let highlighted = self
+            .banners
+            .values()
+            .find(|banner| banner.is_highlighted())
+            .map(|banner| banner.notification().id);
+
+        if hovered == highlighted {
+            return false;
+        }
+
+        for id in [highlighted, hovered].into_iter().flatten() {
+            self.banners[&id].set_highlighted(Some(id) == hovered);
+            self.redraw_banner(id, config, cached_layouts);
+        }
+
+        trace!(
+            target: WINDOW,
+            "Window: Moved the hover highlight from {highlighted:?} to {hovered:?}"
+        );
+        true
+    }
+
+    /// Delays the timeouts of banners by the duration of pause. The banners which appeared
+    /// during the pause start their timeouts from now.
+    pub(super) fn delay_timeouts(&mut self, duration: Duration) {
+        self.banners
+            .values_mut()
+            .for_each(|banner| banner.delay_timeout(duration));
+    }
+
     pub(super) fn reset_timeouts(&mut self) {
         self.banners
             .values_mut()
-            .for_each(BannerRect::reset_timeout);
+            .for_each(|banner| banner.reset_timeout(self.timeout_clock));
     }
 
-    pub(super) fn handle_click(&mut self, config: &Config) -> Vec<Signal> {
+    pub(super) fn handle_click(
+        &mut self,
+        qhandle: &QueueHandle<Window>,
+        config: &Config,
+        cached_layouts: &CachedData<PathBuf, CachedLayout>,
+    ) -> Vec<Notification> {
         if let PrioritiedPressState::Unpressed = self.pointer_state.press_state {
             return vec![];
         }
+        let is_lmb = matches!(self.pointer_state.press_state, PrioritiedPressState::Lmb);
+        let is_mmb = matches!(self.pointer_state.press_state, PrioritiedPressState::Mmb);
         self.pointer_state.press_state.clear();
 
-        if let Some(id) = self.get_hovered_banner(config) {
-            if config.general().anchor.is_bottom() {
-                self.pointer_state.y -=
-                    config.general().height as f64 + config.general().gap as f64;
+        if let Some((id, banner_y)) = self.get_hovered_banner_point(config) {
+            debug!(target: WINDOW, "Window: Clicked to notification banner with id {id}");
+
+            let banner_x = self.pointer_state.x.max(0.0) as usize;
+            if is_lmb && self.banners[&id].is_over_show_more(banner_x, banner_y) {
+                self.expand_banner(id, qhandle, config, cached_layouts);
+                return vec![];
             }
 
-            debug!("Window: Clicked to notification banner with id {id}");
+            let notifications = self.remove_banners_by_id(&[id]);
+            if is_lmb {
+                notifications
+                    .iter()
+                    .filter(|notification| {
+                        notification
+                            .actions
+                            .iter()
+                            .any(|action| action.action_key == NotificationAction::DEFAULT_KEY)
+                    })
+                    .for_each(|notification| {
+                        self.invoke_action(qhandle, notification, NotificationAction::DEFAULT_KEY)
+                    });
+            } else if is_mmb {
+                self.copied_codes.extend(
+                    notifications
+                        .iter()
+                        .filter_map(Notification::otp_code)
+                        .map(ToString::to_string),
+                );
+            }
 
-            return self
-                .remove_banners_by_id(&[id])
-                .into_iter()
-                .map(|notification| Signal::NotificationClosed {
-                    notification_id: notification.id,
-                    reason: dbus::actions::ClosingReason::DismissedByUser,
-                })
-                .collect();
+            return notifications;
         }
 
         vec![]
     }
 
+    /// Expands the banner to its whole body instead of the dismiss by click on the "Show more"
+    /// label, so the window is resized to the new height of banner.
+    fn expand_banner(
+        &mut self,
+        id: u32,
+        qhandle: &QueueHandle<Window>,
+        config: &Config,
+        cached_layouts: &CachedData<PathBuf, CachedLayout>,
+    ) {
+        self.banners[&id].expand();
+        self.redraw_banner(id, config, cached_layouts);
+
+        self.draw(qhandle, config);
+        self.frame(qhandle);
+        self.commit();
+    }
+
+    /// Invokes the action of notification. When the compositor supports `xdg_activation_v1`, the
+    /// action is delayed until the activation token is received.
+    fn invoke_action(
+        &mut self,
+        qhandle: &QueueHandle<Window>,
+        notification: &Notification,
+        action_key: &str,
+    ) {
+        let invoked_action = InvokedAction {
+            notification_id: notification.id,
+            action_key: action_key.to_string(),
+        };
+
+        let (Some(activation), Some(seat)) = (self.activation.as_ref(), self.seat.as_ref()) else {
+            self.invoked_actions.push(invoked_action.into_signal(None));
+            return;
+        };
+
+        let token = activation.get_activation_token(qhandle, invoked_action);
+        token.set_serial(self.pointer_state.serial, seat);
+        if let Some(surface) = self.surface.as_ref() {
+            token.set_surface(surface);
+        }
+        if let Some(desktop_entry) = notification.hints.desktop_entry.as_ref() {
+            token.set_app_id(desktop_entry.to_owned());
+        }
+        token.commit();
+
+        debug!(
+            target: WINDOW,
+            "Window: Requested an activation token for notification with id {}",
+            notification.id
+        );
+    }
+
+    pub(super) fn take_invoked_actions(&mut self) -> Vec<Signal> {
+        std::mem::take(&mut self.invoked_actions)
+    }
+
+    /// Takes the one-time codes of banners which are clicked by the middle button.
+    pub(super) fn take_copied_codes(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.copied_codes)
+    }
+
+    /// Returns the number of scroll steps over the window since the last call, positive for
+    /// scrolling down.
+    pub(super) fn take_scroll_steps(&mut self) -> i32 {
+        self.pointer_state.take_scroll_steps()
+    }
+
+    /// Returns the id of the oldest banner or the newest one by the creation time of
+    /// notifications.
+    pub(super) fn oldest_or_newest_banner(&self, newest: bool) -> Option<u32> {
+        let by_creation =
+            |rect: &&BannerRect| (rect.notification().created_at, rect.notification().id);
+        let rect = match newest {
+            true => self.banners.values().max_by_key(by_creation),
+            false => self.banners.values().min_by_key(by_creation),
+        }?;
+
+        Some(rect.notification().id)
+    }
+
+    /// Finds the hovered banner by the rectangles of current banners which are stacked from the
+    /// anchored edge of window, so it doesn't depend on the window size when the pointer moved
+    /// last time.
     fn get_hovered_banner(&self, config: &Config) -> Option<u32> {
+        self.get_hovered_banner_point(config).map(|(id, _)| id)
+    }
+
+    /// Returns the id of hovered banner with the vertical position of pointer in the banner.
+    fn get_hovered_banner_point(&self, config: &Config) -> Option<(u32, usize)> {
         if !self.pointer_state.entered {
             return None;
         }
 
-        let rect_height = config.general().height as usize;
-        let gap = config.general().gap as usize;
-
-        let region_iter = (0..self.rect_size.height).step_by(rect_height + gap);
-        let finder = |(banner, rect_top): (&BannerRect, usize)| {
-            let rect_bottom = rect_top + rect_height;
-            (rect_top as f64..rect_bottom as f64)
-                .contains(&(self.pointer_state.y))
-                .then(|| banner.notification().id)
-        };
+        let (index, banner_y) = hovered_banner_index(
+            self.pointer_state.y,
+            &self.logical_anchor(),
+            &self.banner_heights(config),
+            self.gap as usize,
+        )?;
+
+        Some((
+            self.banners_from_top(config)[index].notification().id,
+            banner_y as usize,
+        ))
+    }
 
-        if config.general().anchor.is_top() {
-            self.banners
-                .values()
-                .rev()
-                .zip(region_iter)
-                .find_map(finder)
-        } else {
-            self.banners.values().zip(region_iter).find_map(finder)
+    /// Returns the banners in order from the top of window to the bottom.
+    fn banners_from_top(&self, config: &Config) -> Vec<&BannerRect> {
+        let mut banners: Vec<&BannerRect> = self.banners.values().collect();
+        if self.is_stack_reversed(config) {
+            banners.reverse();
         }
+
+        banners
+    }
+
+    fn is_stack_reversed(&self, config: &Config) -> bool {
+        is_stack_reversed(&self.logical_anchor(), config.general().gravity)
+    }
+
+    /// Moves the pointer to the position in the surface and keeps it relative to the anchored
+    /// edge of window.
+    fn relocate_pointer(&mut self, surface_x: f64, surface_y: f64) {
+        let anchored_y = anchored_y(&self.logical_anchor(), self.rect_size.height);
+        self.pointer_state
+            .relocate(surface_x, surface_y - anchored_y);
     }
 
     pub(super) fn redraw(
@@ -371,29 +892,366 @@ impl Window {
         config: &Config,
         cached_layouts: &CachedData<PathBuf, CachedLayout>,
     ) {
+        self.draw_banners(config, cached_layouts);
+
+        self.fit_width(config, cached_layouts);
+        self.draw(qhandle, config);
+
+        debug!(target: WINDOW, "Window: Redrawed banners");
+    }
+
+    fn draw_banners(
+        &mut self,
+        config: &Config,
+        cached_layouts: &CachedData<PathBuf, CachedLayout>,
+    ) {
+        let ids: Vec<u32> = self.banners.keys().copied().collect();
+        for id in ids {
+            self.redraw_banner(id, config, cached_layouts);
+        }
+    }
+
+    /// Draws the banner by id in the size which it can take. The banner is marked as failed when
+    /// it cannot be drawn by its layout, so it's dropped by `remove_failed_banners`. Returns
+    /// whether the banner is drawn.
+    fn redraw_banner(
+        &mut self,
+        id: u32,
+        config: &Config,
+        cached_layouts: &CachedData<PathBuf, CachedLayout>,
+    ) -> bool {
+        let max_size = match self.banners[&id].is_expanded() {
+            true => self.expanded_banner_size(id, config),
+            false => self.max_banner_size(),
+        };
+        let context = DrawContext {
+            font_collection: &self.font_collection.borrow(),
+            config,
+            cached_layouts,
+            max_size,
+            viewport_scaling: self.subsurfaces.is_supported(),
+            output_scale: self.outputs.current().scale.max(1) as u32,
+        };
+
+        let drawn = draw_banner(&mut self.banners[&id], &context);
+        if !drawn {
+            self.failed_banners.push(id);
+        }
+        drawn
+    }
+
+    /// Fits the width of banners to the widest banner content and redraws the banners when the
+    /// width is changed.
+    pub(super) fn fit_width(
+        &mut self,
+        config: &Config,
+        cached_layouts: &CachedData<PathBuf, CachedLayout>,
+    ) {
+        let content_width = self
+            .banners
+            .values()
+            .filter_map(BannerRect::content_width)
+            .max()
+            .unwrap_or_default();
+        let banner_width = render::banner::fit_width(content_width, config.general());
+        if banner_width == self.banner_width {
+            return;
+        }
+
+        self.banner_width = banner_width;
+        self.draw_banners(config, cached_layouts);
+
+        debug!(target: WINDOW, "Window: Fitted the width of banners to {banner_width}");
+    }
+
+    pub(super) fn set_memory_pressure(&mut self, memory_pressure: bool) {
+        self.memory_pressure = memory_pressure;
         self.banners
             .values_mut()
-            .for_each(|banner| banner.draw(&self.font_collection.borrow(), config, cached_layouts));
+            .for_each(|banner| banner.set_memory_pressure(memory_pressure));
+    }
+
+    /// Returns the approximate number of bytes which the drawn banners and the buffer take.
+    pub(super) fn approximate_bytes(&self) -> usize {
+        self.banners
+            .values()
+            .map(BannerRect::approximate_bytes)
+            .sum::<usize>()
+            + self.buffer.as_ref().map_or(0, Buffer::size)
+    }
+
+    /// Returns the ids of banners with the animated images, when they were drawn and how many
+    /// bytes their animations take.
+    pub(super) fn animations(&self) -> impl Iterator<Item = (u32, Instant, usize)> + '_ {
+        self.banners
+            .iter()
+            .filter(|(_, banner)| banner.animation_bytes() > 0)
+            .map(|(&id, banner)| (id, banner.drawn_at(), banner.animation_bytes()))
+    }
+
+    pub(super) fn evict_animation(&mut self, notification_id: u32) {
+        if let Some(banner) = self.banners.get_mut(&notification_id) {
+            banner.evict_animation();
+        }
+    }
+
+    /// Returns true when any banner should be redrawn for the next frame of its image, reveal
+    /// or animation.
+    pub(super) fn has_animations(&self) -> bool {
+        self.banners.values().any(|banner| {
+            banner.is_animation_frame_due()
+                || !banner.is_revealed()
+                || banner.is_playing_animation()
+        })
+    }
+
+    /// Returns true when the compositor is done with the previous frame and it's a good time to
+    /// draw the next one.
+    pub(super) fn is_frame_ready(&self) -> bool {
+        self.frame_ready
+    }
+
+    /// Returns true when the draw was postponed due busy buffers and one of them is released.
+    pub(super) fn has_pending_draw(&self) -> bool {
+        self.pending_draw && self.buffer_slots.has_free_slot()
+    }
+
+    pub(super) fn draw_animation_frames(&mut self, qhandle: &QueueHandle<Window>, config: &Config) {
+        self.banners
+            .values_mut()
+            .filter(|banner| banner.is_animation_frame_due())
+            .for_each(BannerRect::draw_animation_frame);
 
         self.draw(qhandle, config);
+        self.banners
+            .values_mut()
+            .for_each(BannerRect::update_animation);
 
-        debug!("Window: Redrawed banners");
+        trace!(target: WINDOW, "Window: Drew animation frames");
     }
 
     pub(super) fn draw(&mut self, qhandle: &QueueHandle<Window>, config: &Config) {
-        let gap = config.general().gap;
-
-        self.resize(RectSize::new(
-            config.general().width.into(),
-            self.banners.len() * config.general().height as usize
+        let gap = self.gap;
+        let banner_heights = self.banner_heights(config);
+        let rect_size = RectSize::new(
+            self.banner_width,
+            banner_heights.iter().sum::<usize>()
                 + self.banners.len().saturating_sub(1) * gap as usize,
-        ));
+        );
+
+        let frame_len = if self.subsurfaces.is_supported() {
+            banner_heights.iter().sum::<usize>() * self.banner_width * 4 + self.scaled_images_len()
+        } else {
+            rect_size.area() * 4
+        };
+        let Some(offset) = self.buffer_slots.acquire(frame_len) else {
+            self.pending_draw = true;
+            debug!(
+                target: WINDOW,
+                "Window: Both buffers are busy, postponed the draw until one is released"
+            );
+            return;
+        };
+        self.pending_draw = false;
+
+        self.resize(rect_size);
+
+        if self.subsurfaces.is_supported() {
+            self.create_buffer(qhandle, offset);
+            self.write_banners_to_buffer(&[], config);
+            self.write_scaled_images_to_buffer(config);
+            self.build_subsurfaces(qhandle, config);
+            self.update_opaque_region(qhandle, config);
+            self.update_input_region(qhandle, config);
+            return;
+        }
 
         let gap_buffer = self.allocate_gap_buffer(gap);
 
-        self.create_buffer(qhandle);
-        self.write_banners_to_buffer(&config.general().anchor, &gap_buffer);
+        self.create_buffer(qhandle, offset);
+        self.write_banners_to_buffer(&gap_buffer, config);
         self.build_buffer(qhandle);
+        self.update_opaque_region(qhandle, config);
+        self.update_input_region(qhandle, config);
+    }
+
+    /// Builds the buffers of banner subsurfaces over the transparent single-pixel buffer of
+    /// window surface instead of the one buffer with gaps.
+    fn build_subsurfaces(&mut self, qhandle: &QueueHandle<Window>, config: &Config) {
+        let (Some(surface), Some(compositor), Some(shm_pool), Some(buffer)) = (
+            self.surface.as_ref(),
+            self.compositor.as_ref(),
+            self.shm_pool.as_ref(),
+            self.buffer.as_ref(),
+        ) else {
+            return;
+        };
+
+        //INFO: The Buffer size only growth and it guarantee that shm_pool never shrinks
+        shm_pool.resize(buffer.size() as i32);
+
+        let reversed = self.is_stack_reversed(config);
+        let mut banners: Vec<&BannerRect> = self.banners.values().collect();
+        if reversed {
+            banners.reverse();
+        }
+        let banner_sizes: Vec<RectSize> = banners
+            .iter()
+            .map(|banner| RectSize::new(self.banner_width, banner.height(config)))
+            .collect();
+        let mut offset = 0;
+        for banner_size in &banner_sizes {
+            self.buffer_slots
+                .create_buffer(shm_pool, offset, banner_size, qhandle);
+            offset += banner_size.area() * 4;
+        }
+
+        // INFO: the images are written after the banners in the same order
+        for scaled_image in banners.iter().flat_map(|banner| banner.scaled_images()) {
+            self.buffer_slots
+                .create_buffer(shm_pool, offset, &scaled_image.source_size, qhandle);
+            offset += scaled_image.data.len();
+        }
+
+        let scaled_images: Vec<&[ScaledImage]> = banners
+            .iter()
+            .map(|banner| banner.scaled_images())
+            .collect();
+        self.subsurfaces.build(
+            surface,
+            compositor,
+            &self.rect_size,
+            &banner_sizes,
+            self.gap.into(),
+            &scaled_images,
+            qhandle,
+        );
+    }
+
+    /// Returns the length of data of images which are scaled by the compositor.
+    fn scaled_images_len(&self) -> usize {
+        self.banners
+            .values()
+            .flat_map(BannerRect::scaled_images)
+            .map(|scaled_image| scaled_image.data.len())
+            .sum()
+    }
+
+    /// Writes the images which are scaled by the compositor after the banners. They're faded
+    /// like their banners.
+    fn write_scaled_images_to_buffer(&mut self, config: &Config) {
+        let reversed = self.is_stack_reversed(config);
+        let opacities: Vec<f32> = self
+            .banners
+            .values()
+            .map(|rect| self.banner_opacity(rect, config))
+            .collect();
+        let Some(buffer) = self.buffer.as_mut() else {
+            return;
+        };
+
+        let mut banners: Vec<(&BannerRect, f32)> = self.banners.values().zip(opacities).collect();
+        if reversed {
+            banners.reverse();
+        }
+
+        for (rect, opacity) in banners {
+            for scaled_image in rect.scaled_images() {
+                buffer.push(&fade(&scaled_image.data, opacity));
+            }
+        }
+    }
+
+    /// Marks the rectangles of fully opaque banners as the opaque region of surface that lets
+    /// the compositor skip blending behind them.
+    fn update_opaque_region(&self, qhandle: &QueueHandle<Window>, config: &Config) {
+        let surface = unsafe { self.surface.as_ref().unwrap_unchecked() };
+        let Some(compositor) = self.compositor.as_ref() else {
+            return;
+        };
+
+        let width = self.banner_width as i32;
+        let banners = self.banners_from_top(config);
+
+        if self.subsurfaces.is_supported() {
+            surface.set_opaque_region(None);
+            let banner_sizes: Vec<RectSize> = banners
+                .iter()
+                .map(|banner| RectSize::new(self.banner_width, banner.height(config)))
+                .collect();
+            self.subsurfaces.set_opaque_regions(
+                compositor,
+                banners
+                    .into_iter()
+                    .map(|banner| self.is_banner_opaque(banner, config)),
+                &banner_sizes,
+                qhandle,
+            );
+            return;
+        }
+
+        let mut y = 0;
+        let opaque_banners: Vec<(i32, i32)> = banners
+            .into_iter()
+            .filter_map(|banner| {
+                let height = banner.height(config) as i32;
+                let banner_y = y;
+                y += height + self.gap as i32;
+                self.is_banner_opaque(banner, config)
+                    .then_some((banner_y, height))
+            })
+            .collect();
+
+        if opaque_banners.is_empty() {
+            surface.set_opaque_region(None);
+            trace!(target: WINDOW, "Window: Cleared the opaque region");
+            return;
+        }
+
+        let region = compositor.create_region(qhandle, ());
+        opaque_banners
+            .iter()
+            .for_each(|(y, height)| region.add(0, *y, width, *height));
+        surface.set_opaque_region(Some(&region));
+        region.destroy();
+
+        trace!(
+            target: WINDOW,
+            "Window: Set the opaque region for {} banners",
+            opaque_banners.len()
+        );
+    }
+
+    /// Sets the input region of surface by the `input_passthrough` option, so the pointer input
+    /// outside of it passes through to the windows below.
+    fn update_input_region(&self, qhandle: &QueueHandle<Window>, config: &Config) {
+        let surface = unsafe { self.surface.as_ref().unwrap_unchecked() };
+        let Some(compositor) = self.compositor.as_ref() else {
+            return;
+        };
+
+        let input_passthrough = config.general().input_passthrough;
+        if input_passthrough == InputPassthrough::None {
+            surface.set_input_region(None);
+            return;
+        }
+
+        let region = compositor.create_region(qhandle, ());
+        if input_passthrough == InputPassthrough::Gaps {
+            let width = self.banner_width as i32;
+            let mut y = 0;
+            for height in self.banner_heights(config) {
+                region.add(0, y, width, height as i32);
+                y += height as i32 + self.gap as i32;
+            }
+        }
+        surface.set_input_region(Some(&region));
+        region.destroy();
+
+        trace!(
+            target: WINDOW,
+            "Window: Set the input region with passthrough of {input_passthrough:?}"
+        );
     }
 
     fn resize(&mut self, rect_size: RectSize) {
@@ -403,6 +1261,7 @@ impl Window {
         layer_surface.set_size(self.rect_size.width as u32, self.rect_size.height as u32);
 
         debug!(
+            target: WINDOW,
             "Window: Resized to width - {}, height - {}",
             self.rect_size.width, self.rect_size.height
         );
@@ -414,36 +1273,79 @@ impl Window {
         vec![0; gap_size]
     }
 
-    fn write_banners_to_buffer(&mut self, anchor: &config::general::Anchor, gap_buffer: &[u8]) {
+    fn write_banners_to_buffer(&mut self, gap_buffer: &[u8], config: &Config) {
         fn write(buffer: Option<&mut Buffer>, data: &[u8]) {
             unsafe { buffer.unwrap_unchecked() }.push(data);
         }
 
-        let writer = |(i, rect): (usize, &BannerRect)| {
-            write(self.buffer.as_mut(), rect.framebuffer());
+        let reversed = self.is_stack_reversed(config);
+        let opacities: Vec<f32> = self
+            .banners
+            .values()
+            .map(|rect| self.banner_opacity(rect, config))
+            .collect();
+        let width = self.banner_width;
+        let writer = |(i, (rect, opacity)): (usize, (&BannerRect, f32))| {
+            let data = fade(rect.framebuffer(), opacity);
+            write(
+                self.buffer.as_mut(),
+                &animation::apply(data, width, rect.animation_frame()),
+            );
 
             if i < self.banners.len().saturating_sub(1) {
                 write(self.buffer.as_mut(), gap_buffer);
             }
         };
 
-        if anchor.is_top() {
-            self.banners.values().rev().enumerate().for_each(writer)
+        let banners = self.banners.values().zip(opacities);
+        if reversed {
+            banners.rev().enumerate().for_each(writer)
         } else {
-            self.banners.values().enumerate().for_each(writer)
+            banners.enumerate().for_each(writer)
         }
 
-        debug!("Window: Writed banners to buffer");
+        debug!(target: WINDOW, "Window: Writed banners to buffer");
     }
 
-    fn create_buffer(&mut self, qhandle: &QueueHandle<Window>) {
+    /// Returns the opacity of banner by the number of newer banners in the window.
+    fn banner_opacity(&self, rect: &BannerRect, config: &Config) -> f32 {
+        if !rect.is_revealed() {
+            return 0.0;
+        }
+
+        let age_opacity = &config.general().age_opacity;
+        if age_opacity.is_empty() {
+            return 1.0;
+        }
+
+        let by_creation =
+            |rect: &BannerRect| (rect.notification().created_at, rect.notification().id);
+        let newer_banners = self
+            .banners
+            .values()
+            .filter(|other| by_creation(other) > by_creation(rect))
+            .count();
+        age_opacity_at(age_opacity, newer_banners)
+    }
+
+    fn is_banner_opaque(&self, rect: &BannerRect, config: &Config) -> bool {
+        // INFO: the shaken banner uncovers the transparent edge
+        self.buffer_slots.is_format_opaque()
+            || (rect.is_opaque(config)
+                && self.banner_opacity(rect, config) >= 1.0
+                && rect.animation_frame().is_none())
+    }
+
+    /// Prepares the buffer to write the frame from the given offset.
+    fn create_buffer(&mut self, qhandle: &QueueHandle<Window>, offset: usize) {
         if self.buffer.is_some() {
             let buffer = unsafe { self.buffer.as_mut().unwrap_unchecked() };
-            buffer.reset();
+            buffer.reset(offset);
             return;
         }
 
-        let buffer = Buffer::new();
+        let mut buffer = Buffer::new();
+        buffer.reset(offset);
 
         if self.shm_pool.is_none() {
             self.shm_pool = Some(
@@ -461,14 +1363,10 @@ impl Window {
 
         self.buffer = Some(buffer);
 
-        debug!("Window: Created buffer");
+        debug!(target: WINDOW, "Window: Created buffer");
     }
 
     fn build_buffer(&mut self, qhandle: &QueueHandle<Window>) {
-        if let Some(wl_buffer) = self.wl_buffer.as_ref() {
-            wl_buffer.destroy();
-        }
-
         assert!(
             self.shm_pool.is_some() && self.buffer.is_some(),
             "The buffer must be created before build!"
@@ -486,85 +1384,339 @@ impl Window {
         shm_pool
             .resize(unsafe { self.buffer.as_ref().map(Buffer::size).unwrap_unchecked() } as i32);
 
-        self.wl_buffer = Some(shm_pool.create_buffer(
-            0,
-            self.rect_size.width as i32,
-            self.rect_size.height as i32,
-            self.rect_size.width as i32 * 4,
-            wl_shm::Format::Argb8888,
-            qhandle,
-            (),
-        ));
+        self.buffer_slots
+            .create_buffer(shm_pool, 0, &self.rect_size, qhandle);
 
-        debug!("Window: Builded buffer");
+        debug!(target: WINDOW, "Window: Builded buffer");
     }
 
-    pub(super) fn frame(&self, qhandle: &QueueHandle<Window>) {
+    pub(super) fn frame(&mut self, qhandle: &QueueHandle<Window>) {
+        if self.pending_draw {
+            return;
+        }
+
+        self.frame_ready = false;
+
         let surface = unsafe { self.surface.as_ref().unwrap_unchecked() };
         surface.damage(0, 0, i32::MAX, i32::MAX);
         surface.frame(qhandle, ());
-        surface.attach(self.wl_buffer.as_ref(), 0, 0);
 
-        debug!("Window: Requested a frame to the Wayland compositor");
+        if self.subsurfaces.is_supported() {
+            self.subsurfaces
+                .attach(surface, self.buffer_slots.buffers());
+        } else {
+            surface.attach(self.buffer_slots.buffers().next(), 0, 0);
+        }
+        self.buffer_slots.mark_attached();
+
+        debug!(target: WINDOW, "Window: Requested a frame to the Wayland compositor");
     }
 
     pub(super) fn commit(&self) {
         let surface = unsafe { self.surface.as_ref().unwrap_unchecked() };
         surface.commit();
 
-        debug!("Window: Commited")
+        debug!(target: WINDOW, "Window: Commited")
+    }
+}
+
+trait SortByValues<K, V> {
+    fn sort_by_values(&mut self, cmp: impl FnMut(&V, &V) -> Ordering);
+}
+
+impl<K, V> SortByValues<K, V> for IndexMap<K, V> {
+    fn sort_by_values(&mut self, mut cmp: impl FnMut(&V, &V) -> Ordering) {
+        self.sort_by(|_, lhs, _, rhs| cmp(lhs, rhs));
+    }
+}
+
+/// Returns the comparator of banners by the sorting. The banners which are equal by it are
+/// ordered by creation, so their order never changes between redraws.
+fn cmp_banners(sorting: &Sorting) -> impl Fn(&BannerRect, &BannerRect) -> Ordering {
+    let cmp = sorting.get_cmp::<BannerRect>();
+    move |lhs, rhs| cmp(lhs, rhs).then_with(|| lhs.sequence().cmp(&rhs.sequence()))
+}
+
+struct Buffer {
+    file: File,
+    cursor: u64,
+    size: usize,
+}
+
+impl Buffer {
+    fn new() -> Self {
+        debug!(target: WINDOW, "Buffer: Trying to create");
+        Self {
+            file: tempfile::tempfile().expect("The tempfile must be created"),
+            cursor: 0,
+            size: 0,
+        }
+    }
+
+    fn reset(&mut self, offset: usize) {
+        self.cursor = offset as u64;
+        debug!(target: WINDOW, "Buffer: Reset to offset {offset}");
+    }
+
+    fn push(&mut self, data: &[u8]) {
+        self.file
+            .write_all_at(data, self.cursor)
+            .expect("Must be possibility to write into file!");
+        self.cursor += data.len() as u64;
+
+        self.size = std::cmp::max(self.size, self.cursor as usize);
+
+        debug!(target: WINDOW, "Buffer: Received a data to write")
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn as_fd(&self) -> BorrowedFd {
+        self.file.as_fd()
+    }
+}
+
+/// Draws the banner and branches on the render error. Returns false when the banner cannot be
+/// drawn by its layout and should be dropped. The banners which have no buffer to draw into are
+/// left empty until the next draw.
+fn draw_banner(rect: &mut BannerRect, context: &DrawContext) -> bool {
+    match rect.draw(context) {
+        Ok(()) => true,
+        Err(err @ RenderError::Layout { .. }) => {
+            error!(
+                target: WINDOW,
+                "Banner (id={}): {err}, dropping the banner",
+                rect.notification().id
+            );
+            false
+        }
+        Err(err) => {
+            warn!(
+                target: WINDOW,
+                "Banner (id={}): {err}, retrying on the next draw",
+                rect.notification().id
+            );
+            true
+        }
+    }
+}
+
+/// Returns the time when the new banner is revealed. It follows the previous reveal by the
+/// stagger delay, so the banners which appear at once are revealed one by one.
+fn next_reveal(last_revealed_at: Option<Instant>, now: Instant, stagger: Duration) -> Instant {
+    last_revealed_at.map_or(now, |last_revealed_at| {
+        (last_revealed_at + stagger).max(now)
+    })
+}
+
+/// Returns the opacity of banner with the given number of newer banners from the `age_opacity`
+/// list.
+fn age_opacity_at(age_opacity: &[f32], newer_banners: usize) -> f32 {
+    age_opacity
+        .get(newer_banners)
+        .or(age_opacity.last())
+        .map_or(1.0, |opacity| opacity.clamp(0.0, 1.0))
+}
+
+/// Multiplies the premultiplied BGRA data by the opacity. The data is borrowed when the opacity
+/// doesn't change it.
+fn fade(data: &[u8], opacity: f32) -> Cow<'_, [u8]> {
+    if opacity >= 1.0 {
+        return Cow::Borrowed(data);
+    }
+
+    Cow::Owned(
+        data.iter()
+            .map(|channel| (*channel as f32 * opacity).round() as u8)
+            .collect(),
+    )
+}
+
+/// Returns true when the banners are placed from the top of window in reverse order, so the last
+/// banner by sorting is at the top. It's placed at the anchored corner or at the far end of stack
+/// by the gravity.
+fn is_stack_reversed(anchor: &config::general::Anchor, gravity: Gravity) -> bool {
+    anchor.is_top() == (gravity == Gravity::Corner)
+}
+
+/// Returns the vertical position in the window of given height which stays in place on the screen
+/// when the window is resized: the top edge, the bottom edge or the center by the anchor.
+fn anchored_y(anchor: &config::general::Anchor, height: usize) -> f64 {
+    if anchor.is_top() {
+        0.0
+    } else if anchor.is_bottom() {
+        height as f64
+    } else {
+        height as f64 / 2.0
     }
 }
 
-trait SortByValues<K, V> {
-    fn sort_by_values(&mut self, cmp: for<'a> fn(&'a V, &'a V) -> Ordering);
-}
+/// Returns the index of banner from the top of window which is under the pointer with the
+/// vertical position of pointer in the banner. The pointer position is relative to the anchored
+/// edge of window and the banners have the given heights from the top.
+fn hovered_banner_index(
+    pointer_y: f64,
+    anchor: &config::general::Anchor,
+    heights: &[usize],
+    gap: usize,
+) -> Option<(usize, f64)> {
+    let stack_height =
+        (heights.iter().map(|height| height + gap).sum::<usize>()).saturating_sub(gap);
+    let mut y = pointer_y + anchored_y(anchor, stack_height);
+    if y < 0.0 {
+        return None;
+    }
 
-impl<K, V> SortByValues<K, V> for IndexMap<K, V> {
-    fn sort_by_values(&mut self, cmp: for<'a> fn(&'a V, &'a V) -> Ordering) {
-        self.sort_by(|_, lhs, _, rhs| cmp(lhs, rhs));
+    for (index, height) in heights.iter().enumerate() {
+        if y < *height as f64 {
+            return Some((index, y));
+        }
+
+        y -= (height + gap) as f64;
+        if y < 0.0 {
+            return None;
+        }
     }
+
+    None
 }
 
-struct Buffer {
-    file: File,
-    cursor: u64,
-    size: usize,
+/// Checks whether the banner which is shown for `elapsed` milliseconds is expired. The banner
+/// stays at least `min_display` milliseconds even when its timeout is shorter.
+fn is_expired(elapsed: u128, timeout: u128, min_display: u128) -> bool {
+    elapsed > timeout.max(min_display)
 }
 
-impl Buffer {
-    fn new() -> Self {
-        debug!("Buffer: Trying to create");
-        Self {
-            file: tempfile::tempfile().expect("The tempfile must be created"),
-            cursor: 0,
-            size: 0,
-        }
-    }
+/// Checks whether the banner is expired by its timeout and `min_display` milliseconds.
+fn is_banner_expired(rect: &BannerRect, timeout: u128, min_display: u128) -> bool {
+    is_expired(rect.shown_for().as_millis(), timeout, min_display)
+}
 
-    fn reset(&mut self) {
-        self.cursor = 0;
-        debug!("Buffer: Reset");
-    }
+/// Checks whether the banner is shown at least `min_display` milliseconds, so it can be removed
+/// or replaced.
+fn is_shown_long_enough(rect: &BannerRect, min_display: u128) -> bool {
+    rect.shown_for().as_millis() >= min_display
+}
 
-    fn push(&mut self, data: &[u8]) {
-        self.file
-            .write_all_at(data, self.cursor)
-            .expect("Must be possibility to write into file!");
-        self.cursor += data.len() as u64;
+/// Returns the size of window after the `configure` event of layer surface.
+///
+/// The compositor sends zero for the dimensions which are left for the client to decide, so each
+/// zero dimension keeps the last computed size of the banner stack instead of snapping back to
+/// the size of single banner from config.
+fn configured_size(current: &RectSize, width: u32, height: u32) -> RectSize {
+    RectSize::new(
+        match width {
+            0 => current.width,
+            width => width as usize,
+        },
+        match height {
+            0 => current.height,
+            height => height as usize,
+        },
+    )
+}
 
-        self.size = std::cmp::max(self.size, self.cursor as usize);
+/// Returns the height of output in surface coordinates from the size of its mode which is
+/// rotated by the transform and divided by the scale.
+fn logical_output_height(
+    width: i32,
+    height: i32,
+    scale: i32,
+    transform: wl_output::Transform,
+) -> usize {
+    let height = match transform {
+        wl_output::Transform::_90
+        | wl_output::Transform::_270
+        | wl_output::Transform::Flipped90
+        | wl_output::Transform::Flipped270 => width,
+        _ => height,
+    };
+    (height / scale.max(1)).max(0) as usize
+}
 
-        debug!("Buffer: Received a data to write")
+/// Returns the number of banners with gaps between them which fit into the output height. The
+/// shown banners take their heights, e.g. the expanded ones, and the rest have the configured
+/// height. The capacity is unlimited while the height is unknown.
+fn banners_capacity(
+    output_height: Option<usize>,
+    margin: usize,
+    shown_heights: &[usize],
+    banner_height: usize,
+    gap: usize,
+) -> usize {
+    let Some(output_height) = output_height else {
+        return usize::MAX;
+    };
+
+    let mut room = output_height.saturating_sub(margin) + gap;
+    for (fitted, height) in shown_heights.iter().enumerate() {
+        match room.checked_sub(height + gap) {
+            Some(rest) => room = rest,
+            // INFO: one banner is shown anyway even when it's taller than the output
+            None => return fitted.max(1),
+        }
     }
 
-    fn size(&self) -> usize {
-        self.size
-    }
+    (shown_heights.len() + room / (banner_height + gap).max(1)).max(1)
+}
 
-    fn as_fd(&self) -> BorrowedFd {
-        self.file.as_fd()
+/// Maps the anchor and offsets which are configured for the physical edges of the output to the
+/// logical ones which the compositor uses for layer surfaces of transformed (rotated or flipped)
+/// outputs.
+fn transform_anchor(
+    (x, y): (u8, u8),
+    anchor: &config::general::Anchor,
+    transform: wl_output::Transform,
+) -> ((u8, u8), config::general::Anchor) {
+    use config::general::Anchor as A;
+
+    // INFO: clockwise order of anchors where each step is 45 degrees
+    const ORDER: [A; 8] = [
+        A::Top,
+        A::TopRight,
+        A::Right,
+        A::BottomRight,
+        A::Bottom,
+        A::BottomLeft,
+        A::Left,
+        A::TopLeft,
+    ];
+
+    let (flipped, quarter_turns) = match transform {
+        wl_output::Transform::_90 => (false, 1),
+        wl_output::Transform::_180 => (false, 2),
+        wl_output::Transform::_270 => (false, 3),
+        wl_output::Transform::Flipped => (true, 0),
+        wl_output::Transform::Flipped90 => (true, 1),
+        wl_output::Transform::Flipped180 => (true, 2),
+        wl_output::Transform::Flipped270 => (true, 3),
+        _ => (false, 0),
+    };
+
+    let mut position = match anchor {
+        A::Top => 0,
+        A::TopRight => 1,
+        A::Right => 2,
+        A::BottomRight => 3,
+        A::Bottom => 4,
+        A::BottomLeft => 5,
+        A::Left => 6,
+        A::TopLeft => 7,
+    };
+
+    if flipped {
+        position = (8 - position) % 8;
     }
+    position = (position + quarter_turns * 2) % 8;
+
+    let offset = if quarter_turns % 2 == 1 {
+        (y, x)
+    } else {
+        (x, y)
+    };
+    (offset, ORDER[position].clone())
 }
 
 struct Margin {
@@ -611,10 +1763,23 @@ impl Margin {
 #[derive(Default)]
 struct PointerState {
     x: f64,
+    /// The vertical position relative to the anchored edge of window which stays valid when the
+    /// window is resized by removing banners.
     y: f64,
+    serial: u32,
 
     entered: bool,
     press_state: PrioritiedPressState,
+    /// The accumulated vertical scroll of touchpads and other continuous sources which isn't
+    /// turned into steps yet.
+    scroll: f64,
+    /// The whole steps of mouse wheels which aren't taken yet.
+    wheel_steps: i32,
+    /// The rest of high-resolution wheel scroll in fractions of 120 per step.
+    wheel_value120: i32,
+    /// Whether the current pointer frame has the discrete wheel scroll, so its continuous value
+    /// isn't counted twice.
+    wheel_frame: bool,
 }
 
 /// Mouse button press state which have priority (LMB > RMB > MMB) if any is set at least,
@@ -655,17 +1820,23 @@ impl PointerState {
     const RIGHT_BTN: u32 = 273;
     const MIDDLE_BTN: u32 = 274;
 
+    /// The scroll distance of one step which is the distance of one wheel click in most
+    /// compositors.
+    const SCROLL_STEP: f64 = 10.0;
+
     fn leave(&mut self) {
         self.entered = false;
+        self.scroll = 0.0;
+        self.wheel_steps = 0;
+        self.wheel_value120 = 0;
+        self.wheel_frame = false;
 
-        debug!("Pointer: Left");
+        debug!(target: WINDOW, "Pointer: Left");
     }
 
-    fn enter_and_relocate(&mut self, x: f64, y: f64) {
+    fn enter(&mut self) {
         self.entered = true;
-        debug!("Pointer: Entered");
-
-        self.relocate(x, y);
+        debug!(target: WINDOW, "Pointer: Entered");
     }
 
     fn relocate(&mut self, x: f64, y: f64) {
@@ -674,11 +1845,52 @@ impl PointerState {
 
         // INFO: Pointer state updates very frequently so in 'DEBUG' level rows will be filled with
         // useless information about pointer. So moved into 'TRACE' level.
-        trace!("Pointer: Relocate to x - {x}, y - {y}")
+        trace!(target: WINDOW, "Pointer: Relocate to x - {x}, y - {y}")
+    }
+
+    /// Accumulates the continuous scroll. It's skipped in the frames of wheel scroll which is
+    /// counted by its discrete steps.
+    fn scroll(&mut self, value: f64) {
+        if self.wheel_frame {
+            return;
+        }
+
+        self.scroll += value;
+        trace!(target: WINDOW, "Pointer: Scrolled by {value}");
+    }
+
+    /// Counts the wheel clicks of `wl_pointer.axis_discrete` event.
+    fn scroll_discrete(&mut self, steps: i32) {
+        self.wheel_frame = true;
+        self.wheel_steps += steps;
+        trace!(target: WINDOW, "Pointer: Scrolled by {steps} wheel steps");
+    }
+
+    /// Counts the high-resolution wheel scroll of `wl_pointer.axis_value120` event where 120 is
+    /// one wheel click.
+    fn scroll_value120(&mut self, value120: i32) {
+        self.wheel_frame = true;
+        self.wheel_value120 += value120;
+        self.wheel_steps += self.wheel_value120 / 120;
+        self.wheel_value120 %= 120;
+        trace!(target: WINDOW, "Pointer: Scrolled by {value120}/120 wheel steps");
+    }
+
+    fn end_frame(&mut self) {
+        self.wheel_frame = false;
+    }
+
+    /// Returns the number of whole scroll steps, positive for scrolling down, and keeps the rest
+    /// of scroll for the next steps.
+    fn take_scroll_steps(&mut self) -> i32 {
+        let steps = (self.scroll / Self::SCROLL_STEP).trunc();
+        self.scroll -= steps * Self::SCROLL_STEP;
+        steps as i32 + std::mem::take(&mut self.wheel_steps)
     }
 
-    fn press(&mut self, button: u32) {
-        debug!("Pointer: Pressed button {button}");
+    fn press(&mut self, button: u32, serial: u32) {
+        debug!(target: WINDOW, "Pointer: Pressed button {button}");
+        self.serial = serial;
         match button {
             PointerState::LEFT_BTN => self.press_state.update(PrioritiedPressState::Lmb),
             PointerState::RIGHT_BTN => self.press_state.update(PrioritiedPressState::Rmb),
@@ -711,16 +1923,31 @@ impl Dispatch<wl_registry::WlRegistry, ()> for Window {
                         qhandle,
                         (),
                     ));
-                    debug!("Window: Bound the wl_compositor");
+                    debug!(target: WINDOW, "Window: Bound the wl_compositor");
                 }
                 "wl_shm" => {
                     state.shm =
                         Some(registry.bind::<wl_shm::WlShm, _, _>(name, version, qhandle, ()));
-                    debug!("Window: Bound the wl_shm");
+                    debug!(target: WINDOW, "Window: Bound the wl_shm");
+                }
+                "wl_output" => {
+                    registry.bind::<wl_output::WlOutput, _, _>(name, version.min(4), qhandle, ());
+                    debug!(target: WINDOW, "Window: Bound the wl_output");
                 }
                 "wl_seat" => {
-                    registry.bind::<wl_seat::WlSeat, _, _>(name, version, qhandle, ());
-                    debug!("Window: Bound the wl_seat");
+                    state.seat =
+                        Some(registry.bind::<wl_seat::WlSeat, _, _>(name, version, qhandle, ()));
+                    debug!(target: WINDOW, "Window: Bound the wl_seat");
+                }
+                "xdg_activation_v1" => {
+                    state.activation =
+                        Some(registry.bind::<xdg_activation_v1::XdgActivationV1, _, _>(
+                            name,
+                            version,
+                            qhandle,
+                            (),
+                        ));
+                    debug!(target: WINDOW, "Window: Bound the xdg_activation_v1");
                 }
                 "zwlr_layer_shell_v1" => {
                     state.layer_shell = Some(
@@ -731,10 +1958,40 @@ impl Dispatch<wl_registry::WlRegistry, ()> for Window {
                             (),
                         ),
                     );
-                    debug!("Window: Bound the zwlr_layer_shell_v1");
+                    debug!(target: WINDOW, "Window: Bound the zwlr_layer_shell_v1");
 
                     state.configuration_state = ConfigurationState::Ready;
-                    debug!("Window: Ready to configure")
+                    debug!(target: WINDOW, "Window: Ready to configure")
+                }
+                "wl_subcompositor" => {
+                    state.subsurfaces.subcompositor =
+                        Some(registry.bind::<wl_subcompositor::WlSubcompositor, _, _>(
+                            name,
+                            version,
+                            qhandle,
+                            (),
+                        ));
+                    debug!(target: WINDOW, "Window: Bound the wl_subcompositor");
+                }
+                "wp_viewporter" => {
+                    state.subsurfaces.viewporter =
+                        Some(registry.bind::<wp_viewporter::WpViewporter, _, _>(
+                            name,
+                            version,
+                            qhandle,
+                            (),
+                        ));
+                    debug!(target: WINDOW, "Window: Bound the wp_viewporter");
+                }
+                "wp_single_pixel_buffer_manager_v1" => {
+                    state.subsurfaces.single_pixel_buffer_manager = Some(
+                        registry.bind::<
+                            wp_single_pixel_buffer_manager_v1::WpSinglePixelBufferManagerV1,
+                            _,
+                            _,
+                        >(name, version, qhandle, ()),
+                    );
+                    debug!(target: WINDOW, "Window: Bound the wp_single_pixel_buffer_manager_v1");
                 }
                 "wp_cursor_shape_manager_v1" => {
                     state.cursor_manager = Some(
@@ -746,7 +2003,7 @@ impl Dispatch<wl_registry::WlRegistry, ()> for Window {
                         ),
                     );
 
-                    debug!("Window: Bound the wp_cursor_shape_manager_v1");
+                    debug!(target: WINDOW, "Window: Bound the wp_cursor_shape_manager_v1");
                 }
                 _ => (),
             }
@@ -755,14 +2012,108 @@ impl Dispatch<wl_registry::WlRegistry, ()> for Window {
 }
 
 delegate_noop!(Window: ignore wl_compositor::WlCompositor);
-delegate_noop!(Window: ignore wl_surface::WlSurface);
+delegate_noop!(Window: ignore wl_region::WlRegion);
 delegate_noop!(Window: ignore zwlr_layer_shell_v1::ZwlrLayerShellV1);
-delegate_noop!(Window: ignore wl_shm::WlShm);
 delegate_noop!(Window: ignore wl_shm_pool::WlShmPool);
 delegate_noop!(Window: ignore wl_buffer::WlBuffer);
-delegate_noop!(Window: ignore wl_callback::WlCallback);
 delegate_noop!(Window: ignore wp_cursor_shape_manager_v1::WpCursorShapeManagerV1);
 delegate_noop!(Window: ignore wp_cursor_shape_device_v1::WpCursorShapeDeviceV1);
+delegate_noop!(Window: ignore xdg_activation_v1::XdgActivationV1);
+delegate_noop!(Window: ignore wl_subcompositor::WlSubcompositor);
+delegate_noop!(Window: ignore wl_subsurface::WlSubsurface);
+delegate_noop!(Window: ignore wp_viewporter::WpViewporter);
+delegate_noop!(Window: ignore wp_viewport::WpViewport);
+delegate_noop!(Window: ignore wp_single_pixel_buffer_manager_v1::WpSinglePixelBufferManagerV1);
+
+/// The action which waits for an activation token to be emitted.
+#[derive(Clone)]
+struct InvokedAction {
+    notification_id: u32,
+    action_key: String,
+}
+
+impl InvokedAction {
+    fn into_signal(self, activation_token: Option<String>) -> Signal {
+        Signal::ActionInvoked {
+            notification_id: self.notification_id,
+            action_key: self.action_key,
+            activation_token,
+        }
+    }
+}
+
+impl Dispatch<xdg_activation_token_v1::XdgActivationTokenV1, InvokedAction> for Window {
+    fn event(
+        state: &mut Self,
+        activation_token: &xdg_activation_token_v1::XdgActivationTokenV1,
+        event: <xdg_activation_token_v1::XdgActivationTokenV1 as wayland_client::Proxy>::Event,
+        invoked_action: &InvokedAction,
+        _conn: &wayland_client::Connection,
+        _qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        if let xdg_activation_token_v1::Event::Done { token } = event {
+            debug!(
+                target: WINDOW,
+                "Window: Received an activation token for notification with id {}",
+                invoked_action.notification_id
+            );
+
+            state
+                .invoked_actions
+                .push(invoked_action.clone().into_signal(Some(token)));
+            activation_token.destroy();
+        }
+    }
+}
+
+impl Dispatch<wl_shm::WlShm, ()> for Window {
+    fn event(
+        state: &mut Self,
+        _shm: &wl_shm::WlShm,
+        event: <wl_shm::WlShm as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &wayland_client::Connection,
+        _qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        if let wl_shm::Event::Format {
+            format: WEnum::Value(format),
+        } = event
+        {
+            trace!(target: WINDOW, "Window: The wl_shm supports the {format:?} format");
+            state.buffer_slots.add_format(format);
+        }
+    }
+}
+
+impl Dispatch<wl_buffer::WlBuffer, usize> for Window {
+    fn event(
+        state: &mut Self,
+        buffer: &wl_buffer::WlBuffer,
+        event: <wl_buffer::WlBuffer as wayland_client::Proxy>::Event,
+        slot: &usize,
+        _conn: &wayland_client::Connection,
+        _qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        if let wl_buffer::Event::Release = event {
+            state.buffer_slots.release(*slot, buffer);
+        }
+    }
+}
+
+impl Dispatch<wl_callback::WlCallback, ()> for Window {
+    fn event(
+        state: &mut Self,
+        _callback: &wl_callback::WlCallback,
+        event: <wl_callback::WlCallback as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &wayland_client::Connection,
+        _qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        if let wl_callback::Event::Done { .. } = event {
+            state.frame_ready = true;
+        }
+    }
+}
 
 impl Dispatch<wl_seat::WlSeat, ()> for Window {
     fn event(
@@ -779,8 +2130,70 @@ impl Dispatch<wl_seat::WlSeat, ()> for Window {
         {
             if capability.contains(wl_seat::Capability::Pointer) {
                 seat.get_pointer(qhandle, ());
-                debug!("Window: Received a pointer");
+                debug!(target: WINDOW, "Window: Received a pointer");
+            }
+        }
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, ()> for Window {
+    fn event(
+        state: &mut Self,
+        output: &wl_output::WlOutput,
+        event: <wl_output::WlOutput as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &wayland_client::Connection,
+        _qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        let output_id = output.id().protocol_id();
+        match event {
+            wl_output::Event::Geometry {
+                transform: WEnum::Value(transform),
+                ..
+            } => state.update_outputs(|outputs| {
+                outputs.update(output_id, |output| output.transform = transform)
+            }),
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                ..
+            } if flags.contains(wl_output::Mode::Current) => {
+                state
+                    .outputs
+                    .update(output_id, |output| output.mode = Some((width, height)));
+                debug!(
+                    target: WINDOW,
+                    "Window: Mode of output {output_id} changed to {width}x{height}"
+                );
+            }
+            wl_output::Event::Scale { factor } => state
+                .outputs
+                .update(output_id, |output| output.scale = factor),
+            _ => (),
+        }
+    }
+}
+
+impl Dispatch<wl_surface::WlSurface, ()> for Window {
+    fn event(
+        state: &mut Self,
+        _surface: &wl_surface::WlSurface,
+        event: <wl_surface::WlSurface as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &wayland_client::Connection,
+        _qhandle: &wayland_client::QueueHandle<Self>,
+    ) {
+        match event {
+            wl_surface::Event::Enter { output } => {
+                debug!(target: WINDOW, "Window: The surface entered output {}", output.id());
+                state.update_outputs(|outputs| outputs.enter(output.id().protocol_id()));
             }
+            wl_surface::Event::Leave { output } => {
+                debug!(target: WINDOW, "Window: The surface left output {}", output.id());
+                state.update_outputs(|outputs| outputs.leave(output.id().protocol_id()));
+            }
+            _ => (),
         }
     }
 }
@@ -806,19 +2219,35 @@ impl Dispatch<wl_pointer::WlPointer, ()> for Window {
                     cursor_shape.set_shape(serial, wp_cursor_shape_device_v1::Shape::Pointer);
                 }
 
-                state.pointer_state.enter_and_relocate(surface_x, surface_y);
+                state.pointer_state.enter();
+                state.relocate_pointer(surface_x, surface_y);
             }
             wl_pointer::Event::Leave { .. } => state.pointer_state.leave(),
+            wl_pointer::Event::Axis {
+                axis: WEnum::Value(wl_pointer::Axis::VerticalScroll),
+                value,
+                ..
+            } => state.pointer_state.scroll(value),
+            wl_pointer::Event::AxisDiscrete {
+                axis: WEnum::Value(wl_pointer::Axis::VerticalScroll),
+                discrete,
+            } => state.pointer_state.scroll_discrete(discrete),
+            wl_pointer::Event::AxisValue120 {
+                axis: WEnum::Value(wl_pointer::Axis::VerticalScroll),
+                value120,
+            } => state.pointer_state.scroll_value120(value120),
+            wl_pointer::Event::Frame => state.pointer_state.end_frame(),
             wl_pointer::Event::Motion {
                 surface_x,
                 surface_y,
                 ..
-            } => state.pointer_state.relocate(surface_x, surface_y),
+            } => state.relocate_pointer(surface_x, surface_y),
             wl_pointer::Event::Button {
                 button,
+                serial,
                 state: WEnum::Value(ButtonState::Pressed),
                 ..
-            } => state.pointer_state.press(button),
+            } => state.pointer_state.press(button, serial),
             _ => (),
         }
     }
@@ -841,13 +2270,387 @@ impl Dispatch<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, ()> for Window {
         {
             layer_surface.ack_configure(serial);
 
-            if width != 0 || height != 0 {
-                state.rect_size.width = width as usize;
-                state.rect_size.height = height as usize;
-            }
+            state.rect_size = configured_size(&state.rect_size, width, height);
 
             state.configuration_state = ConfigurationState::Configured;
-            debug!("Window: Configured layer surface")
+            debug!(
+                target: WINDOW,
+                "Window: Configured layer surface with size {}x{}",
+                state.rect_size.width,
+                state.rect_size.height
+            )
+        }
+    }
+}
+
+/// Returns the maximum lifetime of banner which never expires otherwise. The resident
+/// notifications and notifications with actions of application wait for the user, so they don't
+/// get it.
+fn never_timeout_fallback(
+    notification: &Notification,
+    fallback_ms: u32,
+    include_critical: bool,
+) -> Option<u128> {
+    let is_exempt = notification.hints.resident == Some(true)
+        || notification
+            .actions
+            .iter()
+            .any(|action| !action.is_synthetic())
+        || (notification.hints.urgency == Urgency::Critical && !include_critical);
+
+    (fallback_ms != 0 && !is_exempt).then_some(fallback_ms as u128)
+}
+
+#[cfg(test)]
+mod tests {
+    use render::types::RectSize;
+
+    use std::time::{Duration, Instant};
+
+    use config::{
+        general::{Anchor, Gravity, TimeoutClock},
+        sorting::Sorting,
+    };
+    use dbus::notification::{Notification, NotificationAction, Timeout, Urgency};
+    use indexmap::IndexMap;
+    use wayland_client::protocol::wl_output;
+
+    use crate::banner::BannerRect;
+
+    use super::{
+        age_opacity_at, anchored_y, banners_capacity, cmp_banners, configured_size, fade,
+        hovered_banner_index, is_banner_expired, is_expired, is_shown_long_enough,
+        is_stack_reversed, logical_output_height, never_timeout_fallback, next_reveal,
+        PointerState, SortByValues,
+    };
+
+    /// The size of window with three banners of 300x150 and gaps of 10 pixels between them.
+    fn stack_size() -> RectSize {
+        RectSize::new(300, 3 * 150 + 2 * 10)
+    }
+
+    fn never_expiring_notification(urgency: Urgency) -> Notification {
+        let mut notification = Notification::test_default();
+        notification.hints.urgency = urgency;
+        notification.expire_timeout = Timeout::Never;
+        notification
+    }
+
+    #[test]
+    fn stack_is_clamped_to_output_height() {
+        assert_eq!(banners_capacity(None, 0, &[], 150, 10), usize::MAX);
+
+        // INFO: 1080 - 20 of margins fits 6 banners of 150 with gaps of 10 (950) but not 7 (1110)
+        assert_eq!(banners_capacity(Some(1080), 20, &[], 150, 10), 6);
+        assert_eq!(banners_capacity(Some(470), 0, &[], 150, 10), 3);
+        assert_eq!(banners_capacity(Some(469), 0, &[], 150, 10), 2);
+        assert_eq!(banners_capacity(Some(100), 0, &[], 150, 10), 1);
+        assert_eq!(banners_capacity(Some(300), 0, &[], 100, 0), 3);
+    }
+
+    #[test]
+    fn stack_capacity_counts_shown_heights() {
+        // INFO: the expanded banner of 630 leaves the room for 2 banners of 150 with gaps of 10
+        assert_eq!(banners_capacity(Some(1000), 0, &[630], 150, 10), 3);
+        assert_eq!(banners_capacity(Some(1000), 0, &[150, 630], 150, 10), 3);
+
+        // INFO: the banners after the one which doesn't fit overflow
+        assert_eq!(
+            banners_capacity(Some(1000), 0, &[630, 400, 150], 150, 10),
+            1
+        );
+        assert_eq!(banners_capacity(Some(500), 0, &[800], 150, 10), 1);
+    }
+
+    #[test]
+    fn output_height_is_logical() {
+        assert_eq!(
+            logical_output_height(3840, 2160, 2, wl_output::Transform::Normal),
+            1080
+        );
+        assert_eq!(
+            logical_output_height(1920, 1080, 1, wl_output::Transform::_90),
+            1920
+        );
+        assert_eq!(
+            logical_output_height(1920, 1080, 0, wl_output::Transform::Flipped180),
+            1080
+        );
+    }
+
+    #[test]
+    fn banners_are_revealed_by_stagger() {
+        let now = Instant::now();
+        let stagger = Duration::from_millis(100);
+
+        assert_eq!(next_reveal(None, now, stagger), now);
+
+        let first = next_reveal(Some(now), now, stagger);
+        assert_eq!(first, now + stagger);
+        assert_eq!(next_reveal(Some(first), now, stagger), now + stagger * 2);
+
+        // INFO: the banners which appear after the stagger delay are revealed immediately
+        let later = now + Duration::from_secs(1);
+        assert_eq!(next_reveal(Some(first), later, stagger), later);
+        assert_eq!(next_reveal(Some(now), now, Duration::ZERO), now);
+    }
+
+    #[test]
+    fn never_timeout_fallback_is_disabled_by_zero() {
+        let notification = never_expiring_notification(Urgency::Normal);
+        assert_eq!(never_timeout_fallback(&notification, 0, true), None);
+        assert_eq!(
+            never_timeout_fallback(&notification, 60000, false),
+            Some(60000)
+        );
+    }
+
+    #[test]
+    fn never_timeout_fallback_exemptions() {
+        let critical = never_expiring_notification(Urgency::Critical);
+        assert_eq!(never_timeout_fallback(&critical, 60000, false), None);
+        assert_eq!(never_timeout_fallback(&critical, 60000, true), Some(60000));
+
+        let mut resident = never_expiring_notification(Urgency::Normal);
+        resident.hints.resident = Some(true);
+        assert_eq!(never_timeout_fallback(&resident, 60000, true), None);
+
+        let mut with_actions = never_expiring_notification(Urgency::Low);
+        with_actions.actions = NotificationAction::from_vec(&["default", "Open"]);
+        assert_eq!(never_timeout_fallback(&with_actions, 60000, true), None);
+
+        let mut with_code = never_expiring_notification(Urgency::Low);
+        with_code.actions = vec![NotificationAction::copy_code("482913".to_string())];
+        assert_eq!(never_timeout_fallback(&with_code, 60000, true), Some(60000));
+        assert_eq!(with_code.otp_code(), Some("482913"));
+    }
+
+    #[test]
+    fn age_opacity_takes_last_value_for_older_banners() {
+        assert_eq!(age_opacity_at(&[], 3), 1.0);
+        assert_eq!(age_opacity_at(&[1.0, 0.8, 0.6], 0), 1.0);
+        assert_eq!(age_opacity_at(&[1.0, 0.8, 0.6], 1), 0.8);
+        assert_eq!(age_opacity_at(&[1.0, 0.8, 0.6], 5), 0.6);
+        assert_eq!(age_opacity_at(&[1.5, -1.0], 0), 1.0);
+        assert_eq!(age_opacity_at(&[1.5, -1.0], 1), 0.0);
+    }
+
+    #[test]
+    fn fade_scales_premultiplied_channels() {
+        assert_eq!(
+            fade(&[200, 100, 50, 255], 0.5).as_ref(),
+            &[100, 50, 25, 128]
+        );
+        assert!(matches!(
+            fade(&[200, 100, 50, 255], 1.0),
+            std::borrow::Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn bottom_anchored_banners_are_clicked_in_sequence() {
+        let anchor = Anchor::BottomRight;
+        let (height, gap) = (150, 10);
+        // The pointer is over the bottom banner of three ones and doesn't move while the window
+        // shrinks after every click
+        let pointer_y = 440.0 - anchored_y(&anchor, stack_size().height);
+
+        for banners in (1..=3).rev() {
+            assert_eq!(
+                hovered_banner_index(pointer_y, &anchor, &vec![height; banners], gap),
+                Some((banners - 1, 120.0))
+            );
+        }
+        assert_eq!(hovered_banner_index(pointer_y, &anchor, &[], gap), None);
+
+        // The pointer over the top banner is outside of window after the click
+        let pointer_y = 20.0 - anchored_y(&anchor, stack_size().height);
+        assert_eq!(
+            hovered_banner_index(pointer_y, &anchor, &[height; 3], gap),
+            Some((0, 20.0))
+        );
+        assert_eq!(
+            hovered_banner_index(pointer_y, &anchor, &[height; 2], gap),
+            None
+        );
+    }
+
+    #[test]
+    fn equal_banners_are_sorted_by_creation() {
+        // The banners arrive in the same second, so the sorting by time doesn't tell them apart
+        let mut banners: IndexMap<u32, BannerRect> = (1..=4)
+            .map(|id| {
+                let mut notification = never_expiring_notification(Urgency::Normal);
+                notification.id = id;
+                (id, BannerRect::init(notification, TimeoutClock::default()))
+            })
+            .collect();
+        banners.reverse();
+
+        for sorting in ["time", "urgency", "id"] {
+            banners.sort_by_values(cmp_banners(&Sorting::from(sorting.to_string())));
+            let ids: Vec<u32> = banners.keys().copied().collect();
+            assert_eq!(ids, [1, 2, 3, 4]);
+
+            // The banners stay in place by repeated sorting
+            banners.sort_by_values(cmp_banners(&Sorting::from(sorting.to_string())));
+            assert_eq!(banners.keys().copied().collect::<Vec<u32>>(), ids);
+        }
+    }
+
+    #[test]
+    fn gravity_places_last_banner() {
+        // The last banner is at the anchored corner
+        assert!(is_stack_reversed(&Anchor::TopRight, Gravity::Corner));
+        assert!(!is_stack_reversed(&Anchor::BottomRight, Gravity::Corner));
+
+        // The last banner is at the far end of stack
+        assert!(!is_stack_reversed(&Anchor::TopRight, Gravity::Far));
+        assert!(is_stack_reversed(&Anchor::BottomRight, Gravity::Far));
+    }
+
+    #[test]
+    fn gaps_are_not_hovered() {
+        for anchor in [Anchor::TopRight, Anchor::BottomRight, Anchor::Right] {
+            let pointer_y = 155.0 - anchored_y(&anchor, stack_size().height);
+            assert_eq!(
+                hovered_banner_index(pointer_y, &anchor, &[150; 3], 10),
+                None
+            );
+
+            let pointer_y = 165.0 - anchored_y(&anchor, stack_size().height);
+            assert_eq!(
+                hovered_banner_index(pointer_y, &anchor, &[150; 3], 10),
+                Some((1, 5.0))
+            );
+        }
+    }
+
+    #[test]
+    fn expanded_banner_is_hovered_by_its_height() {
+        // The middle banner is expanded, so the stack is 150 + 10 + 300 + 10 + 150 = 620
+        let heights = [150, 300, 150];
+        for anchor in [Anchor::TopRight, Anchor::BottomRight, Anchor::Right] {
+            let pointer_y = |y: f64| y - anchored_y(&anchor, 620);
+
+            assert_eq!(
+                hovered_banner_index(pointer_y(400.0), &anchor, &heights, 10),
+                Some((1, 240.0))
+            );
+            assert_eq!(
+                hovered_banner_index(pointer_y(465.0), &anchor, &heights, 10),
+                None
+            );
+            assert_eq!(
+                hovered_banner_index(pointer_y(480.0), &anchor, &heights, 10),
+                Some((2, 10.0))
+            );
+            assert_eq!(
+                hovered_banner_index(pointer_y(630.0), &anchor, &heights, 10),
+                None
+            );
         }
     }
+
+    #[test]
+    fn scroll_is_turned_into_whole_steps() {
+        let mut pointer_state = PointerState::default();
+
+        pointer_state.scroll(4.0);
+        assert_eq!(pointer_state.take_scroll_steps(), 0);
+
+        pointer_state.scroll(21.0);
+        assert_eq!(pointer_state.take_scroll_steps(), 2);
+
+        pointer_state.scroll(-30.0);
+        assert_eq!(pointer_state.take_scroll_steps(), -2);
+        assert_eq!(pointer_state.take_scroll_steps(), 0);
+    }
+
+    #[test]
+    fn wheel_scroll_is_counted_by_clicks() {
+        let mut pointer_state = PointerState::default();
+
+        // INFO: the wheel frame has both the discrete and continuous values of one click
+        pointer_state.scroll_discrete(1);
+        pointer_state.scroll(15.0);
+        pointer_state.end_frame();
+        assert_eq!(pointer_state.take_scroll_steps(), 1);
+
+        pointer_state.scroll_value120(60);
+        pointer_state.scroll(7.5);
+        pointer_state.end_frame();
+        assert_eq!(pointer_state.take_scroll_steps(), 0);
+        pointer_state.scroll_value120(60);
+        pointer_state.scroll(7.5);
+        pointer_state.end_frame();
+        assert_eq!(pointer_state.take_scroll_steps(), 1);
+
+        pointer_state.scroll_value120(-240);
+        pointer_state.end_frame();
+        assert_eq!(pointer_state.take_scroll_steps(), -2);
+
+        // The touchpad frames have only the continuous values
+        pointer_state.scroll(12.0);
+        pointer_state.end_frame();
+        assert_eq!(pointer_state.take_scroll_steps(), 1);
+    }
+
+    #[test]
+    fn zero_configure_keeps_stack_size() {
+        assert_eq!(
+            configured_size(&stack_size(), 0, 0),
+            RectSize::new(300, 470)
+        );
+    }
+
+    #[test]
+    fn partial_configure_keeps_zero_dimension() {
+        assert_eq!(
+            configured_size(&stack_size(), 0, 200),
+            RectSize::new(300, 200)
+        );
+        assert_eq!(
+            configured_size(&stack_size(), 400, 0),
+            RectSize::new(400, 470)
+        );
+    }
+
+    #[test]
+    fn full_configure_replaces_size() {
+        assert_eq!(
+            configured_size(&stack_size(), 320, 160),
+            RectSize::new(320, 160)
+        );
+    }
+
+    #[test]
+    fn short_timeout_stays_minimum_display_time() {
+        assert!(!is_expired(1, 1, 0));
+        assert!(is_expired(2, 1, 0));
+
+        assert!(!is_expired(2, 1, 500));
+        assert!(!is_expired(500, 1, 500));
+        assert!(is_expired(501, 1, 500));
+    }
+
+    #[test]
+    fn long_timeout_is_not_shortened_by_minimum_display_time() {
+        assert!(!is_expired(1000, 2000, 500));
+        assert!(is_expired(2001, 2000, 500));
+    }
+
+    #[test]
+    fn banner_with_short_timeout_stays_minimum_display_time() {
+        let mut notification = Notification::test_default();
+        notification.expire_timeout = Timeout::Millis(1);
+        let rect = BannerRect::init(notification, TimeoutClock::default());
+
+        assert!(!is_banner_expired(&rect, 1, 30));
+        assert!(!is_shown_long_enough(&rect, 30));
+
+        std::thread::sleep(Duration::from_millis(40));
+        assert!(is_banner_expired(&rect, 1, 30));
+        assert!(is_shown_long_enough(&rect, 30));
+    }
 }
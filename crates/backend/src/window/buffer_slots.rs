@@ -0,0 +1,234 @@
+use log::{debug, trace, warn};
+use shared::log_target::WINDOW;
+use wayland_client::{
+    protocol::{wl_buffer, wl_shm, wl_shm_pool},
+    QueueHandle,
+};
+
+use render::types::RectSize;
+
+use super::Window;
+
+/// Two regions of the shared memory which are written in turn, so the next frame is never written
+/// into the memory which the compositor still reads.
+///
+/// The slot is busy since its buffers are attached to surfaces until the compositor releases all
+/// of them by the `wl_buffer.release` event. When both slots are busy, the window postpones the
+/// draw until one of them is released.
+pub(super) struct BufferSlots {
+    slots: [Slot; 2],
+    current: usize,
+    /// The pixel formats which the compositor advertises by the `wl_shm.format` events.
+    formats: Vec<wl_shm::Format>,
+    format: wl_shm::Format,
+}
+
+impl Default for BufferSlots {
+    fn default() -> Self {
+        Self {
+            slots: Default::default(),
+            current: 0,
+            formats: vec![],
+            format: wl_shm::Format::Argb8888,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Slot {
+    offset: usize,
+    len: usize,
+    buffers: Vec<SlotBuffer>,
+}
+
+struct SlotBuffer {
+    buffer: wl_buffer::WlBuffer,
+    busy: bool,
+}
+
+impl BufferSlots {
+    /// Adds the pixel format which is advertised by the compositor.
+    pub(super) fn add_format(&mut self, format: wl_shm::Format) {
+        self.formats.push(format);
+    }
+
+    /// Picks the format of next buffers among the advertised ones. It should be called once after
+    /// the roundtrip which delivers all `wl_shm.format` events, so the format isn't chosen by a
+    /// part of them.
+    pub(super) fn pick_format(&mut self) {
+        let chosen = choose_format(&self.formats);
+        self.format = chosen;
+        match chosen {
+            wl_shm::Format::Xrgb8888 => warn!(
+                target: WINDOW,
+                "Buffer slots: The compositor doesn't support Argb8888, chose {chosen:?} so the \
+                banners are drawn opaque"
+            ),
+            _ => debug!(target: WINDOW, "Buffer slots: Chose the {chosen:?} format"),
+        }
+    }
+
+    /// Returns true when the format of buffers has no alpha channel, so the transparent pixels
+    /// are drawn opaque.
+    pub(super) fn is_format_opaque(&self) -> bool {
+        self.format == wl_shm::Format::Xrgb8888
+    }
+
+    pub(super) fn has_free_slot(&self) -> bool {
+        self.slots.iter().any(|slot| !slot.is_busy())
+    }
+
+    /// Takes the free slot for the next frame of `len` bytes and returns the offset of slot in
+    /// the shared memory. The slot which isn't shown is preferred. Returns `None` when both slots
+    /// are busy.
+    pub(super) fn acquire(&mut self, len: usize) -> Option<usize> {
+        let index = [1 - self.current, self.current]
+            .into_iter()
+            .find(|&index| !self.slots[index].is_busy())?;
+
+        let other = &self.slots[1 - index];
+        let offset = next_offset(len, other.is_busy().then_some((other.offset, other.len)));
+
+        let slot = &mut self.slots[index];
+        slot.destroy_buffers();
+        slot.offset = offset;
+        slot.len = len;
+        self.current = index;
+
+        trace!(
+            target: WINDOW,
+            "Buffer slots: Acquired slot {index} at offset {offset} with length {len}"
+        );
+        Some(offset)
+    }
+
+    /// Creates the buffer of current slot at the given offset relative to the slot.
+    pub(super) fn create_buffer(
+        &mut self,
+        shm_pool: &wl_shm_pool::WlShmPool,
+        offset: usize,
+        size: &RectSize,
+        qhandle: &QueueHandle<Window>,
+    ) {
+        let slot = &mut self.slots[self.current];
+        let buffer = shm_pool.create_buffer(
+            (slot.offset + offset) as i32,
+            size.width as i32,
+            size.height as i32,
+            size.width as i32 * 4,
+            self.format,
+            qhandle,
+            self.current,
+        );
+
+        slot.buffers.push(SlotBuffer {
+            buffer,
+            busy: false,
+        });
+    }
+
+    /// Returns the buffers of current slot in the order of their creation.
+    pub(super) fn buffers(&self) -> impl Iterator<Item = &wl_buffer::WlBuffer> {
+        self.slots[self.current]
+            .buffers
+            .iter()
+            .map(|slot_buffer| &slot_buffer.buffer)
+    }
+
+    /// Marks the buffers of current slot as busy after they are attached to surfaces.
+    pub(super) fn mark_attached(&mut self) {
+        self.slots[self.current]
+            .buffers
+            .iter_mut()
+            .for_each(|slot_buffer| slot_buffer.busy = true);
+    }
+
+    pub(super) fn release(&mut self, index: usize, buffer: &wl_buffer::WlBuffer) {
+        let slot = &mut self.slots[index];
+        if let Some(slot_buffer) = slot
+            .buffers
+            .iter_mut()
+            .find(|slot_buffer| &slot_buffer.buffer == buffer)
+        {
+            slot_buffer.busy = false;
+        }
+
+        if !slot.is_busy() {
+            trace!(target: WINDOW, "Buffer slots: The slot {index} is released");
+        }
+    }
+
+    pub(super) fn destroy(&mut self) {
+        self.slots.iter_mut().for_each(Slot::destroy_buffers);
+        debug!(target: WINDOW, "Buffer slots: Destroyed");
+    }
+}
+
+impl Slot {
+    fn is_busy(&self) -> bool {
+        self.buffers.iter().any(|slot_buffer| slot_buffer.busy)
+    }
+
+    fn destroy_buffers(&mut self) {
+        self.buffers
+            .drain(..)
+            .for_each(|slot_buffer| slot_buffer.buffer.destroy());
+    }
+}
+
+/// Returns the format of buffers among the advertised ones. The `Argb8888` is preferred because
+/// the banners have transparent corners and gaps, and the `Xrgb8888` of the same layout is the
+/// fallback. The `Argb8888` is kept until the formats are advertised, which the compositors must
+/// support anyway.
+fn choose_format(formats: &[wl_shm::Format]) -> wl_shm::Format {
+    [wl_shm::Format::Argb8888, wl_shm::Format::Xrgb8888]
+        .into_iter()
+        .find(|format| formats.contains(format))
+        .unwrap_or(wl_shm::Format::Argb8888)
+}
+
+/// Returns the offset of the next frame of `len` bytes which doesn't overlap the busy region
+/// given by its offset and length.
+fn next_offset(len: usize, busy_region: Option<(usize, usize)>) -> usize {
+    match busy_region {
+        Some((offset, busy_len)) if len > offset => offset + busy_len,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wayland_client::protocol::wl_shm::Format;
+
+    use super::{choose_format, next_offset};
+
+    #[test]
+    fn argb_format_is_preferred() {
+        assert_eq!(choose_format(&[]), Format::Argb8888);
+        assert_eq!(
+            choose_format(&[Format::Xrgb8888, Format::Argb8888]),
+            Format::Argb8888
+        );
+        assert_eq!(
+            choose_format(&[Format::Rgb565, Format::Xrgb8888]),
+            Format::Xrgb8888
+        );
+        assert_eq!(choose_format(&[Format::Rgb565]), Format::Argb8888);
+    }
+
+    #[test]
+    fn offset_without_busy_region() {
+        assert_eq!(next_offset(100, None), 0);
+    }
+
+    #[test]
+    fn offset_before_busy_region() {
+        assert_eq!(next_offset(100, Some((100, 200))), 0);
+    }
+
+    #[test]
+    fn offset_after_busy_region() {
+        assert_eq!(next_offset(100, Some((0, 200))), 200);
+        assert_eq!(next_offset(150, Some((100, 200))), 300);
+    }
+}
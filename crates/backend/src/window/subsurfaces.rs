@@ -0,0 +1,253 @@
+use log::debug;
+use shared::log_target::WINDOW;
+use wayland_client::{
+    protocol::{wl_buffer, wl_compositor, wl_subcompositor, wl_subsurface, wl_surface},
+    QueueHandle,
+};
+use wayland_protocols::wp::{
+    single_pixel_buffer::v1::client::wp_single_pixel_buffer_manager_v1,
+    viewporter::client::{wp_viewport, wp_viewporter},
+};
+
+use render::{image::ScaledImage, types::RectSize};
+
+use super::Window;
+
+/// Draws every banner at its own subsurface above the window surface which is covered by the
+/// transparent single-pixel buffer, so the gaps between banners don't take the shared memory.
+///
+/// It's used only when the compositor supports the `wl_subcompositor`, `wp_viewporter` and
+/// `wp_single_pixel_buffer_manager_v1` globals. Otherwise the banners are written into one buffer
+/// separated by zeroed gaps.
+///
+/// The images which are scaled by the compositor are placed at their own subsurfaces over the
+/// banners and their viewports scale the buffers of source size.
+#[derive(Default)]
+pub(super) struct Subsurfaces {
+    pub(super) subcompositor: Option<wl_subcompositor::WlSubcompositor>,
+    pub(super) viewporter: Option<wp_viewporter::WpViewporter>,
+    pub(super) single_pixel_buffer_manager:
+        Option<wp_single_pixel_buffer_manager_v1::WpSinglePixelBufferManagerV1>,
+
+    viewport: Option<wp_viewport::WpViewport>,
+    background: Option<wl_buffer::WlBuffer>,
+    banners: Vec<BannerSurface>,
+}
+
+struct BannerSurface {
+    surface: wl_surface::WlSurface,
+    subsurface: wl_subsurface::WlSubsurface,
+    images: Vec<ImageSurface>,
+}
+
+struct ImageSurface {
+    surface: wl_surface::WlSurface,
+    subsurface: wl_subsurface::WlSubsurface,
+    viewport: wp_viewport::WpViewport,
+}
+
+impl Subsurfaces {
+    pub(super) fn is_supported(&self) -> bool {
+        self.subcompositor.is_some()
+            && self.viewporter.is_some()
+            && self.single_pixel_buffer_manager.is_some()
+    }
+
+    /// Creates the subsurfaces of banners with their scaled images and places them at their
+    /// positions in window. The buffers of banners are written one after another into the shm
+    /// pool without gaps and the buffers of images follow them.
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn build(
+        &mut self,
+        surface: &wl_surface::WlSurface,
+        compositor: &wl_compositor::WlCompositor,
+        window_size: &RectSize,
+        banner_sizes: &[RectSize],
+        gap: usize,
+        scaled_images: &[&[ScaledImage]],
+        qhandle: &QueueHandle<Window>,
+    ) {
+        let banners_count = scaled_images.len();
+        let (Some(subcompositor), Some(viewporter), Some(single_pixel_buffer_manager)) = (
+            self.subcompositor.as_ref(),
+            self.viewporter.as_ref(),
+            self.single_pixel_buffer_manager.as_ref(),
+        ) else {
+            return;
+        };
+
+        if self.background.is_none() {
+            self.background =
+                Some(single_pixel_buffer_manager.create_u32_rgba_buffer(0, 0, 0, 0, qhandle, ()));
+        }
+
+        let viewport = self
+            .viewport
+            .get_or_insert_with(|| viewporter.get_viewport(surface, qhandle, ()));
+        viewport.set_destination(
+            window_size.width.max(1) as i32,
+            window_size.height.max(1) as i32,
+        );
+
+        while self.banners.len() > banners_count {
+            if let Some(banner_surface) = self.banners.pop() {
+                banner_surface.destroy();
+            }
+        }
+
+        while self.banners.len() < banners_count {
+            let banner_surface = compositor.create_surface(qhandle, ());
+            let subsurface = subcompositor.get_subsurface(&banner_surface, surface, qhandle, ());
+
+            // INFO: the input is handled by the window surface so the hit testing of banners
+            // doesn't depend on the subsurfaces
+            let region = compositor.create_region(qhandle, ());
+            banner_surface.set_input_region(Some(&region));
+            region.destroy();
+
+            self.banners.push(BannerSurface {
+                surface: banner_surface,
+                subsurface,
+                images: vec![],
+            });
+        }
+
+        let mut y = 0;
+        for ((banner_surface, scaled_images), banner_size) in
+            self.banners.iter_mut().zip(scaled_images).zip(banner_sizes)
+        {
+            banner_surface.subsurface.set_position(0, y as i32);
+            y += banner_size.height + gap;
+            banner_surface.build_images(
+                compositor,
+                subcompositor,
+                viewporter,
+                scaled_images,
+                qhandle,
+            );
+        }
+
+        debug!(target: WINDOW, "Window: Built {banners_count} banner subsurfaces");
+    }
+
+    /// Marks the subsurfaces of fully opaque banners as opaque.
+    pub(super) fn set_opaque_regions(
+        &self,
+        compositor: &wl_compositor::WlCompositor,
+        opaque_banners: impl Iterator<Item = bool>,
+        banner_sizes: &[RectSize],
+        qhandle: &QueueHandle<Window>,
+    ) {
+        for ((banner_surface, is_opaque), banner_size) in
+            self.banners.iter().zip(opaque_banners).zip(banner_sizes)
+        {
+            if is_opaque {
+                let region = compositor.create_region(qhandle, ());
+                region.add(0, 0, banner_size.width as i32, banner_size.height as i32);
+                banner_surface.surface.set_opaque_region(Some(&region));
+                region.destroy();
+            } else {
+                banner_surface.surface.set_opaque_region(None);
+            }
+        }
+    }
+
+    /// Attaches the buffers to the window surface and subsurfaces of banners in their order, and
+    /// then the remaining buffers to the subsurfaces of images. The subsurfaces are synchronized,
+    /// so their state is applied with the next commit of window surface.
+    pub(super) fn attach<'a>(
+        &self,
+        surface: &wl_surface::WlSurface,
+        buffers: impl Iterator<Item = &'a wl_buffer::WlBuffer>,
+    ) {
+        surface.attach(self.background.as_ref(), 0, 0);
+
+        let buffers: Vec<_> = buffers.collect();
+        let (banner_buffers, image_buffers) =
+            buffers.split_at(self.banners.len().min(buffers.len()));
+        let mut image_buffers = image_buffers.iter();
+
+        for (banner_surface, buffer) in self.banners.iter().zip(banner_buffers) {
+            // INFO: the nested subsurfaces are committed before their parent to apply their state
+            // together with it
+            for (image_surface, buffer) in banner_surface.images.iter().zip(&mut image_buffers) {
+                image_surface.surface.attach(Some(buffer), 0, 0);
+                image_surface.surface.damage(0, 0, i32::MAX, i32::MAX);
+                image_surface.surface.commit();
+            }
+
+            banner_surface.surface.attach(Some(buffer), 0, 0);
+            banner_surface.surface.damage(0, 0, i32::MAX, i32::MAX);
+            banner_surface.surface.commit();
+        }
+    }
+
+    pub(super) fn destroy(&mut self) {
+        self.banners.drain(..).for_each(BannerSurface::destroy);
+
+        if let Some(viewport) = self.viewport.take() {
+            viewport.destroy();
+        }
+
+        if let Some(background) = self.background.take() {
+            background.destroy();
+        }
+    }
+}
+
+impl BannerSurface {
+    /// Creates the subsurfaces of scaled images over the banner surface and sets their positions
+    /// and sizes.
+    fn build_images(
+        &mut self,
+        compositor: &wl_compositor::WlCompositor,
+        subcompositor: &wl_subcompositor::WlSubcompositor,
+        viewporter: &wp_viewporter::WpViewporter,
+        scaled_images: &[ScaledImage],
+        qhandle: &QueueHandle<Window>,
+    ) {
+        self.images
+            .drain(scaled_images.len().min(self.images.len())..)
+            .for_each(ImageSurface::destroy);
+
+        while self.images.len() < scaled_images.len() {
+            let surface = compositor.create_surface(qhandle, ());
+            let subsurface = subcompositor.get_subsurface(&surface, &self.surface, qhandle, ());
+            let viewport = viewporter.get_viewport(&surface, qhandle, ());
+
+            let region = compositor.create_region(qhandle, ());
+            surface.set_input_region(Some(&region));
+            region.destroy();
+
+            self.images.push(ImageSurface {
+                surface,
+                subsurface,
+                viewport,
+            });
+        }
+
+        for (image_surface, scaled_image) in self.images.iter().zip(scaled_images) {
+            image_surface
+                .subsurface
+                .set_position(scaled_image.offset.x as i32, scaled_image.offset.y as i32);
+            image_surface.viewport.set_destination(
+                scaled_image.size.width as i32,
+                scaled_image.size.height as i32,
+            );
+        }
+    }
+
+    fn destroy(mut self) {
+        self.images.drain(..).for_each(ImageSurface::destroy);
+        self.subsurface.destroy();
+        self.surface.destroy();
+    }
+}
+
+impl ImageSurface {
+    fn destroy(self) {
+        self.viewport.destroy();
+        self.subsurface.destroy();
+        self.surface.destroy();
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use wayland_client::protocol::wl_output;
+
+/// The properties of output which place and limit the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct OutputState {
+    pub(super) transform: wl_output::Transform,
+    /// The size of current output mode in pixels before the transform. It's `None` until the
+    /// compositor sends it.
+    pub(super) mode: Option<(i32, i32)>,
+    pub(super) scale: i32,
+}
+
+impl Default for OutputState {
+    fn default() -> Self {
+        Self {
+            transform: wl_output::Transform::Normal,
+            mode: None,
+            scale: 1,
+        }
+    }
+}
+
+/// The outputs by their protocol ids and the ones which the window surface entered.
+///
+/// The window follows the output where its surface is shown, so the other outputs of multi-head
+/// setup don't change its placement.
+#[derive(Default)]
+pub(super) struct SurfaceOutputs {
+    outputs: HashMap<u32, OutputState>,
+    /// The entered outputs in order of the `wl_surface.enter` events.
+    entered: Vec<u32>,
+}
+
+impl SurfaceOutputs {
+    pub(super) fn update(&mut self, output_id: u32, update: impl FnOnce(&mut OutputState)) {
+        update(self.outputs.entry(output_id).or_default());
+    }
+
+    pub(super) fn enter(&mut self, output_id: u32) {
+        self.entered.retain(|&id| id != output_id);
+        self.entered.push(output_id);
+    }
+
+    pub(super) fn leave(&mut self, output_id: u32) {
+        self.entered.retain(|&id| id != output_id);
+    }
+
+    /// Returns the state of output which the surface entered last. Until the surface enters any
+    /// output, the only known output is used and the default state otherwise.
+    pub(super) fn current(&self) -> OutputState {
+        let output_id = match self.entered.last() {
+            Some(output_id) => Some(output_id),
+            None if self.outputs.len() == 1 => self.outputs.keys().next(),
+            None => None,
+        };
+
+        output_id
+            .and_then(|output_id| self.outputs.get(output_id))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wayland_client::protocol::wl_output;
+
+    use super::{OutputState, SurfaceOutputs};
+
+    fn outputs() -> SurfaceOutputs {
+        let mut outputs = SurfaceOutputs::default();
+        outputs.update(1, |state| {
+            state.mode = Some((1920, 1080));
+        });
+        outputs.update(2, |state| {
+            state.mode = Some((3840, 2160));
+            state.scale = 2;
+            state.transform = wl_output::Transform::_90;
+        });
+        outputs
+    }
+
+    #[test]
+    fn entered_output_is_used() {
+        let mut outputs = outputs();
+        assert_eq!(outputs.current(), OutputState::default());
+
+        outputs.enter(2);
+        assert_eq!(outputs.current().mode, Some((3840, 2160)));
+        assert_eq!(outputs.current().transform, wl_output::Transform::_90);
+
+        // INFO: the events of other output don't change the current one
+        outputs.update(1, |state| state.transform = wl_output::Transform::_180);
+        assert_eq!(outputs.current().scale, 2);
+        assert_eq!(outputs.current().transform, wl_output::Transform::_90);
+    }
+
+    #[test]
+    fn last_entered_output_is_used() {
+        let mut outputs = outputs();
+        outputs.enter(1);
+        outputs.enter(2);
+        assert_eq!(outputs.current().mode, Some((3840, 2160)));
+
+        outputs.leave(2);
+        assert_eq!(outputs.current().mode, Some((1920, 1080)));
+    }
+
+    #[test]
+    fn only_output_is_used_before_enter() {
+        let mut outputs = SurfaceOutputs::default();
+        outputs.update(1, |state| state.mode = Some((1920, 1080)));
+
+        assert_eq!(outputs.current().mode, Some((1920, 1080)));
+    }
+}
@@ -1,129 +1,466 @@
-use std::{cell::RefCell, collections::VecDeque, path::PathBuf, rc::Rc};
-
-use log::debug;
-use shared::cached_data::CachedData;
+//! The window manager keeps a separate layer surface window for each distinct anchor.
+//!
+//! The anchor of notification is chosen by its urgency (the `general.anchor` option can be a
+//! table with per-urgency overrides), so e.g. critical notifications can appear at the top
+//! center while the rest stack at the top right corner. Each window is an [`AnchoredWindow`]
+//! with its own Wayland event queue, banner stack and notifications limit. The queue of incoming
+//! notifications is shared and the notifications are routed from it to the windows by their
+//! anchors. Windows are created lazily when the first banner for their anchor appears and are
+//! destroyed when their last banner is closed.
+
+use std::{
+    cell::RefCell,
+    cmp::Reverse,
+    collections::{HashSet, VecDeque},
+    path::PathBuf,
+    rc::Rc,
+    time::Instant,
+};
+
+use indexmap::IndexMap;
+use log::{debug, error, info, warn};
+use shared::{cached_data::CachedData, log_target::WINDOW};
 use wayland_client::{Connection, EventQueue, QueueHandle};
 
+use crate::banner::BannerRect;
 use crate::cache::CachedLayout;
 use crate::dispatcher::Dispatcher;
-
-use config::Config;
-use dbus::{actions::Signal, notification::Notification};
+use crate::memory_budget::{self, Evictable};
+use crate::sound::SoundPlayer;
+
+use config::{
+    general::{Anchor, QueueOrder},
+    Config,
+};
+use dbus::{
+    actions::{ClosingReason, Signal},
+    notification::{Notification, Urgency},
+};
 
 use super::window::{ConfigurationState, Window};
-use render::font::FontCollection;
+use render::{error::RenderError, font::FontCollection};
 
 pub(crate) struct WindowManager {
     connection: Connection,
-    event_queue: Option<EventQueue<Window>>,
-    qhandle: Option<QueueHandle<Window>>,
-    window: Option<Window>,
+    windows: IndexMap<Anchor, AnchoredWindow>,
 
     font_collection: Rc<RefCell<FontCollection>>,
     cached_layouts: CachedData<PathBuf, CachedLayout>,
 
-    signals: Vec<Signal>,
+    signals: VecDeque<Signal>,
+    copied_codes: Vec<String>,
 
     notification_queue: VecDeque<Notification>,
     close_notifications: Vec<u32>,
+    inhibited: bool,
+    /// The moment when the expiration of banners was paused.
+    paused_at: Option<Instant>,
+    /// The memory usage exceeds the `limits.memory_mb` budget.
+    memory_pressure: bool,
+    sound_player: SoundPlayer,
 }
 
-impl Dispatcher for WindowManager {
+/// The window placed at the specific anchor with its own event queue.
+struct AnchoredWindow {
+    event_queue: EventQueue<Window>,
+    qhandle: QueueHandle<Window>,
+    window: Window,
+}
+
+impl Dispatcher for AnchoredWindow {
     type State = Window;
 
     fn get_event_queue_and_state(
         &mut self,
     ) -> Option<(&mut EventQueue<Self::State>, &mut Self::State)> {
-        Some((self.event_queue.as_mut()?, self.window.as_mut()?))
+        Some((&mut self.event_queue, &mut self.window))
+    }
+}
+
+impl AnchoredWindow {
+    fn init(
+        connection: &Connection,
+        font_collection: Rc<RefCell<FontCollection>>,
+        config: &Config,
+        anchor: Anchor,
+    ) -> anyhow::Result<Self> {
+        let mut event_queue = connection.new_event_queue();
+        let qhandle = event_queue.handle();
+        let display = connection.display();
+        display.get_registry(&qhandle, ());
+
+        let mut window = Window::init(font_collection, config, anchor);
+
+        while let ConfigurationState::NotConfiured = window.configuration_state() {
+            event_queue.blocking_dispatch(&mut window)?;
+        }
+
+        // INFO: the outputs are bound with the other globals and their properties are received
+        // by the next roundtrip. The transform of output must be known before the initial commit,
+        // so the surface is anchored to the right edges from the first frame.
+        // The same roundtrip delivers the pixel formats of wl_shm, so the format of buffers is
+        // chosen among all of them.
+        event_queue.roundtrip(&mut window)?;
+        window.pick_buffer_format();
+        window.configure(&qhandle, config);
+
+        while let ConfigurationState::Ready = window.configuration_state() {
+            event_queue.blocking_dispatch(&mut window)?;
+        }
+
+        debug!(target: WINDOW, "Window Manager: Created a window");
+
+        Ok(Self {
+            event_queue,
+            qhandle,
+            window,
+        })
+    }
+
+    fn update(&mut self, config: &Config, cached_layouts: &CachedData<PathBuf, CachedLayout>) {
+        self.window.fit_width(config, cached_layouts);
+        self.window.draw(&self.qhandle, config);
+        self.window.frame(&self.qhandle);
+        self.window.commit();
+    }
+
+    fn roundtrip(&mut self) -> anyhow::Result<()> {
+        self.event_queue.roundtrip(&mut self.window)?;
+        Ok(())
+    }
+
+    fn deinit(mut self) -> anyhow::Result<()> {
+        self.window.deinit();
+        self.roundtrip()?;
+
+        debug!(target: WINDOW, "Window Manager: Closed window");
+
+        Ok(())
     }
 }
 
 impl WindowManager {
     pub(crate) fn init(config: &Config) -> anyhow::Result<Self> {
         let connection = Connection::connect_to_env()?;
-        let font_collection =
-            Rc::new(FontCollection::load_by_font_name(&config.general().font.name)?.into());
-        let cached_layouts = config
-            .displays()
-            .filter_map(|display| match &display.layout {
-                config::display::Layout::Default => None,
-                config::display::Layout::FromPath { path_buf } => Some(path_buf),
-            })
-            .collect();
+        let mut font_collection = FontCollection::load_by_font_name(
+            &config.general().font.name,
+            &config.general().emoji_font,
+        )?;
+        font_collection.set_monochrome_emoji(config.general().monochrome_emoji);
+        font_collection.set_emoji_placeholder(config.general().emoji_placeholder);
+        font_collection.set_monospace_font(&config.general().monospace_font);
+        font_collection.set_hinting(config.general().font_hinting);
+        font_collection.set_font_size_bounds(
+            config.general().min_font_size,
+            config.general().max_font_size,
+        );
+        let font_collection = Rc::new(font_collection.into());
+        let cached_layouts = Self::layout_paths(config).iter().collect();
 
         let wm = Self {
             connection,
-            event_queue: None,
-            qhandle: None,
-            window: None,
+            windows: IndexMap::new(),
 
             font_collection,
             cached_layouts,
 
-            signals: vec![],
+            signals: VecDeque::new(),
+            copied_codes: vec![],
             notification_queue: VecDeque::new(),
             close_notifications: vec![],
+            inhibited: false,
+            paused_at: None,
+            memory_pressure: false,
+            sound_player: SoundPlayer::default(),
         };
 
-        debug!("Window Manager: Created");
+        debug!(target: WINDOW, "Window Manager: Created");
 
         Ok(wm)
     }
 
+    /// Returns the connection to the Wayland compositor which is shared with the other managers.
+    pub(crate) fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    pub(crate) fn dispatch(&mut self) -> anyhow::Result<bool> {
+        let mut dispatched = false;
+        for anchored_window in self.windows.values_mut() {
+            dispatched |= anchored_window.dispatch()?;
+        }
+
+        Ok(dispatched)
+    }
+
     pub(crate) fn update_cache(&mut self) -> bool {
         self.cached_layouts.update()
     }
 
     pub(crate) fn update_by_config(&mut self, config: &Config) -> anyhow::Result<()> {
-        self.cached_layouts.extend_by_keys(
-            config
-                .displays()
-                .filter_map(|display| match &display.layout {
-                    config::display::Layout::Default => None,
-                    config::display::Layout::FromPath { path_buf } => Some(path_buf.to_owned()),
-                })
-                .collect(),
-        );
+        self.cached_layouts
+            .extend_by_keys(Self::layout_paths(config));
 
-        self.font_collection
-            .borrow_mut()
-            .update_by_font_name(&config.general().font.name)?;
+        {
+            let mut font_collection = self.font_collection.borrow_mut();
+            match font_collection
+                .update_by_font_name(&config.general().font.name, &config.general().emoji_font)
+            {
+                Ok(()) => (),
+                Err(err @ RenderError::FontLoad { .. }) => {
+                    error!(target: WINDOW, "Window Manager: {err}, keeping the previous fonts")
+                }
+                Err(err) => return Err(err.into()),
+            }
+            font_collection.set_monochrome_emoji(config.general().monochrome_emoji);
+            font_collection.set_emoji_placeholder(config.general().emoji_placeholder);
+            font_collection.set_monospace_font(&config.general().monospace_font);
+            font_collection.set_hinting(config.general().font_hinting);
+            font_collection.set_font_size_bounds(
+                config.general().min_font_size,
+                config.general().max_font_size,
+            );
+        }
 
-        if let Some(window) = self.window.as_mut() {
-            let qhandle = unsafe { self.qhandle.as_ref().unwrap_unchecked() };
+        self.relocate_unused_windows(config)?;
 
+        for AnchoredWindow {
+            qhandle, window, ..
+        } in self.windows.values_mut()
+        {
             window.reconfigure(config);
             window.redraw(qhandle, config, &self.cached_layouts);
             window.frame(qhandle);
             window.commit();
         }
 
-        debug!("Window Manager: Updated the windows by updated config");
+        debug!(target: WINDOW, "Window Manager: Updated the windows by updated config");
 
-        self.roundtrip_event_queue()
+        self.roundtrip_event_queues()
+    }
+
+    /// Moves the banners of windows whose anchors are no longer used by any urgency to the
+    /// windows with actual anchors.
+    fn relocate_unused_windows(&mut self, config: &Config) -> anyhow::Result<()> {
+        let anchors = config.general().anchor.distinct();
+        let unused_anchors: Vec<Anchor> = self
+            .windows
+            .keys()
+            .filter(|anchor| !anchors.contains(anchor))
+            .cloned()
+            .collect();
+
+        for anchor in unused_anchors {
+            let Some(mut anchored_window) = self.windows.shift_remove(&anchor) else {
+                continue;
+            };
+
+            let notifications = anchored_window.window.take_notifications();
+            anchored_window.deinit()?;
+            debug!(
+                target: WINDOW,
+                "Window Manager: The anchor {anchor:?} is no longer used, relocating banners"
+            );
+
+            let mut relocated_notifications: IndexMap<Anchor, Vec<Notification>> = IndexMap::new();
+            for notification in notifications {
+                relocated_notifications
+                    .entry(Self::anchor_of(&notification, config).clone())
+                    .or_default()
+                    .push(notification);
+            }
+
+            for (anchor, notifications) in relocated_notifications {
+                self.ensure_window(&anchor, config)?;
+                self.windows[&anchor].window.update_banners(
+                    notifications,
+                    config,
+                    &self.cached_layouts,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Initializes the windows again after the panic of render path. The old windows are dropped
+    /// with their connection and the shown notifications are queued before the queued ones to
+    /// be shown again. The banner which likely caused the panic is closed instead, so it doesn't
+    /// make the render path panic again.
+    pub(crate) fn restart(&mut self, config: &Config) -> anyhow::Result<()> {
+        let mut broken = std::mem::replace(self, Self::init(config)?);
+
+        let mut banners: Vec<BannerRect> = broken
+            .windows
+            .drain(..)
+            .flat_map(|(_, mut anchored_window)| anchored_window.window.take_banners())
+            .collect();
+        if let Some(position) = BannerRect::find_panicked(&banners) {
+            let notification = banners.remove(position).destroy_and_get_notification();
+            warn!(
+                target: WINDOW,
+                "Window Manager: Closed the notification with id {} which likely made the render \
+                path panic",
+                notification.id
+            );
+            broken
+                .signals
+                .extend(closed_signals([notification.id], ClosingReason::Undefined));
+        }
+
+        self.notification_queue.extend(
+            banners
+                .into_iter()
+                .map(BannerRect::destroy_and_get_notification),
+        );
+        self.notification_queue
+            .append(&mut broken.notification_queue);
+
+        self.signals = broken.signals;
+        self.copied_codes = broken.copied_codes;
+        self.close_notifications = broken.close_notifications;
+        self.inhibited = broken.inhibited;
+        self.paused_at = broken.paused_at;
+        self.sound_player = broken.sound_player;
+
+        debug!(
+            target: WINDOW,
+            "Window Manager: Restarted with {} queued notifications",
+            self.notification_queue.len()
+        );
+
+        Ok(())
+    }
+
+    /// Drops the transient runtime state like caches and loads it again from scratch.
+    pub(crate) fn reset_runtime_state(&mut self, config: &Config) {
+        self.cached_layouts.clear();
+        self.cached_layouts
+            .extend_by_keys(Self::layout_paths(config));
+
+        debug!(target: WINDOW, "Window Manager: Reset runtime state");
+    }
+
+    fn layout_paths(config: &Config) -> Vec<PathBuf> {
+        config
+            .displays()
+            .filter_map(|display| match &display.layout {
+                config::display::Layout::BuiltIn(_) => None,
+                config::display::Layout::FromPath { path_buf } => Some(path_buf.to_owned()),
+            })
+            .collect()
+    }
+
+    fn anchor_of<'a>(notification: &Notification, config: &'a Config) -> &'a Anchor {
+        config
+            .general()
+            .anchor
+            .by_urgency(&notification.hints.urgency)
+    }
+
+    fn notifications_limit(config: &Config) -> usize {
+        match config.general().limit as usize {
+            0 => usize::MAX,
+            limit => limit,
+        }
     }
 
     pub(crate) fn create_notification(&mut self, notification: Box<Notification>) {
-        self.notification_queue.push_back(*notification);
+        enqueue(&mut self.notification_queue, *notification);
     }
 
     pub(crate) fn close_notification(&mut self, notification_id: u32) {
         self.close_notifications.push(notification_id);
     }
 
-    pub(crate) fn show_window(&mut self, config: &Config) -> anyhow::Result<()> {
-        let mut notifications_limit = config.general().limit as usize;
-        if notifications_limit == 0 {
-            notifications_limit = usize::MAX;
+    /// Withholds the non-critical notifications in queue while the notifications are inhibited.
+    /// They are shown after the inhibition ends.
+    pub(crate) fn set_inhibited(&mut self, inhibited: bool) {
+        self.inhibited = inhibited;
+        debug!(target: WINDOW, "Window Manager: Set inhibited state to {inhibited}");
+    }
+
+    /// Pauses or resumes the expiration of banners. After resume the timeouts continue from
+    /// where they were paused.
+    pub(crate) fn set_paused(&mut self, paused: bool) {
+        match (paused, self.paused_at) {
+            (true, None) => self.paused_at = Some(Instant::now()),
+            (false, Some(paused_at)) => {
+                let paused_duration = paused_at.elapsed();
+                self.windows.values_mut().for_each(|anchored_window| {
+                    anchored_window.window.delay_timeouts(paused_duration)
+                });
+                self.paused_at = None;
+                debug!(
+                    target: WINDOW,
+                    "Window Manager: Delayed the timeouts by {}ms of pause",
+                    paused_duration.as_millis()
+                );
+            }
+            _ => (),
         }
+    }
 
-        if self
-            .window
-            .as_ref()
-            .is_none_or(|window| window.total_banners() < notifications_limit)
-            && !self.notification_queue.is_empty()
+    /// Sets the image path of notification which is either queued or shown as banner and
+    /// redraws the banner.
+    pub(crate) fn update_image_path(
+        &mut self,
+        notification_id: u32,
+        image_path: String,
+        config: &Config,
+    ) -> anyhow::Result<()> {
+        if let Some(notification) = self
+            .notification_queue
+            .iter_mut()
+            .find(|notification| notification.id == notification_id)
         {
-            self.init_window(config)?;
+            notification.hints.image_path = Some(image_path);
+            return Ok(());
+        }
+
+        for anchored_window in self.windows.values_mut() {
+            if anchored_window.window.update_image_path(
+                notification_id,
+                image_path.clone(),
+                config,
+                &self.cached_layouts,
+            ) {
+                anchored_window.update(config, &self.cached_layouts);
+                anchored_window.roundtrip()?;
+
+                debug!(
+                    target: WINDOW,
+                    "Window Manager: Updated image of notification with id {notification_id}"
+                );
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn show_window(&mut self, config: &Config) -> anyhow::Result<()> {
+        let notifications_limit = Self::notifications_limit(config);
+
+        let has_room = self.notification_queue.iter().any(|notification| {
+            !is_withheld(notification, self.inhibited)
+                && self
+                    .windows
+                    .get(Self::anchor_of(notification, config))
+                    .is_none_or(|AnchoredWindow { window, .. }| {
+                        window.total_banners()
+                            < notifications_limit.min(window.banners_capacity(config))
+                            || window.all_banners_never_expire(config)
+                    })
+        });
+
+        let has_replacement = self.notification_queue.iter().any(|notification| {
+            self.windows
+                .values()
+                .any(|AnchoredWindow { window, .. }| window.is_replaceable(notification.id, config))
+        });
+
+        if has_room || has_replacement {
             self.process_notification_queue(config)?;
         }
 
@@ -131,182 +468,842 @@ impl WindowManager {
     }
 
     fn process_notification_queue(&mut self, config: &Config) -> anyhow::Result<()> {
-        if let Some(window) = self.window.as_mut() {
-            let mut notifications_limit = config.general().limit as usize;
+        // INFO: the replacements of banners which are shown less than `min_display_ms` are held
+        // aside, so they aren't shown as new banners, and queued again after the display.
+        let mut held_notifications = vec![];
+        for AnchoredWindow { window, .. } in self.windows.values_mut() {
+            requeue(
+                &mut self.notification_queue,
+                window.take_overflowing_banners(config),
+            );
+            held_notifications.extend(window.replace_by_indices(
+                &mut self.notification_queue,
+                config,
+                &self.cached_layouts,
+            ));
+        }
+
+        order_queue(&mut self.notification_queue, config.general().queue_order);
 
-            if notifications_limit == 0 {
-                notifications_limit = usize::MAX
+        let inhibited = self.inhibited;
+        let mut anchors: Vec<Anchor> = vec![];
+        for notification in &self.notification_queue {
+            if is_withheld(notification, inhibited) {
+                continue;
             }
 
-            window.replace_by_indices(&mut self.notification_queue, config, &self.cached_layouts);
+            let anchor = Self::anchor_of(notification, config);
+            if !anchors.contains(anchor) {
+                anchors.push(anchor.clone());
+            }
+        }
 
-            let available_slots = notifications_limit.saturating_sub(window.total_banners());
-            let notifications_to_display: Vec<_> = self
-                .notification_queue
-                .drain(..available_slots.min(self.notification_queue.len()))
-                .collect();
+        for anchor in anchors {
+            let mut notifications_limit = Self::notifications_limit(config);
+
+            if let Some(AnchoredWindow { window, .. }) = self.windows.get_mut(&anchor) {
+                // INFO: the banners which never expire can't free the slots by themselves, so
+                // the notifications in queue would wait forever when all shown banners are such.
+                // To avoid it, the limit is extended by `never_expire_overflow` banners and after
+                // reaching it the oldest banner is closed to give a room for the queued
+                // notification.
+                if notifications_limit != usize::MAX && window.all_banners_never_expire(config) {
+                    notifications_limit = notifications_limit
+                        .saturating_add(config.general().never_expire_overflow as usize)
+                        .min(window.banners_capacity(config));
+
+                    if window.total_banners() >= notifications_limit {
+                        if let Some(notification) = window.remove_oldest_banner(config) {
+                            self.signals.extend(closed_signals(
+                                [notification.id],
+                                ClosingReason::Undefined,
+                            ));
+                        }
+                    }
+                }
+            }
+
+            let total_banners = self
+                .windows
+                .get(&anchor)
+                .map(|AnchoredWindow { window, .. }| window.total_banners())
+                .unwrap_or(0);
+            let available_slots = notifications_limit.saturating_sub(total_banners);
+
+            let mut notifications_to_display = vec![];
+            let mut rest_notifications = VecDeque::with_capacity(self.notification_queue.len());
+            for notification in self.notification_queue.drain(..) {
+                if notifications_to_display.len() < available_slots
+                    && !is_withheld(&notification, inhibited)
+                    && Self::anchor_of(&notification, config) == &anchor
+                {
+                    notifications_to_display.push(notification);
+                } else {
+                    rest_notifications.push_back(notification);
+                }
+            }
+            self.notification_queue = rest_notifications;
+
+            if notifications_to_display.is_empty() {
+                continue;
+            }
+
+            // INFO: the output height is known only after the window is created, so the
+            // notifications which don't fit into the output are held in the queue here
+            self.ensure_window(&anchor, config)?;
+            let window = &self.windows[&anchor].window;
+            let available_slots = window
+                .banners_capacity(config)
+                .saturating_sub(window.total_banners());
+            if notifications_to_display.len() > available_slots {
+                requeue(
+                    &mut self.notification_queue,
+                    notifications_to_display.split_off(available_slots),
+                );
+            }
+            if notifications_to_display.is_empty() {
+                continue;
+            }
 
-            window.update_banners(notifications_to_display, config, &self.cached_layouts);
+            notifications_to_display
+                .iter()
+                .for_each(|notification| self.sound_player.play(notification, config));
 
-            self.update_window(config)?;
-            self.roundtrip_event_queue()?;
+            self.windows[&anchor].window.update_banners(
+                notifications_to_display,
+                config,
+                &self.cached_layouts,
+            );
         }
 
-        Ok(())
+        requeue(&mut self.notification_queue, held_notifications);
+
+        self.update_windows(config)?;
+        self.roundtrip_event_queues()
     }
 
     pub(crate) fn handle_close_notifications(&mut self, config: &Config) -> anyhow::Result<()> {
-        if self.window.as_ref().is_some() && !self.close_notifications.is_empty() {
-            let window = self.window.as_mut().unwrap();
-
-            let notifications = window.remove_banners_by_id(&self.close_notifications);
+        if !self.windows.is_empty() && !self.close_notifications.is_empty() {
+            let notifications: Vec<Notification> = self
+                .windows
+                .values_mut()
+                .flat_map(|AnchoredWindow { window, .. }| {
+                    window.remove_banners_by_id(&self.close_notifications)
+                })
+                .collect();
             self.close_notifications.clear();
 
             if notifications.is_empty() {
                 return Ok(());
             }
 
-            notifications
-                .into_iter()
-                .map(|notification| notification.id)
-                .for_each(|id| {
-                    self.signals.push(Signal::NotificationClosed {
-                        notification_id: id,
-                        reason: dbus::actions::ClosingReason::CallCloseNotification,
-                    })
-                });
-
+            // INFO: the server emits the `NotificationClosed` signal when the `CloseNotification`
+            // method is called, so the signals aren't emitted twice.
             self.process_notification_queue(config)?;
         }
 
         Ok(())
     }
 
-    pub(crate) fn remove_expired(&mut self, config: &Config) -> anyhow::Result<()> {
-        if let Some(window) = self.window.as_mut() {
-            let notifications = window.remove_expired_banners(config);
+    /// Closes the shown and queued notifications by the client request and destroys the windows.
+    /// Returns the number of closed notifications.
+    pub(crate) fn close_all_notifications(&mut self) -> anyhow::Result<u32> {
+        let notification_ids = self.drain_notifications()?;
+        let closed = notification_ids.len() as u32;
+        debug!(target: WINDOW, "Window Manager: Closed all {closed} notifications");
 
-            if notifications.is_empty() {
-                return Ok(());
-            }
+        self.signals.extend(requested_signals(notification_ids));
+        Ok(closed)
+    }
 
-            notifications.into_iter().for_each(|notification| {
-                self.signals.push(Signal::NotificationClosed {
-                    notification_id: notification.id,
-                    reason: dbus::actions::ClosingReason::Expired,
-                })
-            });
+    /// Closes the shown and queued notifications of application. Returns the number of closed
+    /// notifications.
+    pub(crate) fn close_notifications_by_app(
+        &mut self,
+        app_name: &str,
+        config: &Config,
+    ) -> anyhow::Result<u32> {
+        let mut notification_ids: Vec<u32> = self
+            .windows
+            .values_mut()
+            .flat_map(|AnchoredWindow { window, .. }| window.remove_banners_by_app(app_name))
+            .map(|notification| notification.id)
+            .collect();
+        notification_ids.extend(drain_by_app(&mut self.notification_queue, app_name));
 
-            self.process_notification_queue(config)?;
+        let closed = notification_ids.len() as u32;
+        debug!(target: WINDOW, "Window Manager: Closed {closed} notifications of {app_name}");
+
+        if closed == 0 {
+            return Ok(0);
         }
 
-        Ok(())
+        self.signals.extend(requested_signals(notification_ids));
+        self.process_notification_queue(config)?;
+
+        Ok(closed)
+    }
+
+    /// Drops the banners which cannot be drawn by their layouts.
+    pub(crate) fn remove_failed(&mut self, config: &Config) -> anyhow::Result<()> {
+        let notifications: Vec<Notification> = self
+            .windows
+            .values_mut()
+            .flat_map(|AnchoredWindow { window, .. }| window.remove_failed_banners())
+            .collect();
+
+        if notifications.is_empty() {
+            return Ok(());
+        }
+
+        self.signals.extend(failed_signals(&notifications));
+
+        self.process_notification_queue(config)
+    }
+
+    pub(crate) fn remove_expired(&mut self, config: &Config) -> anyhow::Result<()> {
+        if self.paused_at.is_some() {
+            return Ok(());
+        }
+
+        let notifications: Vec<Notification> = self
+            .windows
+            .values_mut()
+            .flat_map(|AnchoredWindow { window, .. }| window.remove_expired_banners(config))
+            .collect();
+
+        if notifications.is_empty() {
+            return Ok(());
+        }
+
+        self.signals.extend(expired_signals(&notifications));
+
+        self.process_notification_queue(config)
     }
 
+    /// Takes the earliest signal, so the signals are emitted in the order they are pushed.
     pub(crate) fn pop_signal(&mut self) -> Option<Signal> {
-        self.signals.pop()
+        self.signals.pop_front()
+    }
+
+    pub(crate) fn take_copied_codes(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.copied_codes)
+    }
+
+    /// Closes the shown and queued notifications and destroys the windows. The clients receive
+    /// the `NotificationClosed` signals for the closed notifications.
+    pub(crate) fn close_all(&mut self) -> anyhow::Result<()> {
+        let notification_ids = self.drain_notifications()?;
+        debug!(
+            target: WINDOW,
+            "Window Manager: Closed {} notifications on shutdown",
+            notification_ids.len()
+        );
+
+        self.signals
+            .extend(closed_signals(notification_ids, ClosingReason::Undefined));
+        Ok(())
+    }
+
+    /// Takes the shown and queued notifications and destroys the windows. Returns the ids of
+    /// taken notifications.
+    fn drain_notifications(&mut self) -> anyhow::Result<Vec<u32>> {
+        let mut shown_ids = vec![];
+        for (_, mut anchored_window) in self.windows.drain(..) {
+            shown_ids.extend(
+                anchored_window
+                    .window
+                    .take_notifications()
+                    .iter()
+                    .map(|notification| notification.id),
+            );
+            anchored_window.deinit()?;
+        }
+        let queued_ids = self
+            .notification_queue
+            .drain(..)
+            .map(|notification| notification.id);
+
+        Ok(unique_ids(shown_ids.into_iter().chain(queued_ids)))
     }
 
     pub(crate) fn handle_actions(&mut self, config: &Config) -> anyhow::Result<()> {
         //TODO: change it to actions which defines in config file
 
-        if let Some(window) = self.window.as_mut() {
+        let mut notifications = vec![];
+        for AnchoredWindow {
+            qhandle, window, ..
+        } in self.windows.values_mut()
+        {
             window.handle_hover(config);
+            if window.update_hover_highlight(config, &self.cached_layouts) {
+                window.draw(qhandle, config);
+                window.frame(qhandle);
+                window.commit();
+            }
 
-            let signals = window.handle_click(config);
-            if signals.is_empty() {
-                return Ok(());
+            notifications.extend(window.handle_click(qhandle, config, &self.cached_layouts));
+            self.copied_codes.extend(window.take_copied_codes());
+
+            // INFO: the expanded banner may push the last banners off the output
+            let overflowing = window.take_overflowing_banners(config);
+            if !overflowing.is_empty() {
+                requeue(&mut self.notification_queue, overflowing);
+                window.draw(qhandle, config);
+                window.frame(qhandle);
+                window.commit();
             }
+        }
 
-            self.signals.extend(signals);
-            self.process_notification_queue(config)?;
+        if notifications.is_empty() {
+            return Ok(());
         }
 
-        Ok(())
+        // INFO: the activation tokens of invoked actions are received asynchronously, so do
+        // roundtrip to emit the invoked actions before the notifications are closed.
+        self.roundtrip_event_queues()?;
+        let invoked_actions: Vec<Signal> = self
+            .windows
+            .values_mut()
+            .flat_map(|AnchoredWindow { window, .. }| window.take_invoked_actions())
+            .collect();
+
+        self.signals
+            .extend(clicked_signals(invoked_actions, &notifications));
+        self.process_notification_queue(config)
     }
 
-    pub(crate) fn reset_timeouts(&mut self) -> anyhow::Result<()> {
-        if let Some(window) = self.window.as_mut() {
-            window.reset_timeouts();
+    /// Cycles the banners with the queued notifications of the same anchor when the user scrolls
+    /// over the window, so the notifications which are hidden by the limit come into view.
+    pub(crate) fn handle_scroll(&mut self, config: &Config) -> anyhow::Result<()> {
+        let scrolls: Vec<(Anchor, i32)> = self
+            .windows
+            .iter_mut()
+            .map(|(anchor, AnchoredWindow { window, .. })| {
+                (anchor.clone(), window.take_scroll_steps())
+            })
+            .filter(|(_, steps)| *steps != 0)
+            .collect();
+
+        if scrolls.is_empty() {
+            return Ok(());
         }
 
-        Ok(())
+        for (anchor, steps) in scrolls {
+            let forward = steps > 0;
+            let cycled = (0..steps.unsigned_abs())
+                .take_while(|_| self.cycle_banners(&anchor, forward, config))
+                .count();
+
+            debug!(
+                target: WINDOW,
+                "Window Manager: Cycled {cycled} banners {} by scroll",
+                if forward { "forward" } else { "backward" }
+            );
+        }
+
+        self.update_windows(config)
+    }
+
+    /// Replaces the oldest banner by the first queued notification of anchor and puts the banner
+    /// to the end of queue. Backward it replaces the newest banner by the last queued
+    /// notification and puts the banner to the start of queue. Returns false when there is no
+    /// queued notification to show.
+    fn cycle_banners(&mut self, anchor: &Anchor, forward: bool, config: &Config) -> bool {
+        let inhibited = self.inhibited;
+        let is_cyclable = |notification: &Notification| {
+            !is_withheld(notification, inhibited) && Self::anchor_of(notification, config) == anchor
+        };
+        let position = match forward {
+            true => self.notification_queue.iter().position(is_cyclable),
+            false => self.notification_queue.iter().rposition(is_cyclable),
+        };
+
+        let Some(AnchoredWindow { window, .. }) = self.windows.get_mut(anchor) else {
+            return false;
+        };
+        let (Some(position), Some(id)) = (position, window.oldest_or_newest_banner(!forward))
+        else {
+            return false;
+        };
+
+        let notification = self
+            .notification_queue
+            .remove(position)
+            .expect("The position of queued notification must be valid");
+        let hidden = window.remove_banners_by_id(&[id]);
+        window.update_banners(vec![notification], config, &self.cached_layouts);
+
+        for notification in hidden {
+            match forward {
+                true => self.notification_queue.push_back(notification),
+                false => self.notification_queue.push_front(notification),
+            }
+        }
+
+        true
     }
 
-    fn update_window(&mut self, config: &Config) -> anyhow::Result<()> {
-        if let Some(window) = self.window.as_mut() {
-            if window.is_empty() {
-                return self.deinit_window();
+    /// Redraws the windows which contain banners with outdated relative time since the
+    /// notification arrived.
+    pub(crate) fn refresh_ages(&mut self, config: &Config) -> anyhow::Result<()> {
+        for AnchoredWindow {
+            qhandle, window, ..
+        } in self.windows.values_mut()
+        {
+            if window.refresh_ages(config, &self.cached_layouts) {
+                window.draw(qhandle, config);
+                window.frame(qhandle);
+                window.commit();
             }
+        }
+
+        Ok(())
+    }
 
-            let qhandle = unsafe { self.qhandle.as_ref().unwrap_unchecked() };
+    pub(crate) fn animate(&mut self, config: &Config) -> anyhow::Result<()> {
+        for AnchoredWindow {
+            qhandle, window, ..
+        } in self.windows.values_mut()
+        {
+            if window.has_pending_draw() {
+                window.draw(qhandle, config);
+            } else if window.has_animations() && window.is_frame_ready() {
+                window.draw_animation_frames(qhandle, config);
+            } else {
+                continue;
+            }
 
-            window.draw(qhandle, config);
             window.frame(qhandle);
             window.commit();
-
-            debug!("Window Manager: Updated the windows");
         }
 
         Ok(())
     }
 
-    fn roundtrip_event_queue(&mut self) -> anyhow::Result<()> {
-        if let Some(event_queue) = self.event_queue.as_mut() {
-            event_queue.roundtrip(unsafe { self.window.as_mut().unwrap_unchecked() })?;
+    /// Keeps the memory usage within the `limits.memory_mb` budget. Under the memory pressure the
+    /// new images are downscaled more and aren't animated, and the least recently drawn banners
+    /// drop their animations until the animations alone fit into the budget.
+    pub(crate) fn enforce_memory_budget(&mut self, config: &Config) {
+        let Some(budget) = config.limits().memory_budget() else {
+            return;
+        };
 
-            debug!("Window Manager: Roundtrip events for the windows");
+        let usage = self.cached_layouts.approximate_bytes()
+            + self
+                .windows
+                .values()
+                .map(|anchored_window| anchored_window.window.approximate_bytes())
+                .sum::<usize>();
+
+        let memory_pressure = usage > budget;
+        if memory_pressure != self.memory_pressure {
+            match memory_pressure {
+                true => warn!(
+                    target: WINDOW,
+                    "Window Manager: The memory usage {usage} bytes exceeds the budget of {budget} bytes"
+                ),
+                false => info!(
+                    target: WINDOW,
+                    "Window Manager: The memory usage is within the budget again"
+                ),
+            }
+            self.memory_pressure = memory_pressure;
         }
 
-        Ok(())
+        for AnchoredWindow { window, .. } in self.windows.values_mut() {
+            window.set_memory_pressure(memory_pressure);
+        }
+
+        if !memory_pressure {
+            return;
+        }
+
+        let entries = self
+            .windows
+            .values()
+            .enumerate()
+            .flat_map(|(index, anchored_window)| {
+                anchored_window
+                    .window
+                    .animations()
+                    .map(move |(id, used_at, bytes)| Evictable {
+                        key: (index, id),
+                        used_at,
+                        bytes,
+                    })
+            })
+            .collect();
+
+        for (index, id) in memory_budget::lru_evictions(entries, budget) {
+            self.windows[index].window.evict_animation(id);
+        }
     }
 
-    fn init_window(&mut self, config: &Config) -> anyhow::Result<bool> {
-        if self.window.is_none() {
-            let mut event_queue = self.connection.new_event_queue();
-            let qhandle = event_queue.handle();
-            let display = self.connection.display();
-            display.get_registry(&qhandle, ());
+    pub(crate) fn reset_timeouts(&mut self) -> anyhow::Result<()> {
+        for AnchoredWindow { window, .. } in self.windows.values_mut() {
+            window.reset_timeouts();
+        }
 
-            let mut window = Window::init(self.font_collection.clone(), config);
+        Ok(())
+    }
+
+    /// Redraws the windows with banners and closes the empty ones.
+    fn update_windows(&mut self, config: &Config) -> anyhow::Result<()> {
+        let empty_anchors: Vec<Anchor> = self
+            .windows
+            .iter()
+            .filter(|(_, AnchoredWindow { window, .. })| window.is_empty())
+            .map(|(anchor, _)| anchor.clone())
+            .collect();
 
-            while let ConfigurationState::NotConfiured = window.configuration_state() {
-                event_queue.blocking_dispatch(&mut window)?;
+        for anchor in empty_anchors {
+            if let Some(anchored_window) = self.windows.shift_remove(&anchor) {
+                anchored_window.deinit()?;
             }
+        }
+
+        if self.windows.is_empty() {
+            return Ok(());
+        }
 
-            window.configure(&qhandle, config);
+        self.windows
+            .values_mut()
+            .for_each(|anchored_window| anchored_window.update(config, &self.cached_layouts));
 
-            while let ConfigurationState::Ready = window.configuration_state() {
-                event_queue.blocking_dispatch(&mut window)?;
-            }
+        debug!(target: WINDOW, "Window Manager: Updated the windows");
 
-            self.event_queue = Some(event_queue);
-            self.qhandle = Some(qhandle);
-            self.window = Some(window);
+        Ok(())
+    }
 
-            debug!("Window Manager: Created a window");
+    fn roundtrip_event_queues(&mut self) -> anyhow::Result<()> {
+        if self.windows.is_empty() {
+            return Ok(());
+        }
 
-            Ok(true)
-        } else {
-            Ok(false)
+        for anchored_window in self.windows.values_mut() {
+            anchored_window.roundtrip()?;
         }
+
+        debug!(target: WINDOW, "Window Manager: Roundtrip events for the windows");
+
+        Ok(())
     }
 
-    fn deinit_window(&mut self) -> anyhow::Result<()> {
-        unsafe {
-            let window = self.window.as_mut().unwrap_unchecked();
-            window.deinit();
-            self.event_queue
-                .as_mut()
-                .unwrap_unchecked()
-                .roundtrip(window)?;
+    /// Creates the window for the anchor if it doesn't exist yet.
+    fn ensure_window(&mut self, anchor: &Anchor, config: &Config) -> anyhow::Result<()> {
+        if !self.windows.contains_key(anchor) {
+            let anchored_window = AnchoredWindow::init(
+                &self.connection,
+                self.font_collection.clone(),
+                config,
+                anchor.clone(),
+            )?;
+            self.windows.insert(anchor.clone(), anchored_window);
         }
-        self.window = None;
-        self.event_queue = None;
-        self.qhandle = None;
-
-        debug!("Window Manager: Closed window");
 
         Ok(())
     }
 }
+
+/// Reorders the queued notifications so they are taken to display in the configured order.
+fn order_queue(notification_queue: &mut VecDeque<Notification>, queue_order: QueueOrder) {
+    match queue_order {
+        QueueOrder::Fifo => (),
+        // INFO: the sort is stable, so the notifications with the same urgency keep the order of
+        // their arrival
+        QueueOrder::Priority => notification_queue
+            .make_contiguous()
+            .sort_by_key(|notification| Reverse(notification.hints.urgency)),
+    }
+}
+
+/// Checks whether the notification stays in queue because of inhibition. The critical
+/// notifications are never withheld.
+fn is_withheld(notification: &Notification, inhibited: bool) -> bool {
+    inhibited && notification.hints.urgency != Urgency::Critical
+}
+
+/// Puts the notification to the end of queue or replaces the queued notification with the same
+/// id.
+fn enqueue(notification_queue: &mut VecDeque<Notification>, notification: Notification) {
+    match notification_queue
+        .iter_mut()
+        .find(|queued| queued.id == notification.id)
+    {
+        Some(queued) => *queued = notification,
+        None => notification_queue.push_back(notification),
+    }
+}
+
+/// Removes the queued notifications of application. Returns the ids of removed notifications.
+fn drain_by_app(notification_queue: &mut VecDeque<Notification>, app_name: &str) -> Vec<u32> {
+    let mut notification_ids = vec![];
+    notification_queue.retain(|notification| {
+        let is_removed = notification.app_name == app_name;
+        if is_removed {
+            notification_ids.push(notification.id);
+        }
+        !is_removed
+    });
+
+    notification_ids
+}
+
+/// Puts the notifications which can't be shown yet back to the front of queue in their order.
+fn requeue(notification_queue: &mut VecDeque<Notification>, notifications: Vec<Notification>) {
+    notifications
+        .into_iter()
+        .rev()
+        .for_each(|notification| notification_queue.push_front(notification));
+}
+
+/// Deduplicates the ids keeping their order. The replacement of shown notification waits in the
+/// queue with the same id, so the id would be closed twice otherwise.
+fn unique_ids(notification_ids: impl IntoIterator<Item = u32>) -> Vec<u32> {
+    let mut seen = HashSet::new();
+    notification_ids
+        .into_iter()
+        .filter(|notification_id| seen.insert(*notification_id))
+        .collect()
+}
+
+/// Returns the `NotificationClosed` signals of notifications. The banners which are removed by
+/// the daemon itself, e.g. evicted, failed to draw or closed on shutdown, have the `Undefined`
+/// reason because the specification has no reason for them.
+fn closed_signals(
+    notification_ids: impl IntoIterator<Item = u32>,
+    reason: ClosingReason,
+) -> Vec<Signal> {
+    notification_ids
+        .into_iter()
+        .map(|notification_id| Signal::NotificationClosed {
+            notification_id,
+            reason,
+        })
+        .collect()
+}
+
+/// Returns the signals of notifications which are closed by the client request. The single
+/// `CloseNotification` call is reported by the server itself.
+fn requested_signals(notification_ids: impl IntoIterator<Item = u32>) -> Vec<Signal> {
+    closed_signals(notification_ids, ClosingReason::CallCloseNotification)
+}
+
+/// Returns the signals of banners which cannot be drawn by their layouts, e.g. after the
+/// replacement. The daemon removes them itself, so they are never reported as expired.
+fn failed_signals(notifications: &[Notification]) -> Vec<Signal> {
+    closed_signals(
+        notifications.iter().map(|notification| notification.id),
+        ClosingReason::Undefined,
+    )
+}
+
+/// Returns the signals of banners whose timeout is reached.
+fn expired_signals(notifications: &[Notification]) -> Vec<Signal> {
+    closed_signals(
+        notifications.iter().map(|notification| notification.id),
+        ClosingReason::Expired,
+    )
+}
+
+/// Returns the signals of clicked banners. The invoked actions go before the `NotificationClosed`
+/// signals, otherwise the clients stop listening before they receive the action.
+fn clicked_signals(invoked_actions: Vec<Signal>, notifications: &[Notification]) -> Vec<Signal> {
+    let mut signals = invoked_actions;
+    signals.extend(closed_signals(
+        notifications.iter().map(|notification| notification.id),
+        ClosingReason::DismissedByUser,
+    ));
+    signals
+}
+
+#[cfg(test)]
+mod tests {
+    use dbus::notification::Urgency;
+
+    use super::*;
+
+    fn reasons(signals: Vec<Signal>) -> Vec<(u32, ClosingReason)> {
+        signals
+            .into_iter()
+            .map(|signal| match signal {
+                Signal::NotificationClosed {
+                    notification_id,
+                    reason,
+                } => (notification_id, reason),
+                Signal::ActionInvoked { .. } => panic!("Expected only NotificationClosed signals"),
+            })
+            .collect()
+    }
+
+    fn notifications(ids: &[u32]) -> Vec<Notification> {
+        ids.iter()
+            .map(|&id| Notification {
+                id,
+                ..Notification::test_default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn expired_banners_are_closed_as_expired() {
+        assert_eq!(
+            reasons(expired_signals(&notifications(&[1, 2]))),
+            vec![(1, ClosingReason::Expired), (2, ClosingReason::Expired)]
+        );
+    }
+
+    #[test]
+    fn clicked_banners_are_dismissed_by_user() {
+        assert_eq!(
+            reasons(clicked_signals(vec![], &notifications(&[3]))),
+            vec![(3, ClosingReason::DismissedByUser)]
+        );
+    }
+
+    #[test]
+    fn requested_closing_is_reported_as_call_close_notification() {
+        assert_eq!(
+            reasons(requested_signals([4])),
+            vec![(4, ClosingReason::CallCloseNotification)]
+        );
+    }
+
+    #[test]
+    fn failed_replacement_is_not_expired() {
+        assert_eq!(
+            reasons(failed_signals(&notifications(&[5, 6]))),
+            vec![(5, ClosingReason::Undefined), (6, ClosingReason::Undefined)]
+        );
+    }
+
+    #[test]
+    fn close_all_emits_one_signal_per_notification() {
+        let shown_ids = [3, 1, 4];
+        let queued_ids = [4, 5, 1, 6];
+
+        assert_eq!(
+            reasons(requested_signals(unique_ids(
+                shown_ids.into_iter().chain(queued_ids)
+            ))),
+            [3, 1, 4, 5, 6]
+                .into_iter()
+                .map(|id| (id, ClosingReason::CallCloseNotification))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn action_is_emitted_before_closing_of_clicked_banner() {
+        let notification = Notification {
+            id: 7,
+            ..Notification::test_default()
+        };
+        let invoked_actions = vec![Signal::ActionInvoked {
+            notification_id: 7,
+            action_key: "default".to_string(),
+            activation_token: None,
+        }];
+
+        let mut signals = VecDeque::new();
+        signals.extend(clicked_signals(invoked_actions, &[notification]));
+
+        assert!(matches!(
+            signals.pop_front(),
+            Some(Signal::ActionInvoked {
+                notification_id: 7,
+                ..
+            })
+        ));
+        assert!(matches!(
+            signals.pop_front(),
+            Some(Signal::NotificationClosed {
+                notification_id: 7,
+                reason: ClosingReason::DismissedByUser,
+            })
+        ));
+        assert!(signals.pop_front().is_none());
+    }
+
+    fn queue(urgencies: &[Urgency]) -> VecDeque<Notification> {
+        urgencies
+            .iter()
+            .zip(1..)
+            .map(|(urgency, id)| {
+                let mut notification = Notification {
+                    id,
+                    ..Notification::test_default()
+                };
+                notification.hints.urgency = *urgency;
+                notification
+            })
+            .collect()
+    }
+
+    fn ids(notification_queue: &VecDeque<Notification>) -> Vec<u32> {
+        notification_queue
+            .iter()
+            .map(|notification| notification.id)
+            .collect()
+    }
+
+    const MIXED_URGENCIES: [Urgency; 5] = [
+        Urgency::Normal,
+        Urgency::Low,
+        Urgency::Critical,
+        Urgency::Normal,
+        Urgency::Critical,
+    ];
+
+    #[test]
+    fn queued_notifications_are_drained_by_app() {
+        let mut notification_queue = queue(&MIXED_URGENCIES);
+        notification_queue[1].app_name = "mail".to_string();
+        notification_queue[3].app_name = "mail".to_string();
+
+        assert_eq!(drain_by_app(&mut notification_queue, "mail"), [2, 4]);
+        assert_eq!(ids(&notification_queue), [1, 3, 5]);
+        assert!(drain_by_app(&mut notification_queue, "chat").is_empty());
+    }
+
+    #[test]
+    fn only_critical_notifications_pass_inhibition() {
+        let notification_queue = queue(&MIXED_URGENCIES);
+
+        let passed: Vec<u32> = notification_queue
+            .iter()
+            .filter(|notification| !is_withheld(notification, true))
+            .map(|notification| notification.id)
+            .collect();
+        assert_eq!(passed, vec![3, 5]);
+        assert!(notification_queue
+            .iter()
+            .all(|notification| !is_withheld(notification, false)));
+    }
+
+    #[test]
+    fn fifo_queue_order() {
+        let mut notification_queue = queue(&MIXED_URGENCIES);
+        order_queue(&mut notification_queue, QueueOrder::Fifo);
+
+        assert_eq!(ids(&notification_queue), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn priority_queue_order() {
+        let mut notification_queue = queue(&MIXED_URGENCIES);
+        order_queue(&mut notification_queue, QueueOrder::Priority);
+
+        assert_eq!(ids(&notification_queue), vec![3, 5, 1, 4, 2]);
+    }
+
+    #[test]
+    fn overflowing_notifications_are_requeued_in_order() {
+        let mut notification_queue = queue(&MIXED_URGENCIES);
+        let overflow = notification_queue.split_off(3);
+
+        requeue(&mut notification_queue, overflow.into());
+        assert_eq!(ids(&notification_queue), vec![4, 5, 1, 2, 3]);
+    }
+}
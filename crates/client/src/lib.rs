@@ -1,5 +1,6 @@
 use anyhow::bail;
 use log::debug;
+use shared::log_target::CLIENT;
 use std::collections::HashMap;
 use zbus::zvariant::Value;
 
@@ -22,8 +23,8 @@ pub struct NotiClient<'a> {
 }
 
 impl<'a> NotiClient<'a> {
-    pub async fn init() -> anyhow::Result<Self> {
-        let client = dbus::client::Client::init().await?;
+    pub async fn init(bus_address: Option<&str>) -> anyhow::Result<Self> {
+        let client = dbus::client::Client::init(bus_address).await?;
         Ok(Self {
             dbus_client: client,
         })
@@ -42,12 +43,13 @@ impl<'a> NotiClient<'a> {
         hints: Vec<String>,
         hints_data: HintsData,
     ) -> anyhow::Result<u32> {
-        debug!("Client: Building hints and actions from user prompt");
+        debug!(target: CLIENT, "Client: Building hints and actions from user prompt");
         let new_hints = build_hints(&hints, hints_data)?;
 
         let actions = build_actions(&actions)?;
 
         debug!(
+            target: CLIENT,
             "Client: Send notification with metadata:\n\
             \treplaces_id - {id},\n\
             \tapp_name - {app_name},\n\
@@ -66,15 +68,15 @@ impl<'a> NotiClient<'a> {
             )
             .await?;
 
-        debug!("Client: Successful send");
+        debug!(target: CLIENT, "Client: Successful send");
 
         Ok(notification_id)
     }
 
     pub async fn get_server_info(&self) -> anyhow::Result<()> {
-        debug!("Client: Trying to request server information");
+        debug!(target: CLIENT, "Client: Trying to request server information");
         let server_info = self.dbus_client.get_server_information().await?;
-        debug!("Client: Received server information");
+        debug!(target: CLIENT, "Client: Received server information");
 
         println!(
             "Name: {}\nVendor: {}\nVersion: {}\nSpecification version: {}",
@@ -83,6 +85,60 @@ impl<'a> NotiClient<'a> {
 
         Ok(())
     }
+
+    /// Closes the notification by id, the notifications of application or all of them when
+    /// neither is set.
+    pub async fn close(&self, id: Option<u32>, app_name: Option<&str>) -> anyhow::Result<()> {
+        if let Some(id) = id {
+            debug!(target: CLIENT, "Client: Trying to close notification with id {id}");
+            self.dbus_client.close_notification(id).await?;
+            debug!(target: CLIENT, "Client: The notification is closed");
+            return Ok(());
+        }
+
+        debug!(target: CLIENT, "Client: Trying to close notifications");
+        let closed = self
+            .dbus_client
+            .close_notifications_by_app(app_name)
+            .await?;
+        debug!(target: CLIENT, "Client: The notifications are closed");
+
+        println!("Closed {closed} notification(s)");
+
+        Ok(())
+    }
+
+    pub async fn reset_state(&self) -> anyhow::Result<()> {
+        debug!(target: CLIENT, "Client: Trying to reset server state");
+        self.dbus_client.reset_state().await?;
+        debug!(target: CLIENT, "Client: Server state is reset");
+
+        Ok(())
+    }
+
+    pub async fn copy_last(&self, summary: bool) -> anyhow::Result<()> {
+        debug!(target: CLIENT, "Client: Trying to copy the last notification");
+        self.dbus_client.copy_last_notification(summary).await?;
+        debug!(target: CLIENT, "Client: The last notification is copied");
+
+        Ok(())
+    }
+
+    pub async fn pause(&self) -> anyhow::Result<()> {
+        debug!(target: CLIENT, "Client: Trying to pause the notification timeouts");
+        self.dbus_client.suspend_all().await?;
+        debug!(target: CLIENT, "Client: The notification timeouts are paused");
+
+        Ok(())
+    }
+
+    pub async fn resume(&self) -> anyhow::Result<()> {
+        debug!(target: CLIENT, "Client: Trying to resume the notification timeouts");
+        self.dbus_client.resume_all().await?;
+        debug!(target: CLIENT, "Client: The notification timeouts are resumed");
+
+        Ok(())
+    }
 }
 
 fn build_actions(actions: &[String]) -> anyhow::Result<Vec<&str>> {
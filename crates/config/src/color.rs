@@ -26,6 +26,25 @@ impl Color {
     pub(super) fn new_rgba_red() -> Self {
         Color::Rgba(Rgba::new_red())
     }
+
+    /// Returns the solid color or the colors of gradient.
+    pub fn stops(&self) -> &[Rgba] {
+        match self {
+            Color::LinearGradient(linear_gradient) => &linear_gradient.colors,
+            Color::Rgba(rgba) => std::slice::from_ref(rgba),
+        }
+    }
+
+    /// Checks whether the color doesn't have any transparent part.
+    pub fn is_opaque(&self) -> bool {
+        match self {
+            Color::LinearGradient(linear_gradient) => linear_gradient
+                .colors
+                .iter()
+                .all(|rgba| rgba.alpha == u8::MAX),
+            Color::Rgba(rgba) => rgba.alpha == u8::MAX,
+        }
+    }
 }
 
 impl From<Rgba> for Color {
@@ -80,6 +99,127 @@ impl Rgba {
         }
     }
 
+    /// Returns the relative luminance of color by WCAG 2.
+    pub fn relative_luminance(&self) -> f32 {
+        let linearize = |channel: u8| {
+            let channel = channel as f32 / 255.0;
+            if channel <= 0.03928 {
+                channel / 12.92
+            } else {
+                ((channel + 0.055) / 1.055).powf(2.4)
+            }
+        };
+
+        0.2126 * linearize(self.red)
+            + 0.7152 * linearize(self.green)
+            + 0.0722 * linearize(self.blue)
+    }
+
+    /// Returns the contrast ratio of colors by WCAG 2 which is from 1 to 21.
+    pub fn contrast_ratio(&self, other: &Rgba) -> f32 {
+        let (lhs, rhs) = (self.relative_luminance(), other.relative_luminance());
+        (lhs.max(rhs) + 0.05) / (lhs.min(rhs) + 0.05)
+    }
+
+    /// Changes the lightness of color as little as possible to reach the minimum contrast ratio
+    /// with every background. When it can't be reached, the lightest or darkest color with the
+    /// best contrast is returned. Returns `None` if the contrast is already enough.
+    pub fn with_min_contrast(&self, backgrounds: &[Rgba], min_ratio: f32) -> Option<Rgba> {
+        let contrast = |color: &Rgba| {
+            backgrounds
+                .iter()
+                .map(|background| color.contrast_ratio(background))
+                .fold(f32::INFINITY, f32::min)
+        };
+
+        if contrast(self) >= min_ratio {
+            return None;
+        }
+
+        let (hue, saturation, lightness) = self.to_hsl();
+        let with_lightness =
+            |lightness: f32| Rgba::from_hsl(hue, saturation, lightness, self.alpha);
+
+        let closest_lightness = [1.0, 0.0]
+            .into_iter()
+            .filter(|&target| contrast(&with_lightness(target)) >= min_ratio)
+            .map(|target| {
+                // INFO: the luminance grows with lightness, so the bisection finds the lightness
+                // closest to the original one which still reaches the contrast
+                let (mut reached, mut unreached) = (target, lightness);
+                for _ in 0..16 {
+                    let middle = (reached + unreached) / 2.0;
+                    if contrast(&with_lightness(middle)) >= min_ratio {
+                        reached = middle;
+                    } else {
+                        unreached = middle;
+                    }
+                }
+                reached
+            })
+            .min_by(|lhs, rhs| (lhs - lightness).abs().total_cmp(&(rhs - lightness).abs()));
+
+        let lightness = closest_lightness.unwrap_or_else(|| {
+            if contrast(&with_lightness(1.0)) >= contrast(&with_lightness(0.0)) {
+                1.0
+            } else {
+                0.0
+            }
+        });
+
+        Some(with_lightness(lightness))
+    }
+
+    fn to_hsl(&self) -> (f32, f32, f32) {
+        let [red, green, blue] =
+            [self.red, self.green, self.blue].map(|channel| channel as f32 / 255.0);
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+        let lightness = (max + min) / 2.0;
+
+        let delta = max - min;
+        if delta == 0.0 {
+            return (0.0, 0.0, lightness);
+        }
+
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max == red {
+            ((green - blue) / delta).rem_euclid(6.0)
+        } else if max == green {
+            (blue - red) / delta + 2.0
+        } else {
+            (red - green) / delta + 4.0
+        } * 60.0;
+
+        (hue, saturation, lightness)
+    }
+
+    fn from_hsl(hue: f32, saturation: f32, lightness: f32, alpha: u8) -> Self {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let second = chroma * (1.0 - ((hue / 60.0).rem_euclid(2.0) - 1.0).abs());
+        let (red, green, blue) = match (hue / 60.0) as u8 {
+            0 => (chroma, second, 0.0),
+            1 => (second, chroma, 0.0),
+            2 => (0.0, chroma, second),
+            3 => (0.0, second, chroma),
+            4 => (second, 0.0, chroma),
+            _ => (chroma, 0.0, second),
+        };
+        let lightness_offset = lightness - chroma / 2.0;
+        let to_channel = |value: f32| {
+            ((value + lightness_offset) * 255.0)
+                .round()
+                .clamp(0.0, 255.0) as u8
+        };
+
+        Self {
+            red: to_channel(red),
+            green: to_channel(green),
+            blue: to_channel(blue),
+            alpha,
+        }
+    }
+
     fn pre_mul_alpha(self) -> Self {
         if self.alpha == 255 {
             return self;
@@ -142,6 +282,16 @@ impl TryFrom<String> for Rgba {
     }
 }
 
+impl std::fmt::Display for Rgba {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)?;
+        if self.alpha != u8::MAX {
+            write!(f, "{:02x}", self.alpha)?;
+        }
+        Ok(())
+    }
+}
+
 impl TryFromValue for Rgba {
     fn try_from_string(value: String) -> Result<Self, shared::error::ConversionError> {
         value
@@ -155,9 +305,162 @@ impl TryFromValue for Rgba {
 }
 
 public! {
+    /// The linear gradient which direction is set either by the `degree` or by the CSS-like
+    /// `direction` keyword, e.g. `to top right`.
     #[derive(Debug, Clone, Deserialize)]
+    #[serde(try_from = "TomlLinearGradient")]
     struct LinearGradient {
         degree: i16,
         colors: Vec<Rgba>,
     }
 }
+
+#[derive(Deserialize)]
+struct TomlLinearGradient {
+    degree: Option<i16>,
+    direction: Option<GradientDirection>,
+    colors: Vec<Rgba>,
+}
+
+impl TryFrom<TomlLinearGradient> for LinearGradient {
+    type Error = anyhow::Error;
+
+    fn try_from(value: TomlLinearGradient) -> Result<Self, Self::Error> {
+        let degree = match (value.degree, value.direction) {
+            (Some(degree), None) => degree,
+            (None, Some(direction)) => direction.degree(),
+            (Some(_), Some(_)) => {
+                anyhow::bail!("Expected either degree or direction of linear gradient, not both")
+            }
+            (None, None) => anyhow::bail!("Expected degree or direction of linear gradient"),
+        };
+
+        Ok(LinearGradient {
+            degree,
+            colors: value.colors,
+        })
+    }
+}
+
+/// The CSS-like direction of linear gradient where the first color starts at the opposite side.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+enum GradientDirection {
+    #[serde(rename = "to right")]
+    Right,
+    #[serde(rename = "to bottom right", alias = "to right bottom")]
+    BottomRight,
+    #[serde(rename = "to bottom")]
+    Bottom,
+    #[serde(rename = "to bottom left", alias = "to left bottom")]
+    BottomLeft,
+    #[serde(rename = "to left")]
+    Left,
+    #[serde(rename = "to top left", alias = "to left top")]
+    TopLeft,
+    #[serde(rename = "to top")]
+    Top,
+    #[serde(rename = "to top right", alias = "to right top")]
+    TopRight,
+}
+
+impl GradientDirection {
+    /// Converts the direction to the degree of gradient. The degree goes from the left to right
+    /// side and rotates towards the bottom side as it grows.
+    fn degree(self) -> i16 {
+        match self {
+            GradientDirection::Right => 0,
+            GradientDirection::BottomRight => 45,
+            GradientDirection::Bottom => 90,
+            GradientDirection::BottomLeft => 135,
+            GradientDirection::Left => 180,
+            GradientDirection::TopLeft => 225,
+            GradientDirection::Top => 270,
+            GradientDirection::TopRight => 315,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Color, LinearGradient, Rgba};
+
+    fn parse(gradient: &str) -> Result<LinearGradient, toml::de::Error> {
+        #[derive(serde::Deserialize)]
+        struct Wrapper {
+            color: Color,
+        }
+
+        let gradient = match gradient {
+            "" => String::new(),
+            gradient => format!(", {gradient}"),
+        };
+
+        toml::from_str::<Wrapper>(&format!(
+            r##"color = {{ mode = "linear-gradient", colors = ["#000", "#fff"]{gradient} }}"##
+        ))
+        .map(|wrapper| match wrapper.color {
+            Color::LinearGradient(linear_gradient) => linear_gradient,
+            Color::Rgba(_) => panic!("Expected the linear gradient"),
+        })
+    }
+
+    #[test]
+    fn gradient_degree() {
+        assert_eq!(parse("degree = 30").unwrap().degree, 30);
+    }
+
+    #[test]
+    fn gradient_directions() {
+        let directions = [
+            ("to right", 0),
+            ("to bottom right", 45),
+            ("to right bottom", 45),
+            ("to bottom", 90),
+            ("to bottom left", 135),
+            ("to left bottom", 135),
+            ("to left", 180),
+            ("to top left", 225),
+            ("to left top", 225),
+            ("to top", 270),
+            ("to top right", 315),
+            ("to right top", 315),
+        ];
+
+        for (direction, degree) in directions {
+            let gradient = parse(&format!(r#"direction = "{direction}""#)).unwrap();
+            assert_eq!(gradient.degree, degree, "direction {direction}");
+        }
+    }
+
+    #[test]
+    fn invalid_gradient_direction() {
+        assert!(parse(r#"direction = "to nowhere""#).is_err());
+        assert!(parse(r#"degree = 90, direction = "to top""#).is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn contrast_ratio() {
+        let black = Rgba::try_from("#000".to_string()).unwrap();
+        let white = Rgba::try_from("#fff".to_string()).unwrap();
+
+        assert!((black.contrast_ratio(&white) - 21.0).abs() < 0.01);
+        assert!((white.contrast_ratio(&white) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn foreground_is_adjusted_to_min_contrast() {
+        let background = Rgba::try_from("#1e1e2e".to_string()).unwrap();
+        let foreground = Rgba::try_from("#45475a".to_string()).unwrap();
+
+        let adjusted = foreground
+            .with_min_contrast(std::slice::from_ref(&background), 4.5)
+            .unwrap();
+        assert!(adjusted.contrast_ratio(&background) >= 4.5);
+        assert!(adjusted.relative_luminance() > foreground.relative_luminance());
+
+        assert!(adjusted
+            .with_min_contrast(std::slice::from_ref(&background), 4.5)
+            .is_none());
+    }
+}
@@ -1,9 +1,13 @@
 use std::{collections::HashMap, marker::PhantomData, path::PathBuf};
 
 use dbus::notification::Urgency;
+use log::warn;
 use macros::{ConfigProperty, GenericBuilder};
-use serde::{de::Visitor, Deserialize};
-use shared::{error::ConversionError, value::TryFromValue};
+use serde::{
+    de::{IntoDeserializer, Visitor},
+    Deserialize,
+};
+use shared::{error::ConversionError, log_target::CONFIG, value::TryFromValue};
 
 use crate::{
     public,
@@ -17,6 +21,9 @@ public! {
     struct DisplayConfig {
         layout: Layout,
 
+        #[cfg_prop(default(true))]
+        layout_fallback: bool,
+
         theme: String,
 
         #[cfg_prop(use_type(IconInfoProperty), mergeable)]
@@ -25,6 +32,11 @@ public! {
         #[cfg_prop(use_type(TomlImageProperty), mergeable)]
         image: ImageProperty,
 
+        animate_images: bool,
+
+        #[cfg_prop(use_type(TomlProgressProperty), mergeable)]
+        progress: ProgressProperty,
+
         padding: Spacing,
 
         #[cfg_prop(use_type(TomlBorder), mergeable)]
@@ -48,8 +60,27 @@ public! {
         #[cfg_prop(default(true))]
         markup: bool,
 
+        /// Parses the summary as markup like the body instead of showing it as plain text.
+        summary_markup: bool,
+
+        /// Shows only the summary in one line and omits the body. The banner shrinks to the line
+        /// but never exceeds the height from config.
+        compact: bool,
+
+        /// Shows the "Show more" label when the body doesn't fit, e.g. by `max_lines`. The click
+        /// on it expands the banner to the whole body.
+        expandable_body: bool,
+
+        /// Shows the time since the notification arrived, e.g. "2m ago", by the text with `age`
+        /// kind. The default layout shows it below the body.
+        show_timestamp: bool,
+
         #[cfg_prop(default(Timeout::new(0)))]
         timeout: Timeout,
+
+        /// The animation which is played when the banner appears. It's set for all urgencies or
+        /// overridden per urgency like the timeout.
+        animation: UrgencyAnimation,
     }
 }
 
@@ -62,28 +93,47 @@ impl TomlDisplayConfig {
             }
         };
     }
+
+    /// Warns when the custom layout file is missing. The `owner` describes the config section
+    /// which contains the layout.
+    pub(super) fn check_layout_path(&self, owner: &str) {
+        if let Some(Layout::FromPath { path_buf }) = self.layout.as_ref() {
+            if !path_buf.exists() {
+                warn!(
+                    target: CONFIG,
+                    "Config: The layout file {path_buf:?} of {owner} doesn't exist"
+                );
+            }
+        }
+    }
 }
 
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Debug, Clone)]
 #[serde(from = "String")]
 pub enum Layout {
-    #[default]
-    Default,
+    /// The layout which is built in Noti and selected by name.
+    BuiltIn(BuiltInLayout),
     FromPath {
         path_buf: PathBuf,
     },
 }
 
 impl Layout {
-    pub fn is_default(&self) -> bool {
-        matches!(self, Layout::Default)
+    pub fn is_built_in(&self) -> bool {
+        matches!(self, Layout::BuiltIn(_))
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::BuiltIn(BuiltInLayout::default())
     }
 }
 
 impl From<String> for Layout {
     fn from(value: String) -> Self {
-        if value == "default" {
-            return Layout::Default;
+        if let Some(built_in) = BuiltInLayout::from_name(&value) {
+            return Layout::BuiltIn(built_in);
         }
 
         Layout::FromPath {
@@ -96,6 +146,33 @@ impl From<String> for Layout {
     }
 }
 
+/// The layouts which are selected by name instead of the path to layout file.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum BuiltInLayout {
+    /// The image on the left and the centered summary, body, progress and age next to it.
+    #[default]
+    Classic,
+    /// The image and the summary with progress in one row without body and age.
+    Compact,
+    /// The image on the left and the texts aligned to it.
+    ImageLeft,
+    /// The texts aligned to the left edge and the image on the right.
+    ImageRight,
+}
+
+impl BuiltInLayout {
+    /// Returns the built-in layout by its name. The `default` name is the alias of `classic`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "default" | "classic" => BuiltInLayout::Classic,
+            "compact" => BuiltInLayout::Compact,
+            "image-left" => BuiltInLayout::ImageLeft,
+            "image-right" => BuiltInLayout::ImageRight,
+            _ => return None,
+        })
+    }
+}
+
 public! {
     #[derive(ConfigProperty, Debug)]
     #[cfg_prop(name(IconInfoProperty), derive(Debug, Deserialize, Clone, Default))]
@@ -117,6 +194,12 @@ public! {
         #[gbuilder(default(64))]
         max_size: u16,
 
+        /// The maximum width and height of decoded image which is kept in memory. Larger images
+        /// are downscaled right after decoding.
+        #[cfg_prop(default(1024))]
+        #[gbuilder(default(1024))]
+        max_decode_size: u16,
+
         #[cfg_prop(default(0))]
         #[gbuilder(default(0))]
         rounding: u16,
@@ -126,6 +209,17 @@ public! {
 
         #[gbuilder(default)]
         resizing_method: ResizingMethod,
+
+        /// Lets the compositor scale the image to its size through `wp_viewporter` instead of
+        /// resizing it by `resizing_method`. It's ignored for rounded images and when the
+        /// compositor doesn't support it.
+        #[gbuilder(default(false))]
+        viewport_scaling: bool,
+
+        /// The alignment of image within its area in built-in layouts: vertically and
+        /// relative to the text.
+        #[gbuilder(default)]
+        alignment: ImageAlignment,
     }
 }
 
@@ -137,6 +231,28 @@ impl Default for ImageProperty {
 
 impl TryFromValue for ImageProperty {}
 
+public! {
+    #[derive(ConfigProperty, GenericBuilder, Debug, Clone)]
+    #[cfg_prop(name(TomlProgressProperty), derive(Debug, Clone, Default, Deserialize))]
+    #[gbuilder(name(GBuilderProgressProperty), derive(Clone))]
+    struct ProgressProperty {
+        #[cfg_prop(default(6))]
+        #[gbuilder(default(6))]
+        height: u8,
+
+        #[gbuilder(default)]
+        margin: Spacing,
+    }
+}
+
+impl Default for ProgressProperty {
+    fn default() -> Self {
+        TomlProgressProperty::default().into()
+    }
+}
+
+impl TryFromValue for ProgressProperty {}
+
 #[derive(Debug, Deserialize, Default, Clone)]
 pub enum ResizingMethod {
     #[serde(rename = "nearest")]
@@ -168,6 +284,72 @@ impl TryFromValue for ResizingMethod {
     }
 }
 
+/// The alignment of image like `"top-right"`. Each part can be omitted, then the image is
+/// centered vertically and placed to the left of text.
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(try_from = "String")]
+pub struct ImageAlignment {
+    pub vertical: VerticalAlignment,
+    pub side: ImageSide,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum VerticalAlignment {
+    Top,
+    #[default]
+    Center,
+    Bottom,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ImageSide {
+    #[default]
+    Left,
+    Right,
+}
+
+impl TryFrom<String> for ImageAlignment {
+    type Error = ConversionError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from_string(value)
+    }
+}
+
+impl TryFromValue for ImageAlignment {
+    fn try_from_string(value: String) -> Result<Self, ConversionError> {
+        let invalid_value = || ConversionError::InvalidValue {
+            expected: "top, center, bottom, left, right or their combination like top-right",
+            actual: value.clone(),
+        };
+
+        let mut alignment = ImageAlignment::default();
+        let (mut vertical_is_set, mut side_is_set) = (false, false);
+        for part in value.to_lowercase().split(['-', '_']) {
+            match part {
+                "top" | "center" | "bottom" if !vertical_is_set => {
+                    alignment.vertical = match part {
+                        "top" => VerticalAlignment::Top,
+                        "center" => VerticalAlignment::Center,
+                        _ => VerticalAlignment::Bottom,
+                    };
+                    vertical_is_set = true;
+                }
+                "left" | "right" if !side_is_set => {
+                    alignment.side = match part {
+                        "left" => ImageSide::Left,
+                        _ => ImageSide::Right,
+                    };
+                    side_is_set = true;
+                }
+                _ => return Err(invalid_value()),
+            }
+        }
+
+        Ok(alignment)
+    }
+}
+
 public! {
     #[derive(ConfigProperty, GenericBuilder, Debug, Default, Clone)]
     #[cfg_prop(name(TomlBorder), derive(Debug, Clone, Default, Deserialize))]
@@ -318,3 +500,347 @@ critical = 0 # but for critical the default value will be overriden
         Ok(local_map.into())
     }
 }
+
+/// The animation which draws attention to the banner when it appears.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq)]
+pub enum Animation {
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    /// Brightens the banner twice.
+    #[serde(rename = "flash")]
+    Flash,
+    /// Shakes the banner horizontally.
+    #[serde(rename = "shake")]
+    Shake,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct UrgencyAnimation {
+    default: Option<Animation>,
+    low: Option<Animation>,
+    normal: Option<Animation>,
+    critical: Option<Animation>,
+}
+
+impl UrgencyAnimation {
+    pub fn by_urgency(&self, urgency: &Urgency) -> Animation {
+        match urgency {
+            Urgency::Low => self.low,
+            Urgency::Normal => self.normal,
+            Urgency::Critical => self.critical,
+        }
+        .or(self.default)
+        .unwrap_or_default()
+    }
+}
+
+impl From<Animation> for UrgencyAnimation {
+    fn from(value: Animation) -> Self {
+        UrgencyAnimation {
+            default: Some(value),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<HashMap<String, Animation>> for UrgencyAnimation {
+    fn from(value: HashMap<String, Animation>) -> Self {
+        UrgencyAnimation {
+            default: value.get("default").copied(),
+            low: value.get("low").copied(),
+            normal: value.get("normal").copied(),
+            critical: value.get("critical").copied(),
+        }
+    }
+}
+
+struct UrgencyAnimationVisitor;
+
+impl<'de> Deserialize<'de> for UrgencyAnimation {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(UrgencyAnimationVisitor)
+    }
+}
+
+impl<'de> Visitor<'de> for UrgencyAnimationVisitor {
+    type Value = UrgencyAnimation;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            r#"Either animation name or Table value. The animations are "none", "flash" and "shake".
+
+Example:
+
+display.animation = "flash"
+
+# or
+
+[display.animation]
+default = "none" # for low and normal this value will be set
+critical = "shake"
+"#
+        )
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Animation::deserialize(v.into_deserializer()).map(Into::into)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let mut local_map = HashMap::new();
+
+        while let Some((key, value)) = map.next_entry::<String, Animation>()? {
+            match key.as_str() {
+                "default" | "low" | "normal" | "critical" => {
+                    local_map.insert(key, value);
+                }
+                _ => {
+                    return Err(serde::de::Error::unknown_field(
+                        &key,
+                        &["default", "low", "normal", "critical"],
+                    ))
+                }
+            }
+        }
+
+        Ok(local_map.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::{TextJustification, TextStyle};
+    use std::path::Path;
+
+    fn parse(content: &str) -> DisplayConfig {
+        toml::from_str::<TomlDisplayConfig>(content)
+            .unwrap()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn default_justification() {
+        let display = parse("");
+
+        assert!(matches!(
+            display.title.justification,
+            TextJustification::Center
+        ));
+        assert!(matches!(
+            display.body.justification,
+            TextJustification::Left
+        ));
+    }
+
+    #[test]
+    fn user_body_justification() {
+        let display = parse(
+            r#"
+            [body]
+            justification = "center"
+            "#,
+        );
+
+        assert!(matches!(
+            display.body.justification,
+            TextJustification::Center
+        ));
+        assert!(matches!(
+            display.title.justification,
+            TextJustification::Center
+        ));
+    }
+
+    #[test]
+    fn user_title_justification() {
+        let display = parse(
+            r#"
+            [title]
+            justification = "right"
+            "#,
+        );
+
+        assert!(matches!(
+            display.title.justification,
+            TextJustification::Right
+        ));
+        assert!(matches!(
+            display.body.justification,
+            TextJustification::Left
+        ));
+    }
+
+    #[test]
+    fn partial_title_keeps_defaults() {
+        let display = parse(
+            r#"
+            [title]
+            font_size = 16
+            "#,
+        );
+
+        assert_eq!(display.title.font_size, 16);
+        assert!(matches!(display.title.style, TextStyle::Bold));
+        assert!(matches!(
+            display.title.justification,
+            TextJustification::Center
+        ));
+    }
+
+    #[test]
+    fn text_does_not_override_specific_property() {
+        let display = parse(
+            r#"
+            [text]
+            justification = "right"
+
+            [body]
+            justification = "center"
+            "#,
+        );
+
+        assert!(matches!(
+            display.title.justification,
+            TextJustification::Right
+        ));
+        assert!(matches!(
+            display.body.justification,
+            TextJustification::Center
+        ));
+    }
+
+    #[test]
+    fn app_body_justification_over_global() {
+        let global: TomlDisplayConfig = toml::from_str(
+            r#"
+            [body]
+            justification = "right"
+            "#,
+        )
+        .unwrap();
+
+        let mut app: TomlDisplayConfig = toml::from_str(
+            r#"
+            [body]
+            justification = "center"
+            "#,
+        )
+        .unwrap();
+        app.merge_temporary_fields();
+
+        let display: DisplayConfig = app.merge(Some(global)).unwrap_or_default();
+        assert!(matches!(
+            display.body.justification,
+            TextJustification::Center
+        ));
+    }
+
+    #[test]
+    fn built_in_layouts_by_name() {
+        for (name, built_in) in [
+            ("default", BuiltInLayout::Classic),
+            ("classic", BuiltInLayout::Classic),
+            ("compact", BuiltInLayout::Compact),
+            ("image-left", BuiltInLayout::ImageLeft),
+            ("image-right", BuiltInLayout::ImageRight),
+        ] {
+            let display = parse(&format!(r#"layout = "{name}""#));
+            assert!(matches!(display.layout, Layout::BuiltIn(layout) if layout == built_in));
+        }
+
+        let display = parse(r#"layout = "compact.noti""#);
+        assert!(matches!(display.layout, Layout::FromPath { .. }));
+    }
+
+    #[test]
+    fn app_layout_over_global() {
+        let global: TomlDisplayConfig = toml::from_str(r#"layout = "global.noti""#).unwrap();
+
+        let mut app: TomlDisplayConfig = toml::from_str(r#"layout = "app.noti""#).unwrap();
+        app.use_relative_path(PathBuf::from("/etc/noti"));
+
+        let display: DisplayConfig = app.merge(Some(global.clone())).unwrap_or_default();
+        assert!(matches!(
+            display.layout,
+            Layout::FromPath { path_buf } if path_buf == Path::new("/etc/noti/app.noti")
+        ));
+
+        let app: TomlDisplayConfig = toml::from_str("").unwrap();
+        let display: DisplayConfig = app.merge(Some(global)).unwrap_or_default();
+        assert!(matches!(
+            display.layout,
+            Layout::FromPath { path_buf } if path_buf == Path::new("global.noti")
+        ));
+    }
+
+    #[test]
+    fn image_alignment() {
+        assert_eq!(parse("").image.alignment, ImageAlignment::default());
+
+        for (value, vertical, side) in [
+            ("top", VerticalAlignment::Top, ImageSide::Left),
+            ("right", VerticalAlignment::Center, ImageSide::Right),
+            ("bottom-right", VerticalAlignment::Bottom, ImageSide::Right),
+            ("Left_Top", VerticalAlignment::Top, ImageSide::Left),
+        ] {
+            let display = parse(&format!("[image]\nalignment = \"{value}\""));
+            assert_eq!(display.image.alignment, ImageAlignment { vertical, side });
+        }
+
+        for value in ["middle", "top-bottom", "left-right", ""] {
+            assert!(toml::from_str::<TomlDisplayConfig>(&format!(
+                "[image]\nalignment = \"{value}\""
+            ))
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn animation_by_urgency() {
+        let display = parse("");
+        assert_eq!(
+            display.animation.by_urgency(&Urgency::Critical),
+            Animation::None
+        );
+
+        let display = parse(r#"animation = "flash""#);
+        assert_eq!(
+            display.animation.by_urgency(&Urgency::Low),
+            Animation::Flash
+        );
+        assert_eq!(
+            display.animation.by_urgency(&Urgency::Critical),
+            Animation::Flash
+        );
+
+        let display = parse("[animation]\ncritical = \"shake\"");
+        assert_eq!(
+            display.animation.by_urgency(&Urgency::Normal),
+            Animation::None
+        );
+        assert_eq!(
+            display.animation.by_urgency(&Urgency::Critical),
+            Animation::Shake
+        );
+
+        assert!(toml::from_str::<TomlDisplayConfig>(r#"animation = "bounce""#).is_err());
+        assert!(toml::from_str::<TomlDisplayConfig>("[animation]\ncritcal = \"shake\"").is_err());
+
+        let error = toml::from_str::<TomlDisplayConfig>("animation = 1").unwrap_err();
+        assert!(error
+            .message()
+            .contains("Either animation name or Table value"));
+    }
+}
@@ -2,10 +2,15 @@
 //!
 //! With it the module also stores `TomlGeneralConfig` which can parse data from TOML data.
 
+use std::collections::HashSet;
+
+use dbus::{notification::Urgency, text::TagKind};
+use log::warn;
 use macros::ConfigProperty;
 use serde::Deserialize;
+use shared::log_target::CONFIG;
 
-use crate::{public, sorting::Sorting};
+use crate::{color::Color, public, sorting::Sorting};
 
 public! {
     #[derive(ConfigProperty, Debug)]
@@ -13,22 +18,146 @@ public! {
     struct GeneralConfig {
         font: Font,
 
+        #[cfg_prop(default("NotoColorEmoji".to_string()))]
+        emoji_font: String,
+        monochrome_emoji: bool,
+        /// The font family of keyboard shortcuts in the `<kbd>` markup tag.
+        #[cfg_prop(default("monospace".to_string()))]
+        monospace_font: String,
+        /// The character which is drawn by the text font instead of emoji when the emoji font is
+        /// not found. The missing glyph of the text font is drawn when it's not set.
+        emoji_placeholder: Option<char>,
+        font_hinting: FontHinting,
+        /// The bounds of font sizes in pixels. The font sizes of config and layouts are clamped
+        /// to them, so the extreme sizes don't blow up the memory of rendering.
+        #[cfg_prop(default(6))]
+        min_font_size: u8,
+        #[cfg_prop(default(96))]
+        max_font_size: u8,
+
         #[cfg_prop(default(300))]
         width: u16,
         #[cfg_prop(default(150))]
         height: u16,
+        /// The minimum width of banners when they are sized to their content by `max_width`.
+        #[cfg_prop(default(0))]
+        min_width: u16,
+        /// The maximum width of banners. When it's set, the banners are as wide as their content
+        /// within `min_width` and `max_width` instead of the fixed `width`.
+        max_width: Option<u16>,
 
-        anchor: Anchor,
-        offset: (u8, u8),
-        #[cfg_prop(default(10))]
-        gap: u8,
+        anchor: Anchors,
+        /// The offset of banner stack which can be overridden for each urgency like the anchor.
+        offset: ByUrgency<(u8, u8)>,
+        #[cfg_prop(default("noti".to_string()))]
+        layer_namespace: String,
+        /// The gap between banners which can be overridden for each urgency like the anchor.
+        gap: ByUrgency<Gap>,
 
         sorting: Sorting,
+        gravity: Gravity,
 
         #[cfg_prop(default(0))]
         limit: u8,
+        #[cfg_prop(default(2))]
+        never_expire_overflow: u8,
+        queue_order: QueueOrder,
+        default_urgency: Urgency,
+
+        /// The minimum time in milliseconds which the banner is shown before it expires, is
+        /// evicted or replaced.
+        #[cfg_prop(default(0))]
+        min_display_ms: u32,
+
+        /// The maximum lifetime in milliseconds of banners which never expire otherwise and have
+        /// no actions, so they don't stay forever. It takes precedence over the timeout of
+        /// notification and doesn't apply to resident notifications. 0 disables it.
+        #[cfg_prop(default(0))]
+        never_timeout_fallback_ms: u32,
+        /// Applies `never_timeout_fallback_ms` to critical notifications too.
+        never_timeout_fallback_critical: bool,
 
         idle_threshold: IdleThreshold,
+        /// Keeps the notifications in the queue while all outputs are turned off by DPMS and
+        /// shows them when the screen is turned on.
+        pause_on_screen_off: bool,
+        timeout_clock: TimeoutClock,
+
+        match_desktop_entry: bool,
+
+        markup_tags: MarkupTags,
+        /// Removes the control characters except new lines and tabs from the summary and body,
+        /// e.g. NULs and terminal escape sequences which break the layout.
+        #[cfg_prop(default(true))]
+        sanitize_text: bool,
+        /// Honors the `replaces_id` only when the replaced notification belongs to the same
+        /// application, otherwise the notification gets a new id.
+        #[cfg_prop(default(true))]
+        strict_replaces: bool,
+
+        allow_remote_images: bool,
+
+        hover_highlight: Option<Color>,
+
+        /// The minimum contrast ratio by WCAG between the foreground and background of themes,
+        /// e.g. 4.5. The foreground lightness is adjusted when the contrast is lower.
+        enforce_contrast: Option<f32>,
+
+        input_passthrough: InputPassthrough,
+
+        /// The opacity of banners by their age from the newest one, e.g. `[1.0, 0.8, 0.6]`. The
+        /// banners older than the listed ones take the last value. Empty list keeps all banners
+        /// opaque.
+        age_opacity: Vec<f32>,
+
+        /// The delay in milliseconds between the reveals of banners which appear at once, so
+        /// they show up one by one. 0 reveals them simultaneously.
+        #[cfg_prop(default(0))]
+        stagger_ms: u32,
+    }
+}
+
+/// The markup tags which are honored in the body of notifications. The other tags are dropped
+/// and their content is shown as plain text.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(from = "Vec<String>")]
+pub struct MarkupTags(HashSet<TagKind>);
+
+impl MarkupTags {
+    pub fn tags(&self) -> &HashSet<TagKind> {
+        &self.0
+    }
+}
+
+impl Default for MarkupTags {
+    fn default() -> Self {
+        MarkupTags(TagKind::all())
+    }
+}
+
+impl From<Vec<String>> for MarkupTags {
+    fn from(tag_names: Vec<String>) -> Self {
+        MarkupTags(
+            tag_names
+                .iter()
+                .filter_map(|tag_name| match tag_name.to_lowercase().as_str() {
+                    "b" | "bold" => Some(TagKind::Bold),
+                    "i" | "italic" => Some(TagKind::Italic),
+                    "u" | "underline" => Some(TagKind::Underline),
+                    "kbd" | "keyboard" => Some(TagKind::Kbd),
+                    "a" | "link" => Some(TagKind::Link),
+                    "img" | "image" => Some(TagKind::Image),
+                    other => {
+                        warn!(
+                            target: CONFIG,
+                            "Config: Unknown markup tag \"{other}\" is skipped. \
+                            Possible values: b, i, u, kbd, a, img"
+                        );
+                        None
+                    }
+                })
+                .collect(),
+        )
     }
 }
 
@@ -64,6 +193,74 @@ impl Default for IdleThreshold {
     }
 }
 
+/// The place in the stack where the last banner by sorting, usually the newest one, appears.
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq)]
+pub enum Gravity {
+    /// The banner appears at the anchored corner and pushes the other banners away from it.
+    #[default]
+    #[serde(rename = "corner")]
+    Corner,
+    /// The banner appears at the far end of stack from the anchored corner, so the other banners
+    /// stay in place.
+    #[serde(rename = "far")]
+    Far,
+}
+
+/// The part of window which passes the pointer input through to the windows below.
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq)]
+pub enum InputPassthrough {
+    /// The whole window takes the input.
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    /// Only the banners take the input and the gaps between them pass it through.
+    #[serde(rename = "gaps")]
+    Gaps,
+    /// The whole window passes the input through, so the banners can't be clicked or hovered.
+    #[serde(rename = "all")]
+    All,
+}
+
+/// The clock which measures the banner timeouts.
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq)]
+pub enum TimeoutClock {
+    /// The clock doesn't advance while the system is suspended.
+    #[default]
+    #[serde(rename = "monotonic")]
+    Monotonic,
+    /// The clock includes the time when the system is suspended so the banners expire right
+    /// after resume.
+    #[serde(rename = "boottime")]
+    Boottime,
+}
+
+/// The hinting level of font glyphs.
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq)]
+pub enum FontHinting {
+    /// The glyphs are placed at their exact fractional positions.
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    /// The baseline and advances of glyphs are aligned to the pixel grid, so the horizontal stems
+    /// look crisper and the glyphs are evenly spaced at the cost of slightly less precise
+    /// placement.
+    #[serde(rename = "slight")]
+    Slight,
+}
+
+/// The order in which the queued notifications are shown when the limit of banners is reached.
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq)]
+pub enum QueueOrder {
+    /// The notifications are shown in the order of their arrival.
+    #[default]
+    #[serde(rename = "fifo")]
+    Fifo,
+    /// The notifications with higher urgency are shown first and the ones with the same urgency
+    /// are shown in the order of their arrival.
+    #[serde(rename = "priority")]
+    Priority,
+}
+
 public! {
     #[derive(Debug, Deserialize, Clone)]
     #[serde(from = "String")]
@@ -86,7 +283,115 @@ impl Default for Font {
     }
 }
 
+/// The value which can be overridden for each urgency. It's written either as the single value
+/// or as the table with the `default` value and the values of `low`, `normal` and `critical`
+/// urgencies. Other keys of the table are rejected. When the table has no `default` value, the
+/// default value of the type is used, e.g. 10 pixels of gap.
 #[derive(Debug, Deserialize, Default, Clone)]
+#[serde(
+    from = "TomlByUrgency<T>",
+    bound(deserialize = "T: Deserialize<'de> + Default")
+)]
+pub struct ByUrgency<T> {
+    default: T,
+    low: Option<T>,
+    normal: Option<T>,
+    critical: Option<T>,
+}
+
+impl<T> ByUrgency<T> {
+    pub fn by_urgency(&self, urgency: &Urgency) -> &T {
+        self.overridden(urgency).unwrap_or(&self.default)
+    }
+
+    /// Returns the value for the stack of banners at the anchor. Several urgencies can share the
+    /// anchor, so the override of the most urgent one among them is taken and the default value
+    /// is used when none of them overrides it.
+    pub fn by_anchor(&self, anchors: &Anchors, anchor: &Anchor) -> &T {
+        [Urgency::Critical, Urgency::Normal, Urgency::Low]
+            .iter()
+            .filter(|urgency| anchors.by_urgency(urgency) == anchor)
+            .find_map(|urgency| self.overridden(urgency))
+            .unwrap_or(&self.default)
+    }
+
+    fn overridden(&self, urgency: &Urgency) -> Option<&T> {
+        match urgency {
+            Urgency::Low => self.low.as_ref(),
+            Urgency::Normal => self.normal.as_ref(),
+            Urgency::Critical => self.critical.as_ref(),
+        }
+    }
+}
+
+/// The anchors of banners which can be overridden for each urgency. Notifications with the same
+/// anchor share one stack.
+pub type Anchors = ByUrgency<Anchor>;
+
+impl Anchors {
+    /// Returns the distinct anchors which are used by any urgency.
+    pub fn distinct(&self) -> Vec<Anchor> {
+        let mut anchors = vec![];
+        for urgency in [Urgency::Low, Urgency::Normal, Urgency::Critical] {
+            let anchor = self.by_urgency(&urgency);
+            if !anchors.contains(anchor) {
+                anchors.push(anchor.clone());
+            }
+        }
+        anchors
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(
+    untagged,
+    expecting = "either a value or a table with 'default', 'low', 'normal' and 'critical' keys"
+)]
+enum TomlByUrgency<T> {
+    Single(T),
+    ByUrgency(TomlUrgencyValues<T>),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlUrgencyValues<T> {
+    default: Option<T>,
+    low: Option<T>,
+    normal: Option<T>,
+    critical: Option<T>,
+}
+
+impl<T: Default> From<TomlByUrgency<T>> for ByUrgency<T> {
+    fn from(value: TomlByUrgency<T>) -> Self {
+        match value {
+            TomlByUrgency::Single(value) => ByUrgency {
+                default: value,
+                low: None,
+                normal: None,
+                critical: None,
+            },
+            TomlByUrgency::ByUrgency(values) => ByUrgency {
+                default: values.default.unwrap_or_default(),
+                low: values.low,
+                normal: values.normal,
+                critical: values.critical,
+            },
+        }
+    }
+}
+
+/// The gap between banners in pixels.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(transparent)]
+pub struct Gap(pub u8);
+
+impl Default for Gap {
+    fn default() -> Self {
+        Gap(10)
+    }
+}
+
+#[derive(Debug, Deserialize, Default, Clone, PartialEq, Eq, Hash)]
 #[serde(from = "String")]
 pub enum Anchor {
     Top,
@@ -147,3 +452,113 @@ impl From<String> for Anchor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use dbus::{notification::Urgency, text::TagKind};
+
+    use super::{Anchor, Gap, MarkupTags, TomlGeneralConfig};
+
+    #[test]
+    fn single_anchor() {
+        let general: TomlGeneralConfig = toml::from_str(r#"anchor = "bottom-left""#).unwrap();
+        let anchors = general.anchor.unwrap();
+
+        assert_eq!(anchors.by_urgency(&Urgency::Critical), &Anchor::BottomLeft);
+        assert_eq!(anchors.distinct(), vec![Anchor::BottomLeft]);
+    }
+
+    #[test]
+    fn anchor_by_urgency() {
+        let general: TomlGeneralConfig = toml::from_str(
+            r#"
+            [anchor]
+            default = "top-right"
+            critical = "top"
+            "#,
+        )
+        .unwrap();
+        let anchors = general.anchor.unwrap();
+
+        assert_eq!(anchors.by_urgency(&Urgency::Low), &Anchor::TopRight);
+        assert_eq!(anchors.by_urgency(&Urgency::Normal), &Anchor::TopRight);
+        assert_eq!(anchors.by_urgency(&Urgency::Critical), &Anchor::Top);
+        assert_eq!(anchors.distinct(), vec![Anchor::TopRight, Anchor::Top]);
+    }
+
+    #[test]
+    fn unknown_urgency_is_rejected() {
+        let error = toml::from_str::<TomlGeneralConfig>("gap = { critcal = 20 }").unwrap_err();
+        assert!(error
+            .message()
+            .contains("either a value or a table with 'default', 'low', 'normal' and 'critical'"));
+    }
+
+    #[test]
+    fn gap_and_offset_by_anchor() {
+        let general: TomlGeneralConfig = toml::from_str(
+            r#"
+            anchor = { default = "top-right", critical = "top" }
+            gap = { critical = 20 }
+            offset = { default = [15, 15], normal = [5, 5] }
+            "#,
+        )
+        .unwrap();
+        let general = general.unwrap_or_default();
+
+        assert_eq!(
+            general.gap.by_anchor(&general.anchor, &Anchor::Top),
+            &Gap(20)
+        );
+        assert_eq!(
+            general.gap.by_anchor(&general.anchor, &Anchor::TopRight),
+            &Gap(10)
+        );
+        assert_eq!(
+            general.offset.by_anchor(&general.anchor, &Anchor::Top),
+            &(15, 15)
+        );
+        assert_eq!(
+            general.offset.by_anchor(&general.anchor, &Anchor::TopRight),
+            &(5, 5)
+        );
+
+        let general = TomlGeneralConfig::default().unwrap_or_default();
+        assert_eq!(
+            general.gap.by_anchor(&general.anchor, &Anchor::TopRight),
+            &Gap(10)
+        );
+        assert_eq!(
+            general.offset.by_anchor(&general.anchor, &Anchor::TopRight),
+            &(0, 0)
+        );
+    }
+
+    #[test]
+    fn markup_tags() {
+        let general: TomlGeneralConfig =
+            toml::from_str(r#"markup_tags = ["b", "Italic", "marquee"]"#).unwrap();
+
+        assert_eq!(
+            general.markup_tags.unwrap().tags(),
+            &HashSet::from([TagKind::Bold, TagKind::Italic])
+        );
+        assert_eq!(MarkupTags::default().tags(), &TagKind::all());
+    }
+
+    #[test]
+    fn default_urgency() {
+        let general: TomlGeneralConfig = toml::from_str(r#"default_urgency = "low""#).unwrap();
+        assert_eq!(general.default_urgency, Some(Urgency::Low));
+        assert_eq!(
+            TomlGeneralConfig::default()
+                .unwrap_or_default()
+                .default_urgency,
+            Urgency::Normal
+        );
+
+        assert!(toml::from_str::<TomlGeneralConfig>(r#"default_urgency = "urgent""#).is_err());
+    }
+}
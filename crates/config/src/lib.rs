@@ -1,8 +1,13 @@
+use dbus::notification::Notification;
 use display::{DisplayConfig, TomlDisplayConfig};
 use general::{GeneralConfig, TomlGeneralConfig};
+use limits::{LimitsConfig, TomlLimitsConfig};
 use log::{debug, error, warn};
+use otp::{OtpConfig, TomlOtpConfig};
 use serde::Deserialize;
 use shared::file_watcher::{FileState, FilesWatcher};
+use shared::log_target::CONFIG;
+use sound::{Sound, SoundConfig, TomlSoundConfig};
 use std::{
     collections::HashMap,
     ops::Not,
@@ -13,7 +18,10 @@ use theme::{Theme, TomlTheme};
 pub mod color;
 pub mod display;
 pub mod general;
+pub mod limits;
+pub mod otp;
 pub mod sorting;
+pub mod sound;
 pub mod spacing;
 pub mod text;
 pub mod theme;
@@ -26,15 +34,21 @@ const APP_NAME: &str = env!("APP_NAME");
 const CONFIG_FILE: &str = "config.toml";
 
 pub struct Config {
-    main_watcher: FilesWatcher,
+    /// The watcher of main config file. It's absent when the config is read from memory, so it's
+    /// never reloaded.
+    main_watcher: Option<FilesWatcher>,
     subwatchers: Vec<FilesWatcher>,
     general: GeneralConfig,
     display: DisplayConfig,
+    sound: SoundConfig,
+    otp: OtpConfig,
+    limits: LimitsConfig,
 
     default_theme: Theme,
     themes: HashMap<String, Theme>,
 
     app_configs: HashMap<String, DisplayConfig>,
+    app_sounds: HashMap<String, Sound>,
 }
 
 impl Config {
@@ -48,7 +62,7 @@ impl Config {
         .flatten()
         .collect();
 
-        debug!("Config: Initializing");
+        debug!(target: CONFIG, "Config: Initializing");
         let main_watcher =
             FilesWatcher::init(config_paths).expect("The config watcher must be initialized");
 
@@ -56,35 +70,128 @@ impl Config {
             subwatchers,
             general,
             display,
+            sound,
+            otp,
+            limits,
             themes,
             app_configs,
+            app_sounds,
         } = Self::parse(main_watcher.get_watching_path());
 
-        debug!("Config: Initialized");
+        debug!(target: CONFIG, "Config: Initialized");
 
         Self {
-            main_watcher,
+            main_watcher: Some(main_watcher),
             subwatchers,
             general,
             display,
+            sound,
+            otp,
+            limits,
             app_configs,
+            app_sounds,
 
             default_theme: Theme::default(),
             themes,
         }
     }
 
+    /// Creates the config from TOML content without watching files, so the config isn't reloaded.
+    /// The relative imports are resolved from the current directory.
+    pub fn from_toml(content: &str) -> anyhow::Result<Self> {
+        let toml_config: TomlConfig = toml::from_str(content)?;
+        let current_dir = std::env::current_dir()?;
+
+        let ParsedConfig {
+            general,
+            display,
+            sound,
+            otp,
+            limits,
+            themes,
+            app_configs,
+            app_sounds,
+            ..
+        } = Self::build(Some(
+            toml_config.resolve_imports(current_dir, &mut std::collections::HashSet::new()),
+        ));
+
+        debug!(target: CONFIG, "Config: Initialized from memory");
+
+        Ok(Self {
+            main_watcher: None,
+            subwatchers: vec![],
+            general,
+            display,
+            sound,
+            otp,
+            limits,
+            app_configs,
+            app_sounds,
+
+            default_theme: Theme::default(),
+            themes,
+        })
+    }
+
+    /// Reads the TOML content of config from the standard input.
+    pub fn from_stdin() -> anyhow::Result<Self> {
+        let content = std::io::read_to_string(std::io::stdin())?;
+        Self::from_toml(&content)
+    }
+
     pub fn general(&self) -> &GeneralConfig {
         &self.general
     }
 
+    pub fn sound(&self) -> &SoundConfig {
+        &self.sound
+    }
+
+    pub fn otp(&self) -> &OtpConfig {
+        &self.otp
+    }
+
+    pub fn limits(&self) -> &LimitsConfig {
+        &self.limits
+    }
+
     #[allow(unused)]
     pub fn default_display(&self) -> &DisplayConfig {
         &self.display
     }
 
     pub fn display_by_app(&self, name: &str) -> &DisplayConfig {
-        self.app_configs.get(name).unwrap_or(&self.display)
+        find_app_config(&self.app_configs, name, None).unwrap_or(&self.display)
+    }
+
+    /// Finds the display config by the app name of notification. When `match_desktop_entry` is
+    /// enabled, the `desktop-entry` hint is also used to find the app config.
+    pub fn display_by_notification(&self, notification: &Notification) -> &DisplayConfig {
+        find_app_config(
+            &self.app_configs,
+            &notification.app_name,
+            self.desktop_entry(notification),
+        )
+        .unwrap_or(&self.display)
+    }
+
+    /// Finds the sound of notification which doesn't have the sound hints. The sound of app
+    /// config takes precedence over the sound of notification urgency.
+    pub fn sound_by_notification(&self, notification: &Notification) -> Option<&Sound> {
+        find_app_config(
+            &self.app_sounds,
+            &notification.app_name,
+            self.desktop_entry(notification),
+        )
+        .or_else(|| self.sound.by_urgency(&notification.hints.urgency))
+    }
+
+    fn desktop_entry<'a>(&self, notification: &'a Notification) -> Option<&'a str> {
+        self.general
+            .match_desktop_entry
+            .then_some(notification.hints.desktop_entry.as_deref())
+            .flatten()
     }
 
     pub fn displays(&self) -> impl Iterator<Item = &DisplayConfig> {
@@ -99,8 +206,18 @@ impl Config {
             .unwrap_or(&self.default_theme)
     }
 
+    pub fn theme_by_notification(&self, notification: &Notification) -> &Theme {
+        self.themes
+            .get(&self.display_by_notification(notification).theme)
+            .unwrap_or(&self.default_theme)
+    }
+
     pub fn check_updates(&mut self) -> FileState {
-        self.main_watcher.check_updates()
+        let Some(main_watcher) = self.main_watcher.as_mut() else {
+            return FileState::NothingChanged;
+        };
+
+        main_watcher.check_updates()
             | self
                 .subwatchers
                 .iter_mut()
@@ -109,36 +226,57 @@ impl Config {
     }
 
     pub fn update(&mut self) {
+        let Some(main_watcher) = self.main_watcher.as_ref() else {
+            return;
+        };
+
         let ParsedConfig {
             subwatchers,
             general,
             display,
+            sound,
+            otp,
+            limits,
             themes,
             app_configs: apps,
-        } = Self::parse(self.main_watcher.get_watching_path());
+            app_sounds,
+        } = Self::parse(main_watcher.get_watching_path());
 
         self.subwatchers = subwatchers;
         self.general = general;
         self.display = display;
+        self.sound = sound;
+        self.otp = otp;
+        self.limits = limits;
         self.app_configs = apps;
+        self.app_sounds = app_sounds;
         self.themes = themes;
 
-        debug!("Config: Updated");
+        debug!(target: CONFIG, "Config: Updated");
     }
 
     fn parse(path: Option<&Path>) -> ParsedConfig {
-        let (subwatchers, toml_config) =
-            match TomlConfig::parse_recursive(path, &mut std::collections::HashSet::new()) {
-                Some(ParsedTomlConfig {
-                    subwatchers,
-                    toml_config,
-                }) => (subwatchers, toml_config),
-                None => (vec![], Default::default()),
-            };
+        Self::build(TomlConfig::parse_recursive(
+            path,
+            &mut std::collections::HashSet::new(),
+        ))
+    }
+
+    fn build(parsed_toml_config: Option<ParsedTomlConfig>) -> ParsedConfig {
+        let (subwatchers, toml_config) = match parsed_toml_config {
+            Some(ParsedTomlConfig {
+                subwatchers,
+                toml_config,
+            }) => (subwatchers, toml_config),
+            None => (vec![], Default::default()),
+        };
 
         let TomlConfig {
             general,
             display,
+            sound,
+            otp,
+            limits,
             themes,
             apps,
             ..
@@ -148,7 +286,7 @@ impl Config {
         if let Some(themes) = themes {
             for theme in themes {
                 if theme.name.as_ref().is_none_or(|name| name.is_empty()) {
-                    warn!("Config: Encountered an unnamed theme. Skipped.");
+                    warn!(target: CONFIG, "Config: Encountered an unnamed theme. Skipped.");
                     continue;
                 }
 
@@ -162,9 +300,15 @@ impl Config {
         }
 
         let mut app_configs: HashMap<String, TomlDisplayConfig> = HashMap::new();
+        let mut app_sounds: HashMap<String, Sound> = HashMap::new();
         if let Some(apps) = apps {
             for app in apps {
-                let app_name = app.name.clone();
+                let app_name = normalize_app_name(&app.name);
+                // INFO: the first declared sound takes precedence like the display values
+                if let Some(sound) = app.sound {
+                    app_sounds.entry(app_name.clone()).or_insert(sound);
+                }
+
                 let app_display_config = match app_configs.remove(&app_name) {
                     Some(saved_app_config) => saved_app_config.merge(app.display),
                     None => match app.display {
@@ -176,16 +320,36 @@ impl Config {
             }
         }
 
-        debug!("Config: Parsed from files");
+        if let Some(display) = display.as_ref() {
+            display.check_layout_path("the display section");
+        }
+
+        app_configs.iter().for_each(|(app_name, display)| {
+            display.check_layout_path(&format!("the app \"{app_name}\""))
+        });
+
+        debug!(target: CONFIG, "Config: Parsed from files");
+
+        let general: GeneralConfig = general.unwrap_or_default().into();
+        let themes = theme_table
+            .into_iter()
+            .map(|(key, value)| {
+                let mut theme = value.unwrap_or_default();
+                if let Some(min_ratio) = general.enforce_contrast {
+                    theme.enforce_contrast(min_ratio);
+                }
+                (key, theme)
+            })
+            .collect();
 
         ParsedConfig {
             subwatchers,
-            general: general.unwrap_or_default().into(),
+            general,
             display: display.clone().unwrap_or_default().into(),
-            themes: theme_table
-                .into_iter()
-                .map(|(key, value)| (key, value.unwrap_or_default()))
-                .collect(),
+            sound: sound.unwrap_or_default().into(),
+            otp: otp.unwrap_or_default().into(),
+            limits: limits.unwrap_or_default().into(),
+            themes,
             app_configs: app_configs
                 .into_iter()
                 .map(|(key, mut value)| {
@@ -193,6 +357,7 @@ impl Config {
                     (key, value.merge(display.clone()).unwrap_or_default())
                 })
                 .collect(),
+            app_sounds,
         }
     }
 }
@@ -201,8 +366,12 @@ struct ParsedConfig {
     subwatchers: Vec<FilesWatcher>,
     general: GeneralConfig,
     display: DisplayConfig,
+    sound: SoundConfig,
+    otp: OtpConfig,
+    limits: LimitsConfig,
     themes: HashMap<String, Theme>,
     app_configs: HashMap<String, DisplayConfig>,
+    app_sounds: HashMap<String, Sound>,
 }
 
 #[macro_export]
@@ -225,6 +394,9 @@ pub struct TomlConfig {
 
     general: Option<TomlGeneralConfig>,
     display: Option<TomlDisplayConfig>,
+    sound: Option<TomlSoundConfig>,
+    otp: Option<TomlOtpConfig>,
+    limits: Option<TomlLimitsConfig>,
 
     #[serde(rename(deserialize = "theme"))]
     themes: Option<Vec<TomlTheme>>,
@@ -240,11 +412,11 @@ impl TomlConfig {
     ) -> Option<ParsedTomlConfig> {
         let config_path = PathBuf::from(path?);
         if !config_tree_path.insert(config_path.clone()) {
-            error!("Found circular imports! Check the config file {config_path:?}");
+            error!(target: CONFIG, "Found circular imports! Check the config file {config_path:?}");
             return None;
         }
 
-        let Some(mut base_toml_config) = Self::parse(path?) else {
+        let Some(base_toml_config) = Self::parse(path?) else {
             config_tree_path.remove(&config_path);
             return None;
         };
@@ -255,9 +427,35 @@ impl TomlConfig {
             config_path
         };
 
+        let parsed_toml_config = base_toml_config.resolve_imports(path_prefix, config_tree_path);
+
+        config_tree_path.remove(&config_path);
+        Some(parsed_toml_config)
+    }
+
+    /// Makes the relative paths of config absolute by the given prefix and merges the imported
+    /// configs into this one.
+    fn resolve_imports(
+        self,
+        path_prefix: PathBuf,
+        config_tree_path: &mut std::collections::HashSet<PathBuf>,
+    ) -> ParsedTomlConfig {
+        let mut base_toml_config = self;
         if let Some(display) = base_toml_config.display.as_mut() {
             display.use_relative_path(path_prefix.clone());
         }
+        if let Some(sound) = base_toml_config.sound.as_mut() {
+            sound.use_relative_path(&path_prefix);
+        }
+
+        base_toml_config.apps.iter_mut().flatten().for_each(|app| {
+            if let Some(display) = app.display.as_mut() {
+                display.use_relative_path(path_prefix.clone());
+            }
+            if let Some(sound) = app.sound.as_mut() {
+                sound.use_relative_path(&path_prefix);
+            }
+        });
 
         let mut watchers: Vec<FilesWatcher> = base_toml_config
             .imports
@@ -287,11 +485,10 @@ impl TomlConfig {
             base_toml_config = base_toml_config.merge(toml_config);
         }
 
-        config_tree_path.remove(&config_path);
-        Some(ParsedTomlConfig {
+        ParsedTomlConfig {
             subwatchers: watchers,
             toml_config: base_toml_config,
-        })
+        }
     }
 
     fn parse(path: &Path) -> Option<Self> {
@@ -299,7 +496,7 @@ impl TomlConfig {
         match toml::from_str(&content) {
             Ok(content) => Some(content),
             Err(error) => {
-                error!("{error}");
+                error!(target: CONFIG, "{error}");
                 None
             }
         }
@@ -324,6 +521,21 @@ impl TomlConfig {
             .map(|display| display.merge(other.display.clone()))
             .or(other.display);
 
+        self.sound = self
+            .sound
+            .map(|sound| sound.merge(other.sound.clone()))
+            .or(other.sound);
+
+        self.otp = self
+            .otp
+            .map(|otp| otp.merge(other.otp.clone()))
+            .or(other.otp);
+
+        self.limits = self
+            .limits
+            .map(|limits| limits.merge(other.limits.clone()))
+            .or(other.limits);
+
         let themes: Vec<_> = self
             .themes
             .into_iter()
@@ -348,6 +560,35 @@ struct ParsedTomlConfig {
 pub struct AppConfig {
     pub name: String,
     pub display: Option<TomlDisplayConfig>,
+    pub sound: Option<Sound>,
+}
+
+/// Normalizes the app name to match the app configs regardless of letter case and whitespaces.
+fn normalize_app_name(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Finds the app config by the normalized app name and then by the `desktop-entry` hint. The
+/// desktop entry is matched as is and by its last component, so `org.mozilla.firefox` matches
+/// the `firefox` app config.
+fn find_app_config<'a, T>(
+    app_configs: &'a HashMap<String, T>,
+    app_name: &str,
+    desktop_entry: Option<&str>,
+) -> Option<&'a T> {
+    let desktop_entry = desktop_entry.map(|entry| entry.trim_end_matches(".desktop"));
+
+    [
+        Some(app_name),
+        desktop_entry,
+        desktop_entry.and_then(|entry| entry.rsplit('.').next()),
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|name| app_configs.get(&normalize_app_name(name)))
 }
 
 fn xdg_config_dir(suffix: &str) -> Option<PathBuf> {
@@ -389,7 +630,7 @@ fn expand_path(value: String, path_prefix: &Path) -> Vec<PathBuf> {
     }
 
     let Some(expanded_path_str) = expanded_path.to_str() else {
-        error!("Path {expanded_path:?} is not UTF-8 valid!");
+        error!(target: CONFIG, "Path {expanded_path:?} is not UTF-8 valid!");
         return vec![];
     };
 
@@ -399,6 +640,7 @@ fn expand_path(value: String, path_prefix: &Path) -> Vec<PathBuf> {
             .inspect(|entry| {
                 if let Err(err) = entry {
                     warn!(
+                        target: CONFIG,
                         "Failed to read config file at {:?}. Error: {}",
                         err.path(),
                         err.error()
@@ -408,8 +650,118 @@ fn expand_path(value: String, path_prefix: &Path) -> Vec<PathBuf> {
             .flatten()
             .collect(),
         Err(err) => {
-            error!("Failed to parse path glob pattern. Error: {err}");
+            error!(target: CONFIG, "Failed to parse path glob pattern. Error: {err}");
             vec![]
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use dbus::notification::{Notification, Urgency};
+    use shared::file_watcher::FileState;
+
+    use super::{find_app_config, normalize_app_name, sound::Sound, Config};
+
+    fn app_configs() -> HashMap<String, u8> {
+        HashMap::from([
+            (normalize_app_name("Firefox"), 1),
+            (normalize_app_name("Telegram  Desktop "), 2),
+        ])
+    }
+
+    #[test]
+    fn app_name_normalization() {
+        let app_configs = app_configs();
+
+        assert_eq!(find_app_config(&app_configs, "Firefox", None), Some(&1));
+        assert_eq!(find_app_config(&app_configs, "firefox", None), Some(&1));
+        assert_eq!(find_app_config(&app_configs, "FIREFOX", None), Some(&1));
+        assert_eq!(
+            find_app_config(&app_configs, "telegram desktop", None),
+            Some(&2)
+        );
+        assert_eq!(
+            find_app_config(&app_configs, " Telegram\tDesktop", None),
+            Some(&2)
+        );
+        assert_eq!(find_app_config(&app_configs, "Chromium", None), None);
+    }
+
+    #[test]
+    fn desktop_entry_matching() {
+        let app_configs = app_configs();
+
+        assert_eq!(
+            find_app_config(&app_configs, "org.mozilla.firefox", None),
+            None
+        );
+        assert_eq!(
+            find_app_config(&app_configs, "Web Browser", Some("org.mozilla.firefox")),
+            Some(&1)
+        );
+        assert_eq!(
+            find_app_config(&app_configs, "Web Browser", Some("firefox.desktop")),
+            Some(&1)
+        );
+        assert_eq!(
+            find_app_config(&app_configs, "Web Browser", Some("org.chromium.Chromium")),
+            None
+        );
+    }
+
+    #[test]
+    fn config_from_toml() {
+        let mut config = Config::from_toml(
+            r#"
+            [general]
+            limit = 3
+
+            [[app]]
+            name = "Firefox"
+            display.markup = false
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.general().limit, 3);
+        assert!(!config.display_by_app("firefox").markup);
+        assert!(matches!(config.check_updates(), FileState::NothingChanged));
+
+        assert!(Config::from_toml("[general").is_err());
+    }
+
+    #[test]
+    fn sound_precedence() {
+        let config = Config::from_toml(
+            r#"
+            [sound]
+            normal = "message-new-instant"
+            critical = "dialog-warning"
+
+            [[app]]
+            name = "Thunderbird"
+            sound = "message-new-email"
+            "#,
+        )
+        .unwrap();
+
+        let mut notification = Notification::test_default();
+        notification.app_name = "Thunderbird".to_string();
+        assert_eq!(
+            config.sound_by_notification(&notification),
+            Some(&Sound::Name("message-new-email".to_string()))
+        );
+
+        notification.app_name = "Firefox".to_string();
+        assert_eq!(
+            config.sound_by_notification(&notification),
+            Some(&Sound::Name("message-new-instant".to_string()))
+        );
+
+        notification.hints.urgency = Urgency::Low;
+        assert_eq!(config.sound_by_notification(&notification), None);
+    }
+}
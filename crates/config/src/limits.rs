@@ -0,0 +1,45 @@
+//! The module that contain the structure `LimitsConfig` which stores the limits of resources for
+//! constrained devices.
+
+use macros::ConfigProperty;
+use serde::Deserialize;
+
+use crate::public;
+
+public! {
+    #[derive(ConfigProperty, Debug)]
+    #[cfg_prop(name(TomlLimitsConfig), derive(Debug, Default, Deserialize, Clone))]
+    struct LimitsConfig {
+        /// The soft memory budget in megabytes of decoded images, layouts and framebuffers. When
+        /// it's exceeded, the least recently drawn banners drop their animations and the new
+        /// images are downscaled more. 0 means unlimited.
+        #[cfg_prop(default(0))]
+        memory_mb: u32,
+    }
+}
+
+impl LimitsConfig {
+    /// Returns the memory budget in bytes or `None` when it's unlimited.
+    pub fn memory_budget(&self) -> Option<usize> {
+        (self.memory_mb != 0).then_some(self.memory_mb as usize * 1024 * 1024)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LimitsConfig, TomlLimitsConfig};
+
+    fn limits(content: &str) -> LimitsConfig {
+        toml::from_str::<TomlLimitsConfig>(content).unwrap().into()
+    }
+
+    #[test]
+    fn memory_budget_is_unlimited_by_default() {
+        assert_eq!(limits("").memory_budget(), None);
+        assert_eq!(limits("memory_mb = 0").memory_budget(), None);
+        assert_eq!(
+            limits("memory_mb = 64").memory_budget(),
+            Some(64 * 1024 * 1024)
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! The module that contain the structure `OtpConfig` which stores the extraction of one-time
+//! codes from notifications.
+
+use log::warn;
+use macros::ConfigProperty;
+use regex::Regex;
+use serde::Deserialize;
+use shared::log_target::CONFIG;
+
+use crate::public;
+
+public! {
+    #[derive(ConfigProperty, Debug)]
+    #[cfg_prop(name(TomlOtpConfig), derive(Debug, Default, Deserialize, Clone))]
+    struct OtpConfig {
+        enabled: bool,
+        pattern: OtpPattern,
+    }
+}
+
+impl OtpConfig {
+    /// Finds the one-time code in the text when the extraction is enabled. The first capture
+    /// group of pattern is taken if it has one, otherwise the whole match.
+    pub fn extract_code(&self, text: &str) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+
+        let captures = self.pattern.0.captures(text)?;
+        captures
+            .get(1)
+            .or_else(|| captures.get(0))
+            .map(|code| code.as_str().to_string())
+    }
+}
+
+/// The regular expression which matches one-time codes.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(from = "String")]
+pub struct OtpPattern(Regex);
+
+impl OtpPattern {
+    const DEFAULT: &str = r"\b\d{4,8}\b";
+}
+
+impl From<String> for OtpPattern {
+    fn from(value: String) -> Self {
+        match Regex::new(&value) {
+            Ok(regex) => OtpPattern(regex),
+            Err(err) => {
+                warn!(
+                    target: CONFIG,
+                    "Config: The OTP pattern \"{value}\" is invalid, using the default one. \
+                    Error: {err}"
+                );
+                Self::default()
+            }
+        }
+    }
+}
+
+impl Default for OtpPattern {
+    fn default() -> Self {
+        OtpPattern(Regex::new(Self::DEFAULT).expect("The default OTP pattern must be valid"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OtpConfig, TomlOtpConfig};
+
+    fn otp(content: &str) -> OtpConfig {
+        toml::from_str::<TomlOtpConfig>(content).unwrap().into()
+    }
+
+    #[test]
+    fn code_is_extracted_only_when_enabled() {
+        let body = "Your verification code is 482913. Don't share it";
+
+        assert_eq!(otp("").extract_code(body), None);
+        assert_eq!(
+            otp("enabled = true").extract_code(body),
+            Some("482913".to_string())
+        );
+        assert_eq!(otp("enabled = true").extract_code("Call me at 12:30"), None);
+    }
+
+    #[test]
+    fn capture_group_is_preferred() {
+        let otp = otp(r#"
+            enabled = true
+            pattern = "code: ([A-Z0-9]{6})"
+            "#);
+
+        assert_eq!(
+            otp.extract_code("Sign-in code: AB12CD"),
+            Some("AB12CD".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_pattern_falls_back_to_default() {
+        let otp = otp(r#"
+            enabled = true
+            pattern = "(unclosed"
+            "#);
+
+        assert_eq!(otp.extract_code("Code 1234"), Some("1234".to_string()));
+    }
+}
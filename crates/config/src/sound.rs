@@ -0,0 +1,179 @@
+//! The module that contain the structure `SoundConfig` which stores sound playback properties.
+
+use std::path::{Path, PathBuf};
+
+use dbus::notification::Urgency;
+use log::warn;
+use macros::ConfigProperty;
+use serde::Deserialize;
+use shared::log_target::CONFIG;
+
+use crate::public;
+
+public! {
+    #[derive(ConfigProperty, Debug)]
+    #[cfg_prop(name(TomlSoundConfig), derive(Debug, Default, Deserialize, Clone))]
+    struct SoundConfig {
+        volume: Volume,
+        /// The minimum time in milliseconds between sounds, so only one sound is played when
+        /// many notifications arrive at once. Critical sounds are always played. 0 disables it.
+        #[cfg_prop(default(0))]
+        debounce_ms: u32,
+
+        low: Option<Sound>,
+        normal: Option<Sound>,
+        critical: Option<Sound>,
+    }
+}
+
+impl SoundConfig {
+    /// Returns the sound of notifications with the given urgency.
+    pub fn by_urgency(&self, urgency: &Urgency) -> Option<&Sound> {
+        match urgency {
+            Urgency::Low => self.low.as_ref(),
+            Urgency::Normal => self.normal.as_ref(),
+            Urgency::Critical => self.critical.as_ref(),
+        }
+    }
+}
+
+impl TomlSoundConfig {
+    pub(super) fn use_relative_path(&mut self, prefix: &Path) {
+        [&mut self.low, &mut self.normal, &mut self.critical]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .for_each(|sound| sound.use_relative_path(prefix));
+    }
+}
+
+/// The sound which is played for notifications without the sound hints. It's either the path to
+/// sound file or the name of sound from the freedesktop sound theme.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(from = "String")]
+pub enum Sound {
+    File(PathBuf),
+    Name(String),
+}
+
+impl Sound {
+    /// Makes the relative path to sound file absolute by the directory of config which declares
+    /// it, so the sound doesn't depend on the working directory of daemon.
+    pub(super) fn use_relative_path(&mut self, prefix: &Path) {
+        if let Sound::File(path_buf) = self {
+            if path_buf.is_relative() {
+                *path_buf = prefix.join(&*path_buf);
+            }
+        }
+    }
+}
+
+impl From<String> for Sound {
+    fn from(value: String) -> Self {
+        let value = value
+            .strip_prefix("file://")
+            .map(ToString::to_string)
+            .unwrap_or(value);
+
+        if value.starts_with(['/', '~', '$', '.']) {
+            Sound::File(PathBuf::from(
+                shellexpand::full(&value)
+                    .map(|value| value.into_owned())
+                    .unwrap_or(value),
+            ))
+        } else {
+            Sound::Name(value)
+        }
+    }
+}
+
+/// The volume of played sounds in range from 0.0 to 1.0.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(from = "f32")]
+pub struct Volume(f32);
+
+impl Volume {
+    const MIN: f32 = 0.0;
+    const MAX: f32 = 1.0;
+
+    pub fn value(&self) -> f32 {
+        self.0
+    }
+}
+
+impl From<f32> for Volume {
+    fn from(value: f32) -> Self {
+        if !(Self::MIN..=Self::MAX).contains(&value) {
+            warn!(
+                target: CONFIG,
+                "Config: The sound volume {value} is out of range {}..={}. Clamped.",
+                Self::MIN,
+                Self::MAX
+            );
+        }
+
+        if value.is_nan() {
+            return Self::default();
+        }
+
+        Volume(value.clamp(Self::MIN, Self::MAX))
+    }
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Volume(Self::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{Sound, Volume};
+
+    #[test]
+    fn clamp_volume() {
+        assert_eq!(Volume::from(0.5).value(), 0.5);
+        assert_eq!(Volume::from(-0.3).value(), 0.0);
+        assert_eq!(Volume::from(1.7).value(), 1.0);
+        assert_eq!(Volume::from(f32::NAN).value(), 1.0);
+    }
+
+    #[test]
+    fn sound_kind() {
+        assert_eq!(
+            Sound::from("/usr/share/sounds/chime.oga".to_string()),
+            Sound::File(PathBuf::from("/usr/share/sounds/chime.oga"))
+        );
+        assert_eq!(
+            Sound::from("file:///tmp/chime.oga".to_string()),
+            Sound::File(PathBuf::from("/tmp/chime.oga"))
+        );
+        assert_eq!(
+            Sound::from("message-new-email".to_string()),
+            Sound::Name("message-new-email".to_string())
+        );
+    }
+
+    #[test]
+    fn relative_sound_file_is_resolved_by_config_dir() {
+        let mut sound = Sound::from("./sounds/chime.oga".to_string());
+        sound.use_relative_path(Path::new("/etc/noti"));
+        assert_eq!(
+            sound,
+            Sound::File(PathBuf::from("/etc/noti/./sounds/chime.oga"))
+        );
+
+        let mut sound = Sound::from("/usr/share/sounds/chime.oga".to_string());
+        sound.use_relative_path(Path::new("/etc/noti"));
+        assert_eq!(
+            sound,
+            Sound::File(PathBuf::from("/usr/share/sounds/chime.oga"))
+        );
+
+        let mut sound = Sound::from("message-new-email".to_string());
+        sound.use_relative_path(Path::new("/etc/noti"));
+        assert_eq!(sound, Sound::Name("message-new-email".to_string()));
+    }
+}
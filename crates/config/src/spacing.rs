@@ -99,10 +99,10 @@ impl Add<Spacing> for Spacing {
 
     fn add(self, rhs: Spacing) -> Self::Output {
         Spacing {
-            top: self.top + rhs.top,
-            right: self.right + rhs.right,
-            bottom: self.bottom + rhs.bottom,
-            left: self.left + rhs.left,
+            top: self.top.saturating_add(rhs.top),
+            right: self.right.saturating_add(rhs.right),
+            bottom: self.bottom.saturating_add(rhs.bottom),
+            left: self.left.saturating_add(rhs.left),
         }
     }
 }
@@ -112,20 +112,20 @@ impl Add<Spacing> for &Spacing {
 
     fn add(self, rhs: Spacing) -> Self::Output {
         Spacing {
-            top: self.top + rhs.top,
-            right: self.right + rhs.right,
-            bottom: self.bottom + rhs.bottom,
-            left: self.left + rhs.left,
+            top: self.top.saturating_add(rhs.top),
+            right: self.right.saturating_add(rhs.right),
+            bottom: self.bottom.saturating_add(rhs.bottom),
+            left: self.left.saturating_add(rhs.left),
         }
     }
 }
 
 impl AddAssign<Spacing> for Spacing {
     fn add_assign(&mut self, rhs: Spacing) {
-        self.top += rhs.top;
-        self.right += rhs.right;
-        self.bottom += rhs.bottom;
-        self.left += rhs.left;
+        self.top = self.top.saturating_add(rhs.top);
+        self.right = self.right.saturating_add(rhs.right);
+        self.bottom = self.bottom.saturating_add(rhs.bottom);
+        self.left = self.left.saturating_add(rhs.left);
     }
 }
 impl From<i64> for Spacing {
@@ -312,3 +312,53 @@ margin = {{ top = 5, horizontal = 10 }}"#
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Spacing;
+
+    #[derive(serde::Deserialize)]
+    struct Padding {
+        padding: Spacing,
+    }
+
+    fn parse(content: &str) -> Spacing {
+        toml::from_str::<Padding>(content).unwrap().padding
+    }
+
+    fn sides(spacing: &Spacing) -> (u8, u8, u8, u8) {
+        (
+            spacing.top(),
+            spacing.right(),
+            spacing.bottom(),
+            spacing.left(),
+        )
+    }
+
+    #[test]
+    fn asymmetric_padding() {
+        assert_eq!(sides(&parse("padding = [1, 2, 3, 4]")), (1, 2, 3, 4));
+        assert_eq!(
+            sides(&parse(
+                "padding = { top = 1, right = 2, bottom = 3, left = 4 }"
+            )),
+            (1, 2, 3, 4)
+        );
+        assert_eq!(sides(&parse("padding = [1, 2, 3]")), (1, 2, 3, 2));
+        assert_eq!(
+            sides(&parse("padding = { vertical = 5, left = 6 }")),
+            (5, 0, 5, 6)
+        );
+    }
+
+    #[test]
+    fn ambiguous_padding() {
+        assert!(toml::from_str::<Padding>("padding = { top = 1, vertical = 2 }").is_err());
+    }
+
+    #[test]
+    fn saturating_addition() {
+        let spacing = Spacing::from(vec![250, 10, 0, 1]) + Spacing::all_directional(10);
+        assert_eq!(sides(&spacing), (255, 20, 10, 11));
+    }
+}
@@ -31,6 +31,14 @@ public! {
         #[cfg_prop(default(0))]
         #[gbuilder(default(0))]
         line_spacing: u8,
+
+        #[gbuilder(default)]
+        wrap_width: WrapWidth,
+
+        /// The maximum number of wrapped lines, 0 means unlimited.
+        #[cfg_prop(default(0))]
+        #[gbuilder(default(0))]
+        max_lines: u8,
     }
 }
 
@@ -108,8 +116,74 @@ impl TomlTextProperty {
     }
 }
 
+/// The maximum width of text lines. It's either the percentage of available width, e.g. `"80%"`,
+/// or the width in pixels. By default the text takes all available width.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum WrapWidth {
+    #[default]
+    Full,
+    Percent(u8),
+    Pixels(usize),
+}
+
+impl WrapWidth {
+    /// Clamps the available width of text by this value.
+    pub fn apply(&self, available_width: usize) -> usize {
+        match *self {
+            WrapWidth::Full => available_width,
+            WrapWidth::Percent(percent) => available_width * percent.min(100) as usize / 100,
+            WrapWidth::Pixels(pixels) => pixels.min(available_width),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for WrapWidth {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum WrapWidthRepr {
+            Pixels(usize),
+            String(String),
+        }
+
+        match WrapWidthRepr::deserialize(deserializer)? {
+            WrapWidthRepr::Pixels(pixels) => Ok(WrapWidth::Pixels(pixels)),
+            WrapWidthRepr::String(value) => {
+                WrapWidth::try_from_string(value).map_err(serde::de::Error::custom)
+            }
+        }
+    }
+}
+
+impl TryFromValue for WrapWidth {
+    fn try_from_string(value: String) -> Result<Self, shared::error::ConversionError> {
+        let percent = value
+            .strip_suffix('%')
+            .and_then(|percent| percent.trim().parse::<u8>().ok())
+            .filter(|percent| *percent <= 100);
+
+        Ok(match (value.to_lowercase().as_str(), percent) {
+            ("full", _) => WrapWidth::Full,
+            (_, Some(percent)) => WrapWidth::Percent(percent),
+            _ => Err(shared::error::ConversionError::InvalidValue {
+                expected: "full, the percentage from 0% to 100% or the width in pixels",
+                actual: value,
+            })?,
+        })
+    }
+
+    fn try_from_uint(value: usize) -> Result<Self, shared::error::ConversionError> {
+        Ok(WrapWidth::Pixels(value))
+    }
+}
+
 #[derive(Debug, Deserialize, Default, Clone)]
 pub enum EllipsizeAt {
+    #[serde(rename = "start")]
+    Start,
     #[serde(rename = "middle")]
     Middle,
     #[default]
@@ -120,12 +194,35 @@ pub enum EllipsizeAt {
 impl TryFromValue for EllipsizeAt {
     fn try_from_string(value: String) -> Result<Self, shared::error::ConversionError> {
         Ok(match value.to_lowercase().as_str() {
+            "start" => EllipsizeAt::Start,
             "middle" => EllipsizeAt::Middle,
             "end" => EllipsizeAt::End,
             _ => Err(shared::error::ConversionError::InvalidValue {
-                expected: "middle or end",
+                expected: "start, middle or end",
                 actual: value,
             })?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{TomlTextProperty, WrapWidth};
+
+    fn parse(value: &str) -> Result<Option<WrapWidth>, toml::de::Error> {
+        toml::from_str::<TomlTextProperty>(&format!("wrap_width = {value}"))
+            .map(|property| property.wrap_width)
+    }
+
+    #[test]
+    fn wrap_width() {
+        assert_eq!(parse(r#""full""#).unwrap(), Some(WrapWidth::Full));
+        assert_eq!(parse(r#""80%""#).unwrap(), Some(WrapWidth::Percent(80)));
+        assert_eq!(parse("200").unwrap(), Some(WrapWidth::Pixels(200)));
+        assert!(parse(r#""120%""#).is_err());
+
+        assert_eq!(WrapWidth::Full.apply(250), 250);
+        assert_eq!(WrapWidth::Percent(80).apply(250), 200);
+        assert_eq!(WrapWidth::Pixels(300).apply(250), 250);
+    }
+}
@@ -1,9 +1,14 @@
+use std::borrow::Cow;
+
 use dbus::notification::Urgency;
+use log::info;
 use macros::ConfigProperty;
 use serde::Deserialize;
+use shared::log_target::CONFIG;
 
 use crate::{
     color::{Color, Rgba},
+    display::Border,
     public,
 };
 
@@ -13,14 +18,20 @@ public! {
     struct Theme {
         name: String,
 
-        #[cfg_prop(use_type(TomlColors))]
+        #[cfg_prop(use_type(TomlColors), mergeable)]
         low: Colors,
 
-        #[cfg_prop(use_type(TomlColors))]
+        #[cfg_prop(use_type(TomlColors), mergeable)]
         normal: Colors,
 
-        #[cfg_prop(use_type(TomlColors), default(path = TomlColors::default_critical))]
+        #[cfg_prop(
+            use_type(TomlColors),
+            default(path = TomlColors::default_critical),
+            mergeable
+        )]
         critical: Colors,
+
+        hover: Option<StateColors>,
     }
 }
 
@@ -32,6 +43,42 @@ impl Theme {
             Urgency::Critical => &self.critical,
         }
     }
+
+    /// Returns the colors of urgency where the colors of hover state take precedence when the
+    /// banner is hovered.
+    pub fn colors(&self, urgency: &Urgency, hovered: bool) -> Cow<'_, Colors> {
+        let colors = self.by_urgency(urgency);
+        match self.hover.as_ref().filter(|_| hovered) {
+            Some(hover) => Cow::Owned(hover.apply(colors)),
+            None => Cow::Borrowed(colors),
+        }
+    }
+
+    /// Adjusts the foreground lightness of every urgency whose contrast with the background is
+    /// lower than the minimum ratio.
+    pub(crate) fn enforce_contrast(&mut self, min_ratio: f32) {
+        for (urgency, colors) in [
+            ("low", &mut self.low),
+            ("normal", &mut self.normal),
+            ("critical", &mut self.critical),
+        ] {
+            let Some(foreground) = colors
+                .foreground
+                .with_min_contrast(colors.background.stops(), min_ratio)
+            else {
+                continue;
+            };
+
+            info!(
+                target: CONFIG,
+                "Config: Adjusted the {urgency} foreground of theme \"{}\" from {} to {foreground} \
+                to reach the contrast ratio {min_ratio}",
+                self.name,
+                colors.foreground
+            );
+            colors.foreground = foreground;
+        }
+    }
 }
 
 impl Default for Theme {
@@ -41,7 +88,7 @@ impl Default for Theme {
 }
 
 public! {
-    #[derive(ConfigProperty, Debug)]
+    #[derive(ConfigProperty, Debug, Clone)]
     #[cfg_prop(name(TomlColors), derive(Debug, Clone, Deserialize, Default))]
     struct Colors {
         #[cfg_prop(default(path = Rgba::new_black))]
@@ -49,8 +96,10 @@ public! {
         #[cfg_prop(default(path = Color::new_rgba_white))]
         background: Color,
 
-        #[cfg_prop(default(path = Color::new_rgba_black))]
-        border: Color,
+        #[cfg_prop(use_type(TomlUrgencyBorder), mergeable)]
+        border: UrgencyBorder,
+
+        emblem: Option<Emblem>,
     }
 }
 
@@ -59,7 +108,245 @@ impl TomlColors {
         TomlColors {
             background: Some(Color::new_rgba_white()),
             foreground: Some(Rgba::new_red()),
-            border: Some(Color::new_rgba_red()),
+            border: Some(TomlUrgencyBorder {
+                color: Some(Color::new_rgba_red()),
+                ..Default::default()
+            }),
+            emblem: None,
         }
     }
 }
+
+public! {
+    /// The border settings of specific urgency. The size and radius are optional and if they are
+    /// not set, the values from `display.border` are used.
+    #[derive(ConfigProperty, Debug, Clone)]
+    #[cfg_prop(name(TomlUrgencyBorder), derive(Debug, Clone, Default))]
+    struct UrgencyBorder {
+        #[cfg_prop(default(path = Color::new_rgba_black))]
+        color: Color,
+
+        size: Option<u8>,
+        radius: Option<u8>,
+    }
+}
+
+impl UrgencyBorder {
+    /// Resolves the effective border where the urgency values take precedence over the given one.
+    pub fn resolve(&self, border: &Border) -> Border {
+        Border {
+            size: self.size.unwrap_or(border.size),
+            radius: self.radius.unwrap_or(border.radius),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TomlUrgencyBorder {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum BorderRepr {
+            Color(Color),
+            Table {
+                color: Option<Color>,
+                size: Option<u8>,
+                radius: Option<u8>,
+            },
+        }
+
+        Ok(match BorderRepr::deserialize(deserializer)? {
+            BorderRepr::Color(color) => TomlUrgencyBorder {
+                color: Some(color),
+                ..Default::default()
+            },
+            BorderRepr::Table {
+                color,
+                size,
+                radius,
+            } => TomlUrgencyBorder {
+                color,
+                size: size.map(Some),
+                radius: radius.map(Some),
+            },
+        })
+    }
+}
+
+/// The colors of banner in the specific state, e.g. when it's hovered. The colors which are not
+/// set are taken from the urgency.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StateColors {
+    pub foreground: Option<Rgba>,
+    pub background: Option<Color>,
+    pub border: Option<Color>,
+}
+
+impl StateColors {
+    fn apply(&self, colors: &Colors) -> Colors {
+        let mut colors = colors.clone();
+        if let Some(foreground) = &self.foreground {
+            colors.foreground = foreground.clone();
+        }
+        if let Some(background) = &self.background {
+            colors.background = background.clone();
+        }
+        if let Some(border) = &self.border {
+            colors.border.color = border.clone();
+        }
+        colors
+    }
+}
+
+/// The small icon which is drawn over the corner of banner, e.g. to mark critical notifications.
+/// It's set either by the icon name or path, or by the table with the icon, corner and size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Emblem {
+    pub icon: String,
+    pub corner: Corner,
+    pub size: u16,
+}
+
+impl Emblem {
+    const DEFAULT_SIZE: u16 = 16;
+}
+
+impl<'de> Deserialize<'de> for Emblem {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum EmblemRepr {
+            Icon(String),
+            Table {
+                icon: String,
+                corner: Option<Corner>,
+                size: Option<u16>,
+            },
+        }
+
+        Ok(match EmblemRepr::deserialize(deserializer)? {
+            EmblemRepr::Icon(icon) => Emblem {
+                icon,
+                corner: Corner::default(),
+                size: Self::DEFAULT_SIZE,
+            },
+            EmblemRepr::Table { icon, corner, size } => Emblem {
+                icon,
+                corner: corner.unwrap_or_default(),
+                size: size.unwrap_or(Self::DEFAULT_SIZE),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq)]
+pub enum Corner {
+    #[serde(rename = "top-left")]
+    TopLeft,
+    #[default]
+    #[serde(rename = "top-right")]
+    TopRight,
+    #[serde(rename = "bottom-left")]
+    BottomLeft,
+    #[serde(rename = "bottom-right")]
+    BottomRight,
+}
+
+#[cfg(test)]
+mod tests {
+    use dbus::notification::Urgency;
+
+    use super::{Color, Corner, Emblem, TomlTheme};
+
+    #[test]
+    fn emblem() {
+        let theme: TomlTheme = toml::from_str(
+            r#"
+            name = "emblems"
+            low.emblem = "dialog-information"
+            critical.emblem = { icon = "/usr/share/icons/critical.svg", corner = "bottom-left", size = 24 }
+            "#,
+        )
+        .unwrap();
+        let theme = theme.unwrap_or_default();
+
+        assert_eq!(
+            theme.low.emblem,
+            Some(Emblem {
+                icon: "dialog-information".to_string(),
+                corner: Corner::TopRight,
+                size: 16,
+            })
+        );
+        assert_eq!(theme.normal.emblem, None);
+        assert_eq!(
+            theme.critical.emblem,
+            Some(Emblem {
+                icon: "/usr/share/icons/critical.svg".to_string(),
+                corner: Corner::BottomLeft,
+                size: 24,
+            })
+        );
+    }
+
+    #[test]
+    fn hover_colors_fall_back_to_urgency() {
+        let theme: TomlTheme = toml::from_str(
+            r##"
+            name = "hover"
+            normal = { foreground = "#111111", background = "#222222", border = "#333333" }
+            hover = { background = "#444444" }
+            "##,
+        )
+        .unwrap();
+        let theme = theme.unwrap_or_default();
+
+        let red = |color: &Color| match color {
+            Color::Rgba(rgba) => rgba.red,
+            Color::LinearGradient(_) => panic!("Expected the RGBA color"),
+        };
+
+        let normal = theme.colors(&Urgency::Normal, false);
+        assert_eq!(red(&normal.background), 0x22);
+
+        let hovered = theme.colors(&Urgency::Normal, true);
+        assert_eq!(hovered.foreground.red, 0x11);
+        assert_eq!(red(&hovered.background), 0x44);
+        assert_eq!(red(&hovered.border.color), 0x33);
+    }
+
+    #[test]
+    fn contrast_is_enforced_per_urgency() {
+        let theme: TomlTheme = toml::from_str(
+            r##"
+            name = "low-contrast"
+            low = { foreground = "#45475a", background = "#1e1e2e" }
+            normal = { foreground = "#cdd6f4", background = "#1e1e2e" }
+            critical = { foreground = "#eeeeee", background = { mode = "linear-gradient", direction = "to right", colors = ["#ffffff", "#000000"] } }
+            "##,
+        )
+        .unwrap();
+        let mut theme = theme.unwrap_or_default();
+        theme.enforce_contrast(4.5);
+
+        let contrast = |urgency: &Urgency| {
+            let colors = theme.by_urgency(urgency);
+            colors
+                .background
+                .stops()
+                .iter()
+                .map(|background| colors.foreground.contrast_ratio(background))
+                .fold(f32::INFINITY, f32::min)
+        };
+
+        assert!(contrast(&Urgency::Low) >= 4.5);
+        assert_eq!(theme.normal.foreground.red, 0xcd);
+        // INFO: no color has enough contrast with both white and black
+        assert!(contrast(&Urgency::Critical) < 4.5);
+    }
+}
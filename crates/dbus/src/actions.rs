@@ -1,4 +1,5 @@
 use derive_more::derive::Display;
+use tokio::sync::oneshot;
 
 use crate::notification::ScheduledNotification;
 
@@ -8,7 +9,30 @@ pub enum Action {
     Show(Box<Notification>),
     Schedule(ScheduledNotification),
     Close(Option<u32>),
-    CloseAll,
+    /// Closes all shown and queued notifications. The number of closed notifications is sent
+    /// back.
+    CloseAll(oneshot::Sender<u32>),
+    /// Closes the shown and queued notifications of application. The number of closed
+    /// notifications is sent back.
+    CloseByApp {
+        app_name: String,
+        closed: oneshot::Sender<u32>,
+    },
+    /// Copies the text to the clipboard. The reason of failure is sent back when the text isn't
+    /// copied.
+    Copy {
+        text: String,
+        copied: oneshot::Sender<Result<(), String>>,
+    },
+    ResetState,
+    /// Withholds the notifications while any application inhibits them and shows the withheld
+    /// ones after the last inhibitor is released.
+    Inhibit(bool),
+    /// Freezes the expiration of shown banners, so they stay until resumed or dismissed. The
+    /// new notifications are still shown.
+    Pause(bool),
+    /// Stops the daemon, e.g. when the bus name is taken by another daemon.
+    Shutdown,
 }
 
 #[derive(Display)]
@@ -18,6 +42,8 @@ pub enum Signal {
     ActionInvoked {
         notification_id: u32,
         action_key: String,
+        /// The XDG activation token which lets the application raise its window on Wayland.
+        activation_token: Option<String>,
     },
     #[display("notification_id: {notification_id}, action_key: {reason}")]
     NotificationClosed {
@@ -26,7 +52,7 @@ pub enum Signal {
     },
 }
 
-#[derive(Display)]
+#[derive(Display, Debug, Clone, Copy, PartialEq)]
 pub enum ClosingReason {
     Expired,
     DismissedByUser,
@@ -0,0 +1,32 @@
+use std::str::FromStr;
+
+use zbus::{connection, Address};
+
+/// Creates the builder of connection to the bus at the given address. The session bus is used
+/// when the address isn't set.
+pub(crate) fn connection_builder(
+    address: Option<&str>,
+) -> anyhow::Result<connection::Builder<'static>> {
+    let Some(address) = address else {
+        return Ok(connection::Builder::session()?);
+    };
+
+    let address = Address::from_str(address)
+        .map_err(|err| anyhow::anyhow!("Invalid D-Bus address {address:?}. Error: {err}"))?;
+    Ok(connection::Builder::address(address)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::connection_builder;
+
+    #[test]
+    fn bus_address_is_validated() {
+        assert!(connection_builder(Some("unix:path=/run/user/1000/bus")).is_ok());
+        assert!(connection_builder(Some("tcp:host=localhost,port=4000")).is_ok());
+
+        assert!(connection_builder(Some("")).is_err());
+        assert!(connection_builder(Some("/run/user/1000/bus")).is_err());
+        assert!(connection_builder(Some("unknown:path=/tmp/bus")).is_err());
+    }
+}
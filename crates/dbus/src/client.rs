@@ -1,6 +1,9 @@
 use log::debug;
+use shared::log_target::CLIENT;
 use std::collections::HashMap;
-use zbus::{proxy, zvariant::Value, Connection};
+use zbus::{fdo::DBusProxy, proxy, zvariant::Value};
+
+use crate::bus;
 
 #[proxy(
     default_service = "org.freedesktop.Notifications",
@@ -21,6 +24,20 @@ pub trait Notifications {
     ) -> anyhow::Result<u32>;
 
     async fn get_server_information(&self) -> anyhow::Result<(String, String, String, String)>;
+
+    async fn close_notification(&self, id: u32) -> anyhow::Result<()>;
+
+    async fn close_all_notifications(&self) -> anyhow::Result<u32>;
+
+    async fn close_app_notifications(&self, app_name: &str) -> anyhow::Result<u32>;
+
+    async fn reset_state(&self) -> anyhow::Result<()>;
+
+    async fn copy_last_notification(&self, summary: bool) -> anyhow::Result<()>;
+
+    async fn suspend_all(&self) -> anyhow::Result<()>;
+
+    async fn resume_all(&self) -> anyhow::Result<()>;
 }
 
 pub struct Client<'a> {
@@ -28,12 +45,13 @@ pub struct Client<'a> {
 }
 
 impl<'a> Client<'a> {
-    pub async fn init() -> anyhow::Result<Self> {
-        debug!("D-Bus Client: Initializing");
-        let connection = Connection::session().await?;
+    /// Connects to the bus at the given address or to the session bus when it's not set.
+    pub async fn init(bus_address: Option<&str>) -> anyhow::Result<Self> {
+        debug!(target: CLIENT, "D-Bus Client: Initializing");
+        let connection = bus::connection_builder(bus_address)?.build().await?;
         let proxy = NotificationsProxy::new(&connection).await?;
 
-        debug!("D-Bus Client: Initialized");
+        debug!(target: CLIENT, "D-Bus Client: Initialized");
         Ok(Self { proxy })
     }
 
@@ -49,7 +67,7 @@ impl<'a> Client<'a> {
         hints: HashMap<&str, Value<'_>>,
         expire_timeout: i32,
     ) -> anyhow::Result<u32> {
-        debug!("D-Bus Client: Trying to notify");
+        debug!(target: CLIENT, "D-Bus Client: Trying to notify");
         let reply = self
             .proxy
             .notify(
@@ -64,15 +82,84 @@ impl<'a> Client<'a> {
             )
             .await?;
 
-        debug!("D-Bus Client: Notified");
+        debug!(target: CLIENT, "D-Bus Client: Notified");
         Ok(reply)
     }
 
     pub async fn get_server_information(&self) -> anyhow::Result<(String, String, String, String)> {
-        debug!("D-Bus Client: Trying to get server information");
+        debug!(target: CLIENT, "D-Bus Client: Trying to get server information");
         let reply = self.proxy.get_server_information().await?;
 
-        debug!("D-Bus Client: Receieved server information");
+        debug!(target: CLIENT, "D-Bus Client: Receieved server information");
         Ok(reply)
     }
+
+    /// Returns the name of daemon which owns the notifications bus name or `None` when the name
+    /// is free. Unlike calling the daemon directly, it doesn't start the daemon by D-Bus
+    /// activation.
+    pub async fn server_name(&self) -> anyhow::Result<Option<String>> {
+        debug!(target: CLIENT, "D-Bus Client: Checking the owner of notifications bus name");
+        let dbus_proxy = DBusProxy::new(self.proxy.inner().connection()).await?;
+        if !dbus_proxy
+            .name_has_owner(self.proxy.inner().destination().clone())
+            .await?
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(self.get_server_information().await?.0))
+    }
+
+    pub async fn close_notification(&self, id: u32) -> anyhow::Result<()> {
+        debug!(target: CLIENT, "D-Bus Client: Trying to close notification with id {id}");
+        self.proxy.close_notification(id).await?;
+
+        debug!(target: CLIENT, "D-Bus Client: Closed notification with id {id}");
+        Ok(())
+    }
+
+    /// Closes the notifications of application or all of them when the name isn't set. Returns
+    /// the number of closed notifications.
+    pub async fn close_notifications_by_app(&self, app_name: Option<&str>) -> anyhow::Result<u32> {
+        debug!(target: CLIENT, "D-Bus Client: Trying to close notifications");
+        let closed = match app_name {
+            Some(app_name) => self.proxy.close_app_notifications(app_name).await?,
+            None => self.proxy.close_all_notifications().await?,
+        };
+
+        debug!(target: CLIENT, "D-Bus Client: Closed {closed} notifications");
+        Ok(closed)
+    }
+
+    pub async fn reset_state(&self) -> anyhow::Result<()> {
+        debug!(target: CLIENT, "D-Bus Client: Trying to reset server state");
+        self.proxy.reset_state().await?;
+
+        debug!(target: CLIENT, "D-Bus Client: Reset server state");
+        Ok(())
+    }
+
+    pub async fn copy_last_notification(&self, summary: bool) -> anyhow::Result<()> {
+        debug!(target: CLIENT, "D-Bus Client: Trying to copy the last notification");
+        self.proxy.copy_last_notification(summary).await?;
+
+        debug!(target: CLIENT, "D-Bus Client: Copied the last notification");
+        Ok(())
+    }
+
+    pub async fn suspend_all(&self) -> anyhow::Result<()> {
+        debug!(target: CLIENT, "D-Bus Client: Trying to suspend all notifications");
+        self.proxy.suspend_all().await?;
+
+        debug!(target: CLIENT, "D-Bus Client: Suspended all notifications");
+        Ok(())
+    }
+
+    pub async fn resume_all(&self) -> anyhow::Result<()> {
+        debug!(target: CLIENT, "D-Bus Client: Trying to resume all notifications");
+        self.proxy.resume_all().await?;
+
+        debug!(target: CLIENT, "D-Bus Client: Resumed all notifications");
+        Ok(())
+    }
 }
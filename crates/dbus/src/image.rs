@@ -67,8 +67,8 @@ impl ImageData {
 
         let data = image_raw
             .iter()
-            .map(|value| u8::try_from(value).expect("expected u8"))
-            .collect::<Vec<_>>();
+            .map(|value| u8::try_from(value).ok())
+            .collect::<Option<Vec<_>>>()?;
 
         Some(ImageData {
             width,
@@ -1,4 +1,5 @@
 pub mod actions;
+mod bus;
 pub mod client;
 pub mod image;
 pub mod notification;
@@ -1,6 +1,16 @@
-use super::{image::ImageData, text::Text};
+use super::{
+    image::ImageData,
+    text::{TagKind, Text},
+};
 use derive_more::Display;
-use std::{cmp::Ordering, collections::HashMap};
+use log::warn;
+use serde::Deserialize;
+use shared::log_target::DBUS;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    time::{SystemTime, UNIX_EPOCH},
+};
 use zbus::zvariant::Value;
 
 #[derive(Debug)]
@@ -9,6 +19,9 @@ pub struct Notification {
     pub app_name: String,
     pub app_icon: String,
     pub summary: String,
+    /// The summary parsed as markup which is shown instead of the plain one when the
+    /// `summary_markup` option is enabled.
+    pub parsed_summary: Text,
     pub body: Text,
     pub expire_timeout: Timeout,
     pub hints: Hints,
@@ -17,6 +30,102 @@ pub struct Notification {
     pub created_at: u64,
 }
 
+#[cfg(any(test, feature = "test-utils"))]
+impl Notification {
+    /// Returns the notification with id 1 of the `test` application without texts, hints and
+    /// actions. The tests change the fields which they check.
+    pub fn test_default() -> Self {
+        use crate::text::TagKind;
+
+        Self {
+            id: 1,
+            app_name: "test".to_string(),
+            app_icon: String::new(),
+            summary: String::new(),
+            parsed_summary: Text::parse(String::new(), &TagKind::all()),
+            body: Text::parse(String::new(), &TagKind::all()),
+            expire_timeout: Timeout::Configurable,
+            hints: Hints::from(HashMap::new()),
+            actions: vec![],
+            is_read: false,
+            created_at: 0,
+        }
+    }
+
+    /// Sets the summary which is parsed with all markup tags.
+    pub fn with_summary(mut self, summary: &str) -> Self {
+        use crate::text::TagKind;
+
+        self.summary = summary.to_string();
+        self.parsed_summary = Text::parse(summary.to_string(), &TagKind::all());
+        self
+    }
+
+    /// Sets the body which is parsed with all markup tags.
+    pub fn with_body(mut self, body: &str) -> Self {
+        use crate::text::TagKind;
+
+        self.body = Text::parse(body.to_string(), &TagKind::all());
+        self
+    }
+}
+
+/// The arguments of the `Notify` method which make up the notification.
+pub struct NotifyArgs<'a> {
+    pub app_name: String,
+    pub app_icon: String,
+    pub summary: String,
+    pub body: String,
+    pub actions: Vec<&'a str>,
+    pub hints: HashMap<&'a str, Value<'a>>,
+    pub expire_timeout: i32,
+}
+
+/// The options of server which are applied to the notifications when they are received.
+#[derive(Debug, Clone)]
+pub struct NotifyOptions {
+    pub markup_tags: HashSet<TagKind>,
+    pub default_urgency: Urgency,
+    pub sanitize_text: bool,
+}
+
+impl Notification {
+    /// Makes the received notification from the arguments of `Notify` method. The texts are
+    /// sanitized and parsed, the hints are parsed and the time of receipt is set to now.
+    pub fn from_notify(id: u32, args: NotifyArgs<'_>, options: &NotifyOptions) -> Self {
+        let (summary, body) = match options.sanitize_text {
+            true => (Text::sanitize(&args.summary), Text::sanitize(&args.body)),
+            false => (args.summary, args.body),
+        };
+
+        Self {
+            id,
+            app_name: args.app_name,
+            app_icon: args.app_icon,
+            parsed_summary: Text::parse(summary.clone(), &options.markup_tags),
+            summary,
+            body: Text::parse(body, &options.markup_tags),
+            // INFO: the timeouts less than -1 are invalid for the `Notify` method
+            expire_timeout: Timeout::from(args.expire_timeout.max(-1)),
+            hints: Hints::parse(args.hints, options.default_urgency),
+            actions: NotificationAction::from_vec(&args.actions),
+            is_read: false,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns the one-time code of notification which is copied by its synthetic action.
+    pub fn otp_code(&self) -> Option<&str> {
+        self.actions
+            .iter()
+            .find(|action| action.action_key == NotificationAction::COPY_CODE_KEY)
+            .map(|action| action.localized_string.as_str())
+    }
+}
+
 #[derive(Debug)]
 pub struct ScheduledNotification {
     pub id: u32,
@@ -93,44 +202,109 @@ pub struct Hints {
 
     /// Specifies the time to schedule the notification to be shown.
     pub schedule: Option<String>,
+
+    /// The progress of operation in percents from 0 to 100. Negative values mean that the
+    /// duration of operation is unknown.
+    pub value: Option<i32>,
+
+    /// When set, the progress of operation is shown as a pulsing bar because its duration is
+    /// unknown.
+    pub progress_pulse: Option<bool>,
+
+    /// The slot of transient notification, e.g. volume or brightness OSD. A new notification
+    /// with the same slot replaces the previous one instead of stacking.
+    pub synchronous: Option<String>,
+
+    /// The tag of status-like notification. A new notification with the same tag replaces the
+    /// previous one regardless of its app and id.
+    pub tag: Option<String>,
 }
 
 impl Hints {
+    /// Returns the progress of operation which is specified by the `value` and
+    /// `x-noti-progress-pulse` hints.
+    pub fn progress(&self) -> Option<Progress> {
+        if self.progress_pulse == Some(true) || self.value.is_some_and(|value| value < 0) {
+            return Some(Progress::Pulse);
+        }
+
+        self.value
+            .map(|value| Progress::Determinate(value.min(100) as u8))
+    }
+
     fn get_hint_value<'a, T>(hints: &'a HashMap<&'a str, Value<'a>>, key: &str) -> Option<T>
     where
         T: TryFrom<&'a Value<'a>>,
     {
-        hints.get(key).and_then(|val| T::try_from(val).ok())
+        Self::parse_hint(hints, key, |val| T::try_from(val).ok())
+    }
+
+    /// Parses the hint by its key. The hint with unexpected type or value is skipped with a
+    /// warning, so a malformed hint doesn't drop the whole notification.
+    fn parse_hint<'a, T>(
+        hints: &'a HashMap<&'a str, Value<'a>>,
+        key: &str,
+        parse: impl FnOnce(&'a Value<'a>) -> Option<T>,
+    ) -> Option<T> {
+        let hint = hints.get(key)?;
+        let parsed = parse(hint);
+        if parsed.is_none() {
+            warn!(target: DBUS, "Hints: Skipped the malformed hint \"{key}\" with value {hint}");
+        }
+
+        parsed
     }
 }
 
 impl From<HashMap<&str, Value<'_>>> for Hints {
-    fn from(mut hints: HashMap<&str, Value>) -> Self {
-        let urgency = hints
-            .get("urgency")
-            .and_then(Urgency::from_hint)
-            .unwrap_or_default();
-
-        let category = hints
-            .get("category")
-            .and_then(Category::from_hint)
-            .unwrap_or_default();
+    fn from(hints: HashMap<&str, Value>) -> Self {
+        Self::parse(hints, Urgency::default())
+    }
+}
 
+impl Hints {
+    /// Parses the hints of notification. The `default_urgency` is used when the `urgency` hint
+    /// is absent or malformed.
+    pub fn parse(mut hints: HashMap<&str, Value>, default_urgency: Urgency) -> Self {
         let image_data = ["image-data", "image_data", "icon-data", "icon_data"]
             .iter()
-            .find_map(|&name| hints.remove(name))
-            .and_then(ImageData::from_hint);
-
-        let image_path = Self::get_hint_value(&hints, "image-path");
+            .find_map(|&name| hints.remove(name).map(|hint| (name, hint)))
+            .and_then(|(name, hint)| {
+                let signature = hint.value_signature().to_string();
+                let image_data = ImageData::from_hint(hint);
+                if image_data.is_none() {
+                    warn!(
+                        target: DBUS,
+                        "Hints: Skipped the malformed hint \"{name}\" with signature {signature}"
+                    );
+                }
+
+                image_data
+            });
+
+        let urgency =
+            Self::parse_hint(&hints, "urgency", Urgency::from_hint).unwrap_or(default_urgency);
+        let category =
+            Self::parse_hint(&hints, "category", Category::from_hint).unwrap_or_default();
+
+        let image_path = Self::get_hint_value(&hints, "image-path").map(file_uri_to_path);
         let desktop_entry = Self::get_hint_value(&hints, "desktop-entry");
         let sound_file = Self::get_hint_value(&hints, "sound-file");
         let sound_name = Self::get_hint_value(&hints, "sound-name"); // NOTE: http://0pointer.de/public/sound-naming-spec.html
         let resident = Self::get_hint_value(&hints, "resident");
         let suppress_sound = Self::get_hint_value(&hints, "suppress-sound");
         let transient = Self::get_hint_value(&hints, "transient");
-        let action_icons = Self::get_hint_value(&hints, "action_icons");
+        let action_icons = Self::get_hint_value(&hints, "action-icons");
         let schedule = Self::get_hint_value(&hints, "schedule");
         let coordinates = Coordinates::from_hints(&hints);
+        let value = Self::parse_hint(&hints, "value", |val| {
+            i32::try_from(val)
+                .ok()
+                .or_else(|| u32::try_from(val).ok().map(|value| value.min(100) as i32))
+        });
+        let progress_pulse = Self::get_hint_value(&hints, "x-noti-progress-pulse");
+        let synchronous = Self::get_hint_value(&hints, "x-canonical-private-synchronous");
+        let tag = Self::get_hint_value(&hints, "x-noti-tag");
 
         Hints {
             urgency,
@@ -146,19 +320,50 @@ impl From<HashMap<&str, Value<'_>>> for Hints {
             coordinates,
             action_icons,
             schedule,
+            value,
+            progress_pulse,
+            synchronous,
+            tag,
         }
     }
 }
 
+/// The progress of operation which the notification informs about.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Progress {
+    /// The completion of operation in percents from 0 to 100.
+    Determinate(u8),
+    /// The operation has unknown duration.
+    Pulse,
+}
+
 #[derive(Debug)]
 pub struct NotificationAction {
-    #[allow(unused)]
-    action_key: String,
-    #[allow(unused)]
+    pub action_key: String,
     localized_string: String,
 }
 
 impl NotificationAction {
+    /// The key of action which is invoked when the user clicks the notification itself.
+    pub const DEFAULT_KEY: &str = "default";
+    /// The key of synthetic action which copies the one-time code found in the notification. It's
+    /// handled by the daemon and isn't sent to the application.
+    pub const COPY_CODE_KEY: &str = "noti-copy-code";
+
+    /// Creates the synthetic action which copies the one-time code. The code is kept as the label
+    /// of action.
+    pub fn copy_code(code: String) -> Self {
+        Self {
+            action_key: Self::COPY_CODE_KEY.to_string(),
+            localized_string: code,
+        }
+    }
+
+    /// Whether the action is added by the daemon rather than by the application.
+    pub fn is_synthetic(&self) -> bool {
+        self.action_key == Self::COPY_CODE_KEY
+    }
+
     pub fn from_vec(vec: &[&str]) -> Vec<Self> {
         let mut actions: Vec<Self> = Vec::new();
 
@@ -183,8 +388,8 @@ pub struct Coordinates {
 
 impl Coordinates {
     fn from_hints(hints: &HashMap<&str, Value>) -> Option<Self> {
-        let x = hints.get("x").and_then(|val| i32::try_from(val).ok());
-        let y = hints.get("y").and_then(|val| i32::try_from(val).ok());
+        let x = Hints::get_hint_value(hints, "x");
+        let y = Hints::get_hint_value(hints, "y");
 
         match (x, y) {
             (Some(x), Some(y)) => Some(Self { x, y }),
@@ -274,7 +479,8 @@ impl From<i32> for Timeout {
     }
 }
 
-#[derive(Debug, Clone, Copy, Default, Display, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, Display, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Urgency {
     Low,
     #[default]
@@ -283,25 +489,34 @@ pub enum Urgency {
 }
 
 impl Urgency {
+    /// Parses the urgency from the byte or the name. Returns `None` for unknown values, so the
+    /// configured default urgency is used instead.
     pub fn from_hint(hint: &Value<'_>) -> Option<Self> {
-        fn to_urgency<T: Into<Urgency>>(val: T) -> Urgency {
-            val.into()
-        }
-
         u8::try_from(hint)
-            .map(to_urgency)
             .ok()
-            .or_else(|| String::try_from(hint).map(to_urgency).ok())
+            .and_then(Self::from_level)
+            .or_else(|| {
+                String::try_from(hint)
+                    .ok()
+                    .and_then(|name| Self::from_name(&name))
+            })
     }
-}
 
-impl From<u8> for Urgency {
-    fn from(value: u8) -> Self {
-        match value {
-            0 => Self::Low,
-            1 => Self::Normal,
-            2 => Self::Critical,
-            _ => Default::default(),
+    fn from_level(level: u8) -> Option<Self> {
+        match level {
+            0 => Some(Self::Low),
+            1 => Some(Self::Normal),
+            2 => Some(Self::Critical),
+            _ => None,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "low" => Some(Self::Low),
+            "normal" => Some(Self::Normal),
+            "critical" => Some(Self::Critical),
+            _ => None,
         }
     }
 }
@@ -316,23 +531,6 @@ impl From<&Urgency> for u8 {
     }
 }
 
-impl From<&str> for Urgency {
-    fn from(value: &str) -> Self {
-        match value.to_lowercase().as_str() {
-            "low" => Self::Low,
-            "normal" => Self::Normal,
-            "critical" => Self::Critical,
-            _ => Default::default(),
-        }
-    }
-}
-
-impl From<String> for Urgency {
-    fn from(value: String) -> Self {
-        <Self as From<&str>>::from(&value)
-    }
-}
-
 impl Ord for Urgency {
     fn cmp(&self, other: &Self) -> Ordering {
         Into::<u8>::into(self).cmp(&other.into())
@@ -344,3 +542,179 @@ impl PartialOrd for Urgency {
         Some(self.cmp(other))
     }
 }
+
+/// Converts the `file://` URI with empty or local host into the path in the filesystem and
+/// decodes its percent-encoded characters. Other values like bare paths and icon names are
+/// returned as is.
+fn file_uri_to_path(value: String) -> String {
+    let Some(path) = value
+        .strip_prefix("file://")
+        .map(|rest| rest.strip_prefix("localhost").unwrap_or(rest))
+        .filter(|path| path.starts_with('/'))
+    else {
+        return value;
+    };
+
+    percent_decode(path)
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .filter(|hex| hex.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match byte {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use zbus::zvariant::Value;
+
+    use crate::text::TagKind;
+
+    use super::{
+        file_uri_to_path, Category, CategoryEvent, Hints, Notification, NotifyArgs, NotifyOptions,
+        Progress, Timeout, Urgency,
+    };
+
+    #[test]
+    fn notification_is_made_from_notify_arguments() {
+        let notification = Notification::from_notify(
+            3,
+            NotifyArgs {
+                app_name: "app".to_string(),
+                app_icon: String::new(),
+                summary: "Sum\u{7}mary".to_string(),
+                body: "<b>Bold</b> body".to_string(),
+                actions: vec!["default", "Open"],
+                hints: HashMap::from([("urgency", Value::from(2u8))]),
+                expire_timeout: -5,
+            },
+            &NotifyOptions {
+                markup_tags: TagKind::all(),
+                default_urgency: Urgency::Normal,
+                sanitize_text: true,
+            },
+        );
+
+        assert_eq!(notification.id, 3);
+        assert_eq!(notification.summary, "Summary");
+        assert_eq!(notification.body.body, "Bold body");
+        assert_eq!(notification.hints.urgency, Urgency::Critical);
+        assert_eq!(notification.actions.len(), 1);
+        assert!(matches!(notification.expire_timeout, Timeout::Configurable));
+        assert!(notification.created_at > 0);
+    }
+
+    #[test]
+    fn malformed_hints_are_skipped() {
+        let hints = Hints::from(HashMap::from([
+            ("urgency", Value::from(2u8)),
+            ("category", Value::from("email.arrived")),
+            ("image-path", Value::from("/tmp/image.png")),
+            ("value", Value::from("forty")),
+            ("desktop-entry", Value::from(42)),
+            ("image-data", Value::from("not an image")),
+            ("x", Value::from(10)),
+            ("y", Value::from(true)),
+        ]));
+
+        assert_eq!(hints.urgency, Urgency::Critical);
+        assert!(matches!(
+            hints.category,
+            Category::Email(CategoryEvent::Arrived)
+        ));
+        assert_eq!(hints.image_path.as_deref(), Some("/tmp/image.png"));
+
+        assert_eq!(hints.progress(), None);
+        assert!(hints.desktop_entry.is_none());
+        assert!(hints.image_data.is_none());
+        assert!(hints.coordinates.is_none());
+    }
+
+    #[test]
+    fn malformed_image_data_is_skipped() {
+        let image_data = Value::from((1, 1, 4, true, 8, 4, vec![1000, 0, 0, 255]));
+        let hints = Hints::from(HashMap::from([
+            ("image-data", image_data),
+            ("value", Value::from(40)),
+        ]));
+
+        assert!(hints.image_data.is_none());
+        assert_eq!(hints.progress(), Some(Progress::Determinate(40)));
+    }
+
+    #[test]
+    fn default_urgency_is_used_without_hint() {
+        let hints = Hints::parse(HashMap::new(), Urgency::Low);
+        assert_eq!(hints.urgency, Urgency::Low);
+
+        let hints = Hints::parse(HashMap::from([("urgency", Value::from(2u8))]), Urgency::Low);
+        assert_eq!(hints.urgency, Urgency::Critical);
+    }
+
+    #[test]
+    fn default_urgency_is_used_for_unknown_hint() {
+        let hints = Hints::parse(HashMap::from([("urgency", Value::from(5u8))]), Urgency::Low);
+        assert_eq!(hints.urgency, Urgency::Low);
+
+        let hints = Hints::parse(
+            HashMap::from([("urgency", Value::from("urgent"))]),
+            Urgency::Critical,
+        );
+        assert_eq!(hints.urgency, Urgency::Critical);
+    }
+
+    #[test]
+    fn file_uris_are_resolved_into_paths() {
+        let path = |value: &str| file_uri_to_path(value.to_string());
+
+        assert_eq!(path("file:///tmp/image.png"), "/tmp/image.png");
+        assert_eq!(path("file://localhost/tmp/image.png"), "/tmp/image.png");
+        assert_eq!(path("file:///tmp/my%20image.png"), "/tmp/my image.png");
+        assert_eq!(path("file:///tmp/%E2%9C%93%2Fa.png"), "/tmp/✓/a.png");
+        assert_eq!(path("file:///tmp/100%.png"), "/tmp/100%.png");
+        assert_eq!(path("/tmp/my%20image.png"), "/tmp/my%20image.png");
+        assert_eq!(path("mail-unread"), "mail-unread");
+        assert_eq!(
+            path("file://remote/tmp/image.png"),
+            "file://remote/tmp/image.png"
+        );
+    }
+
+    #[test]
+    fn image_path_hint_accepts_file_uri() {
+        let hints = Hints::from(HashMap::from([(
+            "image-path",
+            Value::from("file:///tmp/my%20image.png"),
+        )]));
+        assert_eq!(hints.image_path.as_deref(), Some("/tmp/my image.png"));
+    }
+
+    #[test]
+    fn action_icons_hint_uses_spec_name() {
+        let hints = Hints::from(HashMap::from([("action-icons", Value::from(true))]));
+        assert_eq!(hints.action_icons, Some(true));
+    }
+}
@@ -1,50 +1,271 @@
 use super::{
     actions::{Action, ClosingReason, Signal},
-    notification::{Hints, Notification, NotificationAction, Timeout},
-    text::Text,
+    bus,
+    notification::{Notification, NotifyArgs, NotifyOptions, Urgency},
+    text::TagKind,
 };
 
 use std::{
-    collections::HashMap,
-    sync::atomic::{AtomicU32, Ordering},
-    time::{SystemTime, UNIX_EPOCH},
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
 };
 
-use log::debug;
+use log::{debug, info, warn};
+use shared::log_target::DBUS;
 use zbus::{
-    connection, fdo::Result, interface, object_server::SignalContext, zvariant::Value, Connection,
+    export::futures_util::StreamExt,
+    fdo::{DBusProxy, RequestNameFlags, Result},
+    interface,
+    message::Header,
+    object_server::SignalContext,
+    zvariant::Value,
+    Connection,
 };
 
-use tokio::sync::mpsc::UnboundedSender;
+use tokio::{
+    runtime::Handle,
+    sync::{mpsc::UnboundedSender, oneshot},
+};
 
 static UNIQUE_ID: AtomicU32 = AtomicU32::new(1);
 
+/// The version of Desktop Notifications Specification which the server implements.
+pub const SPECIFICATION_VERSION: &str = "1.2";
+
+/// The name of server which is returned by the `GetServerInformation` method.
+pub const SERVER_NAME: &str = env!("APP_NAME");
+
 pub struct Server {
     connection: Connection,
+    markup_tags: Arc<RwLock<HashSet<TagKind>>>,
+    default_urgency: Arc<RwLock<Urgency>>,
+    sanitize_text: Arc<AtomicBool>,
+    strict_replaces: Arc<AtomicBool>,
+    synchronous_slots: Arc<Mutex<ReplacementSlots>>,
+    tags: Arc<Mutex<ReplacementSlots>>,
+}
+
+/// The way to acquire the notifications bus name when it's owned by another daemon.
+#[derive(Debug, Default, Clone)]
+pub struct NameRequest {
+    /// Replaces the current owner of name if it allows the replacement.
+    pub replace: bool,
+    /// The number of retries to acquire the name. The delay between retries is doubled each time.
+    pub retries: u8,
 }
 
 impl Server {
     const NOTIFICATIONS_PATH: &'static str = "/org/freedesktop/Notifications";
     const NOTIFICATIONS_NAME: &'static str = "org.freedesktop.Notifications";
 
-    pub async fn init(sender: UnboundedSender<Action>) -> anyhow::Result<Self> {
-        debug!("D-Bus Server: Initializing");
-
-        let handler = Handler { sender };
+    const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+    const MAX_RETRY_DELAY: Duration = Duration::from_secs(8);
+
+    pub async fn init(
+        sender: UnboundedSender<Action>,
+        name_request: &NameRequest,
+        bus_address: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        debug!(target: DBUS, "D-Bus Server: Initializing");
+
+        let markup_tags = Arc::new(RwLock::new(TagKind::all()));
+        let default_urgency = Arc::new(RwLock::new(Urgency::default()));
+        let sanitize_text = Arc::new(AtomicBool::new(true));
+        let strict_replaces = Arc::new(AtomicBool::new(true));
+        let synchronous_slots = Arc::new(Mutex::new(ReplacementSlots::default()));
+        let tags = Arc::new(Mutex::new(ReplacementSlots::default()));
+        let handler = Handler {
+            sender: sender.clone(),
+            runtime: Handle::current(),
+            markup_tags: markup_tags.clone(),
+            default_urgency: default_urgency.clone(),
+            sanitize_text: sanitize_text.clone(),
+            strict_replaces: strict_replaces.clone(),
+            ids: NotificationIds::default(),
+            synchronous_slots: synchronous_slots.clone(),
+            tags: tags.clone(),
+            inhibitors: Inhibitors::default(),
+            last_notification: None,
+        };
 
-        let connection = connection::Builder::session()?
-            .name(Self::NOTIFICATIONS_NAME)?
+        let connection = bus::connection_builder(bus_address)?
             .serve_at(Self::NOTIFICATIONS_PATH, handler)?
             .build()
             .await?;
 
-        debug!("D-Bus Server: Initialized");
+        let dbus_proxy = DBusProxy::new(&connection).await?;
+        // INFO: the stream must be created before requesting the name to not miss the signal.
+        let mut name_lost_stream = dbus_proxy.receive_name_lost().await?;
+        let mut name_owner_changed_stream = dbus_proxy.receive_name_owner_changed().await?;
+
+        Self::acquire_name(&connection, name_request).await?;
+
+        // INFO: the application may exit or crash without releasing its inhibitors, so they are
+        // released when the application disconnects from the bus.
+        let inhibitors_connection = connection.clone();
+        tokio::spawn(async move {
+            while let Some(signal) = name_owner_changed_stream.next().await {
+                let Ok(args) = signal.args() else {
+                    continue;
+                };
+
+                if args.new_owner.is_some() {
+                    continue;
+                }
+
+                if let Err(err) =
+                    Self::release_inhibitors_of(&inhibitors_connection, args.name.as_str()).await
+                {
+                    warn!(target: DBUS, "D-Bus Server: Failed to release inhibitors: {err}");
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            while let Some(signal) = name_lost_stream.next().await {
+                if signal
+                    .args()
+                    .is_ok_and(|args| args.name == Self::NOTIFICATIONS_NAME)
+                {
+                    info!(
+                        target: DBUS,
+                        "D-Bus Server: The name {} is taken by another daemon",
+                        Self::NOTIFICATIONS_NAME
+                    );
+                    let _ = sender.send(Action::Shutdown);
+                    break;
+                }
+            }
+        });
+
+        debug!(target: DBUS, "D-Bus Server: Initialized");
+
+        Ok(Self {
+            connection,
+            markup_tags,
+            default_urgency,
+            sanitize_text,
+            strict_replaces,
+            synchronous_slots,
+            tags,
+        })
+    }
+
+    /// Sets the markup tags which are honored in the body of incoming notifications.
+    pub fn set_markup_tags(&self, markup_tags: HashSet<TagKind>) {
+        *self
+            .markup_tags
+            .write()
+            .expect("The lock of markup tags must not be poisoned") = markup_tags;
+    }
+
+    pub fn set_default_urgency(&self, default_urgency: Urgency) {
+        *self
+            .default_urgency
+            .write()
+            .expect("The lock of default urgency must not be poisoned") = default_urgency;
+    }
+
+    /// Sets whether the control characters are removed from the summary and body of incoming
+    /// notifications.
+    pub fn set_sanitize_text(&self, sanitize_text: bool) {
+        self.sanitize_text.store(sanitize_text, Ordering::Relaxed);
+    }
+
+    /// Sets whether the `replaces_id` is honored only when the notification of the same
+    /// application is replaced.
+    pub fn set_strict_replaces(&self, strict_replaces: bool) {
+        self.strict_replaces
+            .store(strict_replaces, Ordering::Relaxed);
+    }
+
+    /// Closes the connection after all pending messages are written.
+    pub async fn shutdown(self) -> zbus::Result<()> {
+        debug!(target: DBUS, "D-Bus Server: Shutting down");
+        self.connection.close().await
+    }
+
+    async fn release_inhibitors_of(connection: &Connection, owner: &str) -> zbus::Result<()> {
+        let interface = connection
+            .object_server()
+            .interface::<_, Handler>(Self::NOTIFICATIONS_PATH)
+            .await?;
+
+        let mut handler = interface.get_mut().await;
+        handler
+            .update_inhibitors(interface.signal_context(), |inhibitors| {
+                let released = inhibitors.remove_by_owner(owner);
+                if released > 0 {
+                    debug!(
+                        target: DBUS,
+                        "D-Bus Server: Released {released} inhibitors of disconnected {owner}"
+                    );
+                }
+            })
+            .await
+    }
+
+    /// Frees the slots of closed notification, so the next notification of slot is shown anew.
+    ///
+    /// The slots are shared with the handler instead of locking its interface, because the
+    /// methods which wait for the backend hold the interface lock until the backend replies.
+    fn release_slots_of(&self, notification_id: u32) {
+        release_slots(&self.synchronous_slots, &self.tags, notification_id);
+    }
+
+    async fn acquire_name(
+        connection: &Connection,
+        name_request: &NameRequest,
+    ) -> anyhow::Result<()> {
+        // INFO: allow the replacement so the other daemon started with `--replace` can take the
+        // name. In this case the server receives the `NameLost` signal.
+        let mut flags = RequestNameFlags::AllowReplacement | RequestNameFlags::DoNotQueue;
+        if name_request.replace {
+            flags |= RequestNameFlags::ReplaceExisting;
+        }
+
+        let mut delay = Self::INITIAL_RETRY_DELAY;
+        for attempt in 0..=name_request.retries {
+            match connection
+                .request_name_with_flags(Self::NOTIFICATIONS_NAME, flags)
+                .await
+            {
+                Ok(_) => {
+                    debug!(
+                        target: DBUS,
+                        "D-Bus Server: Acquired the name {}",
+                        Self::NOTIFICATIONS_NAME
+                    );
+                    return Ok(());
+                }
+                Err(zbus::Error::NameTaken) if attempt < name_request.retries => {
+                    warn!(
+                        target: DBUS,
+                        "D-Bus Server: The name {} is owned by another daemon, retrying in {}ms",
+                        Self::NOTIFICATIONS_NAME,
+                        delay.as_millis()
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(Self::MAX_RETRY_DELAY);
+                }
+                Err(zbus::Error::NameTaken) => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
 
-        Ok(Self { connection })
+        anyhow::bail!(
+            "The name {} is owned by another notification daemon. \
+            Stop it or use the --replace flag to replace it",
+            Self::NOTIFICATIONS_NAME
+        )
     }
 
     pub async fn emit_signal(&self, signal: Signal) -> zbus::Result<()> {
-        debug!("D-Bus Server: Emitting signal {signal}");
+        debug!(target: DBUS, "D-Bus Server: Emitting signal {signal}");
 
         let ctxt = SignalContext::new(&self.connection, Self::NOTIFICATIONS_PATH)?;
         match signal {
@@ -56,19 +277,296 @@ impl Server {
                     0 => UNIQUE_ID.load(Ordering::Relaxed),
                     _ => notification_id,
                 };
+                self.release_slots_of(id);
 
                 Handler::notification_closed(&ctxt, id, u32::from(reason)).await
             }
             Signal::ActionInvoked {
                 notification_id,
                 action_key,
-            } => Handler::action_invoked(&ctxt, notification_id, &action_key).await,
+                activation_token,
+            } => {
+                // INFO: the specification requires to emit the activation token before the
+                // invoked action so the application can use it during handling the action.
+                if let Some(activation_token) = activation_token {
+                    Handler::activation_token(&ctxt, notification_id, &activation_token).await?;
+                }
+
+                Handler::action_invoked(&ctxt, notification_id, &action_key).await
+            }
         }
     }
 }
 
 struct Handler {
     sender: UnboundedSender<Action>,
+    /// The runtime which runs the timers of methods, because zbus runs the methods by its own
+    /// executor.
+    runtime: Handle,
+    markup_tags: Arc<RwLock<HashSet<TagKind>>>,
+    default_urgency: Arc<RwLock<Urgency>>,
+    sanitize_text: Arc<AtomicBool>,
+    strict_replaces: Arc<AtomicBool>,
+    ids: NotificationIds,
+    synchronous_slots: Arc<Mutex<ReplacementSlots>>,
+    /// The notifications by their `x-noti-tag` hints which replace each other regardless of
+    /// their applications.
+    tags: Arc<Mutex<ReplacementSlots>>,
+    inhibitors: Inhibitors,
+    last_notification: Option<LastNotification>,
+}
+
+/// The texts of the most recently received notification which can be copied to the clipboard.
+struct LastNotification {
+    summary: String,
+    body: String,
+}
+
+impl Handler {
+    /// The time to wait for the reply of backend. zbus holds the lock of interface while the
+    /// method runs, so the stuck backend must not block the whole interface.
+    const BACKEND_REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn notify_options(&self) -> NotifyOptions {
+        NotifyOptions {
+            markup_tags: self
+                .markup_tags
+                .read()
+                .expect("The lock of markup tags must not be poisoned")
+                .clone(),
+            default_urgency: *self
+                .default_urgency
+                .read()
+                .expect("The lock of default urgency must not be poisoned"),
+            sanitize_text: self.sanitize_text.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Asks the backend to close the notifications by the action and waits for the number of
+    /// closed ones.
+    async fn close_notifications(
+        &self,
+        action: impl FnOnce(oneshot::Sender<u32>) -> Action,
+    ) -> Result<u32> {
+        let (closed, receiver) = oneshot::channel();
+        self.sender.send(action(closed)).unwrap();
+
+        self.backend_reply(receiver, "The backend didn't close the notifications")
+            .await
+    }
+
+    /// Waits for the reply of backend at most `BACKEND_REPLY_TIMEOUT`.
+    async fn backend_reply<T: Send + 'static>(
+        &self,
+        receiver: oneshot::Receiver<T>,
+        failure: &str,
+    ) -> Result<T> {
+        let reply = self.runtime.spawn(async move {
+            tokio::time::timeout(Self::BACKEND_REPLY_TIMEOUT, receiver).await
+        });
+
+        match reply.await {
+            Ok(Ok(Ok(reply))) => Ok(reply),
+            Ok(Err(_)) => {
+                warn!(
+                    target: DBUS,
+                    "D-Bus Server: The backend didn't reply in {}s",
+                    Self::BACKEND_REPLY_TIMEOUT.as_secs()
+                );
+                Err(zbus::fdo::Error::Failed(format!("{failure} in time")))
+            }
+            _ => Err(zbus::fdo::Error::Failed(failure.to_string())),
+        }
+    }
+
+    /// Applies the update to inhibitors and notifies the backend and D-Bus clients when the
+    /// inhibited state is changed.
+    async fn update_inhibitors(
+        &mut self,
+        ctxt: &SignalContext<'_>,
+        update: impl FnOnce(&mut Inhibitors),
+    ) -> zbus::Result<()> {
+        let was_inhibited = self.inhibitors.is_inhibited();
+        update(&mut self.inhibitors);
+        let is_inhibited = self.inhibitors.is_inhibited();
+
+        if was_inhibited != is_inhibited {
+            info!(
+                target: DBUS,
+                "D-Bus Server: Notifications are {}",
+                if is_inhibited { "inhibited" } else { "uninhibited" }
+            );
+            self.sender.send(Action::Inhibit(is_inhibited)).unwrap();
+            self.inhibited_changed(ctxt).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Keeps the server-assigned ids authoritative.
+///
+/// The clients may pass any `replaces_id`, so the ids are checked before using them as the key of
+/// notification:
+/// - an id which was used by another application isn't reused in the strict mode, a new id is
+///   assigned instead to not replace an unrelated banner;
+/// - an id chosen by client is reserved while it's remembered, so the server skips it when
+///   assigning the new ids.
+#[derive(Default)]
+struct NotificationIds {
+    owners: HashMap<u32, String>,
+    order: VecDeque<u32>,
+}
+
+impl NotificationIds {
+    /// The number of recent ids whose applications are remembered.
+    const CAPACITY: usize = 1024;
+
+    /// Returns the id for notification of the application. The id isn't remembered until it's
+    /// recorded, because the hints may redirect the notification to another id.
+    fn assign(&self, app_name: &str, replaces_id: u32, strict: bool) -> u32 {
+        match replaces_id {
+            0 => self.next_id(),
+            _ if strict
+                && self
+                    .owners
+                    .get(&replaces_id)
+                    .is_some_and(|owner| owner != app_name) =>
+            {
+                debug!(
+                    target: DBUS,
+                    "D-Bus Server: The id {replaces_id} belongs to another application, \
+                    assigned a new id for notification of {app_name}"
+                );
+                self.next_id()
+            }
+            _ => replaces_id,
+        }
+    }
+
+    /// Remembers the application of the id which is assigned to its notification.
+    fn record(&mut self, app_name: &str, id: u32) {
+        if self.owners.insert(id, app_name.to_string()).is_none() {
+            self.order.push_back(id);
+            if self.order.len() > Self::CAPACITY {
+                if let Some(oldest_id) = self.order.pop_front() {
+                    self.owners.remove(&oldest_id);
+                }
+            }
+        }
+    }
+
+    fn next_id(&self) -> u32 {
+        loop {
+            let id = UNIQUE_ID.fetch_add(1, Ordering::Relaxed);
+            // INFO: the counter wraps around after u32::MAX and 0 isn't a valid id
+            if id != 0 && !self.owners.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+}
+
+/// The notifications which occupy the named slots, e.g. the `x-canonical-private-synchronous`
+/// and `x-noti-tag` hints. The notification of occupied slot replaces the notification which occupies it instead
+/// of stacking. The slot is released when its notification is closed.
+#[derive(Default)]
+struct ReplacementSlots {
+    occupants: HashMap<String, u32>,
+}
+
+impl ReplacementSlots {
+    /// Returns the id of notification which occupies the slot or lets the notification with
+    /// given id occupy it.
+    fn resolve(&mut self, slot: &str, id: u32) -> u32 {
+        match self.occupants.get(slot) {
+            Some(&occupant) => {
+                debug!(
+                    target: DBUS,
+                    "D-Bus Server: The notification with id {id} replaces the notification with \
+                    id {occupant} in slot {slot}"
+                );
+                occupant
+            }
+            None => {
+                self.occupants.insert(slot.to_string(), id);
+                id
+            }
+        }
+    }
+
+    fn release(&mut self, id: u32) {
+        self.occupants.retain(|_, occupant| *occupant != id);
+    }
+}
+
+fn release_slots(
+    synchronous_slots: &Mutex<ReplacementSlots>,
+    tags: &Mutex<ReplacementSlots>,
+    id: u32,
+) {
+    synchronous_slots
+        .lock()
+        .expect("The lock of synchronous slots must not be poisoned")
+        .release(id);
+    tags.lock()
+        .expect("The lock of tags must not be poisoned")
+        .release(id);
+}
+
+/// The active requests of applications to withhold notifications, e.g. while the screen is
+/// recorded. Each request is identified by the cookie which is returned to the application.
+#[derive(Default)]
+struct Inhibitors {
+    last_cookie: u32,
+    active: HashMap<u32, Inhibitor>,
+}
+
+struct Inhibitor {
+    /// The unique bus name of application which requested the inhibition.
+    owner: String,
+    desktop_entry: String,
+    reason: String,
+}
+
+impl Inhibitors {
+    fn add(&mut self, inhibitor: Inhibitor) -> u32 {
+        loop {
+            self.last_cookie = self.last_cookie.wrapping_add(1);
+            if self.last_cookie != 0 && !self.active.contains_key(&self.last_cookie) {
+                break;
+            }
+        }
+
+        debug!(
+            target: DBUS,
+            "D-Bus Server: Added inhibitor {} of {} with reason '{}'",
+            self.last_cookie,
+            inhibitor.desktop_entry,
+            inhibitor.reason
+        );
+        self.active.insert(self.last_cookie, inhibitor);
+        self.last_cookie
+    }
+
+    fn remove(&mut self, cookie: u32) -> bool {
+        self.active.remove(&cookie).is_some()
+    }
+
+    /// Removes all inhibitors of the application and returns their count.
+    fn remove_by_owner(&mut self, owner: &str) -> usize {
+        let count = self.active.len();
+        self.active.retain(|_, inhibitor| inhibitor.owner != owner);
+        count - self.active.len()
+    }
+
+    fn count(&self) -> u32 {
+        self.active.len() as u32
+    }
+
+    fn is_inhibited(&self) -> bool {
+        !self.active.is_empty()
+    }
 }
 
 #[interface(name = "org.freedesktop.Notifications")]
@@ -85,32 +583,49 @@ impl Handler {
         hints: HashMap<&str, Value<'_>>,
         expire_timeout: i32,
     ) -> Result<u32> {
-        debug!("D-Bus Server: Received notification");
-
-        let id = match replaces_id {
-            0 => UNIQUE_ID.fetch_add(1, Ordering::Relaxed),
-            _ => replaces_id,
-        };
+        debug!(target: DBUS, "D-Bus Server: Received notification");
+
+        // INFO: the id is resolved after parsing, because the hints can replace the notification
+        let mut notification = Notification::from_notify(
+            0,
+            NotifyArgs {
+                app_name,
+                app_icon,
+                summary,
+                body,
+                actions,
+                hints,
+                expire_timeout,
+            },
+            &self.notify_options(),
+        );
+
+        let mut id = self.ids.assign(
+            &notification.app_name,
+            replaces_id,
+            self.strict_replaces.load(Ordering::Relaxed),
+        );
+        if let Some(slot) = notification.hints.synchronous.as_ref() {
+            id = self
+                .synchronous_slots
+                .lock()
+                .expect("The lock of synchronous slots must not be poisoned")
+                .resolve(slot, id);
+        }
+        if let Some(tag) = notification.hints.tag.as_ref() {
+            id = self
+                .tags
+                .lock()
+                .expect("The lock of tags must not be poisoned")
+                .resolve(tag, id);
+        }
+        self.ids.record(&notification.app_name, id);
+        notification.id = id;
 
-        #[rustfmt::skip]
-        let created_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-        let hints = Hints::from(hints);
-        let actions = NotificationAction::from_vec(&actions);
-        let body = Text::parse(body);
-        let expire_timeout = Timeout::from(expire_timeout);
-
-        let notification = Notification {
-            id,
-            app_name,
-            app_icon,
-            summary,
-            body,
-            hints,
-            actions,
-            expire_timeout,
-            created_at,
-            is_read: false,
-        };
+        self.last_notification = Some(LastNotification {
+            summary: notification.summary.clone(),
+            body: notification.body.body.clone(),
+        });
 
         if let Some(schedule) = &notification.hints.schedule {
             let scheduled_notification = crate::notification::ScheduledNotification {
@@ -130,23 +645,41 @@ impl Handler {
     }
 
     async fn close_notification(
-        &self,
+        &mut self,
         id: u32,
         #[zbus(signal_context)] ctxt: SignalContext<'_>,
     ) -> Result<()> {
-        debug!("D-Bus Server: Called method 'CloseNotification' by id {id}");
+        debug!(target: DBUS, "D-Bus Server: Called method 'CloseNotification' by id {id}");
+        release_slots(&self.synchronous_slots, &self.tags, id);
         Self::notification_closed(&ctxt, id, ClosingReason::CallCloseNotification.into()).await?;
         self.sender.send(Action::Close(Some(id))).unwrap();
 
         Ok(())
     }
 
+    /// Closes all shown and queued notifications. Returns the number of closed notifications.
+    async fn close_all_notifications(&self) -> Result<u32> {
+        debug!(target: DBUS, "D-Bus Server: Called method 'CloseAllNotifications'");
+        self.close_notifications(Action::CloseAll).await
+    }
+
+    /// Closes the shown and queued notifications of application. Returns the number of closed
+    /// notifications.
+    async fn close_app_notifications(&self, app_name: String) -> Result<u32> {
+        debug!(
+            target: DBUS,
+            "D-Bus Server: Called method 'CloseAppNotifications' by app name {app_name}"
+        );
+        self.close_notifications(|closed| Action::CloseByApp { app_name, closed })
+            .await
+    }
+
     // NOTE: temporary
     async fn close_last_notification(
         &self,
         #[zbus(signal_context)] ctxt: SignalContext<'_>,
     ) -> Result<()> {
-        debug!("D-Bus Server: Called method 'CloseLastNotification'");
+        debug!(target: DBUS, "D-Bus Server: Called method 'CloseLastNotification'");
         //WARNING: temporary id value
         Self::notification_closed(&ctxt, 0, ClosingReason::CallCloseNotification.into()).await?;
         self.sender.send(Action::Close(None)).unwrap();
@@ -154,18 +687,122 @@ impl Handler {
         Ok(())
     }
 
+    /// Copies the body or summary of the most recently received notification to the clipboard.
+    /// The body is copied without markup.
+    async fn copy_last_notification(&self, summary: bool) -> Result<()> {
+        debug!(target: DBUS, "D-Bus Server: Called method 'CopyLastNotification'");
+        let Some(last_notification) = self.last_notification.as_ref() else {
+            return Err(zbus::fdo::Error::Failed(
+                "There is no notification to copy".to_string(),
+            ));
+        };
+
+        let text = if summary {
+            last_notification.summary.clone()
+        } else {
+            last_notification.body.clone()
+        };
+        let (copied, receiver) = oneshot::channel();
+        self.sender.send(Action::Copy { text, copied }).unwrap();
+
+        self.backend_reply(receiver, "The backend didn't copy the text")
+            .await?
+            .map_err(zbus::fdo::Error::Failed)
+    }
+
+    /// Pauses the expiration of shown notifications until `ResumeAll` is called.
+    async fn suspend_all(&self) -> Result<()> {
+        debug!(target: DBUS, "D-Bus Server: Called method 'SuspendAll'");
+        self.sender.send(Action::Pause(true)).unwrap();
+
+        Ok(())
+    }
+
+    /// Resumes the expiration of shown notifications with their remaining timeouts.
+    async fn resume_all(&self) -> Result<()> {
+        debug!(target: DBUS, "D-Bus Server: Called method 'ResumeAll'");
+        self.sender.send(Action::Pause(false)).unwrap();
+
+        Ok(())
+    }
+
+    async fn reset_state(&self) -> Result<()> {
+        debug!(target: DBUS, "D-Bus Server: Called method 'ResetState'");
+        self.sender.send(Action::ResetState).unwrap();
+
+        Ok(())
+    }
+
+    async fn inhibit(
+        &mut self,
+        desktop_entry: String,
+        reason: String,
+        _hints: HashMap<&str, Value<'_>>,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<u32> {
+        debug!(target: DBUS, "D-Bus Server: Called method 'Inhibit' by {desktop_entry}");
+        let owner = header
+            .sender()
+            .map(|sender| sender.to_string())
+            .unwrap_or_default();
+
+        let mut cookie = 0;
+        self.update_inhibitors(&ctxt, |inhibitors| {
+            cookie = inhibitors.add(Inhibitor {
+                owner,
+                desktop_entry,
+                reason,
+            })
+        })
+        .await?;
+
+        Ok(cookie)
+    }
+
+    async fn un_inhibit(
+        &mut self,
+        cookie: u32,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> Result<()> {
+        debug!(target: DBUS, "D-Bus Server: Called method 'UnInhibit' by cookie {cookie}");
+        let mut removed = false;
+        self.update_inhibitors(&ctxt, |inhibitors| removed = inhibitors.remove(cookie))
+            .await?;
+
+        if !removed {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "There is no inhibitor with cookie {cookie}"
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn get_active_inhibitors(&self) -> Result<u32> {
+        debug!(target: DBUS, "D-Bus Server: Called method 'GetActiveInhibitors'");
+        Ok(self.inhibitors.count())
+    }
+
+    /// Whether the notifications are withheld by at least one inhibitor. The change is emitted
+    /// by the `PropertiesChanged` signal.
+    #[zbus(property)]
+    async fn inhibited(&self) -> bool {
+        self.inhibitors.is_inhibited()
+    }
+
     async fn get_server_information(&self) -> Result<(String, String, String, String)> {
-        debug!("D-Bus Server: Called method 'GetServerInformation'");
-        let name = String::from(env!("APP_NAME"));
+        debug!(target: DBUS, "D-Bus Server: Called method 'GetServerInformation'");
+        let name = String::from(SERVER_NAME);
         let vendor = String::from(env!("CARGO_PKG_AUTHORS"));
         let version = String::from(env!("CARGO_PKG_VERSION"));
-        let specification_version = String::from("1.2");
+        let specification_version = String::from(SPECIFICATION_VERSION);
 
         Ok((name, vendor, version, specification_version))
     }
 
     async fn get_capabilities(&self) -> Result<Vec<String>> {
-        debug!("D-Bus Server: Called method 'GetCapabilities'");
+        debug!(target: DBUS, "D-Bus Server: Called method 'GetCapabilities'");
         let capabilities = vec![
             String::from("action-icons"),
             String::from("actions"),
@@ -175,6 +812,7 @@ impl Handler {
             String::from("body-markup"),
             String::from("icon-multi"),
             String::from("icon-static"),
+            String::from("inhibitions"),
             String::from("persistence"),
             String::from("sound"),
         ];
@@ -189,6 +827,13 @@ impl Handler {
         action_key: &str,
     ) -> zbus::Result<()>;
 
+    #[zbus(signal)]
+    async fn activation_token(
+        ctxt: &SignalContext<'_>,
+        id: u32,
+        activation_token: &str,
+    ) -> zbus::Result<()>;
+
     #[zbus(signal)]
     async fn notification_closed(
         ctxt: &SignalContext<'_>,
@@ -196,3 +841,165 @@ impl Handler {
         reason: u32,
     ) -> zbus::Result<()>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Inhibitor, Inhibitors, NotificationIds, ReplacementSlots};
+
+    fn resolve(ids: &mut NotificationIds, app_name: &str, replaces_id: u32, strict: bool) -> u32 {
+        let id = ids.assign(app_name, replaces_id, strict);
+        ids.record(app_name, id);
+        id
+    }
+
+    #[test]
+    fn new_ids_are_unique() {
+        let mut ids = NotificationIds::default();
+
+        assert_ne!(
+            resolve(&mut ids, "first", 0, true),
+            resolve(&mut ids, "first", 0, true)
+        );
+    }
+
+    #[test]
+    fn replace_own_notification() {
+        let mut ids = NotificationIds::default();
+        let id = resolve(&mut ids, "first", 0, true);
+
+        assert_eq!(resolve(&mut ids, "first", id, true), id);
+    }
+
+    #[test]
+    fn id_collision_of_applications() {
+        let mut ids = NotificationIds::default();
+        let first_id = resolve(&mut ids, "first", 0, true);
+
+        let second_id = resolve(&mut ids, "second", first_id, true);
+        assert_ne!(second_id, first_id);
+        assert_eq!(resolve(&mut ids, "first", first_id, true), first_id);
+    }
+
+    #[test]
+    fn id_collision_of_applications_is_honored_when_not_strict() {
+        let mut ids = NotificationIds::default();
+        let first_id = resolve(&mut ids, "first", 0, false);
+
+        assert_eq!(resolve(&mut ids, "second", first_id, false), first_id);
+        assert_ne!(resolve(&mut ids, "first", first_id, true), first_id);
+    }
+
+    #[test]
+    fn notifications_of_slot_replace_each_other() {
+        let mut slots = ReplacementSlots::default();
+
+        assert_eq!(slots.resolve("volume", 1), 1);
+        assert_eq!(slots.resolve("brightness", 2), 2);
+        assert_eq!(slots.resolve("volume", 3), 1);
+    }
+
+    #[test]
+    fn released_slot_is_occupied_anew() {
+        let mut slots = ReplacementSlots::default();
+        slots.resolve("volume", 1);
+
+        slots.release(1);
+        assert_eq!(slots.resolve("volume", 2), 2);
+        assert_eq!(slots.resolve("volume", 3), 2);
+    }
+
+    /// Resolves the id of tagged notification like the `Notify` method.
+    fn resolve_tagged(
+        ids: &mut NotificationIds,
+        tags: &mut ReplacementSlots,
+        app_name: &str,
+        tag: &str,
+    ) -> u32 {
+        let id = tags.resolve(tag, ids.assign(app_name, 0, true));
+        ids.record(app_name, id);
+        id
+    }
+
+    #[test]
+    fn tagged_notifications_of_applications_replace_each_other() {
+        let (mut ids, mut tags) = (NotificationIds::default(), ReplacementSlots::default());
+        let first_id = resolve_tagged(&mut ids, &mut tags, "make", "build");
+
+        assert_eq!(resolve_tagged(&mut ids, &mut tags, "ci", "build"), first_id);
+        assert_ne!(
+            resolve_tagged(&mut ids, &mut tags, "make", "test"),
+            first_id
+        );
+    }
+
+    #[test]
+    fn only_assigned_ids_are_recorded() {
+        let (mut ids, mut tags) = (NotificationIds::default(), ReplacementSlots::default());
+        let first_id = resolve_tagged(&mut ids, &mut tags, "make", "build");
+
+        for _ in 0..10 {
+            resolve_tagged(&mut ids, &mut tags, "make", "build");
+        }
+        assert_eq!(ids.order, [first_id]);
+        assert_eq!(ids.owners.len(), 1);
+    }
+
+    #[test]
+    fn client_chosen_id_is_reserved() {
+        let mut ids = NotificationIds::default();
+        let chosen_id = resolve(&mut ids, "first", 0, true) + 100;
+
+        assert_eq!(resolve(&mut ids, "first", chosen_id, true), chosen_id);
+        assert!((0..100).all(|_| resolve(&mut ids, "second", 0, true) != chosen_id));
+    }
+
+    #[test]
+    fn max_client_chosen_id_is_reserved() {
+        let mut ids = NotificationIds::default();
+
+        assert_eq!(resolve(&mut ids, "first", u32::MAX, true), u32::MAX);
+        let new_ids = [
+            resolve(&mut ids, "second", 0, true),
+            resolve(&mut ids, "second", 0, true),
+        ];
+        assert_ne!(new_ids[0], new_ids[1]);
+        assert!(!new_ids.contains(&u32::MAX));
+    }
+
+    fn inhibitor(owner: &str) -> Inhibitor {
+        Inhibitor {
+            owner: owner.to_string(),
+            desktop_entry: String::from("recorder"),
+            reason: String::from("Screen recording"),
+        }
+    }
+
+    #[test]
+    fn inhibited_until_last_inhibitor_is_removed() {
+        let mut inhibitors = Inhibitors::default();
+        let first_cookie = inhibitors.add(inhibitor(":1.1"));
+        let second_cookie = inhibitors.add(inhibitor(":1.1"));
+
+        assert_ne!(first_cookie, second_cookie);
+        assert_eq!(inhibitors.count(), 2);
+
+        assert!(inhibitors.remove(first_cookie));
+        assert!(!inhibitors.remove(first_cookie));
+        assert!(inhibitors.is_inhibited());
+
+        assert!(inhibitors.remove(second_cookie));
+        assert!(!inhibitors.is_inhibited());
+    }
+
+    #[test]
+    fn inhibitors_of_disconnected_owner_are_removed() {
+        let mut inhibitors = Inhibitors::default();
+        inhibitors.add(inhibitor(":1.1"));
+        inhibitors.add(inhibitor(":1.1"));
+        let cookie = inhibitors.add(inhibitor(":1.2"));
+
+        assert_eq!(inhibitors.remove_by_owner(":1.1"), 2);
+        assert_eq!(inhibitors.count(), 1);
+        assert!(inhibitors.remove(cookie));
+    }
+}
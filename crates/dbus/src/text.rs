@@ -1,5 +1,6 @@
 use log::warn;
-use std::collections::HashMap;
+use shared::log_target::DBUS;
+use std::collections::{HashMap, HashSet};
 use unic_segment;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -9,13 +10,67 @@ pub struct Text {
 }
 
 impl Text {
-    pub fn parse(input: String) -> Self {
-        Parser::new(&input).parse()
+    /// Parses the markup text. The tags which kinds are not in `allowed_tags` are dropped and
+    /// their content is kept as plain text.
+    pub fn parse(input: String, allowed_tags: &HashSet<TagKind>) -> Self {
+        Parser::new(&input, allowed_tags).parse()
+    }
+
+    /// Removes the control characters which break the layout, e.g. NULs and terminal escape
+    /// sequences. The new lines and tabs are kept and the carriage returns are turned into new
+    /// lines.
+    pub fn sanitize(input: &str) -> String {
+        let mut output = String::with_capacity(input.len());
+        let mut chars = input.chars().peekable();
+
+        while let Some(char) = chars.next() {
+            match char {
+                '\n' | '\t' => output.push(char),
+                '\r' => {
+                    chars.next_if_eq(&'\n');
+                    output.push('\n');
+                }
+                '\u{1b}' => skip_escape_sequence(&mut chars),
+                char if char.is_control() => (),
+                char => output.push(char),
+            }
+        }
+
+        output
+    }
+}
+
+/// Skips the rest of terminal escape sequence after the ESC character.
+fn skip_escape_sequence(chars: &mut std::iter::Peekable<std::str::Chars>) {
+    match chars.next() {
+        // INFO: the CSI sequence ends with a character in the range from '@' to '~'
+        Some('[') => {
+            for char in chars.by_ref() {
+                if ('@'..='~').contains(&char) {
+                    break;
+                }
+            }
+        }
+        // INFO: the OSC sequence ends with BEL or with ST which is ESC and backslash
+        Some(']') => {
+            while let Some(char) = chars.next() {
+                match char {
+                    '\u{7}' => break,
+                    '\u{1b}' => {
+                        chars.next_if_eq(&'\\');
+                        break;
+                    }
+                    _ => (),
+                }
+            }
+        }
+        _ => (),
     }
 }
 
 struct Parser<'a> {
     input: &'a str,
+    allowed_tags: &'a HashSet<TagKind>,
     body: String,
     entities: Vec<Entity>,
     stack: Vec<ParsedTag>,
@@ -24,9 +79,10 @@ struct Parser<'a> {
 }
 
 impl<'a> Parser<'a> {
-    fn new(input: &'a str) -> Self {
+    fn new(input: &'a str, allowed_tags: &'a HashSet<TagKind>) -> Self {
         Self {
             input,
+            allowed_tags,
             body: String::new(),
             entities: Vec::new(),
             stack: Vec::new(),
@@ -176,6 +232,10 @@ impl<'a> Parser<'a> {
         let tag = Tag::try_parse(self.input, &mut self.cursor, start_byte_pos)?;
 
         let end = tag.byte_pos_end;
+        if !self.allowed_tags.contains(&tag.kind.tag_kind()) {
+            return Some(end);
+        }
+
         match &tag.tag_type {
             TagType::Opening => {
                 self.stack.push(ParsedTag {
@@ -198,7 +258,7 @@ impl<'a> Parser<'a> {
         if self
             .stack
             .last()
-            .is_some_and(|parsed_tag| parsed_tag.tag.kind.to_id() == closing_tag.kind.to_id())
+            .is_some_and(|parsed_tag| parsed_tag.tag.kind.tag_kind() == closing_tag.kind.tag_kind())
         {
             let ParsedTag {
                 tag,
@@ -221,6 +281,7 @@ impl<'a> Parser<'a> {
         } else {
             let range = closing_tag.byte_pos_begin..closing_tag.byte_pos_end;
             warn!(
+                target: DBUS,
                 "Unexpected closing tag {} at {:?} in text: {}",
                 &self.input[range.clone()],
                 range,
@@ -273,6 +334,7 @@ pub enum EntityKind {
     Bold,      // <b> ... </b>
     Italic,    // <i> ... </i>
     Underline, // <u> ... </u>
+    Kbd,       // <kbd> ... </kbd>
     Link {
         href: Option<String>,
     }, // <a href="hello.com"> ... </a>
@@ -283,17 +345,43 @@ pub enum EntityKind {
 }
 
 impl EntityKind {
-    fn to_id(&self) -> u8 {
+    pub fn tag_kind(&self) -> TagKind {
         match self {
-            EntityKind::Bold => 0,
-            EntityKind::Italic => 1,
-            EntityKind::Underline => 2,
-            EntityKind::Link { .. } => 3,
-            EntityKind::Image { .. } => 4,
+            EntityKind::Bold => TagKind::Bold,
+            EntityKind::Italic => TagKind::Italic,
+            EntityKind::Underline => TagKind::Underline,
+            EntityKind::Kbd => TagKind::Kbd,
+            EntityKind::Link { .. } => TagKind::Link,
+            EntityKind::Image { .. } => TagKind::Image,
         }
     }
 }
 
+/// The kind of markup tag without its attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TagKind {
+    Bold,
+    Italic,
+    Underline,
+    Kbd,
+    Link,
+    Image,
+}
+
+impl TagKind {
+    /// Returns all supported kinds of tags.
+    pub fn all() -> HashSet<TagKind> {
+        HashSet::from([
+            TagKind::Bold,
+            TagKind::Italic,
+            TagKind::Underline,
+            TagKind::Kbd,
+            TagKind::Link,
+            TagKind::Image,
+        ])
+    }
+}
+
 struct ParsedTag {
     tag: Tag,
     begin_position: usize,
@@ -315,7 +403,7 @@ enum TagType {
 }
 
 impl Tag {
-    /// Tries to parse the HTML tags: bold, italic, underline, link and image.
+    /// Tries to parse the HTML tags: bold, italic, underline, keyboard input, link and image.
     ///
     /// For link supported only `href` attribute.
     /// For image supported only `src` and `alt` attributes.
@@ -360,6 +448,19 @@ impl Tag {
                 )?;
                 EntityKind::Underline
             }
+            "k" => {
+                if cursor.next_grapheme(input)? != "b" || cursor.next_grapheme(input)? != "d" {
+                    return None;
+                }
+
+                end_tag_byte_pos = Self::close_unattributed_tag(
+                    input,
+                    cursor,
+                    cursor.cur_cursor(),
+                    &mut tag_type,
+                )?;
+                EntityKind::Kbd
+            }
             "i" => {
                 let end_tag_name = cursor.cur_cursor();
                 let second_grapheme = cursor.next_grapheme(input);
@@ -543,10 +644,27 @@ impl SkipUntil for unic_segment::GraphemeCursor {
 mod tests {
     use super::*;
 
+    #[test]
+    fn sanitize_control_chars() {
+        assert_eq!(
+            Text::sanitize("Line\0 one\r\nLine\u{7} two\rLine\tthree\u{8}"),
+            "Line one\nLine two\nLine\tthree"
+        );
+        assert_eq!(
+            Text::sanitize("\u{1b}[1;31mRed\u{1b}[0m text\u{1b}]0;title\u{7}!"),
+            "Red text!"
+        );
+        assert_eq!(
+            Text::sanitize("\u{1b}]8;;https://example.com\u{1b}\\link\u{1b}]8;;\u{1b}\\"),
+            "link"
+        );
+        assert_eq!(Text::sanitize("Plain ☕️ text"), "Plain ☕️ text");
+    }
+
     #[test]
     fn text_not_tag() {
         let input = String::from("Normal equation: 1 < 2");
-        let text = Text::parse(input.clone());
+        let text = Text::parse(input.clone(), &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -559,7 +677,7 @@ mod tests {
     #[test]
     fn text_with_emoji() {
         let input = String::from("<b>coffee ☕️</b>");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -576,7 +694,7 @@ mod tests {
     #[test]
     fn text() {
         let input = String::from("hello world!!!");
-        let text = Text::parse(input.clone());
+        let text = Text::parse(input.clone(), &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -590,7 +708,7 @@ mod tests {
     fn text_with_tags() {
         let input = String::from("test<b>text</b> <i>parsing</i><u>!!!</u>");
         println!("input: {input}");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -619,7 +737,7 @@ mod tests {
     #[test]
     fn text_with_unclosed_tag() {
         let input = String::from("hello <b>world!!!");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -636,7 +754,7 @@ mod tests {
     #[test]
     fn text_with_multiple_unclosed_tags() {
         let input = String::from("<i>hello <b>world!!!");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -660,7 +778,7 @@ mod tests {
     #[test]
     fn text_with_unmatched_tags() {
         let input = String::from("<i>hello</b>");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -677,7 +795,7 @@ mod tests {
     #[test]
     fn text_with_unmatched_tags2() {
         let input = String::from("hello<i> <b>");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -690,7 +808,7 @@ mod tests {
     #[test]
     fn text_with_nested_tags() {
         let input = String::from("hello <b>wo<i>rld</i></b>!!!");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -714,7 +832,7 @@ mod tests {
     #[test]
     fn text_with_link() {
         let input = String::from("hello <a href=\"link.com\">click</a>!!!");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -733,7 +851,7 @@ mod tests {
     #[test]
     fn text_with_link_without_href() {
         let input = String::from("<a>link</a>");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -747,10 +865,34 @@ mod tests {
         )
     }
 
+    #[test]
+    fn text_with_kbd() {
+        let input = String::from("Press <kbd>Ctrl</kbd>+<kbd>C</kbd> to <kb>copy</kb>");
+        let text = Text::parse(input, &TagKind::all());
+        assert_eq!(
+            text,
+            Text {
+                body: String::from("Press Ctrl+C to <kb>copy</kb>"),
+                entities: vec![
+                    Entity {
+                        offset: 6,
+                        length: 4,
+                        kind: EntityKind::Kbd,
+                    },
+                    Entity {
+                        offset: 11,
+                        length: 1,
+                        kind: EntityKind::Kbd,
+                    },
+                ],
+            }
+        )
+    }
+
     #[test]
     fn text_with_img() {
         let input = String::from("image:<img src=\"/path/to/image.png\"/>");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -770,7 +912,7 @@ mod tests {
     #[test]
     fn text_with_img_2() {
         let input = String::from("<img src=\"/path/to/image.png\"/> some text");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -791,7 +933,7 @@ mod tests {
     fn text_with_img_with_alt() {
         let input =
             String::from("image: <img src=\"/path/to/image.png\" alt=\"some cool image\"/>!!!");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -811,7 +953,7 @@ mod tests {
     #[test]
     fn text_with_stupid_tags() {
         let input = String::from("<b><i> hi </b></i>");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -835,7 +977,7 @@ mod tests {
     #[test]
     fn text_with_empty_tags() {
         let input = String::from("test<b></b> <i> </i> <u>         </u>");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -848,7 +990,7 @@ mod tests {
     #[test]
     fn text_with_empty_tags2() {
         let input = String::from("test<b>  <i> <u>      </u> </i> </b>");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -861,7 +1003,7 @@ mod tests {
     #[test]
     fn text_with_spaces() {
         let input = String::from("test       asdasd");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -874,7 +1016,7 @@ mod tests {
     #[test]
     fn text_with_html_symbol() {
         let input = String::from("<b>hello&quot;</b>");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -891,7 +1033,7 @@ mod tests {
     #[test]
     fn text_with_chained_html_symbols() {
         let input = String::from("<b>hello&amp;quot;</b>");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -908,7 +1050,7 @@ mod tests {
     #[test]
     fn text_with_escaped_slash() {
         let input = String::from("hello<&#47;b>");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -921,7 +1063,7 @@ mod tests {
     #[test]
     fn text_with_lt_and_gt_html_escapes() {
         let input = String::from("<b>&lt;i&gt;penis&lt;/i&gt;</b>");
-        let text = Text::parse(input);
+        let text = Text::parse(input, &TagKind::all());
         assert_eq!(
             text,
             Text {
@@ -934,4 +1076,39 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn text_with_disabled_img() {
+        let input = String::from("image: <img src=\"/path/to/image.png\"/> <b>bold</b>");
+        let mut allowed_tags = TagKind::all();
+        allowed_tags.remove(&TagKind::Image);
+
+        let text = Text::parse(input, &allowed_tags);
+        assert_eq!(
+            text,
+            Text {
+                body: String::from("image:  bold"),
+                entities: vec![Entity {
+                    offset: 8,
+                    length: 4,
+                    kind: EntityKind::Bold
+                }]
+            }
+        )
+    }
+
+    #[test]
+    fn text_with_disabled_link() {
+        let input = String::from("visit <a href=\"https://example.com\">site</a>");
+        let allowed_tags = HashSet::from([TagKind::Bold]);
+
+        let text = Text::parse(input, &allowed_tags);
+        assert_eq!(
+            text,
+            Text {
+                body: String::from("visit site"),
+                entities: vec![]
+            }
+        )
+    }
 }
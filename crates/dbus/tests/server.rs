@@ -0,0 +1,297 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    time::Duration,
+};
+
+use dbus::{
+    actions::{Action, ClosingReason, Signal},
+    client::Client,
+    notification::{Category, CategoryEvent, Notification, Urgency},
+    server::{NameRequest, Server},
+};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use zbus::zvariant::Value;
+
+/// The private session bus which is started by `dbus-daemon` for each test, so the tests don't
+/// interfere with the notification daemon of user and each other.
+///
+/// The tests which need it are ignored by default because `dbus-daemon` may be not installed.
+/// Run them by `cargo test -p dbus --test server -- --ignored`.
+struct PrivateBus {
+    daemon: Child,
+    address: String,
+}
+
+impl PrivateBus {
+    fn launch() -> Self {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("The dbus-daemon should be installed to run the D-Bus tests");
+
+        let mut address = String::new();
+        BufReader::new(
+            daemon
+                .stdout
+                .take()
+                .expect("The output of dbus-daemon should be piped"),
+        )
+        .read_line(&mut address)
+        .expect("The dbus-daemon should print its address");
+
+        Self {
+            daemon,
+            address: address.trim().to_string(),
+        }
+    }
+
+    async fn connect_server(&self, sender: UnboundedSender<Action>) -> Server {
+        Server::init(sender, &NameRequest::default(), Some(&self.address))
+            .await
+            .expect("The server should be initialized on the private bus")
+    }
+
+    async fn connect_client(&self) -> Client<'_> {
+        Client::init(Some(&self.address))
+            .await
+            .expect("The client should connect to the private bus")
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+async fn receive_shown(receiver: &mut UnboundedReceiver<Action>) -> Notification {
+    let action = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+        .await
+        .expect("The server should send the action in time")
+        .expect("The channel of actions should be open");
+
+    match action {
+        Action::Show(notification) => *notification,
+        _ => panic!("Expected the 'Show' action"),
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires dbus-daemon"]
+async fn notify_is_sent_to_backend() {
+    let bus = PrivateBus::launch();
+
+    let (sender, mut receiver) = unbounded_channel();
+    let _server = bus.connect_server(sender).await;
+    let client = bus.connect_client().await;
+
+    let hints = HashMap::from([
+        ("urgency", Value::U8(2)),
+        ("category", Value::from("email.arrived")),
+    ]);
+    let id = client
+        .notify(
+            "mail",
+            0,
+            "mail-unread",
+            "New mail",
+            "<b>Hello</b> from the test",
+            vec!["default", "Open"],
+            hints,
+            3000,
+        )
+        .await
+        .expect("The notification should be sent");
+
+    let notification = receive_shown(&mut receiver).await;
+    assert_eq!(notification.id, id);
+    assert_eq!(notification.app_name, "mail");
+    assert_eq!(notification.app_icon, "mail-unread");
+    assert_eq!(notification.summary, "New mail");
+    assert_eq!(notification.body.body, "Hello from the test");
+    assert_eq!(notification.hints.urgency, Urgency::Critical);
+    assert!(matches!(
+        notification.hints.category,
+        Category::Email(CategoryEvent::Arrived)
+    ));
+    assert_eq!(notification.actions.len(), 1);
+}
+
+#[tokio::test]
+#[ignore = "requires dbus-daemon"]
+async fn closed_notifications_are_counted_by_backend() {
+    let bus = PrivateBus::launch();
+
+    let (sender, mut receiver) = unbounded_channel();
+    let _server = bus.connect_server(sender).await;
+    let client = bus.connect_client().await;
+
+    tokio::spawn(async move {
+        while let Some(action) = receiver.recv().await {
+            match action {
+                Action::CloseByApp { app_name, closed } => {
+                    let _ = closed.send(if app_name == "mail" { 2 } else { 0 });
+                }
+                Action::CloseAll(closed) => {
+                    let _ = closed.send(5);
+                }
+                _ => (),
+            }
+        }
+    });
+
+    assert_eq!(
+        client
+            .close_notifications_by_app(Some("mail"))
+            .await
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        client
+            .close_notifications_by_app(Some("chat"))
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(client.close_notifications_by_app(None).await.unwrap(), 5);
+}
+
+#[tokio::test]
+#[ignore = "requires dbus-daemon"]
+async fn notification_is_closed_while_close_all_waits_for_backend() {
+    let bus = PrivateBus::launch();
+
+    let (sender, mut receiver) = unbounded_channel();
+    let server = bus.connect_server(sender).await;
+    let client = bus.connect_client().await;
+
+    let id = client
+        .notify("mail", 0, "", "New mail", "", vec![], HashMap::new(), -1)
+        .await
+        .expect("The notification should be sent");
+
+    tokio::spawn(async move {
+        while let Some(action) = receiver.recv().await {
+            if let Action::CloseAll(closed) = action {
+                // INFO: the notification expires before the backend replies to the client
+                server
+                    .emit_signal(Signal::NotificationClosed {
+                        notification_id: id,
+                        reason: ClosingReason::Expired,
+                    })
+                    .await
+                    .expect("The signal should be emitted");
+                let _ = closed.send(1);
+            }
+        }
+    });
+
+    let closed = tokio::time::timeout(
+        Duration::from_secs(5),
+        client.close_notifications_by_app(None),
+    )
+    .await
+    .expect("The server should close the notifications in time")
+    .unwrap();
+    assert_eq!(closed, 1);
+}
+
+#[tokio::test]
+#[ignore = "requires dbus-daemon"]
+async fn stuck_backend_does_not_block_interface() {
+    let bus = PrivateBus::launch();
+
+    let (sender, mut receiver) = unbounded_channel();
+    let _server = bus.connect_server(sender).await;
+    let client = bus.connect_client().await;
+
+    tokio::spawn(async move {
+        // INFO: the reply channels are kept open, but the backend never replies
+        let mut pending = vec![];
+        while let Some(action) = receiver.recv().await {
+            if let Action::CloseAll(closed) = action {
+                pending.push(closed);
+            }
+        }
+    });
+
+    let err = tokio::time::timeout(
+        Duration::from_secs(10),
+        client.close_notifications_by_app(None),
+    )
+    .await
+    .expect("The server should stop waiting for the backend")
+    .expect_err("The missed reply should be reported");
+    assert!(err.to_string().contains("in time"));
+
+    client
+        .notify("mail", 0, "", "New mail", "", vec![], HashMap::new(), -1)
+        .await
+        .expect("The notification should be sent after the timeout");
+}
+
+#[tokio::test]
+#[ignore = "requires dbus-daemon"]
+async fn failed_copy_is_reported_to_client() {
+    let bus = PrivateBus::launch();
+
+    let (sender, mut receiver) = unbounded_channel();
+    let _server = bus.connect_server(sender).await;
+    let client = bus.connect_client().await;
+
+    tokio::spawn(async move {
+        while let Some(action) = receiver.recv().await {
+            if let Action::Copy { copied, .. } = action {
+                let _ = copied.send(Err("The clipboard isn't supported".to_string()));
+            }
+        }
+    });
+
+    client
+        .notify("mail", 0, "", "New mail", "", vec![], HashMap::new(), -1)
+        .await
+        .expect("The notification should be sent");
+
+    let err = client
+        .copy_last_notification(false)
+        .await
+        .expect_err("The failed copy should be reported");
+    assert!(err.to_string().contains("The clipboard isn't supported"));
+}
+
+#[tokio::test]
+#[ignore = "requires dbus-daemon"]
+async fn stuck_copy_is_reported_to_client() {
+    let bus = PrivateBus::launch();
+
+    let (sender, mut receiver) = unbounded_channel();
+    let _server = bus.connect_server(sender).await;
+    let client = bus.connect_client().await;
+
+    tokio::spawn(async move {
+        // INFO: the clipboard roundtrip never finishes
+        let mut pending = vec![];
+        while let Some(action) = receiver.recv().await {
+            if let Action::Copy { copied, .. } = action {
+                pending.push(copied);
+            }
+        }
+    });
+
+    client
+        .notify("mail", 0, "", "New mail", "", vec![], HashMap::new(), -1)
+        .await
+        .expect("The notification should be sent");
+
+    let err = tokio::time::timeout(Duration::from_secs(10), client.copy_last_notification(true))
+        .await
+        .expect("The server should stop waiting for the clipboard")
+        .expect_err("The stuck copy should be reported");
+    assert!(err.to_string().contains("in time"));
+}
@@ -5,10 +5,14 @@ use config::{
     display::{Border, GBuilderBorder},
     spacing::{GBuilderSpacing, Spacing},
 };
-use log::warn;
-use pest::iterators::{Pair, Pairs};
+use pest::{
+    error::{Error, ErrorVariant},
+    iterators::{Pair, Pairs},
+    Span,
+};
 use render::widget::{
-    Alignment, GBuilderAlignment, GBuilderFlexContainer, GBuilderWImage, GBuilderWText, Widget,
+    Alignment, GBuilderAlignment, GBuilderFlexContainer, GBuilderWImage, GBuilderWProgress,
+    GBuilderWText, Widget,
 };
 use shared::{
     error::ConversionError,
@@ -17,7 +21,37 @@ use shared::{
 
 use crate::parser::Rule;
 
-pub(super) fn convert_into_widgets(mut pairs: Pairs<Rule>) -> anyhow::Result<Widget> {
+/// The issues which are found in the layout while converting it. They don't break the
+/// conversion because the invalid parts are skipped, but the layout will look different than
+/// expected.
+#[derive(Default)]
+pub(super) struct Diagnostics {
+    issues: Vec<Error<Rule>>,
+}
+
+impl Diagnostics {
+    fn report(&mut self, span: Span, message: String) {
+        self.push(error_at(span, message));
+    }
+
+    fn push(&mut self, issue: Error<Rule>) {
+        self.issues.push(issue);
+    }
+
+    pub(super) fn into_issues(self) -> Vec<Error<Rule>> {
+        self.issues
+    }
+}
+
+/// Creates the error which points to the given place of layout.
+fn error_at(span: Span, message: String) -> Error<Rule> {
+    Error::new_from_span(ErrorVariant::CustomError { message }, span)
+}
+
+pub(super) fn convert_into_widgets(
+    mut pairs: Pairs<Rule>,
+    diagnostics: &mut Diagnostics,
+) -> anyhow::Result<Widget> {
     let pair = pairs
         .next()
         .expect("There should be at least one Pair with Rule - Layout.");
@@ -33,18 +67,19 @@ pub(super) fn convert_into_widgets(mut pairs: Pairs<Rule>) -> anyhow::Result<Wid
 
     let maybe_aliases = inner_pairs.next().unwrap();
     let node_type = if maybe_aliases.as_rule() == Rule::AliasDefinitions {
-        convert_aliases(maybe_aliases, &mut alias_storage)?;
+        convert_aliases(maybe_aliases, &mut alias_storage, diagnostics)?;
         inner_pairs.next().unwrap()
     } else {
         maybe_aliases
     };
 
-    convert_node_type(node_type, &alias_storage)
+    convert_node_type(node_type, &alias_storage, diagnostics)
 }
 
 fn convert_aliases<'a>(
     alias_definitions: Pair<'a, Rule>,
     alias_storage: &mut HashMap<&'a str, GBuilder>,
+    diagnostics: &mut Diagnostics,
 ) -> anyhow::Result<()> {
     assert_eq!(
         alias_definitions.as_rule(),
@@ -68,7 +103,7 @@ fn convert_aliases<'a>(
 
         alias_storage.insert(
             alias_identifier,
-            convert_type_value(type_value_definition, alias_storage)?,
+            convert_type_value(type_value_definition, alias_storage, diagnostics)?,
         );
     }
 
@@ -78,6 +113,7 @@ fn convert_aliases<'a>(
 fn convert_node_type<'a>(
     node_type: Pair<'a, Rule>,
     alias_storage: &'a HashMap<&'a str, GBuilder>,
+    diagnostics: &mut Diagnostics,
 ) -> anyhow::Result<Widget> {
     assert_eq!(
         node_type.as_rule(),
@@ -85,32 +121,43 @@ fn convert_node_type<'a>(
         "In input should be a NodeType"
     );
 
+    let node_span = node_type.as_span();
     let mut node_type_pairs = node_type.into_inner();
 
-    let widget_name = node_type_pairs.next().unwrap().as_str();
-    let mut widget_gbuilder: GBuilder = (widget_name, alias_storage).try_into()?;
+    let widget_identifier = node_type_pairs.next().unwrap();
+    let widget_name = widget_identifier.as_str();
+    let mut widget_gbuilder: GBuilder = (widget_name, alias_storage)
+        .try_into()
+        .map_err(|err: anyhow::Error| error_at(widget_identifier.as_span(), err.to_string()))?;
 
-    let properties = convert_properties(&mut node_type_pairs, alias_storage);
+    let properties = convert_properties(&mut node_type_pairs, alias_storage, diagnostics);
 
-    let children = convert_children(&mut node_type_pairs, alias_storage)?;
-    widget_gbuilder.set_properties(widget_name, properties);
+    let children = convert_children(&mut node_type_pairs, alias_storage, diagnostics)?;
+    widget_gbuilder.set_properties(widget_name, properties, diagnostics);
 
     if !children.is_empty() {
         let assignment_result =
             widget_gbuilder.set_value("children", Value::Any(Box::new(children)));
 
         if let Err(ConversionError::UnknownField { field_name, .. }) = assignment_result {
-            warn!("The {widget_name} doesn't contain the '{field_name}' field! Skipped.");
+            diagnostics.report(
+                node_span,
+                format!("The {widget_name} doesn't contain the '{field_name}' field! Skipped."),
+            );
         }
     }
 
-    Ok(widget_gbuilder.try_build()?.try_downcast()?)
+    Ok(widget_gbuilder
+        .try_build()
+        .map_err(|err| error_at(node_span, err.to_string()))?
+        .try_downcast()?)
 }
 
 fn convert_properties<'a>(
     node_type_pairs: &mut Pairs<'a, Rule>,
     alias_storage: &'a HashMap<&'a str, GBuilder>,
-) -> Vec<Property> {
+    diagnostics: &mut Diagnostics,
+) -> Vec<Property<'a>> {
     let _open_paren = node_type_pairs.next();
 
     let properties_or_close_paren = node_type_pairs.next().unwrap();
@@ -131,9 +178,16 @@ fn convert_properties<'a>(
     let mut converted_properties = vec![];
     let mut properties_pairs = properties.into_inner();
     while let Some(property) = properties_pairs.next() {
-        match convert_property(property, alias_storage) {
+        let span = property.as_span();
+        match convert_property(property, alias_storage, diagnostics) {
             Ok(property) => converted_properties.push(property),
-            Err(err) => warn!("Failed to parse property and skipped. Error: {err}"),
+            Err(err) => match err.downcast::<Error<Rule>>() {
+                Ok(issue) => diagnostics.push(issue),
+                Err(err) => diagnostics.report(
+                    span,
+                    format!("Failed to parse property and skipped. Error: {err}"),
+                ),
+            },
         }
         let _comma = properties_pairs.next();
     }
@@ -144,24 +198,27 @@ fn convert_properties<'a>(
 fn convert_property<'a>(
     property: Pair<'a, Rule>,
     alias_storage: &'a HashMap<&'a str, GBuilder>,
-) -> anyhow::Result<Property> {
+    diagnostics: &mut Diagnostics,
+) -> anyhow::Result<Property<'a>> {
     assert_eq!(
         property.as_rule(),
         Rule::Property,
         "In input should be a Property"
     );
 
+    let span = property.as_span();
     let mut property_pairs = property.into_inner();
     let name = property_pairs.next().unwrap().as_str().to_string();
     let _eq_token = property_pairs.next();
-    let value = convert_property_value(property_pairs.next().unwrap(), alias_storage)?;
+    let value = convert_property_value(property_pairs.next().unwrap(), alias_storage, diagnostics)?;
 
-    Ok(Property { name, value })
+    Ok(Property { name, value, span })
 }
 
 fn convert_property_value<'a>(
     property_value: Pair<'a, Rule>,
     alias_storage: &'a HashMap<&'a str, GBuilder>,
+    diagnostics: &mut Diagnostics,
 ) -> anyhow::Result<Value> {
     assert_eq!(
         property_value.as_rule(),
@@ -171,10 +228,12 @@ fn convert_property_value<'a>(
 
     let value = property_value.into_inner().next().unwrap();
 
+    let span = value.as_span();
     Ok(match value.as_rule() {
-        Rule::TypeValue => convert_type_value(value, alias_storage)
-            .and_then(GBuilder::try_build)
-            .map(Value::Any)?,
+        Rule::TypeValue => convert_type_value(value, alias_storage, diagnostics)?
+            .try_build()
+            .map(Value::Any)
+            .map_err(|err| error_at(span, err.to_string()))?,
         Rule::Literal => Value::String(value.as_str().to_string()),
         Rule::UInt => Value::UInt(value.as_str().parse().unwrap()),
         _ => unreachable!(),
@@ -184,6 +243,7 @@ fn convert_property_value<'a>(
 fn convert_children<'a>(
     node_type_pairs: &mut Pairs<'a, Rule>,
     alias_storage: &'a HashMap<&'a str, GBuilder>,
+    diagnostics: &mut Diagnostics,
 ) -> anyhow::Result<Vec<Widget>> {
     let open_brace = node_type_pairs.next();
 
@@ -209,13 +269,14 @@ fn convert_children<'a>(
 
     children
         .into_inner()
-        .map(|child| convert_node_type(child, alias_storage))
+        .map(|child| convert_node_type(child, alias_storage, diagnostics))
         .collect::<anyhow::Result<Vec<Widget>>>()
 }
 
 fn convert_type_value<'a>(
     type_value: Pair<'a, Rule>,
     alias_storage: &'a HashMap<&'a str, GBuilder>,
+    diagnostics: &mut Diagnostics,
 ) -> anyhow::Result<GBuilder> {
     assert_eq!(
         type_value.as_rule(),
@@ -223,22 +284,27 @@ fn convert_type_value<'a>(
         "In input should be a TypeValue"
     );
 
+    let span = type_value.as_span();
     let mut type_value_pairs = type_value.into_inner();
 
-    let type_name = type_value_pairs.next().unwrap().as_str();
-    let mut type_gbuilder: GBuilder = (type_name, alias_storage).try_into()?;
+    let type_identifier = type_value_pairs.next().unwrap();
+    let type_name = type_identifier.as_str();
+    let mut type_gbuilder: GBuilder = (type_name, alias_storage)
+        .try_into()
+        .map_err(|err: anyhow::Error| error_at(type_identifier.as_span(), err.to_string()))?;
 
     let maybe_value = type_value_pairs.clone().nth(1).unwrap();
-    if maybe_value.as_rule() == Rule::Properties {
-        type_gbuilder.set_properties(
-            type_name,
-            convert_properties(&mut type_value_pairs, alias_storage),
-        );
-    } else {
-        type_gbuilder.constructor(
-            type_name,
-            convert_property_value(maybe_value, alias_storage)?,
-        );
+    match maybe_value.as_rule() {
+        Rule::Properties => {
+            let properties = convert_properties(&mut type_value_pairs, alias_storage, diagnostics);
+            type_gbuilder.set_properties(type_name, properties, diagnostics);
+        }
+        Rule::PropertyValue => {
+            let value = convert_property_value(maybe_value, alias_storage, diagnostics)?;
+            type_gbuilder.constructor(type_name, value, span, diagnostics);
+        }
+        // The type value without properties and value like `Alignment()`
+        _ => (),
     }
 
     Ok(type_gbuilder)
@@ -249,6 +315,7 @@ enum GBuilder {
     FlexContainer(GBuilderFlexContainer),
     WImage(GBuilderWImage),
     WText(GBuilderWText),
+    WProgress(GBuilderWProgress),
 
     Spacing(GBuilderSpacing),
     Alignment(GBuilderAlignment),
@@ -256,17 +323,31 @@ enum GBuilder {
 }
 
 impl GBuilder {
-    fn set_properties(&mut self, self_name: &str, properties: Vec<Property>) {
-        for Property { name, value } in properties {
+    fn set_properties(
+        &mut self,
+        self_name: &str,
+        properties: Vec<Property>,
+        diagnostics: &mut Diagnostics,
+    ) {
+        for Property { name, value, span } in properties {
             if let Err(err) = self.set_value(&name, value) {
-                warn!(
-                    "Cannot set value for the '{name}' field in {self_name} due error and skipped. Error: {err}"
+                diagnostics.report(
+                    span,
+                    format!(
+                        "Cannot set value for the '{name}' field in {self_name} due error and skipped. Error: {err}"
+                    ),
                 );
             }
         }
     }
 
-    fn constructor(&mut self, self_name: &str, value: Value) {
+    fn constructor(
+        &mut self,
+        self_name: &str,
+        value: Value,
+        span: Span,
+        diagnostics: &mut Diagnostics,
+    ) {
         macro_rules! implement_variants {
             ($($variant:ident),*) => {
                 match self {
@@ -279,10 +360,21 @@ impl GBuilder {
             };
         }
 
-        if let Some(err) =
-            implement_variants!(FlexContainer, WImage, WText, Spacing, Alignment, Border)
-        {
-            warn!("Failed to call constructor of {self_name}, trying to defaulting. Error: {err}");
+        if let Some(err) = implement_variants!(
+            FlexContainer,
+            WImage,
+            WText,
+            WProgress,
+            Spacing,
+            Alignment,
+            Border
+        ) {
+            diagnostics.report(
+                span,
+                format!(
+                    "Failed to call constructor of {self_name}, trying to defaulting. Error: {err}"
+                ),
+            );
         }
     }
 
@@ -299,7 +391,15 @@ impl GBuilder {
             };
         }
 
-        implement_variants!(FlexContainer, WImage, WText, Spacing, Alignment, Border);
+        implement_variants!(
+            FlexContainer,
+            WImage,
+            WText,
+            WProgress,
+            Spacing,
+            Alignment,
+            Border
+        );
         Ok(self)
     }
 
@@ -319,6 +419,7 @@ impl GBuilder {
         Ok(implement_variants!(
             WImage into Widget,
             WText into Widget,
+            WProgress into Widget,
             FlexContainer into Widget,
 
             Spacing into Spacing,
@@ -338,6 +439,7 @@ impl TryFrom<(&str, &HashMap<&str, GBuilder>)> for GBuilder {
             "FlexContainer" => GBuilder::FlexContainer(GBuilderFlexContainer::new()),
             "Image" => GBuilder::WImage(GBuilderWImage::new()),
             "Text" => GBuilder::WText(GBuilderWText::new()),
+            "Progress" => GBuilder::WProgress(GBuilderWProgress::new()),
             "Spacing" => GBuilder::Spacing(GBuilderSpacing::new()),
             "Alignment" => GBuilder::Alignment(GBuilderAlignment::new()),
             "Border" => GBuilder::Border(GBuilderBorder::new()),
@@ -353,7 +455,8 @@ impl TryFrom<(&str, &HashMap<&str, GBuilder>)> for GBuilder {
 }
 
 #[derive(Debug)]
-struct Property {
+struct Property<'a> {
     name: String,
     value: Value,
+    span: Span<'a>,
 }
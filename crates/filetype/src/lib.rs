@@ -1,6 +1,11 @@
 use std::path::Path;
 
+use converter::Diagnostics;
+use log::warn;
+use parser::Rule;
+use pest::error::{Error, LineColLocation};
 use render::widget::Widget;
+use shared::log_target::LAYOUT;
 
 mod converter;
 mod parser;
@@ -8,7 +13,43 @@ mod parser;
 pub fn parse_layout(path: &Path) -> anyhow::Result<Widget> {
     let data = std::fs::read_to_string(path)?;
     let pairs = parser::parse(&data)?;
-    converter::convert_into_widgets(pairs)
+
+    let mut diagnostics = Diagnostics::default();
+    let widget = converter::convert_into_widgets(pairs, &mut diagnostics)?;
+
+    for issue in diagnostics.into_issues() {
+        let (LineColLocation::Pos((line, column)) | LineColLocation::Span((line, column), _)) =
+            issue.line_col;
+        warn!(
+            target: LAYOUT,
+            "The layout by path {path:?} has issue at {line}:{column}. {}",
+            issue.variant.message()
+        );
+    }
+
+    Ok(widget)
+}
+
+/// Parses and converts the layout by path without rendering it. Returns the issues which are
+/// skipped while converting, e.g. unknown or invalid fields, and the error when the layout can't
+/// be used at all. Each of them points to the place in the file.
+pub fn check_layout(path: &Path) -> anyhow::Result<Vec<String>> {
+    let data = std::fs::read_to_string(path)?;
+    let file_name = path.to_string_lossy();
+    let with_path = |err: anyhow::Error| match err.downcast::<Error<Rule>>() {
+        Ok(err) => err.with_path(&file_name).into(),
+        Err(err) => err,
+    };
+
+    let pairs = parser::parse(&data).map_err(with_path)?;
+    let mut diagnostics = Diagnostics::default();
+    converter::convert_into_widgets(pairs, &mut diagnostics).map_err(with_path)?;
+
+    Ok(diagnostics
+        .into_issues()
+        .into_iter()
+        .map(|issue| issue.with_path(&file_name).to_string())
+        .collect())
 }
 
 #[test]
@@ -48,5 +89,44 @@ fn minimal_type() {
     "#,
     )
     .unwrap();
-    converter::convert_into_widgets(pairs).unwrap();
+    converter::convert_into_widgets(pairs, &mut Diagnostics::default()).unwrap();
+}
+
+#[test]
+fn invalid_field_is_reported_with_position() {
+    let pairs = parser::parse(
+        r#"
+Text(
+    kind = title,
+    unknown_field = 3,
+)"#,
+    )
+    .unwrap();
+
+    let mut diagnostics = Diagnostics::default();
+    converter::convert_into_widgets(pairs, &mut diagnostics).unwrap();
+
+    let issues = diagnostics.into_issues();
+    assert_eq!(issues.len(), 1);
+    assert!(matches!(
+        issues[0].line_col,
+        LineColLocation::Span((4, 5), _)
+    ));
+}
+
+#[test]
+fn unknown_type_is_error_with_position() {
+    let pairs = parser::parse(
+        r#"
+FlexContainer() {
+    Txt(kind = title)
+}"#,
+    )
+    .unwrap();
+
+    let Err(err) = converter::convert_into_widgets(pairs, &mut Diagnostics::default()) else {
+        panic!("The layout with unknown type should be invalid");
+    };
+    let err = err.downcast::<Error<Rule>>().unwrap();
+    assert!(matches!(err.line_col, LineColLocation::Span((3, 5), _)));
 }
@@ -287,7 +287,15 @@ impl Structure {
                         line = quote! { #line.or(#temporary_field_ident.clone()) }
                     }
 
+                    // INFO: the mergeable fields with custom default value should be merged with it
+                    // to fill the unset nested values by default ones instead of type defaults.
                     match &field_info.default {
+                        DefaultAssignment::Expression(expr) if field_info.mergeable => {
+                            line = quote! { #line.map(|val| val.merge(Some(#expr))).unwrap_or_else(|| #expr) }
+                        }
+                        DefaultAssignment::FunctionCall(function_path) if field_info.mergeable => {
+                            line = quote! { #line.map(|val| val.merge(Some(#function_path()))).unwrap_or_else(#function_path) }
+                        }
                         DefaultAssignment::Expression(expr) => {
                             line = quote! { #line.unwrap_or_else(|| #expr) }
                         }
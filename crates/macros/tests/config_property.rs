@@ -135,3 +135,46 @@ fn complex_test() {
         }
     )
 }
+
+#[test]
+fn mergeable_defaults() {
+    #[derive(macros::ConfigProperty, PartialEq, Debug)]
+    #[cfg_prop(name(TomlSample), derive(Default))]
+    struct Sample {
+        #[cfg_prop(
+            use_type(TomlNestedConfig),
+            default(TomlSample::default_nested()),
+            mergeable
+        )]
+        nested: NestedConfig,
+    }
+
+    impl TomlSample {
+        fn default_nested() -> TomlNestedConfig {
+            TomlNestedConfig {
+                value: Some(2.0),
+                value1: Some(true),
+                ..Default::default()
+            }
+        }
+    }
+
+    let sample = TomlSample {
+        nested: Some(TomlNestedConfig {
+            value: Some(5.0),
+            ..Default::default()
+        }),
+    }
+    .unwrap_or_default();
+
+    assert_eq!(
+        sample,
+        Sample {
+            nested: NestedConfig {
+                value: 5.0,
+                value1: true,
+                value2: 0.0
+            }
+        }
+    )
+}
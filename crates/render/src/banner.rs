@@ -0,0 +1,988 @@
+//! The composition of notification banners which doesn't depend on Wayland surfaces.
+//!
+//! The backend draws banners through it and the same functions let to render banners headlessly,
+//! e.g. to preview layouts or to compare them with snapshots in tests.
+
+use std::path::{Path, PathBuf};
+
+use config::{
+    display::{
+        Border, BuiltInLayout, DisplayConfig, ImageProperty, ImageSide, Layout, VerticalAlignment,
+    },
+    general::GeneralConfig,
+    text::TextStyle,
+    theme::{Corner, Theme},
+    Config,
+};
+use dbus::notification::Notification;
+use log::debug;
+use shared::log_target::RENDER;
+
+use crate::{
+    color::{Bgra, Color},
+    drawer::Drawer,
+    error::RenderError,
+    font::FontCollection,
+    image::{Image, ScaledImage},
+    text::TextRect,
+    types::{Offset, RectSize},
+    widget::{
+        current_age_label, Alignment, Direction, Draw, FlexContainerBuilder, Position, WImage,
+        WProgress, WText, WTextKind, Widget, WidgetConfiguration,
+    },
+};
+
+/// Compiles the layout of banner. The custom layout is used when the display config points to
+/// the layout file, otherwise the built-in layout which is selected by name.
+///
+/// Returns the [`RenderError::Layout`] when the custom layout isn't loaded or fails to compile,
+/// so the caller decides whether to fall back to the default layout by `layout_fallback`.
+pub fn compile_layout(
+    custom_layout: Option<Widget>,
+    rect_size: RectSize,
+    configuration: &WidgetConfiguration,
+) -> Result<Widget, RenderError> {
+    let display = configuration.display_config;
+
+    let mut layout = match (&display.layout, custom_layout) {
+        (Layout::BuiltIn(built_in), _) => {
+            return compile_built_in_layout(*built_in, rect_size, configuration)
+        }
+        (Layout::FromPath { .. }, Some(layout)) => layout,
+        (Layout::FromPath { .. }, None) => {
+            return Err(RenderError::Layout {
+                reason: "The custom layout isn't loaded",
+            })
+        }
+    };
+
+    layout.compile(
+        rect_size,
+        &WidgetConfiguration {
+            override_properties: false,
+            ..*configuration
+        },
+    );
+
+    match layout.is_unknown() {
+        true => Err(RenderError::Layout {
+            reason: "The custom layout doesn't fit to the banner",
+        }),
+        false => Ok(layout),
+    }
+}
+
+/// Compiles the built-in layout of banner. The default one is also used as fallback of the custom
+/// layouts.
+///
+/// Returns the [`RenderError::Layout`] when nothing of the layout fits to the banner.
+pub fn compile_built_in_layout(
+    built_in: BuiltInLayout,
+    rect_size: RectSize,
+    configuration: &WidgetConfiguration,
+) -> Result<Widget, RenderError> {
+    let display = configuration.display_config;
+    let border = configuration.colors().border.resolve(&display.border);
+
+    let mut layout = built_in_layout(built_in, display, border, &rect_size);
+    layout.compile(
+        rect_size,
+        &WidgetConfiguration {
+            override_properties: true,
+            ..*configuration
+        },
+    );
+
+    match layout.is_unknown() {
+        true => Err(RenderError::Layout {
+            reason: "The built-in layout doesn't fit to the banner",
+        }),
+        false => Ok(layout),
+    }
+}
+
+/// Returns the banner width for the content width. The banners fit their content between
+/// `min_width` and `max_width` when the latter is set, otherwise they have the fixed `width`.
+/// The width is never zero to keep the surface size valid.
+pub fn fit_width(content_width: usize, general: &GeneralConfig) -> usize {
+    match general.max_width {
+        Some(max_width) => content_width
+            .max(general.min_width as usize)
+            .min(max_width as usize)
+            .max(1),
+        None => general.width as usize,
+    }
+}
+
+/// The urgency emblem which is drawn over the corner of banner after its content.
+#[derive(Clone)]
+pub struct Emblem {
+    image: Image,
+    offset: Offset,
+}
+
+impl Draw for Emblem {
+    fn draw_with_offset(&self, offset: &Offset, drawer: &mut Drawer) {
+        self.image
+            .draw_with_offset(&(self.offset + *offset), drawer);
+    }
+}
+
+/// The emblem icon of notification which is looked up and decoded once. It's loaded again only
+/// when the emblem, icon theme, banner size or output scale is changed, so the redraws of banner
+/// don't search the icon theme.
+pub struct CachedEmblem {
+    key: Option<EmblemKey>,
+    /// The decoded icon, `None` when it isn't found or the path to icon which cannot be decoded.
+    icon: Result<Option<Image>, PathBuf>,
+}
+
+#[derive(PartialEq)]
+struct EmblemKey {
+    icon: String,
+    size: u16,
+    icon_theme: String,
+    rect_size: RectSize,
+    output_scale: u32,
+}
+
+impl Default for CachedEmblem {
+    fn default() -> Self {
+        Self {
+            key: None,
+            icon: Ok(None),
+        }
+    }
+}
+
+impl CachedEmblem {
+    fn icon(
+        &mut self,
+        key: EmblemKey,
+        load: impl FnOnce(&EmblemKey) -> Result<Option<Image>, PathBuf>,
+    ) -> Result<Option<Image>, RenderError> {
+        if self.key.as_ref() != Some(&key) {
+            self.icon = load(&key);
+            self.key = Some(key);
+        }
+
+        self.icon
+            .clone()
+            .map_err(|path| RenderError::ImageDecode { path })
+    }
+}
+
+/// Loads the emblem of notification urgency from the theme. The emblem is either the path to
+/// image or the icon name which is looked up in the icon theme of display config. The loaded
+/// icon is kept in the `cached_emblem` for the next draws.
+///
+/// Returns `None` when the theme has no emblem or its icon isn't found and the
+/// [`RenderError::ImageDecode`] when the found icon cannot be loaded.
+pub fn compile_emblem(
+    rect_size: &RectSize,
+    configuration: &WidgetConfiguration,
+    cached_emblem: &mut CachedEmblem,
+) -> Result<Option<Emblem>, RenderError> {
+    let colors = configuration.colors();
+    let Some(emblem) = colors.emblem.as_ref() else {
+        return Ok(None);
+    };
+    let display = configuration.display_config;
+
+    let key = EmblemKey {
+        icon: emblem.icon.clone(),
+        size: emblem.size,
+        icon_theme: display.icons.theme.clone(),
+        rect_size: rect_size.clone(),
+        output_scale: configuration.output_scale,
+    };
+    let Some(image) = cached_emblem.icon(key, |key| load_emblem_icon(key, configuration))? else {
+        return Ok(None);
+    };
+    let (Some(width), Some(height)) = (image.width(), image.height()) else {
+        return Ok(None);
+    };
+
+    // INFO: the emblem is placed inside the border to not cover it
+    let inset = colors.border.resolve(&display.border).size as usize;
+    let right = rect_size.width.saturating_sub(width + inset);
+    let bottom = rect_size.height.saturating_sub(height + inset);
+    let offset = match emblem.corner {
+        Corner::TopLeft => Offset::new(inset, inset),
+        Corner::TopRight => Offset::new(right, inset),
+        Corner::BottomLeft => Offset::new(inset, bottom),
+        Corner::BottomRight => Offset::new(right, bottom),
+    };
+
+    Ok(Some(Emblem { image, offset }))
+}
+
+fn load_emblem_icon(
+    key: &EmblemKey,
+    configuration: &WidgetConfiguration,
+) -> Result<Option<Image>, PathBuf> {
+    let icon_path = if Path::new(&key.icon).is_absolute() {
+        PathBuf::from(&key.icon)
+    } else {
+        let icon_path = freedesktop_icons::lookup(&key.icon)
+            .with_size(key.size)
+            .with_theme(&key.icon_theme)
+            .find();
+        match icon_path {
+            Some(icon_path) => icon_path,
+            None => return Ok(None),
+        }
+    };
+
+    let property = ImageProperty {
+        max_size: key.size,
+        ..Default::default()
+    };
+    let image = Image::from_path(&icon_path, &property, &key.rect_size, key.output_scale);
+    if image.width().is_none() || image.height().is_none() {
+        return Err(icon_path);
+    }
+
+    debug!(
+        target: RENDER,
+        "Banner (id={}): Loaded emblem from {icon_path:?}",
+        configuration.notification.id
+    );
+
+    Ok(Some(image))
+}
+
+/// The label at the bottom right corner of banner which body doesn't fit. The banner is expanded
+/// to the whole body by click on it when `expandable_body` is enabled.
+pub struct ShowMore {
+    text: TextRect,
+    background: Color,
+    offset: Offset,
+    size: RectSize,
+}
+
+impl ShowMore {
+    const LABEL: &'static str = "Show more";
+
+    /// Checks whether the point of banner is over the label.
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.offset.x..self.offset.x + self.size.width).contains(&x)
+            && (self.offset.y..self.offset.y + self.size.height).contains(&y)
+    }
+}
+
+impl Draw for ShowMore {
+    fn draw_with_offset(&self, offset: &Offset, drawer: &mut Drawer) {
+        // INFO: the label covers the end of body under it by the banner background
+        let mut subdrawer = Drawer::new(self.background.clone(), self.size.clone());
+        self.text.draw(&mut subdrawer);
+        drawer.draw_area(&(self.offset + *offset), subdrawer);
+    }
+}
+
+/// Compiles the "Show more" label by the font size of body. It's placed inside the border and
+/// padding of banner. Returns `None` when the label doesn't fit.
+pub fn compile_show_more(
+    rect_size: &RectSize,
+    configuration: &WidgetConfiguration,
+) -> Option<ShowMore> {
+    let display = configuration.display_config;
+    let colors = configuration.colors();
+
+    let mut text = TextRect::from_str(
+        ShowMore::LABEL,
+        configuration
+            .font_collection
+            .px_size(display.body.font_size),
+        &TextStyle::Italic,
+        configuration.font_collection,
+    );
+    text.set_foreground(Bgra::from(&colors.foreground));
+    text.compile(rect_size.clone());
+    if text.is_empty() || text.is_truncated() {
+        return None;
+    }
+
+    let size = RectSize::new(text.content_width(), text.height());
+    let border = colors.border.resolve(&display.border).size as usize;
+    let right = rect_size
+        .width
+        .saturating_sub(size.width + border + display.padding.right() as usize);
+    let bottom = rect_size
+        .height
+        .saturating_sub(size.height + border + display.padding.bottom() as usize);
+
+    Some(ShowMore {
+        text,
+        background: colors.background.clone().into(),
+        offset: Offset::new(right, bottom),
+        size,
+    })
+}
+
+/// The drawn banner. The images which are scaled by the compositor aren't drawn into the data but
+/// are placed over it.
+pub struct Frame {
+    pub data: Vec<u8>,
+    pub scaled_images: Vec<ScaledImage>,
+}
+
+/// Draws the compiled layout and the emblem with the "Show more" label over it into the BGRA
+/// buffer with premultiplied alpha which fits to the `ARGB8888` format of Wayland shared memory.
+///
+/// Returns the [`RenderError::Buffer`] when the banner has no area to draw into.
+pub fn render_layout(
+    layout: &Widget,
+    emblem: Option<&Emblem>,
+    show_more: Option<&ShowMore>,
+    rect_size: RectSize,
+) -> Result<Frame, RenderError> {
+    if rect_size.area() == 0 {
+        return Err(RenderError::Buffer {
+            width: rect_size.width,
+            height: rect_size.height,
+        });
+    }
+
+    let mut drawer = Drawer::new(Color::Fill(Bgra::new()), rect_size);
+    layout.draw(&mut drawer);
+    if let Some(show_more) = show_more {
+        show_more.draw(&mut drawer);
+    }
+    if let Some(emblem) = emblem {
+        emblem.draw(&mut drawer);
+    }
+
+    Ok(Frame {
+        scaled_images: drawer.take_scaled_images(),
+        data: drawer.into(),
+    })
+}
+
+/// Renders the banner of notification with the given theme without Wayland. Returns the width,
+/// height and RGBA data with premultiplied alpha.
+///
+/// The custom layouts aren't loaded here, so the default layout is used instead of them when
+/// `layout_fallback` is enabled, otherwise the [`RenderError::Layout`] is returned.
+pub fn render_banner_to_rgba(
+    notification: &Notification,
+    config: &Config,
+    theme: &Theme,
+    font_collection: &FontCollection,
+) -> Result<(usize, usize, Vec<u8>), RenderError> {
+    let general = config.general();
+    let display_config = config.display_by_notification(notification);
+    let age_label = display_config
+        .show_timestamp
+        .then(|| current_age_label(notification.created_at));
+    let configuration = WidgetConfiguration {
+        notification,
+        font_collection,
+        theme,
+        display_config,
+        override_properties: true,
+        highlight: None,
+        hovered: false,
+        viewport_scaling: false,
+        output_scale: 1,
+        expanded: false,
+        memory_pressure: false,
+        age_label: age_label.as_deref(),
+    };
+
+    let width = match general.max_width {
+        Some(max_width) => fit_width(
+            compile_headless_layout(
+                RectSize::new(max_width as usize, general.height as usize),
+                &configuration,
+            )?
+            .content_width(),
+            general,
+        ),
+        None => general.width as usize,
+    };
+    let rect_size = RectSize::new(width, general.height as usize);
+
+    let data = render_to_rgba(&configuration, rect_size.clone())?;
+
+    Ok((rect_size.width, rect_size.height, data))
+}
+
+fn compile_headless_layout(
+    rect_size: RectSize,
+    configuration: &WidgetConfiguration,
+) -> Result<Widget, RenderError> {
+    match compile_layout(None, rect_size.clone(), configuration) {
+        Err(RenderError::Layout { .. }) if configuration.display_config.layout_fallback => {
+            compile_built_in_layout(BuiltInLayout::default(), rect_size, configuration)
+        }
+        result => result,
+    }
+}
+
+fn render_to_rgba(
+    configuration: &WidgetConfiguration,
+    rect_size: RectSize,
+) -> Result<Vec<u8>, RenderError> {
+    let layout = compile_headless_layout(rect_size.clone(), configuration)?;
+    let emblem = compile_emblem(&rect_size, configuration, &mut CachedEmblem::default())?;
+
+    let mut data = render_layout(&layout, emblem.as_ref(), None, rect_size)?.data;
+    data.chunks_exact_mut(4).for_each(|pixel| pixel.swap(0, 2));
+    Ok(data)
+}
+
+/// Builds the widget tree of built-in layout. The texts are placed into the column next to the
+/// image whose alignment and content depend on the layout.
+fn built_in_layout(
+    built_in: BuiltInLayout,
+    display_config: &DisplayConfig,
+    border: Border,
+    rect_size: &RectSize,
+) -> Widget {
+    let full_texts = || {
+        vec![
+            WText::new(WTextKind::Title).into(),
+            WText::new(WTextKind::Body).into(),
+            WProgress::new().into(),
+            WText::new(WTextKind::Age).into(),
+        ]
+    };
+
+    let (texts_alignment, texts) = match built_in {
+        BuiltInLayout::Classic => (Position::Center, full_texts()),
+        BuiltInLayout::Compact => (
+            Position::Start,
+            vec![WText::new(WTextKind::Title).into(), WProgress::new().into()],
+        ),
+        BuiltInLayout::ImageLeft | BuiltInLayout::ImageRight => (Position::Start, full_texts()),
+    };
+
+    let image = &display_config.image;
+    let image_on_right = match built_in {
+        BuiltInLayout::ImageLeft => false,
+        BuiltInLayout::ImageRight => true,
+        BuiltInLayout::Classic | BuiltInLayout::Compact => image.alignment.side == ImageSide::Right,
+    };
+
+    let mut texts = FlexContainerBuilder::default()
+        .spacing(Default::default())
+        .border(Border::default())
+        .direction(Direction::Vertical)
+        .alignment(Alignment::new(texts_alignment, Position::Center))
+        .transparent_background(true)
+        .children(texts);
+
+    if image_on_right {
+        // INFO: the children are compiled in order, so the texts leave the room for image
+        let image_width = image.max_size as usize + image.margin.horizontal() as usize;
+        let insets = display_config.padding.horizontal() as usize + border.size as usize * 2;
+        texts = texts.max_width(rect_size.width.saturating_sub(image_width + insets));
+    }
+
+    let texts = build_container(texts);
+    let children = if image_on_right {
+        vec![texts, WImage::new().into()]
+    } else {
+        vec![WImage::new().into(), texts]
+    };
+
+    // INFO: the texts container takes the whole height, so the vertical alignment of banner
+    // only moves the image
+    let image_alignment = match image.alignment.vertical {
+        VerticalAlignment::Top => Position::Start,
+        VerticalAlignment::Center => Position::Center,
+        VerticalAlignment::Bottom => Position::End,
+    };
+
+    build_container(
+        FlexContainerBuilder::default()
+            .spacing(display_config.padding.clone())
+            .border(border)
+            .direction(Direction::Horizontal)
+            .alignment(Alignment::new(Position::Start, image_alignment))
+            .children(children),
+    )
+}
+
+fn build_container(builder: FlexContainerBuilder) -> Widget {
+    builder
+        .build()
+        .expect("The built-in container should have all required fields")
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::Path};
+
+    use config::{
+        display::{
+            Border, BuiltInLayout, DisplayConfig, ImageAlignment, ImageSide, Layout,
+            TomlDisplayConfig, VerticalAlignment,
+        },
+        general::{GeneralConfig, TomlGeneralConfig},
+        theme::{Corner, Emblem, Theme},
+    };
+    use dbus::{
+        image::ImageData,
+        notification::{Hints, Notification},
+        text::{TagKind, Text},
+    };
+
+    use crate::{
+        font::FontCollection,
+        types::RectSize,
+        widget::{Widget, WidgetConfiguration},
+    };
+
+    use crate::error::RenderError;
+
+    use super::{
+        compile_built_in_layout, compile_emblem, compile_layout, compile_show_more, fit_width,
+        render_layout, render_to_rgba, CachedEmblem,
+    };
+
+    /// Compares the rendered banner with the PNG snapshot in the `snapshots` directory. Run
+    /// tests with the `UPDATE_SNAPSHOTS` environment variable to write the snapshots anew.
+    fn assert_snapshot(name: &str, rect_size: RectSize, data: Vec<u8>) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("snapshots")
+            .join(format!("{name}.png"));
+        let image =
+            image::RgbaImage::from_raw(rect_size.width as u32, rect_size.height as u32, data)
+                .expect("The rendered data should fit to the banner size");
+
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            image.save(&path).expect("The snapshot should be saved");
+            return;
+        }
+
+        let snapshot = image::open(&path)
+            .unwrap_or_else(|error| panic!("Failed to open snapshot {path:?}. Error: {error}"))
+            .into_rgba8();
+        assert!(
+            snapshot == image,
+            "The banner differs from snapshot {path:?}, check it and run tests with \
+            UPDATE_SNAPSHOTS=1 to update it"
+        );
+    }
+
+    fn notification(hints: Hints) -> Notification {
+        Notification {
+            hints,
+            ..Notification::test_default()
+        }
+    }
+
+    #[test]
+    fn progress_banner_snapshot() {
+        // INFO: the banner has no text, so the snapshot doesn't depend on the installed fonts
+        let mut hints = Hints::from(HashMap::new());
+        hints.value = Some(40);
+        let notification = notification(hints);
+
+        let mut display_config = DisplayConfig::from(TomlDisplayConfig::default());
+        display_config.border = Border {
+            size: 2,
+            radius: 10,
+        };
+        let font_collection =
+            FontCollection::load_sans().expect("The font collection should be loaded");
+        let rect_size = RectSize::new(300, 150);
+
+        let data = render_to_rgba(
+            &WidgetConfiguration::test_default(
+                &notification,
+                &font_collection,
+                &Theme::default(),
+                &display_config,
+            ),
+            rect_size.clone(),
+        )
+        .expect("The banner should be rendered");
+        assert_snapshot("progress_banner", rect_size, data);
+    }
+
+    /// Compiles the built-in layout for the notification with the summary, body, progress and
+    /// image.
+    fn compile_built_in(
+        built_in: BuiltInLayout,
+        alignment: ImageAlignment,
+        font_collection: &FontCollection,
+    ) -> Widget {
+        let mut hints = Hints::from(HashMap::new());
+        hints.value = Some(40);
+        hints.image_data = Some(ImageData {
+            width: 32,
+            height: 32,
+            rowstride: 32 * 3,
+            has_alpha: false,
+            bits_per_sample: 8,
+            channels: 3,
+            data: vec![127; 32 * 32 * 3],
+        });
+        let notification = notification(hints)
+            .with_summary("The summary of notification")
+            .with_body("The body of notification");
+
+        let mut display_config = DisplayConfig::from(TomlDisplayConfig::default());
+        display_config.layout = Layout::BuiltIn(built_in);
+        display_config.image.alignment = alignment;
+
+        compile_layout(
+            None,
+            RectSize::new(300, 150),
+            &WidgetConfiguration::test_default(
+                &notification,
+                font_collection,
+                &Theme::default(),
+                &display_config,
+            ),
+        )
+        .expect("The built-in layout should be compiled")
+    }
+
+    /// Lists the compiled widgets of built-in layout in order where the texts are listed by kind.
+    fn widget_kinds(widget: &Widget) -> Vec<String> {
+        match widget {
+            Widget::Image(_) => vec!["image".to_string()],
+            Widget::Text(text) => vec![text.kind().to_string()],
+            Widget::Progress(_) => vec!["progress".to_string()],
+            Widget::FlexContainer(container) => {
+                container.children().iter().flat_map(widget_kinds).collect()
+            }
+            Widget::Unknown => vec![],
+        }
+    }
+
+    #[test]
+    fn built_in_layouts_are_compiled() {
+        let font_collection =
+            FontCollection::load_sans().expect("The font collection should be loaded");
+        let image_on_right = ImageAlignment {
+            vertical: VerticalAlignment::Top,
+            side: ImageSide::Right,
+        };
+
+        let kinds = |built_in, alignment| {
+            let layout = compile_built_in(built_in, alignment, &font_collection);
+            assert!(layout.content_width() <= 300);
+            widget_kinds(&layout)
+        };
+
+        for (built_in, alignment, expected) in [
+            (
+                BuiltInLayout::Classic,
+                ImageAlignment::default(),
+                ["image", "title", "body", "progress"].as_slice(),
+            ),
+            (
+                BuiltInLayout::Classic,
+                image_on_right,
+                &["title", "body", "progress", "image"],
+            ),
+            (
+                BuiltInLayout::Compact,
+                ImageAlignment::default(),
+                &["image", "title", "progress"],
+            ),
+            (
+                BuiltInLayout::Compact,
+                image_on_right,
+                &["title", "progress", "image"],
+            ),
+            (
+                BuiltInLayout::ImageLeft,
+                image_on_right,
+                &["image", "title", "body", "progress"],
+            ),
+            (
+                BuiltInLayout::ImageRight,
+                ImageAlignment::default(),
+                &["title", "body", "progress", "image"],
+            ),
+        ] {
+            assert_eq!(
+                kinds(built_in, alignment),
+                expected,
+                "{built_in:?} layout with {alignment:?} image alignment"
+            );
+        }
+    }
+
+    #[test]
+    fn built_in_layouts_place_image_by_alignment() {
+        let font_collection =
+            FontCollection::load_sans().expect("The font collection should be loaded");
+
+        // INFO: returns the offsets of image and texts in the compiled built-in layout
+        let offsets = |built_in, vertical, side| {
+            let layout = compile_built_in(
+                built_in,
+                ImageAlignment { vertical, side },
+                &font_collection,
+            );
+            let Widget::FlexContainer(container) = &layout else {
+                panic!("{built_in:?} layout should be the flex container");
+            };
+
+            let placed_children = container.placed_children();
+            let find = |predicate: fn(&Widget) -> bool| {
+                placed_children
+                    .iter()
+                    .find(|(child, _)| predicate(child))
+                    .map(|(_, offset)| *offset)
+                    .expect("The built-in layout should contain the image and texts")
+            };
+            (
+                find(|child| matches!(child, Widget::Image(_))),
+                find(|child| matches!(child, Widget::FlexContainer(_))),
+            )
+        };
+
+        for built_in in [
+            BuiltInLayout::Classic,
+            BuiltInLayout::Compact,
+            BuiltInLayout::ImageLeft,
+            BuiltInLayout::ImageRight,
+        ] {
+            let (top_image, _) = offsets(built_in, VerticalAlignment::Top, ImageSide::Left);
+            let (bottom_image, _) = offsets(built_in, VerticalAlignment::Bottom, ImageSide::Left);
+            assert!(
+                top_image.y < bottom_image.y,
+                "{built_in:?} layout doesn't move the image vertically"
+            );
+        }
+
+        for built_in in [BuiltInLayout::Classic, BuiltInLayout::Compact] {
+            let (image, texts) = offsets(built_in, VerticalAlignment::Center, ImageSide::Left);
+            assert!(image.x < texts.x, "{built_in:?} layout with left image");
+
+            let (image, texts) = offsets(built_in, VerticalAlignment::Center, ImageSide::Right);
+            assert!(texts.x < image.x, "{built_in:?} layout with right image");
+        }
+    }
+
+    #[test]
+    fn banner_width_fits_content() {
+        let mut general = GeneralConfig::from(TomlGeneralConfig::default());
+        general.min_width = 100;
+        general.max_width = Some(600);
+
+        let display_config = DisplayConfig::from(TomlDisplayConfig::default());
+        let theme = Theme::default();
+        let font_collection =
+            FontCollection::load_sans().expect("The font collection should be loaded");
+
+        let fitted_width = |summary: &str, general: &GeneralConfig| {
+            let mut notification = notification(Hints::from(HashMap::new()));
+            notification.summary = summary.to_string();
+            let content_width = compile_layout(
+                None,
+                RectSize::new(600, 150),
+                &WidgetConfiguration::test_default(
+                    &notification,
+                    &font_collection,
+                    &theme,
+                    &display_config,
+                ),
+            )
+            .expect("The default layout should be compiled")
+            .content_width();
+            fit_width(content_width, general)
+        };
+
+        let short_width = fitted_width("Hi", &general);
+        let long_width = fitted_width(
+            "The notification with a rather long summary to fit",
+            &general,
+        );
+        assert!((100..=600).contains(&short_width));
+        assert!((100..=600).contains(&long_width));
+        assert!(short_width < long_width);
+
+        // INFO: the text which doesn't fit is reflowed at the maximum width, so the widest line
+        // can be a bit narrower than it
+        let longest_width = fitted_width(
+            "The summary which is much longer than the maximum width of banner, so it's reflowed \
+            into several lines",
+            &general,
+        );
+        assert!((long_width..=600).contains(&longest_width));
+
+        general.max_width = None;
+        assert_eq!(fitted_width("Hi", &general), general.width as usize);
+    }
+
+    #[test]
+    fn truncated_body_is_expanded() {
+        let mut display_config = DisplayConfig::from(TomlDisplayConfig::default());
+        display_config.body.max_lines = 1;
+        display_config.expandable_body = true;
+        let theme = Theme::default();
+        let font_collection =
+            FontCollection::load_sans().expect("The font collection should be loaded");
+        let mut notification = notification(Hints::from(HashMap::new()));
+        notification.body = Text::parse(
+            "The body which is long enough to be wrapped into several lines, so it doesn't fit \
+            into one line of banner and is truncated"
+                .to_string(),
+            &TagKind::all(),
+        );
+
+        let configuration = |expanded| WidgetConfiguration {
+            expanded,
+            ..WidgetConfiguration::test_default(
+                &notification,
+                &font_collection,
+                &theme,
+                &display_config,
+            )
+        };
+        let rect_size = RectSize::new(300, 150);
+
+        let layout = compile_layout(None, rect_size.clone(), &configuration(false))
+            .expect("The default layout should be compiled");
+        assert!(layout.is_body_truncated());
+
+        let show_more = compile_show_more(&rect_size, &configuration(false))
+            .expect("The label should fit into the banner");
+        assert!(show_more.contains(
+            show_more.offset.x + show_more.size.width / 2,
+            show_more.offset.y + show_more.size.height / 2
+        ));
+        assert!(!show_more.contains(0, 0));
+        assert!(show_more.offset.y + show_more.size.height <= rect_size.height);
+
+        let expanded_layout = compile_layout(None, RectSize::new(300, 1000), &configuration(true))
+            .expect("The default layout should be compiled");
+        assert!(!expanded_layout.is_body_truncated());
+        assert!(expanded_layout.content_height() > layout.content_height());
+    }
+
+    #[test]
+    fn emblem_is_placed_inside_border() {
+        let icon_path = std::env::temp_dir().join("noti-test-emblem.png");
+        image::RgbaImage::from_pixel(8, 8, image::Rgba([255, 0, 0, 255]))
+            .save(&icon_path)
+            .expect("The emblem icon should be saved");
+
+        let mut theme = Theme::default();
+        theme.normal.emblem = Some(Emblem {
+            icon: icon_path.to_string_lossy().into_owned(),
+            corner: Corner::BottomRight,
+            size: 8,
+        });
+        let mut display_config = DisplayConfig::from(TomlDisplayConfig::default());
+        display_config.border = Border { size: 2, radius: 0 };
+        let font_collection =
+            FontCollection::load_sans().expect("The font collection should be loaded");
+        let notification = notification(Hints::from(HashMap::new()));
+        let configuration = WidgetConfiguration::test_default(
+            &notification,
+            &font_collection,
+            &theme,
+            &display_config,
+        );
+        let mut cached_emblem = CachedEmblem::default();
+
+        let emblem = compile_emblem(&RectSize::new(300, 150), &configuration, &mut cached_emblem)
+            .ok()
+            .flatten()
+            .expect("The emblem should be loaded");
+        std::fs::remove_file(icon_path).unwrap();
+
+        assert_eq!((emblem.offset.x, emblem.offset.y), (290, 140));
+        assert_eq!(emblem.image.width(), Some(8));
+
+        // INFO: the cached icon is drawn again without loading it from the removed file
+        assert!(matches!(
+            compile_emblem(&RectSize::new(300, 150), &configuration, &mut cached_emblem),
+            Ok(Some(_))
+        ));
+        assert!(matches!(
+            compile_emblem(&RectSize::new(300, 100), &configuration, &mut cached_emblem),
+            Err(RenderError::ImageDecode { .. })
+        ));
+    }
+
+    #[test]
+    fn render_errors_by_kind() {
+        let icon_path = std::env::temp_dir().join("noti-test-broken-emblem.png");
+        std::fs::write(&icon_path, b"not an image").expect("The emblem icon should be saved");
+
+        let mut theme = Theme::default();
+        theme.normal.emblem = Some(Emblem {
+            icon: icon_path.to_string_lossy().into_owned(),
+            corner: Corner::BottomRight,
+            size: 8,
+        });
+        let mut display_config = DisplayConfig::from(TomlDisplayConfig::default());
+        display_config.layout = Layout::from("missing.noti".to_string());
+        display_config.layout_fallback = false;
+        let font_collection =
+            FontCollection::load_sans().expect("The font collection should be loaded");
+        let notification = notification(Hints::from(HashMap::new())).with_summary("Summary");
+        let rect_size = RectSize::new(300, 150);
+        let configuration = WidgetConfiguration {
+            override_properties: false,
+            ..WidgetConfiguration::test_default(
+                &notification,
+                &font_collection,
+                &theme,
+                &display_config,
+            )
+        };
+
+        let emblem = compile_emblem(&rect_size, &configuration, &mut CachedEmblem::default());
+        std::fs::remove_file(icon_path).unwrap();
+        assert!(matches!(emblem, Err(RenderError::ImageDecode { .. })));
+
+        assert!(matches!(
+            compile_layout(None, rect_size.clone(), &configuration),
+            Err(RenderError::Layout { .. })
+        ));
+        assert!(matches!(
+            render_to_rgba(&configuration, rect_size),
+            Err(RenderError::Layout { .. })
+        ));
+
+        display_config.layout_fallback = true;
+        let default_theme = Theme::default();
+        let configuration = WidgetConfiguration {
+            override_properties: false,
+            ..WidgetConfiguration::test_default(
+                &notification,
+                &font_collection,
+                &default_theme,
+                &display_config,
+            )
+        };
+        assert!(render_to_rgba(&configuration, RectSize::new(300, 150)).is_ok());
+
+        let layout = compile_layout(None, RectSize::new(300, 150), &configuration);
+        assert!(matches!(layout, Err(RenderError::Layout { .. })));
+        // INFO: nothing of the default layout fits to the banner
+        assert!(matches!(
+            compile_built_in_layout(
+                BuiltInLayout::default(),
+                RectSize::new(1, 1),
+                &configuration
+            ),
+            Err(RenderError::Layout { .. })
+        ));
+        assert!(matches!(
+            render_layout(
+                &crate::widget::Widget::Unknown,
+                None,
+                None,
+                RectSize::new(300, 0)
+            ),
+            Err(RenderError::Buffer {
+                width: 300,
+                height: 0
+            })
+        ));
+    }
+}
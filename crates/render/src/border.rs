@@ -1,6 +1,7 @@
 use crate::color::{Bgra, Color};
 use derive_builder::Builder;
 use log::warn;
+use shared::log_target::RENDER;
 
 use crate::drawer::Drawer;
 
@@ -285,7 +286,7 @@ impl Draw for Border {
     fn draw_with_offset(&self, offset: &Offset, drawer: &mut Drawer) {
         let Some(corner_size) = self.corner_coverage.as_ref().map(|corner| corner.len()) else {
             if !self.compiled {
-                warn!("Border: Not compiled, refused to draw itself");
+                warn!(target: RENDER, "Border: Not compiled, refused to draw itself");
             }
             return;
         };
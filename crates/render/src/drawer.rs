@@ -1,5 +1,6 @@
 use crate::{
     color::{Bgra, Color},
+    image::ScaledImage,
     types::{Offset, RectSize},
     widget::{Coverage, DrawColor},
 };
@@ -7,6 +8,8 @@ use crate::{
 pub struct Drawer {
     size: RectSize,
     data: Vec<Bgra>,
+    /// The images which are placed over the drawn data and scaled by the compositor.
+    scaled_images: Vec<ScaledImage>,
 }
 
 impl Drawer {
@@ -30,10 +33,38 @@ impl Drawer {
                 }
             };
 
-        Self { data, size }
+        Self {
+            data,
+            size,
+            scaled_images: vec![],
+        }
+    }
+
+    pub fn place_scaled_image(&mut self, scaled_image: ScaledImage) {
+        self.scaled_images.push(scaled_image);
+    }
+
+    /// Takes the images which are placed over the drawn data.
+    pub fn take_scaled_images(&mut self) -> Vec<ScaledImage> {
+        std::mem::take(&mut self.scaled_images)
     }
 
-    pub fn draw_area(&mut self, offset: &Offset, subdrawer: Drawer) {
+    fn place_scaled_images_of(&mut self, offset: &Offset, subdrawer: &mut Drawer) {
+        self.scaled_images
+            .extend(
+                subdrawer
+                    .scaled_images
+                    .drain(..)
+                    .map(|scaled_image| ScaledImage {
+                        offset: scaled_image.offset + *offset,
+                        ..scaled_image
+                    }),
+            );
+    }
+
+    pub fn draw_area(&mut self, offset: &Offset, mut subdrawer: Drawer) {
+        self.place_scaled_images_of(offset, &mut subdrawer);
+
         for x in 0..subdrawer.size.width {
             for y in 0..subdrawer.size.height {
                 let color = subdrawer.get_color_at(x, y);
@@ -51,6 +82,8 @@ impl Drawer {
     }
 
     pub fn draw_area_optimized(&mut self, offset: &Offset, mut subdrawer: Drawer) {
+        self.place_scaled_images_of(offset, &mut subdrawer);
+
         // INFO: this is specific code and it may be hard to read because the main goal is
         // drawing optimization. Previously just single loop was used but after optimization
         // we reachd x3 drawing speed. So I've [jarkz] decided to only make it more readable
@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+
+/// The errors of rendering which let the caller decide what to do with the banner, e.g. to
+/// drop it, to fall back to the default layout or to retry the draw later.
+#[derive(Debug, derive_more::Display)]
+pub enum RenderError {
+    #[display("Failed to load fonts by name {font_name}. Error: {source}")]
+    FontLoad {
+        font_name: String,
+        source: anyhow::Error,
+    },
+    #[display("Failed to decode the image at {path:?}")]
+    ImageDecode { path: PathBuf },
+    #[display("Failed to compile the layout. {reason}")]
+    Layout { reason: &'static str },
+    #[display("Cannot allocate the buffer of size {width}x{height}")]
+    Buffer { width: usize, height: usize },
+}
+
+impl std::error::Error for RenderError {}
@@ -1,6 +1,7 @@
 use ab_glyph::{point, Font as AbGlyphFont, OutlinedGlyph, ScaleFont};
 use derive_more::Display;
 use log::{debug, error, info, warn};
+use shared::log_target::RENDER;
 use std::{
     collections::HashMap,
     ffi::c_void,
@@ -9,43 +10,60 @@ use std::{
     process::Command,
 };
 
-use config::text::TextStyle;
+use config::{general::FontHinting, text::TextStyle};
 use dbus::text::EntityKind;
 
-use crate::drawer::Drawer;
+use crate::{drawer::Drawer, error::RenderError};
 
 use super::{
     color::Bgra,
     image::Image,
+    types::Offset,
     widget::{Coverage, Draw, DrawColor},
 };
 
 pub struct FontCollection {
     font_name: String,
+    emoji_font_name: String,
+    monochrome_emoji: bool,
+    emoji_placeholder: Option<char>,
+    hinting: FontHinting,
+    font_size_bounds: (u8, u8),
     font_map: HashMap<FontStyle, Font>,
     math_font: Option<MathFont>,
     emoji_font: Option<EmojiFont>,
+    monospace_font_name: String,
+    monospace_font: Option<Font>,
 }
 
 impl FontCollection {
     const ELLIPSIS: char = '…';
     const ACCEPTED_STYLES: [&'static str; 3] = ["Regular", "Bold", "Italic"];
-
-    pub fn update_by_font_name(&mut self, font_name: &str) -> anyhow::Result<()> {
-        if self.font_name == font_name {
+    const FONT_SIZE_BOUNDS: (u8, u8) = (6, 96);
+
+    pub fn update_by_font_name(
+        &mut self,
+        font_name: &str,
+        emoji_font_name: &str,
+    ) -> Result<(), RenderError> {
+        if self.font_name == font_name && self.emoji_font_name == emoji_font_name {
             return Ok(());
         }
 
-        *self = Self::load_by_font_name(font_name)?;
+        *self = Self::load_by_font_name(font_name, emoji_font_name)?;
         Ok(())
     }
 
-    pub fn load_by_font_name(font_name: &str) -> anyhow::Result<Self> {
-        debug!("Font: Trying load font by name {font_name}");
+    pub fn load_by_font_name(font_name: &str, emoji_font_name: &str) -> Result<Self, RenderError> {
+        debug!(target: RENDER, "Font: Trying load font by name {font_name}");
 
         let output: String = Command::new("fc-list")
             .args([font_name, "--format", "%{file}:%{style}\n"])
-            .output()?
+            .output()
+            .map_err(|err| RenderError::FontLoad {
+                font_name: font_name.to_owned(),
+                source: err.into(),
+            })?
             .stdout
             .into_iter()
             .map(|data| data as char)
@@ -68,7 +86,10 @@ impl FontCollection {
             let font = match Font::try_read(filepath, styles) {
                 Ok(font) => font,
                 Err(err) => {
-                    error!("Failed to read or parse font at {filepath}. Error: {err}");
+                    error!(
+                        target: RENDER,
+                        "Failed to read or parse font at {filepath}. Error: {err}"
+                    );
                     continue;
                 }
             };
@@ -76,51 +97,185 @@ impl FontCollection {
             font_map.insert(font.style.clone(), font);
         }
 
-        info!("Font: Loaded fonts by name {font_name}");
+        info!(target: RENDER, "Font: Loaded fonts by name {font_name}");
 
         let math_font = match MathFont::try_create() {
             Ok(emoji) => Some(emoji),
             Err(err) => {
-                warn!("Font: Not found the 'NotoSansMath' font, math symbols will not be displayed. Error: {err}");
+                warn!(
+                    target: RENDER,
+                    "Font: Not found the 'NotoSansMath' font, math symbols will not be displayed. Error: {err}"
+                );
                 None
             }
         };
 
-        let emoji_font = match EmojiFont::try_create() {
+        let emoji_font = match EmojiFont::try_create(emoji_font_name) {
             Ok(emoji) => Some(emoji),
             Err(err) => {
-                warn!("Font: Not found the 'NotoColorEmoj' font, emoji will not be displayed. Error: {err}");
+                warn!(
+                    target: RENDER,
+                    "Font: Not found the '{emoji_font_name}' font, emoji will be displayed by placeholder. Error: {err}"
+                );
                 None
             }
         };
 
         Ok(Self {
             font_name: font_name.to_owned(),
+            emoji_font_name: emoji_font_name.to_owned(),
+            monochrome_emoji: false,
+            emoji_placeholder: None,
+            hinting: FontHinting::default(),
+            font_size_bounds: Self::FONT_SIZE_BOUNDS,
             font_map,
             math_font,
             emoji_font,
+            monospace_font_name: String::new(),
+            monospace_font: None,
+        })
+    }
+
+    /// Creates the collection from the single font which matches to the generic "sans" family and
+    /// is used as the regular font. It lets to render text in tests without depending on the
+    /// names of installed fonts.
+    #[cfg(test)]
+    pub(crate) fn load_sans() -> anyhow::Result<Self> {
+        let filepath: String = Command::new("fc-match")
+            .args(["sans", "--format", "%{file}"])
+            .output()?
+            .stdout
+            .into_iter()
+            .map(|byte| byte as char)
+            .collect();
+        let font = Font::try_read(&filepath, "Regular")?;
+
+        Ok(Self {
+            font_name: filepath,
+            emoji_font_name: String::new(),
+            monochrome_emoji: false,
+            emoji_placeholder: None,
+            hinting: FontHinting::default(),
+            font_size_bounds: Self::FONT_SIZE_BOUNDS,
+            font_map: HashMap::from([(FontStyle::Regular, font)]),
+            math_font: None,
+            emoji_font: None,
+            monospace_font_name: String::new(),
+            monospace_font: None,
         })
     }
 
+    /// Sets whether the emoji should be drawn by foreground color using only their alpha channel.
+    pub fn set_monochrome_emoji(&mut self, monochrome_emoji: bool) {
+        self.monochrome_emoji = monochrome_emoji;
+    }
+
+    /// Sets the character which replaces the emoji when the emoji font is not found. The missing
+    /// glyph of the text font is used when it's `None`.
+    pub fn set_emoji_placeholder(&mut self, emoji_placeholder: Option<char>) {
+        self.emoji_placeholder = emoji_placeholder;
+    }
+
+    /// Loads the font of keyboard shortcuts by family name when it's changed. The regular font is
+    /// used when the family isn't found.
+    pub fn set_monospace_font(&mut self, font_name: &str) {
+        if self.monospace_font_name == font_name {
+            return;
+        }
+
+        self.monospace_font_name = font_name.to_owned();
+        self.monospace_font = match Font::try_match(font_name) {
+            Ok(font) => Some(font),
+            Err(err) => {
+                warn!(
+                    target: RENDER,
+                    "Font: Not found the '{font_name}' font, keyboard shortcuts will be displayed by regular font. Error: {err}"
+                );
+                None
+            }
+        };
+    }
+
+    /// Sets the hinting level which is used to place the outlined glyphs.
+    pub fn set_hinting(&mut self, hinting: FontHinting) {
+        self.hinting = hinting;
+    }
+
+    /// Sets the bounds which the font sizes are clamped to. The maximum is raised to the minimum
+    /// when it's less.
+    pub fn set_font_size_bounds(&mut self, min_font_size: u8, max_font_size: u8) {
+        self.font_size_bounds = (min_font_size, max_font_size.max(min_font_size));
+    }
+
+    /// Returns the size in pixels of the font size from config or layout which is clamped to the
+    /// bounds. Every font size should pass through it before the glyphs are loaded.
+    pub fn px_size(&self, font_size: u8) -> f32 {
+        let (min_font_size, max_font_size) = self.font_size_bounds;
+        let clamped = font_size.clamp(min_font_size, max_font_size);
+        if clamped != font_size {
+            debug!(
+                target: RENDER,
+                "Font: Clamped the font size {font_size} to {clamped}"
+            );
+        }
+
+        clamped as f32
+    }
+
     pub fn load_glyph_by_style(&self, font_style: &FontStyle, ch: char, px_size: f32) -> Glyph {
         let font = self.font_map.get(font_style).unwrap_or(self.default_font());
 
-        font.load_glyph(ch, px_size)
+        font.load_glyph(ch, px_size, self.hinting)
             .or_else(|| {
                 self.math_font
                     .as_ref()
-                    .map(|math_font| math_font.load_glyph(ch, px_size))
+                    .map(|math_font| math_font.load_glyph(ch, px_size, self.hinting))
                     .unwrap_or_default()
             })
             .or_else(|| {
-                self.emoji_font
-                    .as_ref()
-                    .and_then(|emoji| emoji.image(ch, px_size.round() as u16))
-                    .map(Glyph::Image)
+                let Some(emoji_font) = self.emoji_font.as_ref() else {
+                    return self.emoji_placeholder(font, ch, px_size);
+                };
+
+                emoji_font
+                    .image(ch, px_size.round() as u16)
+                    .map(|image| {
+                        if self.monochrome_emoji {
+                            Glyph::MonochromeImage {
+                                image,
+                                color: Bgra::new(),
+                            }
+                        } else {
+                            Glyph::Image(image)
+                        }
+                    })
                     .unwrap_or_default()
             })
     }
 
+    /// Loads the glyph of keyboard shortcut by the monospace font. The characters which it
+    /// doesn't have are loaded by the regular font.
+    pub fn load_keyboard_glyph(&self, ch: char, px_size: f32) -> Glyph {
+        self.monospace_font
+            .as_ref()
+            .map(|font| font.load_glyph(ch, px_size, self.hinting))
+            .unwrap_or_default()
+            .or_else(|| self.load_glyph_by_style(&FontStyle::Regular, ch, px_size))
+    }
+
+    /// Draws the placeholder by the text font instead of the character which isn't found in fonts
+    /// when the emoji font is absent, so the emoji don't vanish.
+    fn emoji_placeholder(&self, font: &Font, ch: char, px_size: f32) -> Glyph {
+        if ch.is_whitespace() || ch.is_control() || is_invisible(ch) {
+            return Glyph::Empty;
+        }
+
+        self.emoji_placeholder
+            .map(|placeholder| font.load_glyph(placeholder, px_size, self.hinting))
+            .unwrap_or_default()
+            .or_else(|| font.load_missing_glyph(px_size, self.hinting))
+    }
+
     pub fn max_height(&self, px_size: f32) -> usize {
         self.font_map
             .values()
@@ -144,6 +299,38 @@ impl FontCollection {
     }
 }
 
+/// Checks whether the character isn't drawn by itself, like the variation selectors and
+/// zero-width joiner of emoji sequences, the other default-ignorable and format characters and
+/// the combining marks. The placeholder isn't drawn for them, so "❤️" gets only one.
+fn is_invisible(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{00AD}'
+            | '\u{034F}'
+            | '\u{061C}'
+            | '\u{115F}'..='\u{1160}'
+            | '\u{17B4}'..='\u{17B5}'
+            | '\u{180B}'..='\u{180F}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{206F}'
+            | '\u{3164}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FEFF}'
+            | '\u{FFA0}'
+            | '\u{FFF0}'..='\u{FFF8}'
+            | '\u{1BCA0}'..='\u{1BCA3}'
+            | '\u{1D173}'..='\u{1D17A}'
+            | '\u{E0000}'..='\u{E0FFF}'
+            // INFO: the blocks of combining marks
+            | '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+    )
+}
+
 #[derive(Debug)]
 pub struct Font {
     style: FontStyle,
@@ -172,6 +359,20 @@ impl Font {
         })
     }
 
+    /// Reads the font file which fontconfig matches to the family name, so the generic families
+    /// like "monospace" are resolved too.
+    fn try_match(font_name: &str) -> anyhow::Result<Self> {
+        let filepath: String = Command::new("fc-match")
+            .args([font_name, "--format", "%{file}"])
+            .output()?
+            .stdout
+            .into_iter()
+            .map(|byte| byte as char)
+            .collect();
+
+        Self::try_read(&filepath, "Regular")
+    }
+
     pub fn get_height(&self, px_size: f32) -> f32 {
         self.data.as_scaled(px_size).height()
     }
@@ -183,23 +384,45 @@ impl Font {
         scaled_font.h_advance(glyph_id)
     }
 
-    pub fn load_glyph(&self, ch: char, px_size: f32) -> Glyph {
+    pub fn load_glyph(&self, ch: char, px_size: f32, hinting: FontHinting) -> Glyph {
         if ch.is_whitespace() {
             return Glyph::Empty;
         }
 
-        let scaled_font = self.data.as_scaled(px_size);
         let glyph_id = self.data.glyph_id(ch);
 
         if glyph_id.0 == 0 {
             return Glyph::Empty;
         }
 
-        let glyph = glyph_id.with_scale_and_position(px_size, point(0.0, scaled_font.ascent()));
+        self.load_glyph_by_id(glyph_id, px_size, hinting)
+    }
+
+    /// Loads the glyph which the font draws for characters it doesn't have, usually a box.
+    pub fn load_missing_glyph(&self, px_size: f32, hinting: FontHinting) -> Glyph {
+        self.load_glyph_by_id(ab_glyph::GlyphId(0), px_size, hinting)
+    }
+
+    fn load_glyph_by_id(
+        &self,
+        glyph_id: ab_glyph::GlyphId,
+        px_size: f32,
+        hinting: FontHinting,
+    ) -> Glyph {
+        let scaled_font = self.data.as_scaled(px_size);
+
+        // INFO: the slight hinting snaps the baseline and advance of glyphs to the pixel grid, so
+        // every line of text has the same crisp baseline and the glyphs keep even spacing
+        let snap = |value: f32| match hinting {
+            FontHinting::None => value,
+            FontHinting::Slight => value.round(),
+        };
+        let glyph =
+            glyph_id.with_scale_and_position(px_size, point(0.0, snap(scaled_font.ascent())));
 
         if let Some(outlined_glyph) = self.data.outline_glyph(glyph) {
             Glyph::Outline {
-                advance_width: scaled_font.h_advance(outlined_glyph.glyph().id),
+                advance_width: snap(scaled_font.h_advance(outlined_glyph.glyph().id)),
                 outlined_glyph,
                 color: Bgra::new(),
             }
@@ -238,14 +461,15 @@ struct EmojiFont {
 }
 
 impl EmojiFont {
-    fn try_create() -> anyhow::Result<Self> {
-        let filepath = Command::new("fc-list")
-            .args(["NotoColorEmoji", "--format", "%{file}"])
+    fn try_create(font_name: &str) -> anyhow::Result<Self> {
+        let output = Command::new("fc-list")
+            .args([font_name, "--format", "%{file}\n"])
             .output()?
             .stdout
             .into_iter()
             .map(|byte| byte as char)
             .collect::<String>();
+        let filepath = output.lines().next().unwrap_or_default();
         let file = std::fs::File::open(filepath)?;
         let buffer = Buffer::from(file);
 
@@ -325,11 +549,23 @@ impl<T> Drop for Buffer<T> {
 #[derive(Default, Clone)]
 pub enum Glyph {
     Image(Image),
+    MonochromeImage {
+        image: Image,
+        color: Bgra,
+    },
     Outline {
         color: Bgra,
         advance_width: f32,
         outlined_glyph: OutlinedGlyph,
     },
+    /// The glyph of keyboard shortcut which is drawn over the box. The padding is added at the
+    /// edges of box.
+    Keyboard {
+        glyph: Box<Glyph>,
+        color: Bgra,
+        height: usize,
+        padding: (usize, usize),
+    },
     #[default]
     Empty,
 }
@@ -347,26 +583,47 @@ impl Glyph {
     }
 
     pub fn set_color(&mut self, new_color: Bgra) {
-        if let Glyph::Outline { color, .. } = self {
-            *color = new_color;
+        match self {
+            Glyph::Outline { color, .. } | Glyph::MonochromeImage { color, .. } => {
+                *color = new_color
+            }
+            Glyph::Keyboard { glyph, color, .. } => {
+                glyph.set_color(new_color);
+                *color = new_color;
+            }
+            Glyph::Image(_) | Glyph::Empty => (),
         }
     }
 
     pub fn advance_width(&self) -> usize {
+        self.exact_advance_width().round() as usize
+    }
+
+    /// Returns the advance width with the fraction of outlined glyphs, so the runs of glyphs are
+    /// measured without accumulated rounding errors.
+    pub fn exact_advance_width(&self) -> f32 {
         match self {
-            Glyph::Image(img) => img.width().unwrap_or_default(),
-            Glyph::Outline { advance_width, .. } => advance_width.round() as usize,
-            Glyph::Empty => 0,
+            Glyph::Image(img) | Glyph::MonochromeImage { image: img, .. } => {
+                img.width().unwrap_or_default() as f32
+            }
+            Glyph::Outline { advance_width, .. } => *advance_width,
+            Glyph::Keyboard { glyph, padding, .. } => {
+                padding.0 as f32 + glyph.exact_advance_width() + padding.1 as f32
+            }
+            Glyph::Empty => 0.0,
         }
     }
 }
 
 impl Draw for Glyph {
-    fn draw_with_offset(&self, offset: &super::types::Offset, drawer: &mut Drawer) {
+    fn draw_with_offset(&self, offset: &Offset, drawer: &mut Drawer) {
         match self {
             Glyph::Image(img) => {
                 img.draw_with_offset(offset, drawer);
             }
+            Glyph::MonochromeImage { image, color } => {
+                image.draw_tinted_with_offset(*color, offset, drawer);
+            }
             Glyph::Outline {
                 color,
                 outlined_glyph,
@@ -383,6 +640,9 @@ impl Draw for Glyph {
                     )
                 })
             }
+            Glyph::Keyboard { glyph, padding, .. } => {
+                glyph.draw_with_offset(&(*offset + Offset::new_x(padding.0)), drawer)
+            }
             Glyph::Empty => unreachable!(),
         }
     }
@@ -423,6 +683,8 @@ impl From<&EntityKind> for FontStyle {
         match value {
             EntityKind::Bold => FontStyle::Bold,
             EntityKind::Italic => FontStyle::Italic,
+            // INFO: the keyboard shortcuts are drawn by the monospace font instead of the style
+            EntityKind::Kbd => FontStyle::Regular,
             other => todo!("Unsupported style {other:?} at current moment"),
         }
     }
@@ -578,6 +840,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn emoji_placeholder_is_drawn_without_emoji_font() {
+        let mut font_collection = FontCollection::load_sans().unwrap();
+        // The private use character which isn't drawn by text fonts
+        let emoji = |font_collection: &FontCollection| {
+            font_collection.load_glyph_by_style(&FontStyle::Regular, '\u{F0000}', 13.3)
+        };
+
+        assert!(!emoji(&font_collection).is_empty());
+
+        font_collection.set_emoji_placeholder(Some('?'));
+        let placeholder = font_collection.load_glyph_by_style(&FontStyle::Regular, '?', 13.3);
+        assert_eq!(
+            emoji(&font_collection).advance_width(),
+            placeholder.advance_width()
+        );
+        assert!(font_collection
+            .load_glyph_by_style(&FontStyle::Regular, ' ', 13.3)
+            .is_empty());
+    }
+
+    #[test]
+    fn emoji_placeholder_skips_invisible_characters() {
+        let mut font_collection = FontCollection::load_sans().unwrap();
+        font_collection.set_emoji_placeholder(Some('?'));
+        let drawn_glyphs = |text: &str| {
+            text.chars()
+                .map(|ch| font_collection.load_glyph_by_style(&FontStyle::Regular, ch, 13.3))
+                .filter(|glyph| !glyph.is_empty())
+                .count()
+        };
+
+        // The heart with emoji presentation selector
+        assert_eq!(drawn_glyphs("\u{2764}\u{FE0F}"), 1);
+        // The family emoji which is joined by zero-width joiners
+        assert_eq!(
+            drawn_glyphs("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}"),
+            3
+        );
+        assert_eq!(drawn_glyphs("\u{F0000}\u{20E3}"), 1);
+    }
+
     #[test]
     #[should_panic]
     fn panicky_sub_font_style() {
@@ -1,4 +1,11 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use image::AnimationDecoder;
 use log::{debug, error, warn};
+use shared::log_target::IMAGE;
 use ttf_parser::{RasterGlyphImage, RasterImageFormat};
 
 use config::display::{ImageProperty, ResizingMethod};
@@ -19,51 +26,63 @@ pub enum Image {
         data: ImageData,
         border: Option<Border>,
     },
+    Animated(Animation),
+    /// The image which is scaled by the compositor instead of being resized here.
+    Scaled(ScaledImage),
     Unknown,
 }
 
+/// The image which isn't drawn into the banner but is placed over it and scaled to its size by
+/// the compositor through `wp_viewporter`.
+#[derive(Clone)]
+pub struct ScaledImage {
+    /// The image data of source size in the same format as the banner data.
+    pub data: Arc<[u8]>,
+    pub source_size: RectSize,
+    pub size: RectSize,
+    /// The position of image in the banner which is set when the image is drawn.
+    pub offset: Offset,
+}
+
 impl Image {
     pub fn from_image_data(
         image_data: ImageData,
         image_property: &ImageProperty,
         max_size: &RectSize,
     ) -> Self {
-        let origin_width = image_data.width as u32;
-        let origin_height = image_data.height as u32;
+        let bits_per_sample = image_data.bits_per_sample;
+        let Some(image) = Self::image_data_into_dynamic_image(image_data) else {
+            warn!(target: IMAGE, "Image doesn't fits into its size");
+            return Image::Unknown;
+        };
+        let image = Self::limit_decoded_size(image, image_property);
 
         let Some((width, height)) = Self::try_fit_into_restricted_space(
-            image_data.width,
-            image_data.height,
+            image.width() as i32,
+            image.height() as i32,
             image_property,
             max_size,
         ) else {
-            warn!("The margins for image is very large! The image will not rendered!");
+            warn!(
+                target: IMAGE,
+                "The margins for image is very large! The image will not rendered!"
+            );
             return Image::Unknown;
         };
 
-        let resized_image = if image_data.has_alpha {
-            image::RgbaImage::from_vec(origin_width, origin_height, image_data.data)
-                .map(image::DynamicImage::from)
-        } else {
-            image::RgbImage::from_vec(origin_width, origin_height, image_data.data)
-                .map(image::DynamicImage::from)
-        }
-        .map(|image| {
-            image::imageops::resize(
-                &image,
-                width as u32,
-                height as u32,
-                image_property.resizing_method.to_filter_type(),
-            )
-            .to_vec()
-        });
-
-        let Some(resized_image) = resized_image else {
-            warn!("Image doesn't fits into its size");
-            return Image::Unknown;
+        let image = match Self::scaled_by_compositor(image, width, height, image_property) {
+            Ok(scaled_image) => return scaled_image,
+            Err(image) => image,
         };
+        let resized_image = image::imageops::resize(
+            &image,
+            width as u32,
+            height as u32,
+            image_property.resizing_method.to_filter_type(),
+        )
+        .to_vec();
 
-        debug!("Image: Created from 'image_data'");
+        debug!(target: IMAGE, "Image: Created from 'image_data'");
 
         Image::Exists {
             data: ImageData {
@@ -71,7 +90,7 @@ impl Image {
                 height,
                 rowstride: width * 4,
                 has_alpha: true,
-                bits_per_sample: image_data.bits_per_sample,
+                bits_per_sample,
                 channels: 4,
                 data: resized_image,
             },
@@ -87,6 +106,7 @@ impl Image {
         image_path: &std::path::Path,
         image_property: &ImageProperty,
         max_size: &RectSize,
+        output_scale: u32,
     ) -> Image {
         let data = match std::fs::read(image_path) {
             Ok(data) => data,
@@ -99,18 +119,23 @@ impl Image {
         let format = match image::guess_format(&data) {
             Ok(format) => format,
             Err(err) => {
-                warn!("Cannot guess the format of image at {image_path:?}. Error: {err}. Maybe it's SVG, trying to parse.");
-                return Self::from_svg(image_path, image_property, max_size);
+                warn!(
+                    target: IMAGE,
+                    "Cannot guess the format of image at {image_path:?}. Error: {err}. Maybe it's SVG, trying to parse."
+                );
+                return Self::from_svg(image_path, image_property, max_size, output_scale);
             }
         };
 
         let image = match image::load_from_memory_with_format(&data, format) {
             Ok(image) => image,
             Err(err) => {
-                error!("Cannot laod the image at {image_path:?}. Error: {err}");
+                error!(target: IMAGE, "Cannot laod the image at {image_path:?}. Error: {err}");
                 return Image::Unknown;
             }
         };
+        drop(data);
+        let image = Self::limit_decoded_size(image, image_property);
 
         let Some((width, height)) = Self::try_fit_into_restricted_space(
             image.width() as i32,
@@ -118,10 +143,17 @@ impl Image {
             image_property,
             max_size,
         ) else {
-            warn!("The margins for image is very large! The image will not rendered!");
+            warn!(
+                target: IMAGE,
+                "The margins for image is very large! The image will not rendered!"
+            );
             return Image::Unknown;
         };
 
+        let image = match Self::scaled_by_compositor(image, width, height, image_property) {
+            Ok(scaled_image) => return scaled_image,
+            Err(image) => image,
+        };
         let resized_image = image::imageops::resize(
             &image,
             width as u32,
@@ -148,6 +180,190 @@ impl Image {
         }
     }
 
+    /// Loads the image by path like `from_path` but if the image is animated (GIF, APNG or WebP),
+    /// decodes all its frames. Static images are loaded as usual.
+    pub fn from_path_animated(
+        image_path: &std::path::Path,
+        image_property: &ImageProperty,
+        max_size: &RectSize,
+        output_scale: u32,
+    ) -> Image {
+        let Some(frames) = std::fs::read(image_path)
+            .ok()
+            .and_then(|data| Self::decode_frames(&data, image_property))
+        else {
+            return Self::from_path(image_path, image_property, max_size, output_scale);
+        };
+
+        let Some((first_frame, _)) = frames.first() else {
+            return Image::Unknown;
+        };
+
+        let Some((width, height)) = Self::try_fit_into_restricted_space(
+            first_frame.width() as i32,
+            first_frame.height() as i32,
+            image_property,
+            max_size,
+        ) else {
+            warn!(
+                target: IMAGE,
+                "The margins for image is very large! The image will not rendered!"
+            );
+            return Image::Unknown;
+        };
+
+        let frames = frames
+            .into_iter()
+            .map(|(buffer, delay)| {
+                let data = image::imageops::resize(
+                    &buffer,
+                    width as u32,
+                    height as u32,
+                    image_property.resizing_method.to_filter_type(),
+                )
+                .to_vec();
+
+                (
+                    ImageData {
+                        width,
+                        height,
+                        rowstride: width * 4,
+                        has_alpha: true,
+                        bits_per_sample: 8,
+                        channels: 4,
+                        data,
+                    },
+                    delay.max(Animation::MIN_FRAME_DELAY),
+                )
+            })
+            .collect();
+
+        debug!(target: IMAGE, "Image: Created animated image by path {image_path:?}");
+
+        Image::Animated(Animation {
+            frames,
+            border: Some(Self::border_with_rounding(
+                width,
+                height,
+                image_property.rounding,
+            )),
+            started_at: Instant::now(),
+        })
+    }
+
+    fn decode_frames(
+        data: &[u8],
+        image_property: &ImageProperty,
+    ) -> Option<Vec<(image::RgbaImage, Duration)>> {
+        let cursor = std::io::Cursor::new(data);
+        let frames = match image::guess_format(data).ok()? {
+            image::ImageFormat::Gif => image::codecs::gif::GifDecoder::new(cursor)
+                .ok()?
+                .into_frames(),
+            image::ImageFormat::Png => {
+                let decoder = image::codecs::png::PngDecoder::new(cursor).ok()?;
+                if !decoder.is_apng().ok()? {
+                    return None;
+                }
+                decoder.apng().ok()?.into_frames()
+            }
+            image::ImageFormat::WebP => {
+                let decoder = image::codecs::webp::WebPDecoder::new(cursor).ok()?;
+                if !decoder.has_animation() {
+                    return None;
+                }
+                decoder.into_frames()
+            }
+            _ => return None,
+        };
+
+        // INFO: the frames are downscaled one by one to avoid keeping all full-resolution frames
+        // in memory at once.
+        let frames = match frames
+            .map(|frame| {
+                frame.map(|frame| {
+                    let delay = Duration::from(frame.delay());
+                    let buffer =
+                        Self::limit_decoded_size(frame.into_buffer().into(), image_property);
+                    (buffer, delay)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(frames) => frames,
+            Err(err) => {
+                warn!(
+                    target: IMAGE,
+                    "Image: Failed to decode frames of animated image. Error: {err}"
+                );
+                return None;
+            }
+        };
+
+        (frames.len() > 1).then_some(frames)
+    }
+
+    /// Converts the raw image data into image buffer. The rows of image data can be aligned by
+    /// `rowstride` so the padding is removed.
+    fn image_data_into_dynamic_image(image_data: ImageData) -> Option<image::DynamicImage> {
+        let ImageData {
+            width,
+            height,
+            rowstride,
+            has_alpha,
+            data,
+            ..
+        } = image_data;
+
+        let (width, height) = (u32::try_from(width).ok()?, u32::try_from(height).ok()?);
+        let row_len = width as usize * if has_alpha { 4 } else { 3 };
+        let rowstride = usize::try_from(rowstride).ok()?;
+
+        let data = if rowstride <= row_len {
+            data
+        } else {
+            data.chunks(rowstride)
+                .take(height as usize)
+                .flat_map(|row| row.get(..row_len).unwrap_or(row))
+                .copied()
+                .collect()
+        };
+
+        if has_alpha {
+            image::RgbaImage::from_vec(width, height, data).map(image::DynamicImage::from)
+        } else {
+            image::RgbImage::from_vec(width, height, data).map(image::DynamicImage::from)
+        }
+    }
+
+    /// Downscales the decoded image if it exceeds the `max_decode_size` so the full-resolution
+    /// image is dropped right after decoding.
+    fn limit_decoded_size(
+        image: image::DynamicImage,
+        image_property: &ImageProperty,
+    ) -> image::RgbaImage {
+        let (mut width, mut height) = (image.width() as i32, image.height() as i32);
+        Self::limit_size(&mut width, &mut height, image_property.max_decode_size);
+
+        if width as u32 == image.width() && height as u32 == image.height() {
+            return image.into_rgba8();
+        }
+
+        debug!(
+            target: IMAGE,
+            "Image: Downscaled decoded image from {}x{} to {width}x{height}",
+            image.width(),
+            image.height()
+        );
+
+        image::imageops::resize(
+            &image,
+            width.max(1) as u32,
+            height.max(1) as u32,
+            image_property.resizing_method.to_filter_type(),
+        )
+    }
+
     pub fn from_raster_glyph_image(
         RasterGlyphImage {
             width,
@@ -215,10 +431,13 @@ impl Image {
         })
     }
 
+    /// Rasterizes the SVG image at its shown size. When the image is scaled by the compositor,
+    /// it's rasterized at the size multiplied by `output_scale` to stay crisp on HiDPI outputs.
     pub fn from_svg(
         image_path: &std::path::Path,
         image_property: &ImageProperty,
         max_size: &RectSize,
+        output_scale: u32,
     ) -> Self {
         if !image_path.is_file() {
             return Image::Unknown;
@@ -238,49 +457,82 @@ impl Image {
                 match err {
                     resvg::usvg::Error::MalformedGZip => {
                         warn!(
+                            target: IMAGE,
                             "Malformed gzip format of SVG image in path: {:?}",
                             image_path
                         )
                     }
                     resvg::usvg::Error::NotAnUtf8Str => warn!(
+                        target: IMAGE,
                         "The SVG image file contains non-UTF-8 string in path: {:?}",
                         image_path
                     ),
-                    _ => warn!("Something wrong with SVG image in path: {:?}", image_path),
+                    _ => {
+                        warn!(
+                            target: IMAGE,
+                            "Something wrong with SVG image in path: {:?}", image_path
+                        )
+                    }
                 }
                 return Image::Unknown;
             }
         };
 
+        // INFO: the vector image is rasterized directly at the target size, so unlike raster
+        // images it's also upscaled to the `max_size` to stay crisp.
         let tree_size = tree.size();
+        let (target_width, target_height) = Self::fit_vector_size(
+            tree_size.width(),
+            tree_size.height(),
+            image_property.max_size,
+        );
         let Some((width, height)) = Self::try_fit_into_restricted_space(
-            tree_size.width().round() as i32,
-            tree_size.height().round() as i32,
+            target_width,
+            target_height,
             image_property,
             max_size,
         ) else {
-            warn!("The margins for image is very large! The image will not rendered!");
+            warn!(
+                target: IMAGE,
+                "The margins for image is very large! The image will not rendered!"
+            );
             return Image::Unknown;
         };
 
-        let scale = if width > height {
-            width as f32 / tree_size.width()
-        } else {
-            height as f32 / tree_size.height()
+        // INFO: the banner is drawn in logical pixels, so only the images which are scaled by
+        // the compositor can have more pixels than their shown size
+        let pixel_scale = match image_property.viewport_scaling && image_property.rounding == 0 {
+            true => output_scale.max(1) as i32,
+            false => 1,
         };
+        let (pixel_width, pixel_height) = (width * pixel_scale, height * pixel_scale);
 
-        let Some(mut pixmap) = resvg::tiny_skia::Pixmap::new(width as u32, height as u32) else {
-            warn!("The SVG Image width or height is equal to zero!");
+        let scale =
+            (pixel_width as f32 / tree_size.width()).min(pixel_height as f32 / tree_size.height());
+        let transform = resvg::usvg::Transform::from_scale(scale, scale).post_translate(
+            (pixel_width as f32 - tree_size.width() * scale) / 2.0,
+            (pixel_height as f32 - tree_size.height() * scale) / 2.0,
+        );
+
+        let Some(mut pixmap) =
+            resvg::tiny_skia::Pixmap::new(pixel_width as u32, pixel_height as u32)
+        else {
+            warn!(target: IMAGE, "The SVG Image width or height is equal to zero!");
             return Image::Unknown;
         };
 
-        resvg::render(
-            &tree,
-            resvg::usvg::Transform::from_scale(scale, scale),
-            &mut pixmap.as_mut(),
-        );
+        resvg::render(&tree, transform, &mut pixmap.as_mut());
 
-        debug!("Image: Created image from svg by path {image_path:?}");
+        debug!(target: IMAGE, "Image: Created image from svg by path {image_path:?}");
+
+        if pixel_scale > 1 {
+            let image = image::RgbaImage::from_raw(pixmap.width(), pixmap.height(), pixmap.take())
+                .expect("The pixmap data should match its size");
+            match Self::scaled_by_compositor(image, width, height, image_property) {
+                Ok(scaled_image) => return scaled_image,
+                Err(_) => unreachable!("The rasterized image is larger than its shown size"),
+            }
+        }
 
         Image::Exists {
             data: ImageData {
@@ -300,16 +552,62 @@ impl Image {
         }
     }
 
+    /// Keeps the decoded image in its size to let the compositor scale it when the
+    /// `viewport_scaling` of image property is enabled. Returns the image back when it should be
+    /// resized here: the size is already fitted or the image is rounded because the rounding is
+    /// drawn by the mask of image.
+    fn scaled_by_compositor(
+        image: image::RgbaImage,
+        width: i32,
+        height: i32,
+        image_property: &ImageProperty,
+    ) -> Result<Image, image::RgbaImage> {
+        let source_size = RectSize::new(image.width() as usize, image.height() as usize);
+        let size = RectSize::new(width as usize, height as usize);
+        if !image_property.viewport_scaling || image_property.rounding != 0 || source_size == size {
+            return Err(image);
+        }
+
+        let data = ImageData {
+            width: source_size.width as i32,
+            height: source_size.height as i32,
+            rowstride: source_size.width as i32 * 4,
+            has_alpha: true,
+            bits_per_sample: 8,
+            channels: 4,
+            data: image.into_raw(),
+        };
+        let mut drawer = Drawer::new(Color::Fill(Bgra::new()), source_size.clone());
+        Self::draw_image_data(&data, None, None, &Offset::no_offset(), &mut drawer);
+        let data: Vec<u8> = drawer.into();
+
+        debug!(
+            target: IMAGE,
+            "Image: Kept the size {}x{} to scale it to {width}x{height} by compositor",
+            source_size.width,
+            source_size.height
+        );
+
+        Ok(Image::Scaled(ScaledImage {
+            data: data.into(),
+            source_size,
+            size,
+            offset: Offset::no_offset(),
+        }))
+    }
+
     fn print_readable_fs_error(error: std::io::Error, image_path: &std::path::Path) {
         match error.kind() {
             std::io::ErrorKind::NotFound => {
-                warn!("Not found SVG image in path: {:?}", image_path)
+                warn!(target: IMAGE, "Not found SVG image in path: {:?}", image_path)
             }
             std::io::ErrorKind::PermissionDenied => warn!(
+                target: IMAGE,
                 "Permission to read SVG image in path is denied: {:?}",
                 image_path
             ),
             _ => warn!(
+                target: IMAGE,
                 "Something wrong happened during reading SVG image in path: {:?}",
                 image_path
             ),
@@ -333,12 +631,45 @@ impl Image {
     }
 
     pub fn is_exists(&self) -> bool {
-        matches!(self, Image::Exists { .. })
+        matches!(
+            self,
+            Image::Exists { .. } | Image::Animated(_) | Image::Scaled(_)
+        )
+    }
+
+    pub fn is_animated(&self) -> bool {
+        matches!(self, Image::Animated(_))
+    }
+
+    /// Returns the time until the animated image shows the next frame or `None` when the image
+    /// is still.
+    pub fn next_frame_in(&self) -> Option<Duration> {
+        match self {
+            Image::Animated(animation) => Some(animation.next_frame_in()),
+            _ => None,
+        }
+    }
+
+    /// Returns the approximate number of bytes which the decoded data of image takes. The
+    /// animation takes all its frames.
+    pub fn approximate_bytes(&self) -> usize {
+        match self {
+            Image::Exists { data, .. } => data.data.len(),
+            Image::Animated(animation) => animation
+                .frames
+                .iter()
+                .map(|(frame, _)| frame.data.len())
+                .sum(),
+            Image::Scaled(scaled_image) => scaled_image.data.len(),
+            Image::Unknown => 0,
+        }
     }
 
     pub fn width(&self) -> Option<usize> {
         match self {
             Image::Exists { data, .. } => Some(data.width as usize),
+            Image::Animated(animation) => Some(animation.current_frame().width as usize),
+            Image::Scaled(scaled_image) => Some(scaled_image.size.width),
             Image::Unknown => None,
         }
     }
@@ -346,6 +677,8 @@ impl Image {
     pub fn height(&self) -> Option<usize> {
         match self {
             Image::Exists { data, .. } => Some(data.height as usize),
+            Image::Animated(animation) => Some(animation.current_frame().height as usize),
+            Image::Scaled(scaled_image) => Some(scaled_image.size.height),
             Image::Unknown => None,
         }
     }
@@ -376,6 +709,15 @@ impl Image {
         }
     }
 
+    /// Scales the size of vector image so its largest side is equal to `max_size`.
+    fn fit_vector_size(width: f32, height: f32, max_size: u16) -> (i32, i32) {
+        let factor = max_size as f32 / width.max(height);
+        (
+            (width * factor).round().max(1.0) as i32,
+            (height * factor).round().max(1.0) as i32,
+        )
+    }
+
     fn limit_size(width: &mut i32, height: &mut i32, max_size: u16) {
         let swap = height > width;
         if swap {
@@ -404,27 +746,31 @@ impl Image {
             .expect("Create Border for image rounding")
     }
 
-    fn converter(has_alpha: bool) -> fn(&[u8]) -> Rgba {
-        //SAFETY: it always safe way while the framebuffer have ARGB format and gives the correct
-        //postiton.
-        if has_alpha {
-            |chunk: &[u8]| unsafe {
-                Rgba::from(TryInto::<&[u8; 4]>::try_into(chunk).unwrap_unchecked())
-            }
-        } else {
-            |chunk: &[u8]| unsafe {
-                Rgba::from(TryInto::<&[u8; 3]>::try_into(chunk).unwrap_unchecked())
+    /// Draws the image using only its alpha channel as coverage of the given color.
+    pub fn draw_tinted_with_offset(&self, tint: Bgra, offset: &Offset, drawer: &mut Drawer) {
+        match self {
+            Image::Exists { data, border } => {
+                Self::draw_image_data(data, border.as_ref(), Some(tint), offset, drawer)
             }
+            Image::Animated(animation) => Self::draw_image_data(
+                animation.current_frame(),
+                animation.border.as_ref(),
+                Some(tint),
+                offset,
+                drawer,
+            ),
+            Image::Scaled(_) => self.draw_with_offset(offset, drawer),
+            Image::Unknown => (),
         }
     }
-}
-
-impl Draw for Image {
-    fn draw_with_offset(&self, offset: &Offset, drawer: &mut Drawer) {
-        let Image::Exists { data, border } = self else {
-            return;
-        };
 
+    fn draw_image_data(
+        data: &ImageData,
+        border: Option<&Border>,
+        tint: Option<Bgra>,
+        offset: &Offset,
+        drawer: &mut Drawer,
+    ) {
         let mut chunks = data
             .data
             .chunks_exact(data.channels as usize)
@@ -432,26 +778,108 @@ impl Draw for Image {
 
         for y in 0..data.height as usize {
             for x in 0..data.width as usize {
-                let border_coverage =
-                    match border.as_ref().and_then(|border| border.get_color_at(x, y)) {
-                        Some(DrawColor::Transparent(Coverage(factor))) => factor,
-                        None => 1.0,
-                        _ => unreachable!(),
-                    };
+                let border_coverage = match border.and_then(|border| border.get_color_at(x, y)) {
+                    Some(DrawColor::Transparent(Coverage(factor))) => factor,
+                    None => 1.0,
+                    _ => unreachable!(),
+                };
 
                 let color = unsafe { chunks.next().unwrap_unchecked() }.into_bgra();
+                let (color, coverage) = match tint {
+                    Some(tint) => (tint, color.alpha * border_coverage),
+                    None => (color, border_coverage),
+                };
+
                 drawer.draw_color(
                     x + offset.x,
                     y + offset.y,
-                    if border_coverage == 1.0 {
+                    if coverage == 1.0 {
                         DrawColor::Overlay(color)
                     } else {
-                        DrawColor::OverlayWithCoverage(color, Coverage(border_coverage))
+                        DrawColor::OverlayWithCoverage(color, Coverage(coverage))
                     },
                 );
             }
         }
     }
+
+    fn converter(has_alpha: bool) -> fn(&[u8]) -> Rgba {
+        //SAFETY: it always safe way while the framebuffer have ARGB format and gives the correct
+        //postiton.
+        if has_alpha {
+            |chunk: &[u8]| unsafe {
+                Rgba::from(TryInto::<&[u8; 4]>::try_into(chunk).unwrap_unchecked())
+            }
+        } else {
+            |chunk: &[u8]| unsafe {
+                Rgba::from(TryInto::<&[u8; 3]>::try_into(chunk).unwrap_unchecked())
+            }
+        }
+    }
+}
+
+impl Draw for Image {
+    fn draw_with_offset(&self, offset: &Offset, drawer: &mut Drawer) {
+        match self {
+            Image::Exists { data, border } => {
+                Self::draw_image_data(data, border.as_ref(), None, offset, drawer)
+            }
+            Image::Animated(animation) => Self::draw_image_data(
+                animation.current_frame(),
+                animation.border.as_ref(),
+                None,
+                offset,
+                drawer,
+            ),
+            Image::Scaled(scaled_image) => drawer.place_scaled_image(ScaledImage {
+                offset: *offset,
+                ..scaled_image.clone()
+            }),
+            Image::Unknown => (),
+        }
+    }
+}
+
+/// The frames of animated image. The current frame is computed by elapsed time since the image
+/// creation so it always shows the actual frame during redraws.
+#[derive(Clone)]
+pub struct Animation {
+    frames: Vec<(ImageData, Duration)>,
+    border: Option<Border>,
+    started_at: Instant,
+}
+
+impl Animation {
+    /// The minimal delay between frames. It caps the frame rate of animations because some of them
+    /// have zero delays.
+    const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+
+    fn current_frame(&self) -> &ImageData {
+        let (index, _) = self.frame_at(self.started_at.elapsed());
+        &self.frames[index].0
+    }
+
+    /// Returns the time until the current frame is replaced by the next one.
+    fn next_frame_in(&self) -> Duration {
+        self.frame_at(self.started_at.elapsed()).1
+    }
+
+    /// Returns the index of frame which is shown after the elapsed time since the start of
+    /// looped animation and the time left until the next frame.
+    fn frame_at(&self, elapsed: Duration) -> (usize, Duration) {
+        let total_duration: Duration = self.frames.iter().map(|(_, delay)| *delay).sum();
+        let mut elapsed =
+            Duration::from_nanos((elapsed.as_nanos() % total_duration.as_nanos().max(1)) as u64);
+
+        for (index, (_, delay)) in self.frames.iter().enumerate() {
+            if elapsed < *delay {
+                return (index, *delay - elapsed);
+            }
+            elapsed -= *delay;
+        }
+
+        (self.frames.len() - 1, Duration::ZERO)
+    }
 }
 
 trait ToFilterType {
@@ -469,3 +897,181 @@ impl ToFilterType for ResizingMethod {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use config::display::ImageProperty;
+    use dbus::image::ImageData;
+
+    use crate::{
+        color::{Bgra, Color},
+        drawer::Drawer,
+        types::{Offset, RectSize},
+        widget::Draw,
+    };
+
+    use super::{Animation, Image};
+
+    fn synthetic_image_data(width: i32, height: i32, padding: i32) -> ImageData {
+        let rowstride = width * 3 + padding;
+        ImageData {
+            width,
+            height,
+            rowstride,
+            has_alpha: false,
+            bits_per_sample: 8,
+            channels: 3,
+            data: vec![127; (rowstride * height) as usize],
+        }
+    }
+
+    fn image_property(max_size: u16, max_decode_size: u16) -> ImageProperty {
+        ImageProperty {
+            max_size,
+            max_decode_size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn oversized_image_data_is_downscaled() {
+        let image = Image::from_image_data(
+            synthetic_image_data(4000, 2000, 0),
+            &image_property(64, 256),
+            &RectSize::new(300, 150),
+        );
+
+        assert_eq!(image.width(), Some(64));
+        assert_eq!(image.height(), Some(32));
+    }
+
+    #[test]
+    fn image_is_scaled_by_compositor() {
+        let mut property = image_property(64, 256);
+        property.viewport_scaling = true;
+
+        let image = Image::from_image_data(
+            synthetic_image_data(4000, 2000, 0),
+            &property,
+            &RectSize::new(300, 150),
+        );
+        let Image::Scaled(scaled_image) = &image else {
+            panic!("The image should be scaled by compositor");
+        };
+        assert_eq!(scaled_image.source_size, RectSize::new(256, 128));
+        assert_eq!(scaled_image.size, RectSize::new(64, 32));
+        assert_eq!(scaled_image.data.len(), 256 * 128 * 4);
+
+        // INFO: the scaled images are placed by the offset of subdrawers
+        let mut drawer = Drawer::new(Color::Fill(Bgra::new()), RectSize::new(300, 150));
+        let mut subdrawer = Drawer::new(Color::Fill(Bgra::new()), RectSize::new(100, 100));
+        image.draw_with_offset(&Offset::new(5, 10), &mut subdrawer);
+        drawer.draw_area(&Offset::new(20, 30), subdrawer);
+        let placed = drawer.take_scaled_images();
+        assert_eq!(placed.len(), 1);
+        assert_eq!((placed[0].offset.x, placed[0].offset.y), (25, 40));
+
+        property.rounding = 8;
+        let image = Image::from_image_data(
+            synthetic_image_data(4000, 2000, 0),
+            &property,
+            &RectSize::new(300, 150),
+        );
+        assert!(matches!(image, Image::Exists { .. }));
+    }
+
+    #[test]
+    fn decoded_size_is_limited() {
+        let image = Image::image_data_into_dynamic_image(synthetic_image_data(4000, 2000, 0))
+            .expect("The synthetic image data must be valid");
+        let image = Image::limit_decoded_size(image, &image_property(64, 256));
+
+        assert_eq!(image.dimensions(), (256, 128));
+    }
+
+    #[test]
+    fn small_image_keeps_decoded_size() {
+        let image = Image::image_data_into_dynamic_image(synthetic_image_data(100, 50, 0))
+            .expect("The synthetic image data must be valid");
+        let image = Image::limit_decoded_size(image, &image_property(64, 256));
+
+        assert_eq!(image.dimensions(), (100, 50));
+    }
+
+    fn svg_image(width: u32, height: u32, output_scale: u32) -> Image {
+        let svg_path = std::env::temp_dir().join(format!(
+            "noti-test-{}-{width}x{height}.svg",
+            std::process::id()
+        ));
+        std::fs::write(
+            &svg_path,
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}">
+                <rect width="{width}" height="{height}" fill="red"/>
+                </svg>"#
+            ),
+        )
+        .unwrap();
+
+        let mut property = image_property(64, 256);
+        property.viewport_scaling = output_scale > 1;
+        let image = Image::from_svg(&svg_path, &property, &RectSize::new(300, 150), output_scale);
+        std::fs::remove_file(svg_path).unwrap();
+        image
+    }
+
+    #[test]
+    fn svg_is_rasterized_at_max_size() {
+        let image = svg_image(16, 16, 1);
+        assert_eq!((image.width(), image.height()), (Some(64), Some(64)));
+
+        let image = svg_image(100, 50, 1);
+        assert_eq!((image.width(), image.height()), (Some(64), Some(32)));
+
+        let image = svg_image(512, 1024, 1);
+        assert_eq!((image.width(), image.height()), (Some(32), Some(64)));
+    }
+
+    #[test]
+    fn svg_is_rasterized_by_output_scale() {
+        let image = svg_image(100, 50, 2);
+        let Image::Scaled(scaled_image) = &image else {
+            panic!("The SVG image should be scaled by compositor");
+        };
+        assert_eq!(scaled_image.source_size, RectSize::new(128, 64));
+        assert_eq!(scaled_image.size, RectSize::new(64, 32));
+    }
+
+    #[test]
+    fn image_data_with_padded_rows() {
+        let image = Image::image_data_into_dynamic_image(synthetic_image_data(33, 10, 1))
+            .expect("The padded rows must be removed");
+
+        assert_eq!((image.width(), image.height()), (33, 10));
+    }
+
+    #[test]
+    fn animation_steps_frames_by_delays() {
+        let animation = Animation {
+            frames: [10, 30, 20]
+                .into_iter()
+                .map(|delay| (synthetic_image_data(1, 1, 0), Duration::from_millis(delay)))
+                .collect(),
+            border: None,
+            started_at: Instant::now(),
+        };
+        let frame_at = |millis| animation.frame_at(Duration::from_millis(millis));
+
+        assert_eq!(frame_at(0), (0, Duration::from_millis(10)));
+        assert_eq!(frame_at(9), (0, Duration::from_millis(1)));
+        assert_eq!(frame_at(10), (1, Duration::from_millis(30)));
+        assert_eq!(frame_at(25), (1, Duration::from_millis(15)));
+        assert_eq!(frame_at(45), (2, Duration::from_millis(15)));
+
+        // INFO: the animation is looped
+        assert_eq!(frame_at(60), (0, Duration::from_millis(10)));
+        assert_eq!(frame_at(75), (1, Duration::from_millis(25)));
+    }
+}
@@ -1,6 +1,8 @@
+pub mod banner;
 pub mod border;
 pub mod color;
 pub mod drawer;
+pub mod error;
 pub mod font;
 pub mod image;
 pub mod text;
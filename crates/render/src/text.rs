@@ -4,20 +4,25 @@ use derive_builder::Builder;
 use itertools::Itertools;
 
 use config::{
+    display::ImageProperty,
     spacing::Spacing,
     text::{EllipsizeAt, TextJustification},
 };
-use dbus::text::Text;
+use dbus::text::{EntityKind, Text};
 
-use crate::drawer::Drawer;
+use crate::{drawer::Drawer, image::Image};
 
 use super::{
-    color::Bgra,
+    border::BorderBuilder,
+    color::{Bgra, Color},
     font::{FontCollection, FontStyle, Glyph},
     types::{Offset, RectSize},
-    widget::Draw,
+    widget::{Coverage, Draw, DrawColor},
 };
 
+/// The opacity of box behind keyboard shortcuts which is filled by the text color.
+const KEYBOARD_BOX_OPACITY: f32 = 0.2;
+
 #[derive(Default)]
 pub struct TextRect {
     paragraphs: VecDeque<VecDeque<WordRect>>,
@@ -25,6 +30,10 @@ pub struct TextRect {
 
     lines: Vec<LineRect>,
     wrap: bool,
+    /// The maximum number of wrapped lines, 0 means unlimited.
+    max_lines: usize,
+    /// Whether the text doesn't fit to the lines, so it's ellipsized.
+    truncated: bool,
 
     rect_size: RectSize,
 
@@ -82,6 +91,14 @@ impl TextRect {
     ) -> Self {
         let Text { body, entities } = text;
         let base_style: FontStyle = base_style.into();
+        let is_keyboard = |position: usize| {
+            entities.iter().any(|entity| {
+                entity.kind == EntityKind::Kbd
+                    && (entity.offset..entity.offset + entity.length).contains(&position)
+            })
+        };
+        let line_height = font_collection.max_height(px_size);
+        let keyboard_padding = (px_size / 4.0).round() as usize;
 
         let mut entities = VecDeque::from_iter(entities.iter());
         let mut current_entities = VecDeque::new();
@@ -92,17 +109,42 @@ impl TextRect {
 
         for (position, ch) in body.chars().enumerate() {
             while let Some(entity) = entities.front() {
-                if entity.offset == position {
-                    current_style += FontStyle::from(&entity.kind);
-                    current_entities.push_back(entities.pop_front().unwrap());
-                } else {
+                if entity.offset != position {
                     break;
                 }
+
+                let entity = entities.pop_front().unwrap();
+                match &entity.kind {
+                    EntityKind::Image { src, alt } => current_paragraph.extend(Self::inline_image(
+                        src.as_deref(),
+                        alt.as_deref(),
+                        line_height,
+                        px_size,
+                        &base_style,
+                        font_collection,
+                    )),
+                    kind => {
+                        current_style += FontStyle::from(kind);
+                        current_entities.push_back(entity);
+                    }
+                }
             }
 
             if ch == '\n' {
+                pad_keyboard_runs(&mut current_paragraph, keyboard_padding);
                 paragraphs.push_back(Self::convert_to_words(current_paragraph));
                 current_paragraph = vec![];
+            } else if is_keyboard(position) {
+                let glyph = font_collection.load_keyboard_glyph(ch, px_size);
+                current_paragraph.push(match glyph.is_empty() {
+                    true => glyph,
+                    false => Glyph::Keyboard {
+                        glyph: Box::new(glyph),
+                        color: Bgra::new(),
+                        height: line_height,
+                        padding: (0, 0),
+                    },
+                });
             } else {
                 current_paragraph.push(font_collection.load_glyph_by_style(
                     &(&base_style + &current_style),
@@ -121,7 +163,22 @@ impl TextRect {
             }
         }
 
+        // INFO: the images after the last character
+        for entity in entities {
+            if let EntityKind::Image { src, alt } = &entity.kind {
+                current_paragraph.extend(Self::inline_image(
+                    src.as_deref(),
+                    alt.as_deref(),
+                    line_height,
+                    px_size,
+                    &base_style,
+                    font_collection,
+                ));
+            }
+        }
+
         if !current_paragraph.is_empty() {
+            pad_keyboard_runs(&mut current_paragraph, keyboard_padding);
             paragraphs.push_back(Self::convert_to_words(current_paragraph));
         }
 
@@ -130,11 +187,42 @@ impl TextRect {
             wrap: true,
             spacebar_width: Self::get_spacebar_width(font_collection, px_size),
             ellipsis: font_collection.get_ellipsis(px_size),
-            line_height: font_collection.max_height(px_size),
+            line_height,
             ..Default::default()
         }
     }
 
+    /// Returns the glyphs of `<img>` tag. The image is drawn in the line height, otherwise its
+    /// alternative text is drawn.
+    fn inline_image(
+        src: Option<&str>,
+        alt: Option<&str>,
+        line_height: usize,
+        px_size: f32,
+        style: &FontStyle,
+        font_collection: &FontCollection,
+    ) -> Vec<Glyph> {
+        let image = src
+            .map(|src| src.strip_prefix("file://").unwrap_or(src))
+            .map(|path| {
+                let property = ImageProperty {
+                    max_size: line_height.min(u16::MAX as usize) as u16,
+                    ..Default::default()
+                };
+                let line_size = RectSize::new(line_height, line_height);
+                Image::from_path(std::path::Path::new(path), &property, &line_size, 1)
+            });
+
+        match image {
+            Some(image) if image.is_exists() => vec![Glyph::Image(image)],
+            _ => alt
+                .unwrap_or_default()
+                .chars()
+                .map(|ch| font_collection.load_glyph_by_style(style, ch, px_size))
+                .collect(),
+        }
+    }
+
     fn convert_to_words(glyph_collection: Vec<Glyph>) -> VecDeque<WordRect> {
         glyph_collection
             .into_iter()
@@ -152,6 +240,10 @@ impl TextRect {
         self.wrap = wrap;
     }
 
+    pub fn set_max_lines(&mut self, max_lines: usize) {
+        self.max_lines = max_lines;
+    }
+
     pub fn set_line_spacing(&mut self, line_spacing: usize) {
         self.line_spacing = line_spacing;
     }
@@ -176,6 +268,23 @@ impl TextRect {
         self.rect_size.width = rect_size.width;
         rect_size.shrink_by(&self.margin);
 
+        match self.ellipsize_at {
+            EllipsizeAt::End => {
+                let paragraph_num = self.compile_lines(&rect_size);
+                self.truncated = !self.is_fully_placed();
+                self.ellipsize(paragraph_num);
+            }
+            EllipsizeAt::Start | EllipsizeAt::Middle => {
+                self.compile_with_inner_ellipsis(&rect_size)
+            }
+        }
+
+        self.apply_color();
+    }
+
+    /// Places the words of paragraphs to lines and returns the number of paragraph where the
+    /// placement is stopped.
+    fn compile_lines(&mut self, rect_size: &RectSize) -> u8 {
         let mut paragraph_num = 0;
         self.current_paragraph = self.paragraphs.pop_front().unwrap_or_default();
 
@@ -184,7 +293,11 @@ impl TextRect {
         for y in (0..rect_size.height)
             .step_by(self.line_height + self.line_spacing)
             .take_while(|y| rect_size.height - *y >= self.line_height)
-            .take(if self.wrap { usize::MAX } else { 1 })
+            .take(match (self.wrap, self.max_lines) {
+                (false, _) => 1,
+                (true, 0) => usize::MAX,
+                (true, max_lines) => max_lines,
+            })
         {
             let mut line = LineRectBuilder::create_empty()
                 .paragraph_num(paragraph_num)
@@ -229,8 +342,66 @@ impl TextRect {
         }
 
         self.lines = lines;
-        self.ellipsize(paragraph_num);
-        self.apply_color();
+        paragraph_num
+    }
+
+    /// Checks whether the all words are placed into lines after `compile_lines` call.
+    fn is_fully_placed(&self) -> bool {
+        self.current_paragraph.is_empty()
+            && self.paragraphs.iter().all(VecDeque::is_empty)
+            && !self.lines.last().is_some_and(LineRect::is_overflow)
+    }
+
+    /// Truncates the text at start or in the middle so it fits to the available space. Only the
+    /// glyphs are truncated, so the kept head and tail preserve their words and paragraphs.
+    fn compile_with_inner_ellipsis(&mut self, rect_size: &RectSize) {
+        let tokens = Token::flatten(std::mem::take(&mut self.paragraphs));
+
+        self.paragraphs = Token::collect_paragraphs(tokens.iter().cloned());
+        self.compile_lines(rect_size);
+        self.truncated = !self.is_fully_placed();
+        if !self.truncated {
+            return;
+        }
+
+        let ellipsis = self.ellipsis.clone();
+        let is_start = matches!(self.ellipsize_at, EllipsizeAt::Start);
+        let truncate = |kept: usize| {
+            let (head, tail) = if is_start {
+                (0, kept)
+            } else {
+                (kept.div_ceil(2), kept / 2)
+            };
+
+            Token::collect_paragraphs(
+                tokens[..head]
+                    .iter()
+                    .cloned()
+                    .chain([Token::Glyph(ellipsis.clone())])
+                    .chain(tokens[tokens.len() - tail..].iter().cloned()),
+            )
+        };
+
+        // INFO: the whole text doesn't fit so look for the largest amount of kept tokens which
+        // fits together with ellipsis.
+        let (mut low, mut high) = (0, tokens.len() - 1);
+        while low < high {
+            let middle = (low + high).div_ceil(2);
+            self.paragraphs = truncate(middle);
+            self.compile_lines(rect_size);
+
+            if self.is_fully_placed() {
+                low = middle;
+            } else {
+                high = middle - 1;
+            }
+        }
+
+        self.paragraphs = truncate(low);
+        self.compile_lines(rect_size);
+        if !self.is_fully_placed() {
+            self.lines.clear();
+        }
     }
 
     fn ellipsize(&mut self, paragraph_num: u8) {
@@ -242,7 +413,6 @@ impl TextRect {
                     paragraph_num,
                     self.current_paragraph.pop_front(),
                     self.ellipsis.clone(),
-                    &self.ellipsize_at,
                 )
             })
             .unwrap_or_default();
@@ -258,12 +428,7 @@ impl TextRect {
                 .lines
                 .last_mut()
                 .map(|last_line| {
-                    last_line.ellipsize(
-                        paragraph_num,
-                        last_word,
-                        self.ellipsis.clone(),
-                        &self.ellipsize_at,
-                    )
+                    last_line.ellipsize(paragraph_num, last_word, self.ellipsis.clone())
                 })
                 .unwrap_or_default();
         }
@@ -275,6 +440,11 @@ impl TextRect {
             .for_each(|line| line.set_color(self.foreground));
     }
 
+    /// Returns true when the compiled text is ellipsized because it doesn't fit to the lines.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
     pub(crate) fn is_empty(&self) -> bool {
         self.lines.is_empty() || self.lines.iter().all(|line| line.is_empty())
     }
@@ -284,6 +454,17 @@ impl TextRect {
         self.rect_size.width
     }
 
+    /// Returns the width which the compiled lines actually take including the margin, so it's
+    /// not larger than the width given to `compile()`.
+    pub fn content_width(&self) -> usize {
+        self.lines
+            .iter()
+            .map(LineRect::used_width)
+            .max()
+            .unwrap_or_default()
+            + self.margin.horizontal() as usize
+    }
+
     pub fn height(&self) -> usize {
         let total_lines = self.lines.len();
         total_lines * self.line_height
@@ -335,75 +516,25 @@ impl LineRect {
         self.available_space as usize + self.spacebar_width * self.words.len().saturating_sub(1)
     }
 
+    fn used_width(&self) -> usize {
+        self.words.iter().map(WordRect::width).sum::<usize>()
+            + self.spacebar_width * self.words.len().saturating_sub(1)
+    }
+
     fn ellipsize(
         &mut self,
         paragraph_num: u8,
         mut last_word: Option<WordRect>,
         ellipsis: Glyph,
-        ellipsize_at: &EllipsizeAt,
     ) -> EllipsizationState {
-        match ellipsize_at {
-            EllipsizeAt::Middle => {
-                self.ellipsize_middle(last_word, ellipsis);
-                EllipsizationState::Complete
-            }
-            EllipsizeAt::End => {
-                if last_word.is_some() || self.available_space < 0 {
-                    if paragraph_num != self.paragraph_num {
-                        last_word.replace(WordRect::new_empty());
-                    }
-                    self.ellipsize_end(ellipsis)
-                } else {
-                    EllipsizationState::Complete
-                }
-            }
-        }
-    }
-
-    fn ellipsize_middle(&mut self, last_word: Option<WordRect>, ellipsis: Glyph) {
-        let ellipsis_width = ellipsis.advance_width();
-
-        if let Some(mut last_word) = last_word {
-            while !last_word.is_blank()
-                && (last_word.width() + self.spacebar_width + ellipsis_width) as isize
-                    > self.available_space
-            {
-                last_word.pop_glyph();
-            }
-
-            if !last_word.is_blank() {
-                last_word.push_glyph(ellipsis);
-                self.push_word(last_word);
-                return;
-            } else if ellipsis_width as isize <= self.available_space {
-                self.push_ellipsis_to_last_word(ellipsis);
-                return;
+        if last_word.is_some() || self.available_space < 0 {
+            if paragraph_num != self.paragraph_num {
+                last_word.replace(WordRect::new_empty());
             }
-        } else if self.available_space >= 0 {
-            return;
-        }
-
-        let mut last_word = self.pop_word().expect(
-            "Here must have a WordRect struct in the LineRect. \
-            But it doesn't have, so the LineRect is not correct. Please to contact the devs of \
-            the Noti application with this information, please.",
-        );
-
-        while !last_word.is_blank()
-            && (last_word.width() + self.spacebar_width + ellipsis_width) as isize
-                > self.available_space
-        {
-            last_word.pop_glyph();
-        }
-
-        if last_word.is_blank() {
-            return;
+            self.ellipsize_end(ellipsis)
+        } else {
+            EllipsizationState::Complete
         }
-
-        // INFO: here MUST be enough space for cutting word and ellipsization
-        // so here doesn't check if the last word is blank
-        last_word.push_glyph(ellipsis);
-        self.push_word(last_word);
     }
 
     fn ellipsize_end(&mut self, ellipsis: Glyph) -> EllipsizationState {
@@ -495,6 +626,61 @@ impl Draw for LineRect {
     }
 }
 
+/// Adds the padding at the edges of keyboard glyph runs, so the boxes behind them don't touch
+/// their text.
+fn pad_keyboard_runs(glyphs: &mut [Glyph], padding: usize) {
+    let is_keyboard = |glyph: Option<&Glyph>| matches!(glyph, Some(Glyph::Keyboard { .. }));
+
+    for i in 0..glyphs.len() {
+        let is_start = !is_keyboard(i.checked_sub(1).and_then(|i| glyphs.get(i)));
+        let is_end = !is_keyboard(glyphs.get(i + 1));
+
+        if let Glyph::Keyboard {
+            padding: (left, right),
+            ..
+        } = &mut glyphs[i]
+        {
+            *left = if is_start { padding } else { 0 };
+            *right = if is_end { padding } else { 0 };
+        }
+    }
+}
+
+/// Draws the rounded box behind the run of keyboard glyphs with the given width.
+fn draw_keyboard_box(
+    width: usize,
+    height: usize,
+    color: Bgra,
+    offset: &Offset,
+    drawer: &mut Drawer,
+) {
+    // INFO: the rounding of box reuses the coverage of border corners
+    let border = BorderBuilder::default()
+        .color(Color::default())
+        .size(0_usize)
+        .radius((height / 4).min(width / 2))
+        .frame_width(width)
+        .frame_height(height)
+        .compile()
+        .expect("Create Border for keyboard box rounding");
+
+    for y in 0..height {
+        for x in 0..width {
+            let coverage = match border.get_color_at(x, y) {
+                Some(DrawColor::Transparent(Coverage(factor))) => factor,
+                None => 1.0,
+                _ => unreachable!(),
+            };
+
+            drawer.draw_color(
+                x + offset.x,
+                y + offset.y,
+                DrawColor::OverlayWithCoverage(color, Coverage(coverage * KEYBOARD_BOX_OPACITY)),
+            );
+        }
+    }
+}
+
 #[derive(Default)]
 enum EllipsizationState {
     Continue {
@@ -505,6 +691,62 @@ enum EllipsizationState {
     Complete,
 }
 
+/// The flat representation of text which allows to cut it at any glyph.
+#[derive(Clone)]
+enum Token {
+    Glyph(Glyph),
+    Space,
+    LineBreak,
+}
+
+impl Token {
+    fn flatten(paragraphs: VecDeque<VecDeque<WordRect>>) -> Vec<Token> {
+        let mut tokens = vec![];
+
+        for (i, paragraph) in paragraphs.into_iter().enumerate() {
+            if i != 0 {
+                tokens.push(Token::LineBreak);
+            }
+
+            for (j, word) in paragraph.into_iter().enumerate() {
+                if j != 0 {
+                    tokens.push(Token::Space);
+                }
+
+                tokens.extend(word.glyphs.into_iter().map(Token::Glyph));
+            }
+        }
+
+        tokens
+    }
+
+    fn collect_paragraphs<I: Iterator<Item = Token>>(tokens: I) -> VecDeque<VecDeque<WordRect>> {
+        tokens
+            .chunk_by(|token| !matches!(token, Token::LineBreak))
+            .into_iter()
+            .filter_map(|(matches, paragraph)| {
+                matches.then(|| {
+                    paragraph
+                        .chunk_by(|token| matches!(token, Token::Glyph(_)))
+                        .into_iter()
+                        .filter_map(|(matches, word)| {
+                            matches.then(|| {
+                                WordRect::from_glyphs(
+                                    word.filter_map(|token| match token {
+                                        Token::Glyph(glyph) => Some(glyph),
+                                        _ => None,
+                                    })
+                                    .collect(),
+                                )
+                            })
+                        })
+                        .collect()
+                })
+            })
+            .collect()
+    }
+}
+
 pub struct WordRect {
     advance_width: usize,
     glyphs: Vec<Glyph>,
@@ -519,17 +761,22 @@ impl WordRect {
     }
 
     fn from_glyphs(outlined_glyphs: Vec<Glyph>) -> Self {
-        let advance_width = outlined_glyphs
-            .iter()
-            .map(|glyph| glyph.advance_width())
-            .sum();
-
         Self {
-            advance_width,
+            advance_width: Self::measure(&outlined_glyphs),
             glyphs: outlined_glyphs,
         }
     }
 
+    /// Returns the width of glyphs which is rounded once for the whole run. The glyph advances
+    /// are already whole when the hinting snaps them.
+    fn measure<'a>(glyphs: impl IntoIterator<Item = &'a Glyph>) -> usize {
+        glyphs
+            .into_iter()
+            .map(Glyph::exact_advance_width)
+            .sum::<f32>()
+            .round() as usize
+    }
+
     #[inline(always = true)]
     fn set_color(&mut self, color: Bgra) {
         self.glyphs
@@ -544,34 +791,264 @@ impl WordRect {
 
     #[inline(always = true)]
     fn push_glyph(&mut self, new_glyph: Glyph) {
-        self.advance_width += new_glyph.advance_width();
         self.glyphs.push(new_glyph);
+        self.advance_width = Self::measure(&self.glyphs);
     }
+}
 
-    #[inline(always = true)]
-    fn pop_glyph(&mut self) -> Option<Glyph> {
-        let last_glyph = self.glyphs.pop();
-        if let Some(last_glyph) = last_glyph.as_ref() {
-            self.advance_width = self
-                .advance_width
-                .saturating_sub(last_glyph.advance_width());
+impl Draw for WordRect {
+    fn draw_with_offset(&self, offset: &Offset, drawer: &mut Drawer) {
+        // INFO: the pen keeps the fraction of advances and every glyph is placed at the rounded
+        // pen position
+        let mut pen = 0.0;
+        let pen_offset = |pen: f32| *offset + Offset::new_x(pen.round() as usize);
+        for (is_keyboard, run) in &self
+            .glyphs
+            .iter()
+            .chunk_by(|glyph| matches!(glyph, Glyph::Keyboard { .. }))
+        {
+            let run: Vec<&Glyph> = run.collect();
+            if let (true, Some(Glyph::Keyboard { color, height, .. })) = (is_keyboard, run.first())
+            {
+                let width = Self::measure(run.iter().copied());
+                draw_keyboard_box(width, *height, *color, &pen_offset(pen), drawer);
+            }
+
+            run.into_iter().for_each(|glyph| {
+                glyph.draw_with_offset(&pen_offset(pen), drawer);
+                pen += glyph.exact_advance_width();
+            })
         }
+    }
+}
 
-        last_glyph
+#[cfg(test)]
+mod tests {
+    use config::{general::FontHinting, text::EllipsizeAt};
+    use dbus::image::ImageData;
+
+    use crate::{
+        font::{FontCollection, FontStyle, Glyph},
+        image::Image,
+        types::RectSize,
+    };
+
+    use super::{pad_keyboard_runs, TextRect, WordRect};
+
+    const GLYPH_SIZE: usize = 10;
+
+    // INFO: the glyphs are replaced by the fake images which keep the character in data to check
+    // the result of ellipsization without loaded fonts.
+    fn glyph(ch: char) -> Glyph {
+        Glyph::Image(Image::Exists {
+            data: ImageData {
+                width: GLYPH_SIZE as i32,
+                height: GLYPH_SIZE as i32,
+                rowstride: GLYPH_SIZE as i32 * 4,
+                has_alpha: true,
+                bits_per_sample: 8,
+                channels: 4,
+                data: vec![ch as u8],
+            },
+            border: None,
+        })
     }
 
-    #[inline(always = true)]
-    fn is_blank(&self) -> bool {
-        self.glyphs.is_empty()
+    fn text_rect(text: &str, ellipsize_at: EllipsizeAt, wrap: bool) -> TextRect {
+        TextRect {
+            paragraphs: text
+                .split('\n')
+                .map(|paragraph| {
+                    paragraph
+                        .split(' ')
+                        .map(|word| WordRect::from_glyphs(word.chars().map(glyph).collect()))
+                        .collect()
+                })
+                .collect(),
+            wrap,
+            spacebar_width: GLYPH_SIZE,
+            line_height: GLYPH_SIZE,
+            ellipsis: glyph('~'),
+            ellipsize_at,
+            ..Default::default()
+        }
     }
-}
 
-impl Draw for WordRect {
-    fn draw_with_offset(&self, offset: &Offset, drawer: &mut Drawer) {
-        let mut offset = offset.to_owned();
-        self.glyphs.iter().for_each(|glyph| {
-            glyph.draw_with_offset(&offset, drawer);
-            offset.x += glyph.advance_width();
-        })
+    fn compiled_text(text_rect: &TextRect) -> String {
+        text_rect
+            .lines
+            .iter()
+            .map(|line| {
+                line.words
+                    .iter()
+                    .map(|word| {
+                        word.glyphs
+                            .iter()
+                            .map(|glyph| match glyph {
+                                Glyph::Image(Image::Exists { data, .. }) => data.data[0] as char,
+                                _ => unreachable!(),
+                            })
+                            .collect::<String>()
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn compile(text: &str, ellipsize_at: EllipsizeAt, wrap: bool, lines: usize) -> String {
+        let mut text_rect = text_rect(text, ellipsize_at, wrap);
+        text_rect.compile(RectSize::new(10 * GLYPH_SIZE, lines * GLYPH_SIZE));
+        compiled_text(&text_rect)
+    }
+
+    fn keyboard_glyph(ch: char) -> Glyph {
+        Glyph::Keyboard {
+            glyph: Box::new(glyph(ch)),
+            color: Default::default(),
+            height: GLYPH_SIZE,
+            padding: (0, 0),
+        }
+    }
+
+    fn hinted_text(hinting: FontHinting) -> TextRect {
+        let mut font_collection =
+            FontCollection::load_sans().expect("The font collection should be loaded");
+        font_collection.set_hinting(hinting);
+        let mut text_rect = TextRect::from_str(
+            "The quick brown fox jumps over the lazy dog",
+            13.3,
+            FontStyle::Regular,
+            &font_collection,
+        );
+        text_rect.set_line_spacing(3);
+        text_rect.compile(RectSize::new(100, 1000));
+        text_rect
+    }
+
+    #[test]
+    fn slight_hinting_snaps_every_line() {
+        let text_rect = hinted_text(FontHinting::Slight);
+        assert!(text_rect.lines.len() > 1);
+
+        for (index, line) in text_rect.lines.iter().enumerate() {
+            assert_eq!(line.y_offset, index * (text_rect.line_height + 3));
+
+            for word in &line.words {
+                let mut pen = 0.0_f32;
+                for glyph in &word.glyphs {
+                    let Glyph::Outline {
+                        advance_width,
+                        outlined_glyph,
+                        ..
+                    } = glyph
+                    else {
+                        panic!("Expected the outlined glyph");
+                    };
+                    // The baseline of line and the pen position of glyph are whole pixels
+                    let baseline = line.y_offset as f32 + outlined_glyph.glyph().position.y;
+                    assert_eq!(baseline.fract(), 0.0);
+                    assert_eq!(pen.fract(), 0.0);
+                    pen += advance_width;
+                }
+                assert_eq!(word.width(), pen as usize);
+            }
+        }
+    }
+
+    #[test]
+    fn unhinted_words_are_measured_without_rounding_errors() {
+        let text_rect = hinted_text(FontHinting::None);
+
+        for word in text_rect.lines.iter().flat_map(|line| &line.words) {
+            let exact_width: f32 = word.glyphs.iter().map(Glyph::exact_advance_width).sum();
+            assert_eq!(word.width(), exact_width.round() as usize);
+        }
+    }
+
+    #[test]
+    fn keyboard_runs_are_padded_at_edges() {
+        let mut glyphs = vec![
+            glyph('a'),
+            keyboard_glyph('C'),
+            keyboard_glyph('t'),
+            keyboard_glyph('l'),
+            glyph('+'),
+            keyboard_glyph('C'),
+        ];
+        pad_keyboard_runs(&mut glyphs, 3);
+
+        let paddings: Vec<(usize, usize)> = glyphs
+            .iter()
+            .filter_map(|glyph| match glyph {
+                Glyph::Keyboard { padding, .. } => Some(*padding),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(paddings, vec![(3, 0), (0, 0), (0, 3), (3, 3)]);
+
+        let word = WordRect::from_glyphs(glyphs);
+        assert_eq!(word.width(), 6 * GLYPH_SIZE + 4 * 3);
+    }
+
+    #[test]
+    fn fitting_text_is_not_ellipsized() {
+        for ellipsize_at in [EllipsizeAt::Start, EllipsizeAt::Middle, EllipsizeAt::End] {
+            assert_eq!(compile("abc def", ellipsize_at, false, 1), "abc def");
+        }
+    }
+
+    #[test]
+    fn ellipsize_at_middle_keeps_head_and_tail() {
+        assert_eq!(
+            compile("abcdefghij klmnopqrst", EllipsizeAt::Middle, false, 1),
+            "abcde~qrst"
+        );
+        assert_eq!(
+            compile(
+                "head of the body\nsome long middle part\nthe tail",
+                EllipsizeAt::Middle,
+                true,
+                2
+            ),
+            "head of t~\nthe tail"
+        );
+    }
+
+    #[test]
+    fn ellipsize_at_start_keeps_tail() {
+        assert_eq!(
+            compile("abcdefghij klmnopqrst", EllipsizeAt::Start, false, 1),
+            "~lmnopqrst"
+        );
+    }
+
+    #[test]
+    fn max_lines_limits_wrapped_text() {
+        let text = "the long body which is wrapped to many lines";
+
+        let mut text_rect = text_rect(text, EllipsizeAt::End, true);
+        text_rect.set_max_lines(2);
+        text_rect.compile(RectSize::new(10 * GLYPH_SIZE, 10 * GLYPH_SIZE));
+        assert_eq!(compiled_text(&text_rect), "the long\nbody~");
+        assert!(text_rect.is_truncated());
+
+        assert_eq!(
+            compile(text, EllipsizeAt::End, true, 10),
+            "the long\nbody which\nis wrapped\nto many\nlines"
+        );
+
+        let mut unlimited = self::text_rect(text, EllipsizeAt::End, true);
+        unlimited.compile(RectSize::new(10 * GLYPH_SIZE, 10 * GLYPH_SIZE));
+        assert!(!unlimited.is_truncated());
+    }
+
+    #[test]
+    fn ellipsize_at_end_keeps_head() {
+        assert_eq!(
+            compile("abcd efgh ijkl", EllipsizeAt::End, false, 1),
+            "abcd efgh~"
+        );
     }
 }
@@ -2,7 +2,7 @@ use std::ops::{Add, AddAssign};
 
 use config::spacing::Spacing;
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct RectSize {
     pub width: usize,
     pub height: usize,
@@ -1,6 +1,13 @@
-use config::{display::DisplayConfig, theme::Theme};
+use std::{borrow::Cow, time::Duration};
+
+use config::{
+    color::Color,
+    display::DisplayConfig,
+    theme::{Colors, Theme},
+};
 use dbus::notification::Notification;
-use log::warn;
+use log::{debug, warn};
+use shared::log_target::RENDER;
 
 use crate::drawer::Drawer;
 
@@ -12,6 +19,7 @@ use super::{
 
 mod flex_container;
 mod image;
+mod progress;
 mod text;
 
 pub use flex_container::{
@@ -19,7 +27,8 @@ pub use flex_container::{
     GBuilderFlexContainer, Position,
 };
 pub use image::{GBuilderWImage, WImage};
-pub use text::{GBuilderWText, WText, WTextKind};
+pub use progress::{GBuilderWProgress, WProgress};
+pub use text::{age_label, current_age_label, GBuilderWText, WText, WTextKind};
 
 #[derive(Clone, Copy)]
 pub struct Coverage(pub f32);
@@ -44,6 +53,7 @@ pub trait Draw {
 pub enum Widget {
     Image(WImage),
     Text(WText),
+    Progress(WProgress),
     FlexContainer(FlexContainer),
     Unknown,
 }
@@ -57,25 +67,74 @@ impl Widget {
         match self {
             Widget::Image(_) => "image",
             Widget::Text(_) => "text",
+            Widget::Progress(_) => "progress",
             Widget::FlexContainer(_) => "flex container",
             Widget::Unknown => "unknown",
         }
     }
 
-    pub fn compile(&mut self, rect_size: RectSize, configuration: &WidgetConfiguration) {
+    pub fn compile(
+        &mut self,
+        rect_size: RectSize,
+        configuration: &WidgetConfiguration,
+    ) -> CompileState {
         let state = match self {
             Widget::Image(image) => image.compile(rect_size, configuration),
             Widget::Text(text) => text.compile(rect_size, configuration),
+            Widget::Progress(progress) => progress.compile(rect_size, configuration),
             Widget::FlexContainer(container) => container.compile(rect_size, configuration),
             Widget::Unknown => CompileState::Success,
         };
 
-        if let CompileState::Failure = state {
-            warn!(
-                "A {wtype} widget is not compiled due errors!",
-                wtype = self.get_type()
-            );
-            *self = Widget::Unknown;
+        match state {
+            CompileState::Success => (),
+            CompileState::Skipped => {
+                debug!(
+                    target: RENDER,
+                    "A {wtype} widget has nothing to display, collapsed it",
+                    wtype = self.get_type()
+                );
+                *self = Widget::Unknown;
+            }
+            CompileState::Failure => {
+                warn!(
+                    target: RENDER,
+                    "A {wtype} widget is not compiled due errors!",
+                    wtype = self.get_type()
+                );
+                *self = Widget::Unknown;
+            }
+        }
+
+        state
+    }
+
+    pub fn is_animated(&self) -> bool {
+        match self {
+            Widget::Image(image) => image.is_animated(),
+            Widget::Progress(progress) => progress.is_animated(),
+            Widget::FlexContainer(container) => container.is_animated(),
+            Widget::Text(_) | Widget::Unknown => false,
+        }
+    }
+
+    /// Returns the time until the animated content of widget changes or `None` when the widget
+    /// is still.
+    pub fn next_frame_in(&self) -> Option<Duration> {
+        match self {
+            Widget::Image(image) => image.next_frame_in(),
+            Widget::Progress(progress) => progress.next_frame_in(),
+            Widget::FlexContainer(container) => container.next_frame_in(),
+            Widget::Text(_) | Widget::Unknown => None,
+        }
+    }
+
+    /// Returns the approximate number of bytes which the decoded images of widget take.
+    pub fn approximate_bytes(&self) -> usize {
+        match self {
+            Widget::Image(image) => image.approximate_bytes(),
+            Widget::FlexContainer(container) => container.approximate_bytes(),
+            Widget::Text(_) | Widget::Progress(_) | Widget::Unknown => 0,
         }
     }
 
@@ -90,19 +149,51 @@ impl Widget {
         match self {
             Widget::Image(image) => image.width(),
             Widget::Text(text) => text.width(),
+            Widget::Progress(progress) => progress.width(),
             Widget::FlexContainer(container) => container.max_width(),
             Widget::Unknown => 0,
         }
     }
 
+    /// Returns the width which the widget content takes, unlike `width()` it doesn't include the
+    /// space which is only stretched to fill the available width.
+    pub fn content_width(&self) -> usize {
+        match self {
+            Widget::Image(image) => image.width(),
+            Widget::Text(text) => text.content_width(),
+            Widget::Progress(progress) => progress.content_width(),
+            Widget::FlexContainer(container) => container.content_width(),
+            Widget::Unknown => 0,
+        }
+    }
+
     pub fn height(&self) -> usize {
         match self {
             Widget::Image(image) => image.height(),
             Widget::Text(text) => text.height(),
+            Widget::Progress(progress) => progress.height(),
             Widget::FlexContainer(container) => container.max_height(),
             Widget::Unknown => 0,
         }
     }
+
+    /// Returns the height which the widget content takes, unlike `height()` it doesn't include
+    /// the space which is only stretched to fill the available height.
+    pub fn content_height(&self) -> usize {
+        match self {
+            Widget::FlexContainer(container) => container.content_height(),
+            widget => widget.height(),
+        }
+    }
+
+    /// Returns true when the body text is ellipsized because it doesn't fit.
+    pub fn is_body_truncated(&self) -> bool {
+        match self {
+            Widget::Text(text) => text.is_body_truncated(),
+            Widget::FlexContainer(container) => container.is_body_truncated(),
+            Widget::Image(_) | Widget::Progress(_) | Widget::Unknown => false,
+        }
+    }
 }
 
 impl Draw for Widget {
@@ -110,6 +201,7 @@ impl Draw for Widget {
         match self {
             Widget::Image(image) => image.draw_with_offset(offset, output),
             Widget::Text(text) => text.draw_with_offset(offset, output),
+            Widget::Progress(progress) => progress.draw_with_offset(offset, output),
             Widget::FlexContainer(container) => container.draw_with_offset(offset, output),
             Widget::Unknown => (),
         }
@@ -118,15 +210,72 @@ impl Draw for Widget {
 
 pub enum CompileState {
     Success,
+    /// The widget has no content to display so it should be collapsed without reserving any
+    /// space.
+    Skipped,
     Failure,
 }
 
+#[derive(Clone, Copy)]
 pub struct WidgetConfiguration<'a> {
     pub notification: &'a Notification,
     pub font_collection: &'a FontCollection,
     pub theme: &'a Theme,
     pub display_config: &'a DisplayConfig,
     pub override_properties: bool,
+    /// The border color of highlighted banner, e.g. when it's hovered.
+    pub highlight: Option<&'a Color>,
+    /// Whether the banner is hovered, so the hover colors of theme are used.
+    pub hovered: bool,
+    /// Whether the images can be scaled by the compositor when their `viewport_scaling` is
+    /// enabled.
+    pub viewport_scaling: bool,
+    /// The scale of output where the banner is shown. The vector images are rasterized by it
+    /// when they're scaled by the compositor, so they stay crisp on HiDPI outputs.
+    pub output_scale: u32,
+    /// Whether the body is shown in full, so it's wrapped without the limit of lines.
+    pub expanded: bool,
+    /// Whether the memory budget is exceeded, so the images are decoded at their shown size and
+    /// aren't animated.
+    pub memory_pressure: bool,
+    /// The relative time since the notification arrived which the age text shows. It's computed
+    /// once per draw and is `None` when the timestamp isn't shown.
+    pub age_label: Option<&'a str>,
+}
+
+#[cfg(test)]
+impl<'a> WidgetConfiguration<'a> {
+    /// Returns the configuration of banner in the default state which overrides the properties
+    /// of layout by the display config. The tests change the fields which they check.
+    pub(crate) fn test_default(
+        notification: &'a Notification,
+        font_collection: &'a FontCollection,
+        theme: &'a Theme,
+        display_config: &'a DisplayConfig,
+    ) -> Self {
+        Self {
+            notification,
+            font_collection,
+            theme,
+            display_config,
+            override_properties: true,
+            highlight: None,
+            hovered: false,
+            viewport_scaling: false,
+            output_scale: 1,
+            expanded: false,
+            memory_pressure: false,
+            age_label: None,
+        }
+    }
+}
+
+impl<'a> WidgetConfiguration<'a> {
+    /// Returns the theme colors of notification urgency in the current state of banner.
+    pub fn colors(&self) -> Cow<'a, Colors> {
+        self.theme
+            .colors(&self.notification.hints.urgency, self.hovered)
+    }
 }
 
 impl From<WImage> for Widget {
@@ -141,6 +290,12 @@ impl From<WText> for Widget {
     }
 }
 
+impl From<WProgress> for Widget {
+    fn from(value: WProgress) -> Self {
+        Widget::Progress(value)
+    }
+}
+
 impl From<FlexContainer> for Widget {
     fn from(value: FlexContainer) -> Self {
         Widget::FlexContainer(value)
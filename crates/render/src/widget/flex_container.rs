@@ -1,6 +1,8 @@
+use std::time::Duration;
+
 use config::spacing::Spacing;
 use log::warn;
-use shared::{error::ConversionError, value::TryFromValue};
+use shared::{error::ConversionError, log_target::RENDER, value::TryFromValue};
 
 use crate::{
     border::{Border, BorderBuilder},
@@ -65,14 +67,18 @@ impl FlexContainer {
         };
         self.rect_size = Some(rect_size.clone());
 
-        let colors = &configuration
-            .theme
-            .by_urgency(&configuration.notification.hints.urgency);
+        let colors = configuration.colors();
 
         self.background_color = colors.background.clone().into();
         self.compiled_border = Some(
             BorderBuilder::default()
-                .color(colors.border.clone().into())
+                .color(
+                    configuration
+                        .highlight
+                        .unwrap_or(&colors.border.color)
+                        .clone()
+                        .into(),
+                )
                 .frame_width(rect_size.width)
                 .frame_height(rect_size.height)
                 .size(self.border.size)
@@ -81,11 +87,13 @@ impl FlexContainer {
                 .expect("Border should be have possibility to compile"),
         );
 
-        rect_size.shrink_by(&(self.spacing.clone() + Spacing::all_directional(self.border.size)));
+        let (_, rect_size) = self.content_area(rect_size);
         let mut container_axes = FlexContainerPlane::new(rect_size, &self.direction);
 
+        let mut all_skipped = !self.children.is_empty();
         self.children.iter_mut().for_each(|child| {
-            child.compile(container_axes.as_rect_size(), configuration);
+            let state = child.compile(container_axes.as_rect_size(), configuration);
+            all_skipped &= matches!(state, CompileState::Skipped);
 
             container_axes.main_len = container_axes
                 .main_len
@@ -93,8 +101,11 @@ impl FlexContainer {
         });
         self.children.retain(|child| !child.is_unknown());
 
-        if self.children.is_empty() {
+        if all_skipped {
+            CompileState::Skipped
+        } else if self.children.is_empty() {
             warn!(
+                target: RENDER,
                 "The flex container is empty! Did you add the widgets? \
                 Or check them, maybe they doesn't fit available space."
             );
@@ -104,6 +115,35 @@ impl FlexContainer {
         }
     }
 
+    /// Computes the offset and size of the area where the children are placed. Each side is
+    /// shrunk independently by the spacing of container and its border.
+    fn content_area(&self, mut rect_size: RectSize) -> (Offset, RectSize) {
+        let insets = self.spacing.clone() + Spacing::all_directional(self.border.size);
+        rect_size.shrink_by(&insets);
+
+        (
+            Offset::new(insets.left() as usize, insets.top() as usize),
+            rect_size,
+        )
+    }
+
+    pub(super) fn is_animated(&self) -> bool {
+        self.children.iter().any(Widget::is_animated)
+    }
+
+    pub(super) fn approximate_bytes(&self) -> usize {
+        self.children.iter().map(Widget::approximate_bytes).sum()
+    }
+
+    pub(super) fn next_frame_in(&self) -> Option<Duration> {
+        self.children.iter().filter_map(Widget::next_frame_in).min()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn children(&self) -> &[Widget] {
+        &self.children
+    }
+
     pub(super) fn max_width(&self) -> usize {
         self.max_width
     }
@@ -121,6 +161,36 @@ impl FlexContainer {
         }
     }
 
+    /// Returns the width which the content of children takes including the spacing and border
+    /// of container.
+    pub fn content_width(&self) -> usize {
+        let widths = self.children.iter().map(Widget::content_width);
+        let insets = self.spacing.horizontal() as usize + self.border.size as usize * 2;
+
+        insets
+            + match self.direction {
+                Direction::Horizontal => widths.sum(),
+                Direction::Vertical => widths.max().unwrap_or_default(),
+            }
+    }
+
+    /// Returns the height which the content of children takes including the spacing and border
+    /// of container.
+    pub fn content_height(&self) -> usize {
+        let heights = self.children.iter().map(Widget::content_height);
+        let insets = self.spacing.vertical() as usize + self.border.size as usize * 2;
+
+        insets
+            + match self.direction {
+                Direction::Horizontal => heights.max().unwrap_or_default(),
+                Direction::Vertical => heights.sum(),
+            }
+    }
+
+    pub(super) fn is_body_truncated(&self) -> bool {
+        self.children.iter().any(Widget::is_body_truncated)
+    }
+
     pub fn height(&self) -> usize {
         let heights = self.children.iter().map(|child| child.height());
 
@@ -178,7 +248,7 @@ impl FlexContainer {
 
 impl Draw for FlexContainer {
     fn draw_with_offset(&self, offset: &Offset, drawer: &mut Drawer) {
-        let Some(mut rect_size) = self.rect_size.as_ref().cloned() else {
+        let Some(rect_size) = self.rect_size.as_ref().cloned() else {
             panic!(
                 "The rectangle size must be computed by `compile()` method of parent container!"
             );
@@ -200,13 +270,32 @@ impl Draw for FlexContainer {
             (&mut subdrawer, Offset::no_offset())
         };
 
-        rect_size.shrink_by(&(self.spacing.clone() + Spacing::all_directional(self.border.size)));
+        self.children
+            .iter()
+            .zip(self.child_offsets(rect_size))
+            .for_each(|(child, child_offset)| {
+                child.draw_with_offset(&(child_offset + base_offset), picked_drawer)
+            });
+
+        if let Some(compiled_border) = self.compiled_border.as_ref() {
+            compiled_border.draw_with_offset(&base_offset, picked_drawer);
+        }
+
+        if !transparent_bg {
+            match &self.background_color {
+                Color::Fill(_) => drawer.draw_area_optimized(offset, subdrawer),
+                Color::LinearGradient(_) => drawer.draw_area(offset, subdrawer),
+            }
+        }
+    }
+}
+
+impl FlexContainer {
+    /// Computes the offsets of children relative to the container by its direction and alignment.
+    fn child_offsets(&self, rect_size: RectSize) -> Vec<Offset> {
+        let (initial_offset, rect_size) = self.content_area(rect_size);
         let mut plane = FlexContainerPlane::new(rect_size, &self.direction);
 
-        let initial_offset = Offset::new(
-            self.spacing.left() as usize + self.border.size as usize,
-            self.spacing.top() as usize + self.border.size as usize,
-        );
         let initial_plane = FlexContainerPlane::new_only_offset(initial_offset, &self.direction);
         plane.relocate(&initial_plane.as_offset());
 
@@ -225,29 +314,34 @@ impl Draw for FlexContainer {
             }
         };
 
-        self.children.iter().for_each(|child| {
-            plane.auxiliary_axis_offset = initial_plane.auxiliary_axis_offset
-                + self.auxiliary_axis_alignment().compute_initial_pos(
-                    plane.auxiliary_len,
-                    child.len_by_direction(&self.direction.orthogonalize()),
-                );
-
-            child.draw_with_offset(&(plane.as_offset() + base_offset), picked_drawer);
-
-            plane.main_axis_offset += child.len_by_direction(&self.direction) + incrementor;
-            plane.auxiliary_axis_offset = initial_plane.auxiliary_axis_offset;
-        });
-
-        if let Some(compiled_border) = self.compiled_border.as_ref() {
-            compiled_border.draw_with_offset(&base_offset, picked_drawer);
-        }
+        self.children
+            .iter()
+            .map(|child| {
+                plane.auxiliary_axis_offset = initial_plane.auxiliary_axis_offset
+                    + self.auxiliary_axis_alignment().compute_initial_pos(
+                        plane.auxiliary_len,
+                        child.len_by_direction(&self.direction.orthogonalize()),
+                    );
+                let child_offset = plane.as_offset();
+
+                plane.main_axis_offset += child.len_by_direction(&self.direction) + incrementor;
+                plane.auxiliary_axis_offset = initial_plane.auxiliary_axis_offset;
+                child_offset
+            })
+            .collect()
+    }
 
-        if !transparent_bg {
-            match &self.background_color {
-                Color::Fill(_) => drawer.draw_area_optimized(offset, subdrawer),
-                Color::LinearGradient(_) => drawer.draw_area(offset, subdrawer),
-            }
-        }
+    /// Returns the compiled children with their offsets relative to the container.
+    #[cfg(test)]
+    pub(crate) fn placed_children(&self) -> Vec<(&Widget, Offset)> {
+        let rect_size = self
+            .rect_size
+            .clone()
+            .expect("The container should be compiled before placing the children");
+        self.children
+            .iter()
+            .zip(self.child_offsets(rect_size))
+            .collect()
     }
 }
 
@@ -408,3 +502,241 @@ impl<'a> FlexContainerPlane<'a> {
         Offset::new(x, y)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use config::{
+        display::{Border, DisplayConfig, TomlDisplayConfig},
+        spacing::Spacing,
+        theme::Theme,
+    };
+    use dbus::notification::Notification;
+
+    use crate::{
+        color::{Bgra, Color},
+        drawer::Drawer,
+        font::FontCollection,
+        types::RectSize,
+        widget::{CompileState, Draw, WText, WTextKind, Widget, WidgetConfiguration},
+    };
+
+    use super::{Alignment, Direction, FlexContainer, FlexContainerBuilder, Position};
+
+    fn container(spacing: Spacing, border_size: u8) -> FlexContainer {
+        FlexContainerBuilder::default()
+            .spacing(spacing)
+            .border(Border {
+                size: border_size,
+                radius: 0,
+            })
+            .direction(Direction::Horizontal)
+            .alignment(Alignment::new(Position::Start, Position::Start))
+            .children(vec![])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn asymmetric_padding_content_area() {
+        let (offset, rect_size) =
+            container(Spacing::from(vec![1, 2, 3, 4]), 0).content_area(RectSize::new(100, 50));
+
+        assert_eq!((offset.x, offset.y), (4, 1));
+        assert_eq!((rect_size.width, rect_size.height), (94, 46));
+    }
+
+    #[test]
+    fn asymmetric_padding_with_border_content_area() {
+        let (offset, rect_size) =
+            container(Spacing::from(vec![1, 2, 3, 4]), 5).content_area(RectSize::new(100, 50));
+
+        assert_eq!((offset.x, offset.y), (9, 6));
+        assert_eq!((rect_size.width, rect_size.height), (84, 36));
+    }
+
+    fn notification(summary: &str, body: &str) -> Notification {
+        Notification::test_default()
+            .with_summary(summary)
+            .with_body(body)
+    }
+
+    fn render_texts(kinds: Vec<WTextKind>, notification: &Notification) -> (CompileState, Vec<u8>) {
+        render_texts_with(
+            kinds,
+            notification,
+            &DisplayConfig::from(TomlDisplayConfig::default()),
+        )
+    }
+
+    fn render_texts_with(
+        kinds: Vec<WTextKind>,
+        notification: &Notification,
+        display_config: &DisplayConfig,
+    ) -> (CompileState, Vec<u8>) {
+        let font_collection =
+            FontCollection::load_sans().expect("The font collection should be loaded");
+        let theme = Theme::default();
+        let configuration = WidgetConfiguration::test_default(
+            notification,
+            &font_collection,
+            &theme,
+            display_config,
+        );
+
+        let rect_size = RectSize::new(300, 150);
+        let mut layout: Widget = FlexContainerBuilder::default()
+            .spacing(Spacing::all_directional(10))
+            .border(Border::default())
+            .direction(Direction::Vertical)
+            .alignment(Alignment::new(Position::Start, Position::Start))
+            .children(
+                kinds
+                    .into_iter()
+                    .map(|kind| WText::new(kind).into())
+                    .collect(),
+            )
+            .build()
+            .unwrap()
+            .into();
+        let state = layout.compile(rect_size.clone(), &configuration);
+
+        let mut drawer = Drawer::new(Color::Fill(Bgra::new()), rect_size);
+        layout.draw(&mut drawer);
+        (state, drawer.into())
+    }
+
+    #[test]
+    fn empty_summary_is_collapsed() {
+        let notification = notification("", "Only the body is here");
+
+        let (state, with_title) =
+            render_texts(vec![WTextKind::Title, WTextKind::Body], &notification);
+        assert!(matches!(state, CompileState::Success));
+
+        let (_, body_only) = render_texts(vec![WTextKind::Body], &notification);
+        assert!(with_title == body_only);
+
+        let (_, with_summary) = render_texts(
+            vec![WTextKind::Title, WTextKind::Body],
+            &self::notification("Summary", "Only the body is here"),
+        );
+        assert!(with_summary != body_only);
+    }
+
+    #[test]
+    fn summary_markup_is_parsed_only_when_enabled() {
+        let escaped = notification("Tom &amp; Jerry", "");
+        let unescaped = notification("Tom & Jerry", "");
+        let mut display_config = DisplayConfig::from(TomlDisplayConfig::default());
+
+        let (_, plain) = render_texts(vec![WTextKind::Title], &escaped);
+        let (_, expected) = render_texts(vec![WTextKind::Title], &unescaped);
+        assert!(plain != expected);
+
+        display_config.summary_markup = true;
+        let (state, parsed) = render_texts_with(vec![WTextKind::Title], &escaped, &display_config);
+        assert!(matches!(state, CompileState::Success));
+        assert!(parsed == expected);
+    }
+
+    #[test]
+    fn body_is_omitted_in_compact_mode() {
+        let notification = notification("Typing", "The body which isn't shown");
+        let mut display_config = DisplayConfig::from(TomlDisplayConfig::default());
+        display_config.compact = true;
+
+        let (state, compact) = render_texts_with(
+            vec![WTextKind::Title, WTextKind::Body],
+            &notification,
+            &display_config,
+        );
+        assert!(matches!(state, CompileState::Success));
+
+        let (_, title_only) = render_texts(vec![WTextKind::Title], &notification);
+        assert!(compact == title_only);
+
+        let (state, _) = render_texts_with(vec![WTextKind::Body], &notification, &display_config);
+        assert!(matches!(state, CompileState::Skipped));
+    }
+
+    #[test]
+    fn capped_wrap_width() {
+        // INFO: the banner is 300px wide with the padding of 10px, so the text starts at 10px
+        const TEXT_START: usize = 10;
+        const WRAP_WIDTH: usize = 100;
+
+        let notification = notification(
+            "",
+            "The long body which is wrapped to several lines when the wrap width is capped",
+        );
+        let mut display_config = DisplayConfig::from(TomlDisplayConfig::default());
+        display_config.body.wrap_width = config::text::WrapWidth::Pixels(WRAP_WIDTH);
+
+        let has_text_after = |data: &[u8], x: usize| {
+            data.chunks_exact(300 * 4).any(|row| {
+                let background = &row[row.len() - 4..];
+                row[x * 4..row.len() - 4]
+                    .chunks_exact(4)
+                    .any(|pixel| pixel != background)
+            })
+        };
+
+        let (state, capped) =
+            render_texts_with(vec![WTextKind::Body], &notification, &display_config);
+        assert!(matches!(state, CompileState::Success));
+        assert!(!has_text_after(&capped, TEXT_START + WRAP_WIDTH));
+
+        let (_, full) = render_texts(vec![WTextKind::Body], &notification);
+        assert!(has_text_after(&full, TEXT_START + WRAP_WIDTH));
+    }
+
+    #[test]
+    fn container_with_empty_texts_is_skipped() {
+        let (state, _) = render_texts(
+            vec![WTextKind::Title, WTextKind::Body],
+            &notification(" ", "\n"),
+        );
+        assert!(matches!(state, CompileState::Skipped));
+    }
+
+    #[test]
+    fn highlighted_border_is_redrawn() {
+        let font_collection =
+            FontCollection::load_sans().expect("The font collection should be loaded");
+        let display_config = DisplayConfig::from(TomlDisplayConfig::default());
+        let theme = Theme::default();
+        let notification = notification("Summary", "Body");
+
+        let highlight = config::color::Color::from(
+            config::color::Rgba::try_from("#ff0000".to_string())
+                .expect("The color should be valid"),
+        );
+        let render = |highlight: Option<&config::color::Color>| -> Vec<u8> {
+            let configuration = WidgetConfiguration {
+                highlight,
+                ..WidgetConfiguration::test_default(
+                    &notification,
+                    &font_collection,
+                    &theme,
+                    &display_config,
+                )
+            };
+
+            let rect_size = RectSize::new(300, 150);
+            let mut layout: Widget = container(Spacing::all_directional(10), 2).into();
+            if let Widget::FlexContainer(container) = &mut layout {
+                container.children = vec![WText::new(WTextKind::Title).into()];
+            }
+            layout.compile(rect_size.clone(), &configuration);
+
+            let mut drawer = Drawer::new(Color::Fill(Bgra::new()), rect_size);
+            layout.draw(&mut drawer);
+            drawer.into()
+        };
+
+        let (plain, highlighted) = (render(None), render(Some(&highlight)));
+        // INFO: the border is drawn at the edge, so the first pixel changes its color
+        assert!(plain[..4] != highlighted[..4]);
+        assert!(plain[4 * 300 * 75 + 4 * 150..][..4] == highlighted[4 * 300 * 75 + 4 * 150..][..4]);
+    }
+}
@@ -1,5 +1,8 @@
+use std::time::Duration;
+
 use config::display::{GBuilderImageProperty, ImageProperty};
 use log::warn;
+use shared::log_target::IMAGE;
 
 use crate::{
     drawer::Drawer,
@@ -41,12 +44,22 @@ impl WImage {
             notification,
             display_config,
             override_properties,
+            viewport_scaling,
+            output_scale,
+            memory_pressure,
             ..
         }: &WidgetConfiguration,
     ) -> CompileState {
         if *override_properties {
             self.property = display_config.image.clone();
         }
+        self.property.viewport_scaling &= *viewport_scaling;
+        if *memory_pressure {
+            // INFO: the image is decoded right at its shown size to keep only necessary pixels
+            self.property.max_decode_size =
+                self.property.max_decode_size.min(self.property.max_size);
+        }
+        let animate_images = display_config.animate_images && !*memory_pressure;
 
         self.content = notification
             .hints
@@ -60,7 +73,18 @@ impl WImage {
                     .image_path
                     .as_deref()
                     .map(std::path::Path::new)
-                    .map(|svg_path| Image::from_svg(svg_path, &self.property, &rect_size))
+                    .map(|image_path| {
+                        if animate_images {
+                            Image::from_path_animated(
+                                image_path,
+                                &self.property,
+                                &rect_size,
+                                *output_scale,
+                            )
+                        } else {
+                            Image::from_svg(image_path, &self.property, &rect_size, *output_scale)
+                        }
+                    })
             })
             .or_else(|| {
                 display_config
@@ -73,7 +97,18 @@ impl WImage {
                             .with_theme(&display_config.theme)
                             .find()
                     })
-                    .map(|icon_path| Image::from_path(&icon_path, &self.property, &rect_size))
+                    .map(|icon_path| {
+                        if animate_images {
+                            Image::from_path_animated(
+                                &icon_path,
+                                &self.property,
+                                &rect_size,
+                                *output_scale,
+                            )
+                        } else {
+                            Image::from_path(&icon_path, &self.property, &rect_size, *output_scale)
+                        }
+                    })
             })
             .unwrap_or(Image::Unknown);
 
@@ -90,6 +125,7 @@ impl WImage {
 
         if self.width > rect_size.width || self.height > rect_size.height {
             warn!(
+                target: IMAGE,
                 "The image doesn't fit to available space.\
                 \nThe image size: width={}, height={}.\
                 \nAvailable space: width={}, height={}.",
@@ -105,6 +141,18 @@ impl WImage {
         }
     }
 
+    pub fn is_animated(&self) -> bool {
+        self.content.is_animated()
+    }
+
+    pub fn next_frame_in(&self) -> Option<Duration> {
+        self.content.next_frame_in()
+    }
+
+    pub fn approximate_bytes(&self) -> usize {
+        self.content.approximate_bytes()
+    }
+
     pub fn width(&self) -> usize {
         self.width
     }
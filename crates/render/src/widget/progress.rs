@@ -0,0 +1,204 @@
+use std::time::{Duration, Instant};
+
+use config::display::{GBuilderProgressProperty, ProgressProperty};
+use dbus::notification::Progress;
+use log::warn;
+use shared::log_target::RENDER;
+
+use crate::{
+    color::Bgra,
+    drawer::Drawer,
+    types::{Offset, RectSize},
+};
+
+use super::{CompileState, Draw, DrawColor, WidgetConfiguration};
+
+#[derive(macros::GenericBuilder, Clone)]
+#[gbuilder(name(GBuilderWProgress), derive(Clone))]
+pub struct WProgress {
+    #[gbuilder(hidden, default(None))]
+    progress: Option<Progress>,
+
+    #[gbuilder(hidden, default(0))]
+    width: usize,
+    #[gbuilder(hidden, default(Bgra::new()))]
+    foreground: Bgra,
+    #[gbuilder(hidden, default(Instant::now()))]
+    started_at: Instant,
+
+    #[gbuilder(use_gbuilder(GBuilderProgressProperty), default)]
+    property: ProgressProperty,
+}
+
+impl WProgress {
+    /// The time for which the pulsing segment passes the bar in one direction.
+    const PULSE_PERIOD: Duration = Duration::from_millis(1200);
+    /// The opacity of track behind the filled part of bar relatively to the foreground color.
+    const TRACK_OPACITY: f32 = 0.3;
+
+    pub fn new() -> Self {
+        Self {
+            progress: None,
+            width: 0,
+            foreground: Bgra::new(),
+            started_at: Instant::now(),
+            property: Default::default(),
+        }
+    }
+
+    pub fn compile(
+        &mut self,
+        rect_size: RectSize,
+        WidgetConfiguration {
+            notification,
+            display_config,
+            theme,
+            override_properties,
+            hovered,
+            ..
+        }: &WidgetConfiguration,
+    ) -> CompileState {
+        if *override_properties {
+            self.property = display_config.progress.clone();
+        }
+
+        self.progress = notification.hints.progress();
+        self.width = 0;
+        if self.progress.is_none() {
+            return CompileState::Skipped;
+        }
+
+        if self.height() > rect_size.height
+            || self.property.margin.horizontal() as usize >= rect_size.width
+        {
+            warn!(
+                target: RENDER,
+                "The progress bar doesn't fit to available space.\
+                \nThe progress bar height: {}.\
+                \nAvailable space: width={}, height={}.",
+                self.height(),
+                rect_size.width,
+                rect_size.height
+            );
+            return CompileState::Failure;
+        }
+
+        self.width = rect_size.width;
+        self.foreground = Bgra::from(
+            &theme
+                .colors(&notification.hints.urgency, *hovered)
+                .foreground,
+        );
+
+        CompileState::Success
+    }
+
+    pub fn is_animated(&self) -> bool {
+        matches!(self.progress, Some(Progress::Pulse))
+    }
+
+    /// The pulsing segment moves continuously, so the next frame is always due.
+    pub fn next_frame_in(&self) -> Option<Duration> {
+        self.is_animated().then_some(Duration::ZERO)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.property.height as usize + self.property.margin.vertical() as usize
+    }
+
+    /// The progress bar stretches to the available width, so it takes only its margin.
+    pub fn content_width(&self) -> usize {
+        self.property.margin.horizontal() as usize
+    }
+
+    /// Returns the horizontal range of bar which should be filled.
+    fn filled_range(&self, bar_width: usize) -> std::ops::Range<usize> {
+        match self.progress {
+            Some(Progress::Determinate(value)) => 0..bar_width * value as usize / 100,
+            Some(Progress::Pulse) => {
+                let segment_width = (bar_width / 4).max(1).min(bar_width);
+                let period = Self::PULSE_PERIOD.as_secs_f32();
+                let phase = self.started_at.elapsed().as_secs_f32() % (period * 2.0) / period;
+                // INFO: the segment goes forward in first half of cycle and returns back in second
+                let position = if phase > 1.0 { 2.0 - phase } else { phase };
+
+                let start = ((bar_width - segment_width) as f32 * position).round() as usize;
+                start..start + segment_width
+            }
+            None => 0..0,
+        }
+    }
+}
+
+impl Default for WProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Draw for WProgress {
+    fn draw_with_offset(&self, offset: &Offset, drawer: &mut Drawer) {
+        if self.progress.is_none() {
+            return;
+        }
+
+        let offset = Offset::from(&self.property.margin) + *offset;
+        let bar_width = self
+            .width
+            .saturating_sub(self.property.margin.horizontal() as usize);
+        let filled_range = self.filled_range(bar_width);
+
+        let track = Bgra {
+            alpha: self.foreground.alpha * Self::TRACK_OPACITY,
+            ..self.foreground
+        };
+
+        for y in 0..self.property.height as usize {
+            for x in 0..bar_width {
+                let color = if filled_range.contains(&x) {
+                    self.foreground
+                } else {
+                    track
+                };
+
+                drawer.draw_color(offset.x + x, offset.y + y, DrawColor::Overlay(color));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dbus::notification::Progress;
+
+    use super::WProgress;
+
+    fn progress(progress: Progress) -> WProgress {
+        WProgress {
+            progress: Some(progress),
+            ..WProgress::new()
+        }
+    }
+
+    #[test]
+    fn determinate_fill() {
+        assert_eq!(progress(Progress::Determinate(0)).filled_range(200), 0..0);
+        assert_eq!(progress(Progress::Determinate(40)).filled_range(200), 0..80);
+        assert_eq!(
+            progress(Progress::Determinate(100)).filled_range(200),
+            0..200
+        );
+    }
+
+    #[test]
+    fn pulse_stays_within_bar() {
+        let filled_range = progress(Progress::Pulse).filled_range(200);
+
+        assert_eq!(filled_range.len(), 50);
+        assert!(filled_range.end <= 200);
+    }
+}
@@ -1,7 +1,9 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use config::text::{GBuilderTextProperty, TextProperty};
-use dbus::text::Text;
-use log::warn;
-use shared::{error::ConversionError, value::TryFromValue};
+use dbus::text::{EntityKind, Text};
+use log::{debug, warn};
+use shared::{error::ConversionError, log_target::RENDER, value::TryFromValue};
 
 use crate::{
     color::Bgra,
@@ -50,6 +52,10 @@ pub enum WTextKind {
     Title,
     #[display("body")]
     Body,
+    /// The time since the notification arrived which is shown when the `show_timestamp` option
+    /// is enabled.
+    #[display("age")]
+    Age,
 }
 
 impl TryFromValue for WTextKind {
@@ -57,8 +63,9 @@ impl TryFromValue for WTextKind {
         Ok(match value.to_lowercase().as_str() {
             "title" | "summary" => WTextKind::Title,
             "body" => WTextKind::Body,
+            "age" => WTextKind::Age,
             _ => Err(ConversionError::InvalidValue {
-                expected: "title or body",
+                expected: "title, body or age",
                 actual: value,
             })?,
         })
@@ -74,6 +81,11 @@ impl WText {
         }
     }
 
+    #[cfg(test)]
+    pub(crate) fn kind(&self) -> &WTextKind {
+        &self.kind
+    }
+
     pub fn compile(
         &mut self,
         rect_size: RectSize,
@@ -83,6 +95,10 @@ impl WText {
             font_collection,
             override_properties,
             theme,
+            hovered,
+            expanded,
+            age_label,
+            ..
         }: &WidgetConfiguration,
     ) -> CompileState {
         let mut override_if = |r#override: bool, property: &TextProperty| {
@@ -91,13 +107,21 @@ impl WText {
             }
         };
 
-        let colors = theme.by_urgency(&notification.hints.urgency);
+        let colors = theme.colors(&notification.hints.urgency, *hovered);
         let foreground = Bgra::from(&colors.foreground);
 
         let notification_content: NotificationContent = match self.kind {
             WTextKind::Title => {
                 override_if(*override_properties, &display_config.title);
-                notification.summary.as_str().into()
+                if display_config.summary_markup {
+                    (&notification.parsed_summary).into()
+                } else {
+                    notification.summary.as_str().into()
+                }
+            }
+            WTextKind::Body if display_config.compact => {
+                debug!(target: RENDER, "The body is omitted in compact mode");
+                return CompileState::Skipped;
             }
             WTextKind::Body => {
                 override_if(*override_properties, &display_config.body);
@@ -107,9 +131,29 @@ impl WText {
                     notification.body.body.as_str().into()
                 }
             }
+            WTextKind::Age if !display_config.show_timestamp || display_config.compact => {
+                return CompileState::Skipped;
+            }
+            WTextKind::Age => {
+                let Some(age_label) = age_label else {
+                    return CompileState::Skipped;
+                };
+                override_if(*override_properties, &display_config.body);
+                (*age_label).into()
+            }
         };
 
-        let px_size = self.property.font_size as f32;
+        self.content = None;
+        if notification_content.is_blank() {
+            debug!(
+                target: RENDER,
+                "The text with kind {} is empty, skipped it to not reserve space",
+                self.kind
+            );
+            return CompileState::Skipped;
+        }
+
+        let px_size = font_collection.px_size(self.property.font_size);
         let mut content = match notification_content {
             NotificationContent::Text(text) => {
                 TextRect::from_text(text, px_size, &self.property.style, font_collection)
@@ -120,11 +164,29 @@ impl WText {
         };
 
         Self::apply_properties(&mut content, &self.property);
+        if display_config.compact {
+            // INFO: the compact banner is clamped to the single line of summary
+            content.set_wrap(false);
+            content.set_max_lines(1);
+        }
+        if *expanded && matches!(self.kind, WTextKind::Body) {
+            content.set_wrap(true);
+            content.set_max_lines(0);
+        }
         Self::apply_color(&mut content, foreground);
 
-        content.compile(rect_size.clone());
+        let margin = self.property.margin.horizontal() as usize;
+        let text_width = self
+            .property
+            .wrap_width
+            .apply(rect_size.width.saturating_sub(margin));
+        content.compile(RectSize {
+            width: text_width + margin,
+            ..rect_size.clone()
+        });
         if content.is_empty() {
             warn!(
+                target: RENDER,
                 "The text with kind {} doesn't fit to available space. \
                 Available space: width={}, height={}.",
                 self.kind, rect_size.width, rect_size.height
@@ -138,6 +200,7 @@ impl WText {
 
     fn apply_properties(element: &mut TextRect, properties: &TextProperty) {
         element.set_wrap(properties.wrap);
+        element.set_max_lines(properties.max_lines as usize);
         element.set_margin(&properties.margin);
         element.set_line_spacing(properties.line_spacing as usize);
         element.set_ellipsize_at(&properties.ellipsize_at);
@@ -161,6 +224,21 @@ impl WText {
             .map(|content| content.height())
             .unwrap_or(0)
     }
+
+    pub fn content_width(&self) -> usize {
+        self.content
+            .as_ref()
+            .map(|content| content.content_width())
+            .unwrap_or(0)
+    }
+
+    pub fn is_body_truncated(&self) -> bool {
+        matches!(self.kind, WTextKind::Body)
+            && self
+                .content
+                .as_ref()
+                .is_some_and(|content| content.is_truncated())
+    }
 }
 
 impl Draw for WText {
@@ -171,11 +249,51 @@ impl Draw for WText {
     }
 }
 
+/// Returns the relative time since the notification was created like "now" or "2m ago". Both
+/// times are the seconds since the Unix epoch.
+pub fn age_label(created_at: u64, now: u64) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+
+    match now.saturating_sub(created_at) {
+        age if age < MINUTE => "now".to_string(),
+        age if age < HOUR => format!("{}m ago", age / MINUTE),
+        age if age < DAY => format!("{}h ago", age / HOUR),
+        age => format!("{}d ago", age / DAY),
+    }
+}
+
+/// Returns the [`age_label`] of notification at the current time.
+pub fn current_age_label(created_at: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    age_label(created_at, now)
+}
+
 enum NotificationContent<'a> {
     String(&'a str),
     Text(&'a Text),
 }
 
+impl NotificationContent<'_> {
+    fn is_blank(&self) -> bool {
+        match self {
+            NotificationContent::String(str) => str.trim().is_empty(),
+            NotificationContent::Text(text) => {
+                text.body.trim().is_empty()
+                    && !text
+                        .entities
+                        .iter()
+                        .any(|entity| matches!(entity.kind, EntityKind::Image { .. }))
+            }
+        }
+    }
+}
+
 impl<'a> From<&'a str> for NotificationContent<'a> {
     fn from(value: &'a str) -> Self {
         NotificationContent::String(value)
@@ -187,3 +305,153 @@ impl<'a> From<&'a Text> for NotificationContent<'a> {
         NotificationContent::Text(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use config::{
+        display::{DisplayConfig, TomlDisplayConfig},
+        theme::Theme,
+    };
+    use dbus::notification::Notification;
+
+    use crate::{
+        font::FontCollection,
+        types::RectSize,
+        widget::{CompileState, WidgetConfiguration},
+    };
+
+    use super::{age_label, WText, WTextKind};
+
+    #[test]
+    fn age_label_is_rounded_down_to_largest_unit() {
+        assert_eq!(age_label(1000, 1000), "now");
+        assert_eq!(age_label(1000, 1059), "now");
+        assert_eq!(age_label(1000, 1000 + 2 * 60 + 30), "2m ago");
+        assert_eq!(age_label(1000, 1000 + 3 * 3600 + 59 * 60), "3h ago");
+        assert_eq!(age_label(1000, 1000 + 2 * 86400), "2d ago");
+        // The clock of the system is moved back
+        assert_eq!(age_label(1000, 900), "now");
+    }
+
+    #[test]
+    fn age_text_shows_label_of_draw() {
+        let font_collection =
+            FontCollection::load_sans().expect("The font collection should be loaded");
+        let theme = Theme::default();
+        let mut display_config = DisplayConfig::from(TomlDisplayConfig::default());
+        display_config.show_timestamp = true;
+        let notification = Notification::test_default();
+
+        let compile = |age_label| {
+            let configuration = WidgetConfiguration {
+                age_label,
+                ..WidgetConfiguration::test_default(
+                    &notification,
+                    &font_collection,
+                    &theme,
+                    &display_config,
+                )
+            };
+            let mut text = WText::new(WTextKind::Age);
+            matches!(
+                text.compile(RectSize::new(1000, 1000), &configuration),
+                CompileState::Success
+            )
+        };
+
+        assert!(!compile(None));
+        assert!(compile(Some("2m ago")));
+    }
+
+    #[test]
+    fn compact_summary_is_clamped_to_single_line() {
+        let font_collection =
+            FontCollection::load_sans().expect("The font collection should be loaded");
+        let theme = Theme::default();
+        let mut display_config = DisplayConfig::from(TomlDisplayConfig::default());
+        let compiled_height = |display_config: &DisplayConfig, summary: &str| {
+            let notification = Notification::test_default().with_summary(summary);
+            let configuration = WidgetConfiguration::test_default(
+                &notification,
+                &font_collection,
+                &theme,
+                display_config,
+            );
+            let mut text = WText::new(WTextKind::Title);
+            text.compile(RectSize::new(300, 300), &configuration);
+            text.height()
+        };
+        let summary = "The summary which is long enough to be wrapped into several lines";
+
+        assert!(compiled_height(&display_config, summary) > compiled_height(&display_config, "A"));
+        display_config.compact = true;
+        assert_eq!(
+            compiled_height(&display_config, summary),
+            compiled_height(&display_config, "A")
+        );
+    }
+
+    #[test]
+    fn inline_images_are_not_blank() {
+        let font_collection =
+            FontCollection::load_sans().expect("The font collection should be loaded");
+        let theme = Theme::default();
+        let display_config = DisplayConfig::from(TomlDisplayConfig::default());
+        let image_path = std::env::temp_dir().join("noti-test-inline-image.png");
+        image::RgbaImage::from_pixel(8, 8, image::Rgba([255, 0, 0, 255]))
+            .save(&image_path)
+            .expect("The inline image should be saved");
+
+        let compile = |body: &str| {
+            let notification = Notification::test_default().with_body(body);
+            let configuration = WidgetConfiguration::test_default(
+                &notification,
+                &font_collection,
+                &theme,
+                &display_config,
+            );
+            let mut text = WText::new(WTextKind::Body);
+            let state = text.compile(RectSize::new(300, 300), &configuration);
+            (matches!(state, CompileState::Success), text.height())
+        };
+
+        assert_eq!(compile("<b> </b>"), (false, 0));
+        let (compiled, height) = compile(&format!("<img src=\"{}\"/>", image_path.display()));
+        std::fs::remove_file(image_path).unwrap();
+        assert!(compiled);
+        assert!(height > 0);
+        assert!(compile("<img src=\"/missing.png\" alt=\"cat\"/>").0);
+    }
+
+    #[test]
+    fn oversized_layout_font_is_clamped() {
+        let mut font_collection =
+            FontCollection::load_sans().expect("The font collection should be loaded");
+        font_collection.set_font_size_bounds(6, 24);
+        let theme = Theme::default();
+        let display_config = DisplayConfig::from(TomlDisplayConfig::default());
+        let notification = Notification::test_default().with_summary("Summary");
+        let configuration = WidgetConfiguration {
+            // INFO: the properties of layout are kept like in the layout files
+            override_properties: false,
+            ..WidgetConfiguration::test_default(
+                &notification,
+                &font_collection,
+                &theme,
+                &display_config,
+            )
+        };
+
+        let compiled_height = |font_size| {
+            let mut text = WText::new(WTextKind::Title);
+            text.property.font_size = font_size;
+            text.property.wrap = false;
+            text.compile(RectSize::new(10000, 10000), &configuration);
+            text.height()
+        };
+
+        assert_eq!(compiled_height(250), compiled_height(24));
+        assert!(compiled_height(24) > compiled_height(12));
+        assert_eq!(compiled_height(1), compiled_height(6));
+    }
+}
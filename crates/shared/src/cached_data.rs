@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use log::error;
 
-use crate::file_watcher::FileState;
+use crate::{file_watcher::FileState, log_target::CONFIG};
 
 pub struct CachedData<K, V>(HashMap<K, V>)
 where
@@ -22,6 +22,21 @@ where
         self.0.get(key)
     }
 
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Returns the approximate number of bytes which the cached values take.
+    pub fn approximate_bytes(&self) -> usize
+    where
+        V: ApproximateBytes,
+    {
+        self.0
+            .values()
+            .map(ApproximateBytes::approximate_bytes)
+            .sum()
+    }
+
     pub fn update(&mut self) -> bool
     where
         V: CacheUpdate,
@@ -52,7 +67,7 @@ where
                     self.0.insert(key, data);
                 }
                 Err(err) => {
-                    error!("{err}")
+                    error!(target: CONFIG, "{err}")
                 }
             }
         }
@@ -92,6 +107,12 @@ pub trait CacheUpdate {
     fn update(&mut self);
 }
 
+/// The cached value which tells how much memory it takes, so the cache can be kept within the
+/// memory budget.
+pub trait ApproximateBytes {
+    fn approximate_bytes(&self) -> usize;
+}
+
 #[derive(derive_more::Display)]
 pub enum CachedValueError {
     #[display("Failed to init file watcher for file. Error: {source}")]
@@ -1,5 +1,6 @@
 use std::path::{Path, PathBuf};
 
+use crate::log_target::CONFIG;
 use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
 use log::debug;
 
@@ -26,18 +27,18 @@ impl FilesWatcher {
             "At least one path should be provided to FilesWatcher"
         );
 
-        debug!("Watcher: Initializing");
+        debug!(target: CONFIG, "Watcher: Initializing");
         let inotify = Inotify::init()?;
 
         let paths: Vec<FilePath> = paths.into_iter().map(From::from).collect();
-        debug!("Watcher: Received paths - {paths:?}");
+        debug!(target: CONFIG, "Watcher: Received paths - {paths:?}");
 
         let config_wd = paths
             .iter()
             .find(|path| path.is_file())
             .map(|path| inotify.new_wd(path));
 
-        debug!("Watcher: Initialized");
+        debug!(target: CONFIG, "Watcher: Initialized");
         Ok(Self {
             inotify,
             paths,
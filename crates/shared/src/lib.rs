@@ -1,4 +1,5 @@
 pub mod cached_data;
 pub mod error;
 pub mod file_watcher;
+pub mod log_target;
 pub mod value;
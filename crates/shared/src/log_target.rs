@@ -0,0 +1,32 @@
+//! The targets of log records which group them by subsystems.
+//!
+//! Every log macro call should specify the target of its subsystem, e.g.
+//! `debug!(target: WINDOW, "...")`, so the logs can be filtered like
+//! `NOTI_LOG=noti::window=debug,info`.
+
+/// The main loop of backend which dispatches incoming actions.
+pub const BACKEND: &str = "noti::backend";
+/// The layer surface windows and their banners.
+pub const WINDOW: &str = "noti::window";
+/// The idle state of user.
+pub const IDLE: &str = "noti::idle";
+/// The power mode of outputs.
+pub const POWER: &str = "noti::power";
+/// The clipboard selection which is set by the daemon.
+pub const CLIPBOARD: &str = "noti::clipboard";
+/// The scheduled notifications.
+pub const SCHEDULER: &str = "noti::scheduler";
+/// The sounds of notifications.
+pub const SOUND: &str = "noti::sound";
+/// The loading of images and icons.
+pub const IMAGE: &str = "noti::image";
+/// The rendering of banners by widgets and fonts.
+pub const RENDER: &str = "noti::render";
+/// The loading and watching of configuration files.
+pub const CONFIG: &str = "noti::config";
+/// The parsing of layout files.
+pub const LAYOUT: &str = "noti::layout";
+/// The D-Bus server and notifications received by it.
+pub const DBUS: &str = "noti::dbus";
+/// The client which sends requests to the running server.
+pub const CLIENT: &str = "noti::client";